@@ -0,0 +1,13 @@
+fn main() {
+    // The gRPC service is optional; only generate code when it is enabled.
+    #[cfg(feature = "grpc")]
+    {
+        let proto = "proto/ollama_file_find.proto";
+        println!("cargo:rerun-if-changed={proto}");
+        let fds = protox::compile([proto], ["proto"]).expect("compile proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(fds)
+            .expect("generate gRPC code");
+    }
+}
@@ -1,5 +1,8 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use ollama_file_find::{HiddenPolicy, LayerKind};
+use std::{path::PathBuf, time::Duration};
+
+use crate::{output::OutputFormat, progress::ProgressMode};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -8,20 +11,1545 @@ use std::path::PathBuf;
     about = "List locally installed Ollama models by reading the manifests directory"
 )]
 pub(crate) struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options for the default `list` command
+    #[command(flatten)]
+    pub list: ListArgs,
+
+    /// Root of models directory (overrides env + fallback)
+    #[arg(long, global = true)]
+    pub models_dir: Option<PathBuf>,
+
+    /// Config file (default: `<config dir>/ollama-file-find/config.toml`)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Ollama API endpoint for `list --running` and `show --warmup`; repeat to use
+    /// several servers (default: `OLLAMA_HOST`, then http://127.0.0.1:11434)
+    #[arg(long = "api-url", value_name = "URL", global = true)]
+    pub api_urls: Vec<String>,
+
+    /// Run at the lowest CPU and I/O priority and cap hashing at 64 MiB/s, so scrubs,
+    /// seals, and backups don't slow down a running Ollama
+    #[arg(long, global = true)]
+    pub nice: bool,
+
+    /// Cap hashing throughput (bytes per second, e.g. `200MiB`); implied by `--nice`
+    #[arg(long, value_name = "SIZE", global = true, value_parser = crate::format::parse_bytes)]
+    pub max_hash_rate: Option<u64>,
+
+    /// How long-running operations (scrub, bundle, backup, restore, peer pulls) report
+    /// progress on stderr: `json` for one JSON event per line, for wrappers drawing their
+    /// own UI
+    #[arg(long, value_enum, value_name = "MODE", global = true, default_value_t)]
+    pub progress: ProgressMode,
+
+    /// Print the commands and flags (of the subcommand given before it, else all of them)
+    /// as JSON instead of running anything, for generating GUIs and forms
+    #[arg(long, global = true)]
+    pub help_json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// List installed models (the default when no subcommand is given)
+    List(ListArgs),
+    /// Summarize declared, apparent, and allocated disk usage
+    Du(DuArgs),
+    /// Keep an index of the store up to date and answer `list` / `which <model>` /
+    /// `health` queries over a local socket
+    Daemon(DaemonArgs),
+    /// Show store changes recorded by the daemon's event log
+    Events(EventsArgs),
+    /// Write one JSON file per model plus an `index.json`, for static dashboards
+    ExportStatic(ExportStaticArgs),
+    /// Estimate how many bytes `ollama pull <model>` would download, given the blobs
+    /// already present locally
+    #[cfg(feature = "network")]
+    Estimate(EstimateArgs),
+    /// Exit 0 if a size or a model pull fits in the free space of the blobs volume,
+    /// 1 otherwise
+    CanFit(CanFitArgs),
+    /// Print installed model names starting with a prefix, one per line, for shell
+    /// completion scripts and editor pickers
+    CompleteNames(CompleteNamesArgs),
+    /// Per-namespace usage against the `[quota]` limits in the config file
+    Quota(QuotaArgs),
+    /// Evaluate the `[policy]` rules in the config file, e.g. before `ollama pull`
+    Policy(PolicyArgs),
+    /// Group models by detected license family and flag missing license blobs
+    Compliance(ComplianceArgs),
+    /// Find models by name, template style, config, params, license, annotations, or
+    /// configured labels (every term must match somewhere)
+    Search(SearchArgs),
+    /// Show what differs between two installed models: layers, config, params,
+    /// template, and system prompt
+    Compare(CompareArgs),
+    /// List blobs no manifest references, with their age
+    Orphans(OrphansArgs),
+    /// Delete orphaned blobs, keeping those inside the retention window
+    Prune(PruneArgs),
+    /// Health check, blob scrub, and orphan pruning in one pass for cron; with
+    /// `--every` or `--if-due`, only when a run is due. Exit 1 if anything is wrong
+    Maintain(MaintainArgs),
+    /// Make a throwaway models directory (copied manifests, hardlinked blobs) for
+    /// experiments, or remove one
+    Sandbox(SandboxArgs),
+    /// Find orphaned GGUF blobs and synthesize manifests for them under `recovered/`,
+    /// and put back pinned manifests that were removed
+    Recover(RecoverArgs),
+    /// List every blob with its content type (sniffed from the first bytes) and whether
+    /// a manifest references it
+    ListBlobs(ListBlobsArgs),
+    /// Check that every manifest parses and, given a digest index, is unchanged;
+    /// exit 1 on any problem
+    VerifyManifests(VerifyManifestsArgs),
+    /// Show one model: layers, sizes, and blob paths
+    Show(ShowArgs),
+    /// Where each model came from: registry, official library or third-party namespace,
+    /// signature layers, and source annotations
+    Provenance(ProvenanceArgs),
+    /// Re-hash every blob the models reference and compare it with its digest, or with
+    /// --signatures check their signed attestations (DSSE / in-toto, ed25519) against a
+    /// trust store; exit 1 unless everything checked passes
+    Verify(VerifyArgs),
+    /// Check the store for names that only differ by case (which mis-resolve on
+    /// macOS and Windows filesystems) and describe the Ollama directory (identity key,
+    /// prompt history); exit 1 if any names collide
+    Doctor(DoctorArgs),
+    /// Time scans, GC marking, and manifest loading on the store, or on a generated
+    /// store of a given size
+    Bench(BenchArgs),
+    /// Hash every manifest and referenced blob into a Merkle-style seal (per-model
+    /// roots and a store root), or check the store against one
+    Seal(SealArgs),
+    /// Copy models with their blobs, a seal, and install notes into a self-contained
+    /// directory for offline machines, or merge such a bundle into this store
+    Bundle(BundleArgs),
+    /// Estimate how much data models share below the blob level (content-defined
+    /// chunks), to judge whether dedup or delta transfer is worth it
+    ChunkStats(ChunkStatsArgs),
+    /// Serve the store over HTTP so other machines can fetch blobs from it
+    /// (`GET /blobs/<digest>` with range requests, `GET /models`)
+    Serve(ServeArgs),
+    /// Serve the store like `serve` and advertise it to the LAN over mDNS
+    #[cfg(feature = "peer")]
+    Peer(PeerArgs),
+    /// Fetch a model's manifest and missing blobs from the nearest LAN peer,
+    /// verifying each blob's digest before it is moved into place.
+    ///
+    /// Peers are not authenticated: blobs are checked against the manifest, but the
+    /// manifest itself is only as trustworthy as the peers. It is accepted only when
+    /// every peer that answered serves the same bytes; use --peer to trust one host.
+    #[cfg(feature = "peer")]
+    PullFromPeer(PullFromPeerArgs),
+    /// Upload blobs (keyed by digest, skipping ones already there) and manifests to
+    /// S3-compatible object storage or an rclone remote
+    Backup(BackupArgs),
+    /// Download manifests and missing blobs from a `backup`, verifying every blob
+    Restore(RestoreArgs),
+    /// Roll a manifest back to a copy kept under `manifests/.bak/` when it was last
+    /// overwritten, or list those copies
+    RestoreManifest(RestoreManifestArgs),
+    /// Change default parameters (`temperature=0.2`, `num_ctx=8192`, `stop=<|eot|>`) by
+    /// rewriting the model's params layer, without `ollama create`
+    SetParam(SetParamArgs),
+    /// Replace (or add) a model's chat template from a file, e.g. to fix a broken one
+    /// offline; check the result with `show --template`
+    SetTemplate(SetTemplateArgs),
+    /// Replace, add, or clear a model's system prompt
+    SetSystem(SetSystemArgs),
+    /// Write a model's manifest without some layer types (license, messages, system,
+    /// ...), in place or as a new tag
+    StripLayer(StripLayerArgs),
+    /// Copy a model's manifest under a new tag (or name) that shares all its blobs, e.g.
+    /// to snapshot it before `set-param` / `set-template`
+    Clone(CloneArgs),
+    /// Pin a model so GC keeps its blobs even if the manifest is removed; with
+    /// `--link`, also keep a hardlink of the manifest that `recover` can put back
+    Pin(PinArgs),
+    /// Show which models and pins reference a blob, given its digest or a unique prefix
+    /// of it (`9f3a1`, `sha256:9f3a1`)
+    Owners(OwnersArgs),
+    /// Write a blob's bytes to stdout, by digest (or unique prefix) or as a model's
+    /// layer, e.g. `cat-blob llama3.1 --layer template`
+    CatBlob(CatBlobArgs),
+    /// Write a Markdown model card: provenance, size breakdown, GGUF metadata, params,
+    /// template, system prompt, and license text
+    Card(CardArgs),
+    /// Sign an inventory of the store (a seal of every manifest and blob) with the key
+    /// from the config file, or check an inventory's signature and the store against it
+    #[cfg(feature = "signatures")]
+    Inventory(InventoryArgs),
+    /// Bring the store in line with a desired-state file of required, allowed, and
+    /// banned models (pull missing ones, remove banned ones), or with `--check` only
+    /// compare; exit 1 if a required model is missing or a banned one installed, 3 if
+    /// the only drift is extra models
+    Apply(ApplyArgs),
+    /// Print flat key-value JSON about the store (model and blob counts, bytes, path,
+    /// and whether each listed model is installed) for Ansible facts or a Terraform
+    /// `external` data source
+    Facts(FactsArgs),
+    /// Write a model out for another tool: a Docker build context that bakes it into an
+    /// Ollama image, or a Nix expression that fetches its blobs by hash
+    Export(ExportArgs),
+    /// Estimate from GGUF metadata which installed models fit fully in a given amount of
+    /// VRAM, which need partial CPU offload, and which won't run on the GPU at all
+    Fit(FitArgs),
+    /// Find tags of one model that are different quantizations of the same parameter
+    /// count and remove all but one (manifests are backed up first)
+    DedupeTags(DedupeTagsArgs),
+    /// Mount a read-only filesystem of models by name (`llama3:8b.gguf` and a
+    /// `llama3:8b/` folder with its template, system prompt, and params) until Ctrl-C
+    #[cfg(all(feature = "fuse", unix))]
+    Mount(MountArgs),
+    /// Print shell exports of a model's paths (`OFF_MODEL_PATH`, `OFF_MODEL_TEMPLATE`,
+    /// ...) for `eval "$(ollama-file-find env mistral:7b)"`
+    Env(EnvArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ListArgs {
     /// Emit plain text (just model names) instead of JSON
     #[arg(long)]
     pub plain: bool,
 
-    /// Include hidden tags (those beginning with '.')
+    /// One line of totals instead of the models (`14 models · 162.3 GB (121.7 GB
+    /// unique) · 2 broken · 3.1 GB orphaned`), for MOTD scripts and status bars
+    #[arg(long, conflicts_with_all = [
+        "plain", "verbose", "format", "output", "canonical", "license_contains",
+        "only_broken", "only_ok",
+    ])]
+    pub summary: bool,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+
+    /// Show layer digests, sizes, total size, timestamps,
+    /// and blob paths (also prints scan statistics to stderr)
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Print scan statistics (manifests seen, parsed, hidden, errors, blobs stat'ed,
+    /// elapsed time) to stderr
     #[arg(long)]
+    pub timings: bool,
+
+    /// Output format (default: inferred from --output's extension, else JSON)
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Print the NAME, ID, SIZE, MODIFIED table of `ollama list` (same as `--format table`)
+    #[arg(long, conflicts_with = "format")]
+    pub table: bool,
+
+    /// Write to this file atomically (temp file + rename) instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Byte-identical output for identical stores: sorted keys and arrays, no timestamps
+    #[arg(long)]
+    pub canonical: bool,
+
+    /// Omit fields that change without content changes (mtime)
+    #[arg(long, alias = "no-mtime")]
+    pub stable_fields: bool,
+
+    /// Only models whose license text contains this (case-insensitive)
+    #[arg(long, value_name = "TEXT")]
+    pub license_contains: Option<String>,
+
+    /// Only models with a missing, empty or wrong-sized blob
+    #[arg(long, conflicts_with = "only_ok")]
+    pub only_broken: bool,
+
+    /// Only models whose blobs are all present (and the right size, where known)
+    #[arg(long)]
+    pub only_ok: bool,
+
+    /// Read manifests damaged by a crash (byte order mark, comments, stray or missing
+    /// commas, trailing garbage) and report what was repaired
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// Name order: `natural` compares numbers by value, so llama3.2 comes before llama3.10
+    #[arg(long, value_enum, default_value_t = Collate::Ascii)]
+    pub collate: Collate,
+
+    /// Ask the Ollama server(s) which models are loaded (`/api/ps`) and add `running`,
+    /// the reporting endpoints, and memory and VRAM footprint to each model
+    #[cfg(feature = "network")]
+    #[arg(long)]
+    pub running: bool,
+
+    /// Keep running and print the listing again whenever it changes (models added,
+    /// removed, retagged or re-pulled); with --output, rewrite the file instead
+    #[arg(long)]
+    pub watch: bool,
+
+    /// With --watch, poll for changes at this interval (e.g. `30s`) instead of using
+    /// native notifications; chosen automatically for network mounts
+    #[arg(long, requires = "watch", value_parser = crate::format::parse_duration)]
+    pub poll: Option<Duration>,
+}
+
+/// Hidden-tag selection shared by the commands that scan manifests.
+#[derive(clap::Args, Debug, Clone, Copy)]
+pub(crate) struct HiddenArgs {
+    /// Include hidden tags (those beginning with '.')
+    #[arg(long, conflicts_with = "only_hidden")]
     pub include_hidden: bool,
 
-    /// Show layer digests, sizes, total size, timestamps,
-    /// and blob paths
+    /// Only hidden tags, e.g. to see what is staged under a '.' tag
     #[arg(long)]
-    pub verbose: bool,
+    pub only_hidden: bool,
+}
 
-    /// Root of models directory (overrides env + fallback)
+impl HiddenArgs {
+    pub(crate) fn policy(self) -> HiddenPolicy {
+        match (self.include_hidden, self.only_hidden) {
+            (_, true) => HiddenPolicy::Only,
+            (true, false) => HiddenPolicy::Include,
+            (false, false) => HiddenPolicy::Exclude,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Collate {
+    /// Byte-wise
+    Ascii,
+    /// Digit runs compared as numbers
+    Natural,
+}
+
+impl From<Collate> for ollama_file_find::Collation {
+    fn from(collate: Collate) -> Self {
+        match collate {
+            Collate::Ascii => ollama_file_find::Collation::Ascii,
+            Collate::Natural => ollama_file_find::Collation::Natural,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct DuArgs {
+    /// Emit a human readable table instead of JSON
     #[arg(long)]
-    pub models_dir: Option<PathBuf>,
+    pub plain: bool,
+
+    /// Also report apparent vs allocated size for every blob
+    #[arg(long)]
+    pub blobs: bool,
+
+    /// Estimate how well the blobs would compress (zstd over sampled 128 KiB blocks,
+    /// like filesystem compression) before enabling compression on the blobs volume
+    #[cfg(feature = "zstd")]
+    #[arg(long)]
+    pub compressibility: bool,
+
+    /// zstd level for `--compressibility`
+    #[cfg(feature = "zstd")]
+    #[arg(
+        long,
+        default_value_t = 3,
+        requires = "compressibility",
+        value_parser = clap::value_parser!(i32).range(1..=22)
+    )]
+    pub zstd_level: i32,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct DaemonArgs {
+    /// Socket path (a named pipe path such as `\\.\pipe\name` on Windows)
+    #[arg(long)]
+    pub socket: Option<PathBuf>,
+
+    /// Poll for changes at this interval (e.g. `30s`) instead of using native
+    /// notifications; chosen automatically for network mounts
+    #[arg(long, value_parser = crate::format::parse_duration)]
+    pub poll: Option<Duration>,
+
+    /// Append observed store changes to this JSONL file
+    /// (default: `<data dir>/ollama-file-find/events/<hash of the models dir>.jsonl`)
+    #[arg(long)]
+    pub event_log: Option<PathBuf>,
+
+    /// Do not record events to disk
+    #[arg(long, conflicts_with = "event_log")]
+    pub no_event_log: bool,
+
+    /// Also serve the gRPC API on this address (e.g. `127.0.0.1:50051`)
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    pub grpc: Option<std::net::SocketAddr>,
+
+    /// Instead of running, write a service that runs the daemon with these options at
+    /// login (systemd user unit, launchd agent, or Task Scheduler task) and print the
+    /// command that enables it
+    #[arg(long)]
+    pub install_service: bool,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct EventsArgs {
+    /// Only show events newer than this (e.g. `90m`, `24h`, `7d`)
+    #[arg(long, value_parser = crate::format::parse_duration)]
+    pub since: Option<Duration>,
+
+    /// Event log to read (default: the daemon's default log for the store)
+    #[arg(long)]
+    pub log: Option<PathBuf>,
+
+    /// One event per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ExportStaticArgs {
+    /// Output directory (created if missing)
+    pub dir: PathBuf,
+
+    /// Omit timestamps (model mtimes and the index's `generated_at`)
+    #[arg(long, alias = "no-mtime")]
+    pub stable_fields: bool,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct EstimateArgs {
+    /// Model to estimate, e.g. `llama3.3:70b`
+    pub model: String,
+
+    /// Print a one-line summary instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CanFitArgs {
+    /// A byte count (`40G`, `512MiB`) or a model name whose pull size is estimated
+    pub target: String,
+
+    /// Extra headroom that must remain free afterwards
+    #[arg(long, default_value = "0", value_parser = crate::format::parse_bytes)]
+    pub reserve: u64,
+
+    /// Only set the exit code
+    #[arg(long, short)]
+    pub quiet: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CompleteNamesArgs {
+    /// Only print names starting with this
+    pub prefix: Option<String>,
+
+    /// Daemon socket to ask first (default: the daemon's default socket)
+    #[arg(long)]
+    pub socket: Option<PathBuf>,
+
+    /// Always walk the manifests directory instead of asking a running daemon
+    #[arg(long)]
+    pub no_daemon: bool,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct QuotaArgs {
+    #[command(subcommand)]
+    pub action: Option<QuotaAction>,
+
+    /// One namespace per line instead of JSON
+    #[arg(long, global = true)]
+    pub plain: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum QuotaAction {
+    /// Report only namespaces over their limit; exit 1 if there are any
+    Check,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct PolicyArgs {
+    #[command(subcommand)]
+    pub action: PolicyAction,
+
+    /// Human readable verdict instead of JSON
+    #[arg(long, global = true)]
+    pub plain: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum PolicyAction {
+    /// Check one model (installed metadata first, else the registry); exit 1 if denied
+    Check(PolicyCheckArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct PolicyCheckArgs {
+    /// Model to check, e.g. `llama3.3:70b`
+    pub model: String,
+
+    /// Never contact the registry; fail if the model is not installed
+    #[arg(long)]
+    pub local: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ComplianceArgs {
+    /// Human readable report instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SearchArgs {
+    /// Search terms, e.g. `chatml 128k`
+    #[arg(required = true)]
+    pub query: Vec<String>,
+
+    /// One `name<TAB>matched fields` line per hit instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+
+    #[command(flatten)]
+    pub hidden: HiddenArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CompareArgs {
+    pub a: String,
+    pub b: String,
+
+    /// Human readable summary (with unified diffs) instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+
+    /// Only print unified diffs of the template, system, and params layers
+    #[arg(long, conflicts_with = "plain")]
+    pub diff: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct OrphansArgs {
+    /// Mark orphans modified more recently than this as retained
+    /// (default: `[retention] keep_orphans_newer_than` from the config file)
+    #[arg(long, value_parser = crate::format::parse_duration)]
+    pub keep_newer: Option<Duration>,
+
+    /// List every model an orphan used to belong to instead of the first few
+    #[arg(long)]
+    pub full_refs: bool,
+
+    /// One orphan per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct PruneArgs {
+    /// Keep orphans modified more recently than this, e.g. `7d`
+    /// (default: `[retention] keep_orphans_newer_than` from the config file)
+    #[arg(long, value_parser = crate::format::parse_duration)]
+    pub keep_newer: Option<Duration>,
+
+    /// Delete what is listed; without this, prune only lists it and the space it
+    /// would free
+    #[arg(long, visible_alias = "yes", short = 'y')]
+    pub execute: bool,
+
+    /// Accepted for symmetry with other tools; listing is already the default
+    #[arg(long, short = 'n', hide = true, conflicts_with = "execute")]
+    pub dry_run: bool,
+
+    /// Also delete blobs only manifest backups (`.bak`) still reference, which leaves
+    /// those copies unrestorable; pinned blobs are kept until the pin is removed
+    #[arg(long)]
+    pub include_held: bool,
+
+    /// Also delete partial downloads (`sha256-<hex>-partial*`) left by interrupted pulls;
+    /// a pull resumes from these, so only ones older than --partials-older-than go
+    #[arg(long)]
+    pub partials: bool,
+
+    /// Age since the last write after which a partial download counts as abandoned
+    #[arg(long, default_value = "1d", requires = "partials", value_parser = crate::format::parse_duration)]
+    pub partials_older_than: Duration,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct MaintainArgs {
+    /// Run only if this cron schedule (`minute hour day month weekday`, UTC) has come
+    /// due since the last run, e.g. `'0 3 * * *'`
+    #[arg(long, value_name = "CRON", conflicts_with = "if_due")]
+    pub every: Option<crate::cron::Schedule>,
+
+    /// Run only if the last run was at least this long ago, e.g. `1d`
+    #[arg(long, value_parser = crate::format::parse_duration)]
+    pub if_due: Option<Duration>,
+
+    /// Where the last run is recorded
+    /// (default: `<data dir>/ollama-file-find/maintain.json`)
+    #[arg(long)]
+    pub state: Option<PathBuf>,
+
+    /// Skip re-hashing referenced blobs
+    #[arg(long)]
+    pub no_scrub: bool,
+
+    /// Continue an interrupted scrub: blobs it already checked are skipped if their
+    /// size and modification time are unchanged
+    #[arg(long, conflicts_with = "no_scrub")]
+    pub resume: bool,
+
+    /// Blobs hashed at once on each disk the store's blobs are on
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs_per_device: u16,
+
+    /// Report prunable orphans without deleting them
+    #[arg(long, short = 'n')]
+    pub dry_run: bool,
+
+    /// Short summary instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SandboxArgs {
+    #[command(subcommand)]
+    pub action: SandboxAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum SandboxAction {
+    /// Create a sandbox and print the `OLLAMA_MODELS` setting that selects it
+    Create(SandboxCreateArgs),
+    /// Delete a sandbox (default: the one `OLLAMA_MODELS` points at)
+    Destroy(SandboxDestroyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SandboxCreateArgs {
+    /// Models to include (default: all)
+    pub models: Vec<String>,
+
+    /// Where to create it (default: a new directory under the temp dir); use the
+    /// store's filesystem so blobs can be hardlinked
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SandboxDestroyArgs {
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct RecoverArgs {
+    /// Write the manifests and put back removed pinned ones (default: only report what
+    /// would be recovered)
+    #[arg(long)]
+    pub write: bool,
+
+    /// One line per blob instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ListBlobsArgs {
+    /// Only blobs of this type (gguf, safetensors, json, text, zip, empty, unknown)
+    #[arg(long)]
+    pub class: Option<ollama_file_find::BlobClass>,
+
+    /// List every referencing model instead of the first few
+    #[arg(long)]
+    pub full_refs: bool,
+
+    /// One blob per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SealArgs {
+    #[command(subcommand)]
+    pub action: Option<SealAction>,
+
+    /// Write the seal to this file atomically instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// One change per line instead of JSON
+    #[arg(long, global = true)]
+    pub plain: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum SealAction {
+    /// Report every change since the seal was made; exit 1 if there are any
+    Verify(SealVerifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SealVerifyArgs {
+    /// Seal file written by `seal`
+    pub seal: PathBuf,
+
+    /// Store root published with the bundle; a seal with another root is rejected
+    #[arg(long, value_name = "DIGEST")]
+    pub expect_root: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub(crate) struct BundleArgs {
+    #[command(subcommand)]
+    pub action: Option<BundleAction>,
+
+    /// Models to bundle, e.g. `llama3.3:70b`
+    #[arg(required = true)]
+    pub models: Vec<String>,
+
+    /// Bundle directory to create (must be empty or absent)
+    #[arg(short, long, required = true)]
+    pub output: Option<PathBuf>,
+
+    /// Leave out blobs the target already has, per its `list --verbose` JSON or the
+    /// seal of its store
+    #[arg(long, value_name = "INVENTORY")]
+    pub delta: Option<PathBuf>,
+
+    /// Continue an interrupted bundle in the same directory: blobs it already copied
+    /// are kept if their size is unchanged
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Blobs copied at once from each disk the store's blobs are on
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs_per_device: u16,
+
+    /// One manifest per line instead of JSON (`install`)
+    #[arg(long, global = true)]
+    pub plain: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum BundleAction {
+    /// Check a bundle against its seal and merge it into the models directory:
+    /// missing blobs first, then manifests
+    Install(BundleInstallArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct BundleInstallArgs {
+    /// Directory written by `bundle`
+    pub bundle: PathBuf,
+
+    /// Store root published with the bundle; a bundle with another root is rejected
+    #[arg(long, value_name = "DIGEST")]
+    pub expect_root: Option<String>,
+
+    /// Replace installed manifests whose content differs from the bundle's
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ChunkStatsArgs {
+    /// Models to compare (default: all)
+    pub models: Vec<String>,
+
+    /// Fingerprint one chunk in N; higher is less precise but uses less memory (every
+    /// blob is still read in full)
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u64).range(1..))]
+    pub sample: u64,
+
+    /// How many of the most similar model pairs to show
+    #[arg(long, default_value_t = 10)]
+    pub top: usize,
+
+    /// Summary and one pair per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ServeArgs {
+    /// Address to listen on; use `0.0.0.0:<port>` to serve the LAN
+    #[arg(long, default_value = "127.0.0.1:11435")]
+    pub listen: std::net::SocketAddr,
+
+    /// Requests handled at once
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    pub threads: u16,
+
+    /// Include each model's `manifest_path` in `GET /models`; left out by default, since
+    /// it reveals the server's directory layout to every client
+    #[arg(long)]
+    pub expose_paths: bool,
+}
+
+#[cfg(feature = "peer")]
+#[derive(clap::Args, Debug)]
+pub(crate) struct PeerArgs {
+    /// Address to listen on (and advertise)
+    #[arg(long, default_value = "0.0.0.0:11435")]
+    pub listen: std::net::SocketAddr,
+
+    /// Requests handled at once
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+    pub threads: u16,
+}
+
+#[cfg(feature = "peer")]
+#[derive(clap::Args, Debug)]
+pub(crate) struct PullFromPeerArgs {
+    /// Model to fetch (e.g. `llama3:8b`)
+    pub model: String,
+
+    /// Fetch from this `host:port` instead of discovering peers, trusting its manifest
+    #[arg(long)]
+    pub peer: Option<std::net::SocketAddr>,
+
+    /// How long to listen for peer announcements
+    #[arg(long, default_value = "2s", value_parser = crate::format::parse_duration)]
+    pub wait: Duration,
+
+    /// One line summary instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+/// Where `backup` writes to and `restore` reads from; exactly one is required.
+#[derive(clap::Args, Debug)]
+#[group(required = true, multiple = false)]
+pub(crate) struct BackupTarget {
+    /// Bucket and key prefix, e.g. `s3://models-backup/workstation`
+    #[cfg(feature = "s3")]
+    #[arg(long, value_name = "s3://BUCKET/PREFIX")]
+    pub s3: Option<crate::commands::backup::S3Url>,
+
+    /// Any remote rclone is configured for, e.g. `gdrive:ollama` (runs `rclone`)
+    #[arg(long, value_name = "REMOTE:PATH")]
+    pub rclone: Option<String>,
+}
+
+#[cfg(feature = "s3")]
+#[derive(clap::Args, Debug)]
+pub(crate) struct S3Options {
+    /// Endpoint of an S3-compatible service (MinIO, R2, …; default: `AWS_ENDPOINT_URL`);
+    /// uses path-style requests
+    #[arg(long, conflicts_with = "rclone")]
+    pub endpoint: Option<String>,
+
+    /// Region (default: `AWS_REGION`, else us-east-1)
+    #[arg(long, conflicts_with = "rclone")]
+    pub region: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct BackupArgs {
+    /// Models to back up (default: all)
+    pub models: Vec<String>,
+
+    #[command(flatten)]
+    pub target: BackupTarget,
+
+    #[cfg(feature = "s3")]
+    #[command(flatten)]
+    pub s3_options: S3Options,
+
+    /// One manifest per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct RestoreArgs {
+    /// Models to restore (default: every manifest in the backup)
+    pub models: Vec<String>,
+
+    #[command(flatten)]
+    pub target: BackupTarget,
+
+    #[cfg(feature = "s3")]
+    #[command(flatten)]
+    pub s3_options: S3Options,
+
+    /// Replace local manifests whose content differs from the backup
+    #[arg(long)]
+    pub force: bool,
+
+    /// Continue an interrupted restore: blobs it already downloaded and verified are
+    /// kept if their size is unchanged
+    #[arg(long)]
+    pub resume: bool,
+
+    /// One manifest per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct RestoreManifestArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// Newest copy taken at or before this time (seconds since the epoch, as `--list`
+    /// shows them; default: the newest copy)
+    #[arg(long, value_name = "TS", conflicts_with = "list")]
+    pub at: Option<u64>,
+
+    /// List the kept copies, oldest first, instead of restoring
+    #[arg(long)]
+    pub list: bool,
+
+    /// Tab-separated lines instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SetParamArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// `KEY=VALUE` pairs; numbers and booleans keep their type, `stop` may repeat
+    #[arg(value_name = "KEY=VALUE", required_unless_present = "unset")]
+    pub params: Vec<String>,
+
+    /// Remove a parameter (repeatable)
+    #[arg(long, value_name = "KEY")]
+    pub unset: Vec<String>,
+
+    /// Delete the replaced params blob if no other manifest uses it
+    #[arg(long)]
+    pub gc: bool,
+
+    /// One summary line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SetTemplateArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// Go template file, as in a Modelfile `TEMPLATE` (`-` for stdin)
+    #[arg(long)]
+    pub file: PathBuf,
+
+    /// Delete the replaced template blob if no other manifest uses it
+    #[arg(long)]
+    pub gc: bool,
+
+    /// One summary line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct SetSystemArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// The new system prompt
+    #[arg(required_unless_present_any = ["file", "clear"], conflicts_with_all = ["file", "clear"])]
+    pub prompt: Option<String>,
+
+    /// Read the prompt from a file (`-` for stdin)
+    #[arg(long, conflicts_with = "clear")]
+    pub file: Option<PathBuf>,
+
+    /// Remove the system layer
+    #[arg(long)]
+    pub clear: bool,
+
+    /// Delete the replaced system blob if no other manifest uses it
+    #[arg(long)]
+    pub gc: bool,
+
+    /// One summary line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct StripLayerArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// Layer types to drop (repeat or comma-separate)
+    #[arg(
+        long = "type",
+        value_name = "TYPE",
+        value_enum,
+        value_delimiter = ',',
+        required = true
+    )]
+    pub kinds: Vec<StripKind>,
+
+    /// Write the result as this tag of the same model and leave the original as is
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    /// Replace the `--tag` target if it already exists
+    #[arg(long, requires = "tag")]
+    pub force: bool,
+
+    /// Delete the stripped blobs if no other manifest uses them
+    #[arg(long, conflicts_with = "tag")]
+    pub gc: bool,
+
+    /// Human readable summary instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+/// Layer types `strip-layer` may drop; weights and config are always kept.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StripKind {
+    License,
+    Messages,
+    System,
+    Template,
+    Params,
+    Adapter,
+    Projector,
+}
+
+impl From<StripKind> for LayerKind {
+    fn from(kind: StripKind) -> Self {
+        match kind {
+            StripKind::License => LayerKind::License,
+            StripKind::Messages => LayerKind::Messages,
+            StripKind::System => LayerKind::System,
+            StripKind::Template => LayerKind::Template,
+            StripKind::Params => LayerKind::Params,
+            StripKind::Adapter => LayerKind::Adapter,
+            StripKind::Projector => LayerKind::Projector,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CloneArgs {
+    /// Model to copy (`llama3.1:8b`)
+    pub source: String,
+
+    /// New tag of the same model (`8b-backup`), or a full name (`me/llama3.1:tuned`)
+    pub target: String,
+
+    /// Replace the target if it already exists (the old manifest is backed up)
+    #[arg(long)]
+    pub force: bool,
+
+    /// One summary line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct PinArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    #[arg(required_unless_present = "list")]
+    pub model: Option<String>,
+
+    /// Also hardlink the manifest under `manifests/.pinned/`
+    #[arg(long, conflicts_with = "remove")]
+    pub link: bool,
+
+    /// Unpin the model (and drop its linked manifest)
+    #[arg(long)]
+    pub remove: bool,
+
+    /// List pinned models instead
+    #[arg(long, conflicts_with_all = ["model", "link", "remove"])]
+    pub list: bool,
+
+    /// Tab-separated lines instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct OwnersArgs {
+    /// Blob digest, or a hex prefix of at least 4 characters matching one blob
+    pub digest: String,
+
+    /// Tab-separated lines instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CatBlobArgs {
+    /// Blob digest or unique prefix; with `--layer`, a model name
+    pub target: String,
+
+    /// Write the model's layer(s) of this type, in manifest order
+    #[arg(long, value_name = "TYPE", value_enum)]
+    pub layer: Option<CatLayer>,
+
+    /// Hash the blob while streaming and exit 1 if it doesn't match its digest (the
+    /// bytes are already written by then)
+    #[arg(long)]
+    pub verify: bool,
+}
+
+/// Layer types `cat-blob --layer` can select.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CatLayer {
+    Weights,
+    Adapter,
+    Projector,
+    Template,
+    System,
+    Params,
+    Messages,
+    License,
+    Config,
+}
+
+impl From<CatLayer> for LayerKind {
+    fn from(kind: CatLayer) -> Self {
+        match kind {
+            CatLayer::Weights => LayerKind::Weights,
+            CatLayer::Adapter => LayerKind::Adapter,
+            CatLayer::Projector => LayerKind::Projector,
+            CatLayer::Template => LayerKind::Template,
+            CatLayer::System => LayerKind::System,
+            CatLayer::Params => LayerKind::Params,
+            CatLayer::Messages => LayerKind::Messages,
+            CatLayer::License => LayerKind::License,
+            CatLayer::Config => LayerKind::Config,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CardArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// Write the card to this file instead of stdout, e.g. `MODELCARD.md`
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct InventoryArgs {
+    #[command(subcommand)]
+    pub action: InventoryAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum InventoryAction {
+    /// Seal the store into an inventory file and sign it with `[inventory] signing_key`
+    Sign(InventorySignArgs),
+    /// Check the inventory's signature against `[inventory] trust_store`, then the store
+    /// against the inventory; exit 1 on a bad signature or any change
+    Verify(InventoryVerifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct InventorySignArgs {
+    /// Signature file to write, e.g. `inventory.json.sig`
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Inventory file to write (default: `--output` without `.sig`)
+    #[arg(long)]
+    pub inventory: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct InventoryVerifyArgs {
+    /// Signature file written by `inventory sign`
+    pub signature: PathBuf,
+
+    /// Inventory the signature is for (default: the signature path without `.sig`)
+    #[arg(long)]
+    pub inventory: Option<PathBuf>,
+
+    /// One line per finding instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ApplyArgs {
+    /// Desired-state TOML file (`required`, `allowed`, and `banned` lists) to reconcile
+    /// the store with
+    #[arg(required_unless_present = "check", conflicts_with = "check")]
+    pub file: Option<PathBuf>,
+
+    /// Only compare the store to this desired-state file; nothing is installed or
+    /// removed
+    #[arg(long, value_name = "FILE")]
+    pub check: Option<PathBuf>,
+
+    /// Also remove extra models (neither required nor allowed), not just banned ones
+    #[arg(long)]
+    pub remove_extra: bool,
+
+    /// How to pull missing models: through the server at `--api-url`, or by running
+    /// `ollama pull`
+    #[arg(long, value_enum, default_value_t)]
+    pub pull_via: PullVia,
+
+    /// One model per line (`name<TAB>missing|banned|extra|pulled|removed|failed`)
+    /// instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct FactsArgs {
+    /// Models to report presence for, as `<prefix>_has_<name>` booleans (the
+    /// normalized name with every other character than a-z and 0-9 turned into `_`)
+    pub models: Vec<String>,
+
+    /// Prefix of every key
+    #[arg(long, default_value = "ollama")]
+    pub prefix: String,
+
+    /// Render every value as a string, as Terraform's `external` data source requires
+    #[arg(long)]
+    pub strings: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ExportArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    #[command(flatten)]
+    pub target: ExportTarget,
+}
+
+/// What `export` writes; exactly one is required.
+#[derive(clap::Args, Debug)]
+#[group(required = true, multiple = false)]
+pub(crate) struct ExportTarget {
+    /// Write a Dockerfile and the model's blobs (hardlinked when possible) to this
+    /// empty directory, for `docker build`
+    #[arg(long, value_name = "DIR")]
+    pub docker_context: Option<PathBuf>,
+
+    /// Print a Nix expression that builds a models directory from fixed-output fetches of
+    /// the blobs (`--nix json`: the URLs, paths, and hashes as JSON)
+    #[cfg(feature = "network")]
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "expr")]
+    pub nix: Option<NixFormat>,
+}
+
+#[cfg(feature = "network")]
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NixFormat {
+    /// A `callPackage`-able expression
+    Expr,
+    /// The same data as JSON, for `builtins.fromJSON`
+    Json,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct FitArgs {
+    /// GPU memory to fit in, e.g. `24G` (add up the cards for a multi-GPU setup)
+    #[arg(long, value_parser = crate::format::parse_bytes)]
+    pub vram: u64,
+
+    /// Context window to size the KV cache for (default: each model's `num_ctx`
+    /// parameter, else 4096)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub ctx: Option<u64>,
+
+    /// One model per line (`name<TAB>fit<TAB>memory needed`) instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct DedupeTagsArgs {
+    /// Which tag of each group to keep
+    #[arg(long, value_enum, default_value_t = Keep::HighestQuant)]
+    pub keep: Keep,
+
+    /// Remove the tags listed for removal; without this, dedupe-tags only shows the
+    /// groups and what it would remove
+    #[arg(long, visible_alias = "yes", short = 'y')]
+    pub execute: bool,
+
+    /// Human readable groups instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Keep {
+    /// The most bits per weight (`fp16` over `q8_0` over `q4_K_M`)
+    HighestQuant,
+    /// The most recently pulled or created
+    Latest,
+    /// The least bytes on disk
+    Smallest,
+}
+
+impl From<Keep> for ollama_file_find::KeepPolicy {
+    fn from(keep: Keep) -> Self {
+        match keep {
+            Keep::HighestQuant => ollama_file_find::KeepPolicy::HighestQuant,
+            Keep::Latest => ollama_file_find::KeepPolicy::Latest,
+            Keep::Smallest => ollama_file_find::KeepPolicy::Smallest,
+        }
+    }
+}
+
+#[cfg(all(feature = "fuse", unix))]
+#[derive(clap::Args, Debug)]
+pub(crate) struct MountArgs {
+    /// Empty directory to mount on
+    pub dir: PathBuf,
+
+    /// Let other users (and root) read the mount; needs `user_allow_other` in
+    /// `/etc/fuse.conf` unless run as root
+    #[arg(long)]
+    pub allow_other: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct EnvArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// Syntax of the exports (default: `fish` if `$SHELL` is fish, `powershell` on
+    /// Windows, else `posix` for sh, bash, and zsh)
+    #[arg(long, value_enum)]
+    pub shell: Option<Shell>,
+
+    /// Also `cd` into the blobs directory
+    #[arg(long)]
+    pub cd: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shell {
+    /// `export VAR='value'` (sh, bash, zsh)
+    Posix,
+    /// `set -gx VAR 'value'`
+    Fish,
+    /// `$env:VAR = 'value'`
+    Powershell,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PullVia {
+    /// `POST /api/pull` (needs the `network` feature)
+    Api,
+    /// `ollama pull <model>`, with `OLLAMA_HOST` set to `--api-url`
+    Cli,
+}
+
+impl Default for PullVia {
+    fn default() -> Self {
+        if cfg!(feature = "network") {
+            PullVia::Api
+        } else {
+            PullVia::Cli
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct VerifyManifestsArgs {
+    /// Compare manifest digests against this index (written by `--write-index`)
+    #[arg(long)]
+    pub index: Option<PathBuf>,
+
+    /// Record the current manifest digests to this file
+    #[arg(long)]
+    pub write_index: Option<PathBuf>,
+
+    /// One problem per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ShowArgs {
+    /// Model name as `ollama` takes it (`llama3.1`, `llama3.1:8b`, `ns/model:tag`)
+    pub model: String,
+
+    /// Print the API call that loads the model into memory and keeps it there for
+    /// `--keep-alive`, instead of the details (one call per `--api-url`)
+    #[arg(long, conflicts_with_all = ["template", "system"])]
+    pub warmup: bool,
+
+    /// Print the model's chat template as stored, instead of the details
+    #[arg(long, conflicts_with = "system")]
+    pub template: bool,
+
+    /// Print the model's system prompt as stored, instead of the details
+    #[arg(long, conflicts_with = "gguf")]
+    pub system: bool,
+
+    /// Print the GGUF header and metadata of the model's weights, instead of the
+    /// details; only the start of the file is read
+    #[arg(long, conflicts_with_all = ["template", "warmup"])]
+    pub gguf: bool,
+
+    /// Give up on `--gguf` if the metadata runs past this much of the file
+    #[arg(long, value_name = "SIZE", default_value = "256MiB", value_parser = crate::format::parse_bytes, requires = "gguf")]
+    pub gguf_limit: u64,
+
+    /// How long the server keeps the model loaded after warm-up (`10m`, `24h`, or `-1`
+    /// for until it is stopped)
+    #[arg(
+        long,
+        default_value = "30m",
+        requires = "warmup",
+        allow_hyphen_values = true
+    )]
+    pub keep_alive: String,
+
+    /// Print the model's ollama.com page instead of the details (official library and
+    /// community namespaces only)
+    #[arg(long, conflicts_with_all = ["warmup", "template", "system", "gguf"])]
+    pub web_url: bool,
+
+    /// With `--web-url`, also fetch the page and print the model's short description
+    #[cfg(feature = "network")]
+    #[arg(long, requires = "web_url")]
+    pub description: bool,
+
+    /// Human readable summary instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ProvenanceArgs {
+    /// Only these models (default: all)
+    pub models: Vec<String>,
+
+    /// Only models not from the official library (community namespaces and other
+    /// registries)
+    #[arg(long)]
+    pub third_party: bool,
+
+    /// Only models without signature or attestation layers
+    #[arg(long)]
+    pub unsigned: bool,
+
+    /// Only models from this registry host
+    #[arg(long, value_name = "HOST")]
+    pub registry: Option<String>,
+
+    /// Also fetch each manifest from its registry and report whether the local
+    /// layers still match what is published
+    #[cfg(feature = "network")]
+    #[arg(long)]
+    pub check_registry: bool,
+
+    /// One model per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct VerifyArgs {
+    /// Only these models (default: all)
+    pub models: Vec<String>,
+
+    /// Verify signatures and attestations (signature layers in the manifest, plus
+    /// any given with `--attestations`) instead of blob checksums
+    #[cfg(feature = "signatures")]
+    #[arg(long, requires = "trust_store")]
+    pub signatures: bool,
+
+    /// Directory of trusted ed25519 public keys (`*.pub` in OpenSSH format, or `*.pem`)
+    #[cfg(feature = "signatures")]
+    #[arg(long, value_name = "DIR", requires = "signatures")]
+    pub trust_store: Option<PathBuf>,
+
+    /// Directory of out-of-band DSSE envelopes (`*.json`, `*.intoto.jsonl`)
+    #[cfg(feature = "signatures")]
+    #[arg(long, value_name = "DIR", requires = "signatures")]
+    pub attestations: Option<PathBuf>,
+
+    /// Blobs hashed at once on each disk the store's blobs are on
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs_per_device: u16,
+
+    /// One blob (or model, with --signatures) per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct DoctorArgs {
+    /// One finding per line instead of JSON
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct BenchArgs {
+    /// Generate a store with this many models instead of timing the real one
+    #[arg(long, value_name = "MODELS")]
+    pub synthetic: Option<usize>,
+
+    /// Layers per generated model (config excluded)
+    #[arg(long, default_value_t = 4, requires = "synthetic")]
+    pub layers: usize,
+
+    /// Generated layers per model shared by every model
+    #[arg(long, default_value_t = 2, requires = "synthetic")]
+    pub shared_layers: usize,
+
+    /// Namespaces to spread generated models over
+    #[arg(long, default_value_t = 8, requires = "synthetic")]
+    pub namespaces: usize,
+
+    /// Size of each generated blob, e.g. `4GiB` (use with --sparse for large sizes)
+    #[arg(long, default_value = "1KiB", value_parser = crate::format::parse_bytes, requires = "synthetic")]
+    pub layer_size: u64,
+
+    /// Create generated blobs as sparse files
+    #[arg(long, requires = "synthetic")]
+    pub sparse: bool,
+
+    /// Generate into this directory and keep it (default: a temporary directory,
+    /// removed afterwards)
+    #[arg(long, requires = "synthetic")]
+    pub dir: Option<PathBuf>,
+
+    /// Timed runs per operation, after one untimed warm-up run
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    pub iterations: u32,
+
+    /// Human readable table instead of JSON
+    #[arg(long)]
+    pub plain: bool,
 }
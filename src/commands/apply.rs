@@ -0,0 +1,352 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    process::{self, ExitCode},
+};
+
+use anyhow::Context;
+use ollama_file_find::{HiddenPolicy, ManifestStore, ModelId};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Result, StorePaths,
+    args::{ApplyArgs, PullVia},
+    config::Config,
+    progress::Progress,
+    removal::{Failed, remove_models},
+};
+
+/// The models a host should have, from a desired-state file.
+///
+/// ```toml
+/// required = ["llama3.1:8b", "nomic-embed-text"]
+/// allowed = ["myteam/*"]
+/// banned = ["*:*q2_k*", "hf.co/*"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DesiredState {
+    /// Models that must be installed
+    required: Vec<String>,
+    /// Patterns for models that may be installed besides the required ones
+    allowed: Vec<String>,
+    /// Patterns for models that must not be installed, required or not
+    banned: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Drift {
+    in_sync: bool,
+    /// Required models that are not installed
+    missing: Vec<String>,
+    /// Installed models matching a banned pattern
+    banned: Vec<String>,
+    /// Installed models that are neither required nor allowed
+    extra: Vec<String>,
+}
+
+/// What reconciling did, followed by the drift that is left.
+#[derive(Serialize)]
+struct Reconciled {
+    pulled: Vec<String>,
+    removed: Vec<String>,
+    failed: Vec<Failed>,
+    #[serde(flatten)]
+    drift: Drift,
+}
+
+/// Exit status when the only drift is extra models (2 is taken by usage errors).
+const EXIT_EXTRA_ONLY: u8 = 3;
+
+pub(crate) fn run(
+    paths: &StorePaths,
+    config: &Config,
+    api_url: &str,
+    args: ApplyArgs,
+) -> Result<ExitCode> {
+    let (file, reconcile) = match (&args.check, &args.file) {
+        (Some(file), _) => (file, false),
+        (None, Some(file)) => (file, true),
+        (None, None) => anyhow::bail!("a desired-state file is required"),
+    };
+    let text = fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+    let desired: DesiredState =
+        toml::from_str(&text).with_context(|| format!("parsing {}", file.display()))?;
+    let required = desired
+        .required
+        .iter()
+        .map(|name| Ok(name.parse::<ModelId>()?.normalize()))
+        .collect::<Result<BTreeSet<String>>>()?;
+    let manifests = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+
+    let drift = compute_drift(&manifests, &desired, &required);
+    if !reconcile {
+        print_drift(&drift, args.plain)?;
+        return Ok(exit_code(&drift, false));
+    }
+
+    let (mut pulled, mut failed) = (Vec::new(), Vec::new());
+    let progress = Progress::start("pull", drift.missing.len(), None);
+    for name in &drift.missing {
+        progress.note(format_args!("Pulling {name}..."));
+        match pull(args.pull_via, api_url, name) {
+            Ok(()) => pulled.push(name.clone()),
+            Err(e) => failed.push(Failed {
+                model: name.clone(),
+                error: format!("{e:#}"),
+            }),
+        }
+        progress.step(name, 0);
+    }
+    progress.finish();
+    let extra: &[String] = if args.remove_extra { &drift.extra } else { &[] };
+    let (removed, not_removed) = remove_models(&manifests, drift.banned.iter().chain(extra));
+    failed.extend(not_removed);
+
+    let report = Reconciled {
+        pulled,
+        removed,
+        failed,
+        drift: compute_drift(&manifests, &desired, &required),
+    };
+    if args.plain {
+        for (names, what) in [(&report.pulled, "pulled"), (&report.removed, "removed")] {
+            for name in names {
+                println!("{name}\t{what}");
+            }
+        }
+        for f in &report.failed {
+            println!("{}\tfailed\t{}", f.model, f.error);
+        }
+        print_drift(&report.drift, true)?;
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(exit_code(&report.drift, !report.failed.is_empty()))
+}
+
+fn compute_drift(
+    manifests: &ManifestStore,
+    desired: &DesiredState,
+    required: &BTreeSet<String>,
+) -> Drift {
+    let (ids, errors) = manifests.ids(HiddenPolicy::Exclude);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let installed: BTreeSet<String> = ids.iter().map(ModelId::normalize).collect();
+    let matches_any = |patterns: &[String], name: &str| patterns.iter().any(|p| matches(p, name));
+
+    let missing: Vec<String> = required.difference(&installed).cloned().collect();
+    let banned: Vec<String> = installed
+        .iter()
+        .filter(|name| matches_any(&desired.banned, name))
+        .cloned()
+        .collect();
+    let extra: Vec<String> = installed
+        .iter()
+        .filter(|name| {
+            !required.contains(*name)
+                && !matches_any(&desired.allowed, name)
+                && !matches_any(&desired.banned, name)
+        })
+        .cloned()
+        .collect();
+    Drift {
+        in_sync: missing.is_empty() && banned.is_empty() && extra.is_empty(),
+        missing,
+        banned,
+        extra,
+    }
+}
+
+fn print_drift(drift: &Drift, plain: bool) -> Result<()> {
+    if plain {
+        for (names, what) in [
+            (&drift.missing, "missing"),
+            (&drift.banned, "banned"),
+            (&drift.extra, "extra"),
+        ] {
+            for name in names {
+                println!("{name}\t{what}");
+            }
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(drift)?);
+    }
+    Ok(())
+}
+
+fn exit_code(drift: &Drift, failed: bool) -> ExitCode {
+    if failed || !drift.missing.is_empty() || !drift.banned.is_empty() {
+        ExitCode::FAILURE
+    } else if !drift.extra.is_empty() {
+        ExitCode::from(EXIT_EXTRA_ONLY)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn pull(via: PullVia, api_url: &str, name: &str) -> Result<()> {
+    match via {
+        #[cfg(feature = "network")]
+        PullVia::Api => Ok(ollama_file_find::api::pull_model(api_url, name)?),
+        #[cfg(not(feature = "network"))]
+        PullVia::Api => anyhow::bail!("built without the `network` feature; use --pull-via cli"),
+        PullVia::Cli => {
+            // The child's progress output goes to stderr so stdout stays parseable.
+            let status = process::Command::new("ollama")
+                .args(["pull", name])
+                .env("OLLAMA_HOST", api_url)
+                .stdout(io::stderr())
+                .status()
+                .context("running `ollama pull`")?;
+            anyhow::ensure!(status.success(), "`ollama pull` failed ({status})");
+            Ok(())
+        }
+    }
+}
+
+/// Match a normalized model name against a pattern where `*` is any run of characters
+/// and `?` any one. A pattern without a tag (`hf.co/*`, `llama3.1`) matches every tag.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let name = name.to_lowercase();
+    // A tag separator after the last `/` (not a host port).
+    let has_tag = pattern
+        .rsplit_once('/')
+        .map_or(pattern.as_str(), |(_, last)| last)
+        .contains(':');
+    let pattern = if has_tag {
+        pattern
+    } else {
+        format!("{pattern}:*")
+    };
+    glob(pattern.as_bytes(), name.as_bytes())
+}
+
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was, and the text position it currently covers up to.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: ApplyArgs,
+    }
+
+    /// A store with four models and a spec that requires one, allows one, and bans one.
+    fn setup(dir: &std::path::Path) -> (StorePaths, std::path::PathBuf) {
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"llama3")
+            .model("myteam/tuned:latest")
+            .layer(LayerKind::Weights, b"tuned")
+            .model("phi3:mini")
+            .layer(LayerKind::Weights, b"phi3")
+            .model("qwen2:7b-q2_k")
+            .layer(LayerKind::Weights, b"qwen2")
+            .build(dir.join("models"))
+            .unwrap();
+        let spec = dir.join("desired.toml");
+        fs::write(
+            &spec,
+            "required = [\"llama3:8b\"]\nallowed = [\"myteam/*\"]\nbanned = [\"*:*q2_k*\"]\n",
+        )
+        .unwrap();
+        let paths = StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap();
+        (paths, spec)
+    }
+
+    fn installed(paths: &StorePaths) -> Vec<String> {
+        let (ids, _) = ManifestStore::new(&paths.manifests).ids(HiddenPolicy::Exclude);
+        ids.iter().map(ModelId::normalize).collect()
+    }
+
+    fn apply(paths: &StorePaths, argv: &[&str]) -> ExitCode {
+        let args = Cli::parse_from(std::iter::once("apply").chain(argv.iter().copied())).args;
+        // Every required model is installed, so the API is never called.
+        run(paths, &Config::default(), "http://127.0.0.1:9", args).unwrap()
+    }
+
+    #[test]
+    fn test_default_plan_deletes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let (paths, spec) = setup(dir.path());
+        let spec = spec.to_str().unwrap();
+        let before = installed(&paths);
+        assert_eq!(before.len(), 4);
+
+        assert_eq!(
+            apply(&paths, &["--check", spec, "--plain"]),
+            ExitCode::FAILURE
+        );
+        assert_eq!(installed(&paths), before);
+
+        // Without a banned list, a plain reconcile leaves extra models alone.
+        let lenient = dir.path().join("lenient.toml");
+        fs::write(&lenient, "required = [\"llama3:8b\"]\n").unwrap();
+        let code = apply(&paths, &[lenient.to_str().unwrap(), "--plain"]);
+        assert_eq!(code, ExitCode::from(EXIT_EXTRA_ONLY));
+        assert_eq!(installed(&paths), before);
+    }
+
+    #[test]
+    fn test_reconcile_removes_only_models_outside_spec() {
+        let dir = tempfile::tempdir().unwrap();
+        let (paths, spec) = setup(dir.path());
+        let spec = spec.to_str().unwrap();
+
+        // By default only the banned model goes; the extra one is reported.
+        apply(&paths, &[spec, "--plain"]);
+        let remaining = installed(&paths);
+        assert!(
+            !remaining.iter().any(|m| m.contains("qwen2")),
+            "{remaining:?}"
+        );
+        assert!(
+            remaining.iter().any(|m| m.contains("phi3")),
+            "{remaining:?}"
+        );
+
+        assert_eq!(
+            apply(&paths, &[spec, "--remove-extra", "--plain"]),
+            ExitCode::SUCCESS
+        );
+        let remaining = installed(&paths);
+        assert_eq!(remaining.len(), 2, "{remaining:?}");
+        assert!(remaining.iter().any(|m| m.contains("llama3:8b")));
+        assert!(remaining.iter().any(|m| m.contains("myteam/tuned")));
+    }
+}
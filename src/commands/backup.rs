@@ -0,0 +1,896 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ollama_file_find::{
+    BlobStore, Digest, HiddenPolicy, ManifestData, ManifestStore, ModelId, StoredManifest,
+    sha256_file,
+};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::{BackupArgs, BackupTarget, RestoreArgs},
+    config::Config,
+    format::human_bytes,
+    progress::Progress,
+    resume::{Done, ResumeLog},
+};
+
+#[cfg(feature = "s3")]
+pub(crate) use s3::S3Url;
+
+/// A backup location. Keys mirror the models directory (`blobs/sha256-<hex>`,
+/// `manifests/<host>/<namespace>/<model>/<tag>`), so other tools can restore it too.
+trait Remote {
+    /// Sizes of the blobs already there, by digest.
+    fn blobs(&self) -> Result<HashMap<Digest, u64>>;
+    /// Upload the given blob files, then confirm they arrived intact.
+    fn upload_blobs(
+        &self,
+        local: &BlobStore,
+        digests: &[Digest],
+        staging: &Path,
+        progress: &Progress,
+    ) -> Result<()>;
+    /// Upload manifests by their `/`-separated path relative to the manifests root.
+    fn upload_manifests(
+        &self,
+        local: &ManifestStore,
+        paths: &[String],
+        staging: &Path,
+    ) -> Result<()>;
+    /// Manifests in the backup by relative path: those in `wanted`, or all of them.
+    fn manifests(
+        &self,
+        wanted: Option<&BTreeSet<String>>,
+        staging: &Path,
+    ) -> Result<BTreeMap<String, Vec<u8>>>;
+    /// Download blobs into `staging` under their file names.
+    fn download_blobs(&self, digests: &[Digest], staging: &Path, progress: &Progress)
+    -> Result<()>;
+}
+
+fn open_remote(
+    target: &BackupTarget,
+    #[cfg(feature = "s3")] options: &crate::args::S3Options,
+) -> Result<Box<dyn Remote>> {
+    #[cfg(feature = "s3")]
+    if let Some(url) = &target.s3 {
+        return Ok(Box::new(s3::Bucket::open(url, options)?));
+    }
+    match &target.rclone {
+        Some(remote) => Ok(Box::new(rclone::Rclone::new(remote))),
+        None => anyhow::bail!("no backup target given"),
+    }
+}
+
+#[derive(Serialize, Default)]
+struct TransferReport {
+    /// Manifest paths (relative to `manifests/`) transferred
+    manifests: Vec<String>,
+    blobs_transferred: usize,
+    bytes_transferred: u64,
+    /// Blobs already at the destination with the right size
+    blobs_skipped: usize,
+}
+
+/// Upload blobs missing from the backup, then manifests, so no manifest in the backup
+/// ever refers to a blob that isn't there.
+pub(crate) fn run(paths: &StorePaths, args: BackupArgs) -> Result<()> {
+    #[cfg(feature = "s3")]
+    let remote = open_remote(&args.target, &args.s3_options)?;
+    #[cfg(not(feature = "s3"))]
+    let remote = open_remote(&args.target)?;
+    upload(&*remote, paths, &args)
+}
+
+fn upload(remote: &dyn Remote, paths: &StorePaths, args: &BackupArgs) -> Result<()> {
+    let store = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let manifests = select_manifests(&store, &args.models)?;
+    let staging = Staging::new(&std::env::temp_dir(), "backup")?;
+
+    let existing = remote.blobs()?;
+    let mut report = TransferReport::default();
+    let mut upload = Vec::new();
+    let mut seen = BTreeSet::new();
+    for m in &manifests {
+        for layer in m.data.layers.iter().chain(m.data.config.iter()) {
+            if !seen.insert(layer.digest) {
+                continue;
+            }
+            let path = blobs.path_for(&layer.digest);
+            let size = fs::metadata(&path)
+                .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?
+                .len();
+            if existing.get(&layer.digest) == Some(&size) {
+                report.blobs_skipped += 1;
+            } else {
+                upload.push(layer.digest);
+                report.bytes_transferred += size;
+            }
+        }
+    }
+    if !upload.is_empty() {
+        let progress = Progress::start("upload", upload.len(), Some(report.bytes_transferred));
+        remote.upload_blobs(&blobs, &upload, &staging.dir, &progress)?;
+        progress.finish();
+    }
+    report.blobs_transferred = upload.len();
+    report.manifests = manifests
+        .iter()
+        .map(|m| relative_key(store.root(), &m.path))
+        .collect();
+    remote.upload_manifests(&store, &report.manifests, &staging.dir)?;
+    print_report(&report, args.plain, "uploaded")
+}
+
+/// Download manifests from the backup and any blobs they need that aren't here yet,
+/// checking each blob against its digest before it is moved into place.
+pub(crate) fn restore(models_dir: &Path, config: &Config, args: RestoreArgs) -> Result<()> {
+    #[cfg(feature = "s3")]
+    let remote = open_remote(&args.target, &args.s3_options)?;
+    #[cfg(not(feature = "s3"))]
+    let remote = open_remote(&args.target)?;
+    download(&*remote, models_dir, config, &args)
+}
+
+fn download(
+    remote: &dyn Remote,
+    models_dir: &Path,
+    config: &Config,
+    args: &RestoreArgs,
+) -> Result<()> {
+    let store = ManifestStore::new(models_dir.join("manifests"))
+        .with_backup_retention(config.retention.manifest_backups());
+    let blobs = BlobStore::new(models_dir.join("blobs"));
+    let wanted: Option<BTreeSet<String>> = (!args.models.is_empty())
+        .then(|| {
+            args.models
+                .iter()
+                .map(|m| {
+                    let id: ModelId = m.parse()?;
+                    Ok(relative_key(
+                        Path::new(""),
+                        &ManifestStore::new("").path_for(&id),
+                    ))
+                })
+                .collect::<Result<_>>()
+        })
+        .transpose()?;
+    // Next to `blobs/`, so verified blobs can be renamed into place.
+    fs::create_dir_all(models_dir)?;
+    let staging = Staging::resumable(models_dir, "restore", args.resume)?;
+    let mut log = ResumeLog::open(&staging.dir.join(".resume"), "restore", args.resume)?;
+
+    let fetched = remote.manifests(wanted.as_ref(), &staging.dir)?;
+    if let Some(wanted) = &wanted {
+        let missing: Vec<_> = wanted
+            .iter()
+            .filter(|w| !fetched.contains_key(*w))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!("not in the backup: {}", missing.join(", "));
+        }
+    }
+    if fetched.is_empty() {
+        anyhow::bail!("no manifests in the backup");
+    }
+    let mut parsed = BTreeMap::new();
+    for (relative, body) in &fetched {
+        let data: ManifestData = serde_json::from_slice(body)
+            .map_err(|e| anyhow::anyhow!("manifests/{relative}: {e}"))?;
+        parsed.insert(relative, data);
+    }
+
+    let conflicts: Vec<_> = fetched
+        .iter()
+        .filter(|(relative, body)| {
+            fs::read(store.root().join(relative)).is_ok_and(|local| local != **body)
+        })
+        .map(|(relative, _)| relative.clone())
+        .collect();
+    if !conflicts.is_empty() && !args.force {
+        anyhow::bail!(
+            "already present with different content (use --force to replace): {}",
+            conflicts.join(", ")
+        );
+    }
+
+    let mut report = TransferReport::default();
+    let needed: BTreeMap<Digest, Option<u64>> = parsed
+        .values()
+        .flat_map(|data| data.layers.iter().chain(data.config.iter()))
+        .map(|l| (l.digest, l.size))
+        .collect();
+    let mut fetch = Vec::new();
+    let mut staged_earlier = Vec::new();
+    for (&digest, &size) in &needed {
+        if fs::metadata(blobs.path_for(&digest)).is_ok_and(|m| size.is_none_or(|s| m.len() == s)) {
+            report.blobs_skipped += 1;
+        } else if staged_intact(&mut log, &staging.dir, digest, args.resume)? {
+            staged_earlier.push(digest);
+        } else {
+            fetch.push(digest);
+        }
+    }
+    if !staged_earlier.is_empty() {
+        eprintln!(
+            "{} blobs already downloaded by an earlier run",
+            staged_earlier.len()
+        );
+    }
+    if !fetch.is_empty() {
+        let progress = Progress::start(
+            "download",
+            fetch.len(),
+            fetch.iter().map(|d| needed[d]).sum::<Option<u64>>(),
+        );
+        remote.download_blobs(&fetch, &staging.dir, &progress)?;
+        progress.finish();
+    }
+    // Check every download before moving any, so a bad backup leaves the store as it was.
+    for digest in &fetch {
+        let staged = staging.dir.join(digest.blob_file_name());
+        let actual = sha256_file(&staged)?;
+        if actual != *digest {
+            anyhow::bail!("backup copy of {digest} hashes to {actual}");
+        }
+        report.bytes_transferred += fs::metadata(&staged)?.len();
+        if let Some(done) = Done::of(&staged) {
+            log.record(*digest, done)?;
+        }
+    }
+    let download: Vec<Digest> = staged_earlier.into_iter().chain(fetch).collect();
+    if !download.is_empty() {
+        fs::create_dir_all(blobs.root())?;
+    }
+    for digest in &download {
+        fs::rename(
+            staging.dir.join(digest.blob_file_name()),
+            blobs.path_for(digest),
+        )?;
+        report.blobs_transferred += 1;
+    }
+    for (relative, body) in &fetched {
+        if let Some(backup) = store.write_manifest(&store.root().join(relative), body)? {
+            eprintln!("Previous manifest saved to {}", backup.path.display());
+        }
+        report.manifests.push(relative.clone());
+    }
+    log.finish()?;
+    staging.finish();
+    print_report(&report, args.plain, "restored")
+}
+
+/// Whether an interrupted restore left a good copy of `digest` in `staging`: recorded
+/// in the log and unchanged since, or (when resuming) hashing to the right digest,
+/// which is still cheaper than downloading it again.
+fn staged_intact(
+    log: &mut ResumeLog,
+    staging: &Path,
+    digest: Digest,
+    resume: bool,
+) -> Result<bool> {
+    let staged = staging.join(digest.blob_file_name());
+    let now = Done::of(&staged);
+    if log.is_done(&digest, now) {
+        return Ok(true);
+    }
+    match now {
+        Some(done) if resume && sha256_file(&staged).ok() == Some(digest) => {
+            log.record(digest, done)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Every manifest, or the named models (all of which must exist).
+fn select_manifests(store: &ManifestStore, models: &[String]) -> Result<Vec<StoredManifest>> {
+    let (manifests, errors) = store.manifests(HiddenPolicy::Include);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    if models.is_empty() {
+        return Ok(manifests);
+    }
+    let wanted: BTreeSet<String> = models
+        .iter()
+        .map(|m| m.parse::<ModelId>().map(|id| id.normalize()))
+        .collect::<std::result::Result<_, _>>()?;
+    let manifests: Vec<_> = manifests
+        .into_iter()
+        .filter(|m| wanted.contains(&m.id.normalize()))
+        .collect();
+    if manifests.len() < wanted.len() {
+        let found: BTreeSet<_> = manifests.iter().map(|m| m.id.normalize()).collect();
+        let missing: Vec<_> = wanted.difference(&found).cloned().collect();
+        anyhow::bail!("model not found: {}", missing.join(", "));
+    }
+    Ok(manifests)
+}
+
+/// Scratch directory removed when dropped, unless kept for `--resume`.
+struct Staging {
+    dir: PathBuf,
+    keep: bool,
+}
+
+impl Staging {
+    fn new(parent: &Path, what: &str) -> Result<Self> {
+        let dir = parent.join(format!(".ollama-file-find-{what}-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        Ok(Staging { dir, keep: false })
+    }
+
+    /// A directory with a fixed name that outlives a failed run, so the next one can
+    /// pick up what was already staged; emptied first unless `resume`.
+    fn resumable(parent: &Path, what: &str, resume: bool) -> Result<Self> {
+        let dir = parent.join(format!(".ollama-file-find-{what}"));
+        if !resume && dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        Ok(Staging { dir, keep: true })
+    }
+
+    /// The operation succeeded: remove the directory after all.
+    fn finish(mut self) {
+        self.keep = false;
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+/// `/`-separated path of a manifest relative to the manifests root, as used for keys.
+fn relative_key(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn print_report(report: &TransferReport, plain: bool, what: &str) -> Result<()> {
+    if plain {
+        for m in &report.manifests {
+            println!("{m}\t{what}");
+        }
+        eprintln!(
+            "{} blobs {what} ({}), {} already there",
+            report.blobs_transferred,
+            human_bytes(report.bytes_transferred),
+            report.blobs_skipped
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(report)?);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "s3")]
+mod s3 {
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        fs,
+        path::Path,
+        str::FromStr,
+    };
+
+    use ollama_file_find::{BlobStore, Digest, ManifestStore};
+    use s3::{Region, creds::Credentials};
+
+    use super::Remote;
+    use crate::{Result, args::S3Options, progress::Progress};
+
+    /// `s3://bucket/prefix`; the prefix is kept without leading or trailing slashes.
+    #[derive(Debug, Clone)]
+    pub(crate) struct S3Url {
+        bucket: String,
+        prefix: String,
+    }
+
+    impl FromStr for S3Url {
+        type Err = String;
+
+        fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+            let rest = s
+                .strip_prefix("s3://")
+                .ok_or_else(|| format!("expected s3://bucket/prefix, got `{s}`"))?;
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                return Err(format!("no bucket in `{s}`"));
+            }
+            Ok(S3Url {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_matches('/').to_string(),
+            })
+        }
+    }
+
+    pub(super) struct Bucket {
+        bucket: Box<s3::Bucket>,
+        prefix: String,
+    }
+
+    impl Bucket {
+        pub(super) fn open(url: &S3Url, options: &S3Options) -> Result<Self> {
+            let endpoint = options
+                .endpoint
+                .clone()
+                .or_else(|| std::env::var("AWS_ENDPOINT_URL").ok());
+            let region = match &endpoint {
+                Some(endpoint) => Region::Custom {
+                    region: options.region.clone().unwrap_or_else(|| "us-east-1".into()),
+                    endpoint: endpoint.clone(),
+                },
+                None => match &options.region {
+                    Some(region) => region.parse()?,
+                    None => Region::from_default_env().unwrap_or(Region::UsEast1),
+                },
+            };
+            let bucket = s3::Bucket::new(&url.bucket, region, Credentials::default()?)?;
+            Ok(Bucket {
+                // Most S3-compatible servers (MinIO, Garage, Ceph) only do path-style requests.
+                bucket: if endpoint.is_some() {
+                    bucket.with_path_style()
+                } else {
+                    bucket
+                },
+                prefix: url.prefix.clone(),
+            })
+        }
+
+        /// Object key for a path relative to the models directory.
+        fn key(&self, relative: &str) -> String {
+            if self.prefix.is_empty() {
+                relative.to_string()
+            } else {
+                format!("{}/{relative}", self.prefix)
+            }
+        }
+    }
+
+    impl Remote for Bucket {
+        fn blobs(&self) -> Result<HashMap<Digest, u64>> {
+            let prefix = self.key("blobs/");
+            let mut found = HashMap::new();
+            for page in self.bucket.list(prefix.clone(), None)? {
+                for object in page.contents {
+                    if let Some(digest) = object
+                        .key
+                        .strip_prefix(&prefix)
+                        .and_then(Digest::from_blob_file_name)
+                    {
+                        found.insert(digest, object.size);
+                    }
+                }
+            }
+            Ok(found)
+        }
+
+        fn upload_blobs(
+            &self,
+            local: &BlobStore,
+            digests: &[Digest],
+            _: &Path,
+            progress: &Progress,
+        ) -> Result<()> {
+            for digest in digests {
+                let mut file = fs::File::open(local.path_for(digest))?;
+                let size = file.metadata()?.len();
+                // Multipart above 8 MiB; S3 checks every part's checksum on arrival.
+                self.bucket.put_object_stream(
+                    &mut file,
+                    self.key(&format!("blobs/{}", digest.blob_file_name())),
+                )?;
+                progress.step(digest, size);
+                progress.note(format_args!("  {digest}"));
+            }
+            Ok(())
+        }
+
+        fn upload_manifests(
+            &self,
+            local: &ManifestStore,
+            paths: &[String],
+            _: &Path,
+        ) -> Result<()> {
+            for relative in paths {
+                let body = fs::read(local.root().join(relative))?;
+                self.bucket
+                    .put_object(self.key(&format!("manifests/{relative}")), &body)?;
+            }
+            Ok(())
+        }
+
+        fn manifests(
+            &self,
+            wanted: Option<&BTreeSet<String>>,
+            _: &Path,
+        ) -> Result<BTreeMap<String, Vec<u8>>> {
+            let prefix = self.key("manifests/");
+            let mut found = BTreeMap::new();
+            for page in self.bucket.list(prefix.clone(), None)? {
+                for object in page.contents {
+                    let Some(relative) = object.key.strip_prefix(&prefix) else {
+                        continue;
+                    };
+                    if relative.split('/').count() != 4
+                        || wanted.is_some_and(|w| !w.contains(relative))
+                    {
+                        continue;
+                    }
+                    let body = self.bucket.get_object(&object.key)?.to_vec();
+                    found.insert(relative.to_string(), body);
+                }
+            }
+            Ok(found)
+        }
+
+        fn download_blobs(
+            &self,
+            digests: &[Digest],
+            staging: &Path,
+            progress: &Progress,
+        ) -> Result<()> {
+            for digest in digests {
+                let name = digest.blob_file_name();
+                let mut file = fs::File::create(staging.join(&name))?;
+                self.bucket
+                    .get_object_to_writer(self.key(&format!("blobs/{name}")), &mut file)?;
+                progress.step(digest, file.metadata()?.len());
+                progress.note(format_args!("  {digest}"));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Driver for any remote the user has configured in rclone. Transfers go through
+/// `rclone copy` with generated file lists, and uploads are checked with `rclone check`
+/// (hashes where the remote has them, sizes otherwise).
+mod rclone {
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        fs,
+        path::Path,
+        process::{Command, Stdio},
+    };
+
+    use ollama_file_find::{BlobStore, Digest, HiddenPolicy, ManifestStore};
+
+    use super::Remote;
+    use crate::{Result, progress::Progress};
+
+    /// rclone's exit code for "directory not found".
+    const DIR_NOT_FOUND: i32 = 3;
+
+    pub(super) struct Rclone {
+        remote: String,
+    }
+
+    impl Rclone {
+        pub(super) fn new(remote: &str) -> Self {
+            Rclone {
+                remote: remote.trim_end_matches('/').to_string(),
+            }
+        }
+
+        fn path(&self, relative: &str) -> String {
+            if self.remote.ends_with(':') {
+                format!("{}{relative}", self.remote)
+            } else {
+                format!("{}/{relative}", self.remote)
+            }
+        }
+
+        /// `rclone copy` of the listed files (relative to `from`).
+        fn copy_files(
+            &self,
+            from: &str,
+            to: &str,
+            files: &[String],
+            staging: &Path,
+            check: bool,
+        ) -> Result<()> {
+            let list = staging.join("files.txt");
+            fs::write(&list, files.join("\n") + "\n")?;
+            let list = list.to_string_lossy();
+            let filter = ["--files-from-raw", &list, "--no-traverse"];
+            run(&[&["copy", from, to][..], &filter].concat())?;
+            if check {
+                run(&[&["check", "--one-way", from, to][..], &filter[..2]].concat())?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Remote for Rclone {
+        fn blobs(&self) -> Result<HashMap<Digest, u64>> {
+            let output = rclone()
+                .args(["lsf", "--files-only", "--format", "ps", "--separator", "\t"])
+                .arg(self.path("blobs"))
+                .stderr(Stdio::inherit())
+                .output()?;
+            if output.status.code() == Some(DIR_NOT_FOUND) {
+                return Ok(HashMap::new());
+            }
+            if !output.status.success() {
+                anyhow::bail!("rclone lsf failed ({})", output.status);
+            }
+            Ok(String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| {
+                    let (name, size) = line.split_once('\t')?;
+                    Some((Digest::from_blob_file_name(name)?, size.parse().ok()?))
+                })
+                .collect())
+        }
+
+        fn upload_blobs(
+            &self,
+            local: &BlobStore,
+            digests: &[Digest],
+            staging: &Path,
+            progress: &Progress,
+        ) -> Result<()> {
+            let names: Vec<_> = digests.iter().map(Digest::blob_file_name).collect();
+            self.copy_files(
+                &local.root().to_string_lossy(),
+                &self.path("blobs"),
+                &names,
+                staging,
+                true,
+            )?;
+            // One rclone run moves them all, so they are done all at once.
+            for digest in digests {
+                progress.step(digest, fs::metadata(local.path_for(digest))?.len());
+            }
+            Ok(())
+        }
+
+        fn upload_manifests(
+            &self,
+            local: &ManifestStore,
+            paths: &[String],
+            staging: &Path,
+        ) -> Result<()> {
+            self.copy_files(
+                &local.root().to_string_lossy(),
+                &self.path("manifests"),
+                paths,
+                staging,
+                true,
+            )
+        }
+
+        fn manifests(
+            &self,
+            wanted: Option<&BTreeSet<String>>,
+            staging: &Path,
+        ) -> Result<BTreeMap<String, Vec<u8>>> {
+            let into = staging.join("manifests");
+            let from = self.path("manifests");
+            match wanted {
+                Some(wanted) => {
+                    let files: Vec<_> = wanted.iter().cloned().collect();
+                    self.copy_files(&from, &into.to_string_lossy(), &files, staging, false)?;
+                }
+                None => run(&["copy", &from, &into.to_string_lossy()])?,
+            }
+            let store = ManifestStore::new(&into);
+            let (ids, _) = store.ids(HiddenPolicy::Include);
+            ids.iter()
+                .map(|id| {
+                    let path = store.path_for(id);
+                    let relative = super::relative_key(&into, &path);
+                    Ok((relative, fs::read(&path)?))
+                })
+                .collect()
+        }
+
+        fn download_blobs(
+            &self,
+            digests: &[Digest],
+            staging: &Path,
+            progress: &Progress,
+        ) -> Result<()> {
+            let names: Vec<_> = digests.iter().map(Digest::blob_file_name).collect();
+            self.copy_files(
+                &self.path("blobs"),
+                &staging.to_string_lossy(),
+                &names,
+                staging,
+                false,
+            )?;
+            for (digest, name) in digests.iter().zip(&names) {
+                progress.step(digest, fs::metadata(staging.join(name))?.len());
+            }
+            Ok(())
+        }
+    }
+
+    fn rclone() -> Command {
+        Command::new(std::env::var_os("RCLONE").unwrap_or_else(|| "rclone".into()))
+    }
+
+    fn run(args: &[&str]) -> Result<()> {
+        let status = rclone().args(args).status().map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                anyhow::anyhow!(
+                    "rclone not found (install it from https://rclone.org, or set RCLONE)"
+                )
+            } else {
+                e.into()
+            }
+        })?;
+        if !status.success() {
+            anyhow::bail!("rclone {} failed ({status})", args[0]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+
+    /// A backup in a plain directory, laid out as the real remotes lay theirs out.
+    struct Dir(PathBuf);
+
+    impl Remote for Dir {
+        fn blobs(&self) -> Result<HashMap<Digest, u64>> {
+            let Ok(entries) = fs::read_dir(self.0.join("blobs")) else {
+                return Ok(HashMap::new());
+            };
+            let mut found = HashMap::new();
+            for entry in entries {
+                let entry = entry?;
+                if let Some(digest) =
+                    Digest::from_blob_file_name(&entry.file_name().to_string_lossy())
+                {
+                    found.insert(digest, entry.metadata()?.len());
+                }
+            }
+            Ok(found)
+        }
+
+        fn upload_blobs(
+            &self,
+            local: &BlobStore,
+            digests: &[Digest],
+            _staging: &Path,
+            progress: &Progress,
+        ) -> Result<()> {
+            fs::create_dir_all(self.0.join("blobs"))?;
+            for digest in digests {
+                let to = self.0.join("blobs").join(digest.blob_file_name());
+                progress.step(digest, fs::copy(local.path_for(digest), to)?);
+            }
+            Ok(())
+        }
+
+        fn upload_manifests(
+            &self,
+            local: &ManifestStore,
+            paths: &[String],
+            _staging: &Path,
+        ) -> Result<()> {
+            for relative in paths {
+                let to = self.0.join("manifests").join(relative);
+                fs::create_dir_all(to.parent().unwrap())?;
+                fs::copy(local.root().join(relative), to)?;
+            }
+            Ok(())
+        }
+
+        fn manifests(
+            &self,
+            wanted: Option<&BTreeSet<String>>,
+            _staging: &Path,
+        ) -> Result<BTreeMap<String, Vec<u8>>> {
+            let root = self.0.join("manifests");
+            let store = ManifestStore::new(&root);
+            let (ids, _) = store.ids(HiddenPolicy::Include);
+            ids.iter()
+                .map(|id| relative_key(&root, &store.path_for(id)))
+                .filter(|relative| wanted.is_none_or(|w| w.contains(relative)))
+                .map(|relative| Ok((relative.clone(), fs::read(root.join(&relative))?)))
+                .collect()
+        }
+
+        fn download_blobs(
+            &self,
+            digests: &[Digest],
+            staging: &Path,
+            progress: &Progress,
+        ) -> Result<()> {
+            for digest in digests {
+                let name = digest.blob_file_name();
+                progress.step(
+                    digest,
+                    fs::copy(self.0.join("blobs").join(&name), staging.join(&name))?,
+                );
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Parser)]
+    struct Cli<T: clap::Args> {
+        #[command(flatten)]
+        args: T,
+    }
+
+    /// Parsed command-line arguments. A target is required but unused: tests hand the
+    /// remote over directly.
+    fn args<T: clap::Args>(argv: &[&str]) -> T {
+        let target = ["test", "--rclone", "unused:"];
+        Cli::<T>::parse_from(target.into_iter().chain(argv.iter().copied())).args
+    }
+
+    #[test]
+    fn test_backup_restore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Template, b"{{ .Prompt }}")
+            .model("phi3:mini")
+            .layer(LayerKind::Weights, b"other weights")
+            .build(dir.path().join("models"))
+            .unwrap();
+        let models_dir = store.models_dir().to_path_buf();
+        let paths = StorePaths::resolve(Some(models_dir.clone())).unwrap();
+        let remote = Dir(dir.path().join("backup"));
+        upload(&remote, &paths, &args(&["--plain"])).unwrap();
+
+        let manifest = store.manifest_path("llama3:8b");
+        let original = sha256_file(&manifest).unwrap();
+        let weights = store.blob_path(b"weights");
+        fs::remove_file(&weights).unwrap();
+        fs::remove_file(store.manifest_path("phi3:mini")).unwrap();
+        fs::write(&manifest, b"{ corrupted").unwrap();
+
+        // A local manifest that differs from the backup is only replaced with --force.
+        let config = Config::default();
+        assert!(download(&remote, &models_dir, &config, &args(&["--plain"])).is_err());
+        download(
+            &remote,
+            &models_dir,
+            &config,
+            &args(&["--plain", "--force"]),
+        )
+        .unwrap();
+
+        assert_eq!(sha256_file(&manifest).unwrap(), original);
+        let restored = ManifestStore::new(&paths.manifests);
+        let (manifests, errors) = restored.manifests(HiddenPolicy::Include);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(manifests.len(), 2);
+        let blobs = BlobStore::new(&paths.blobs);
+        for layer in manifests.iter().flat_map(|m| &m.data.layers) {
+            assert_eq!(
+                sha256_file(&blobs.path_for(&layer.digest)).unwrap(),
+                layer.digest
+            );
+        }
+        assert!(weights.exists());
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    time::{Duration, Instant},
+};
+
+use ollama_file_find::{
+    BlobStore, HiddenPolicy, ManifestStore, ScanArgs, SyntheticStore, gc_plan, scan_manifests,
+};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::BenchArgs};
+
+#[derive(Serialize)]
+struct Timing {
+    operation: &'static str,
+    min_ms: f64,
+    median_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Serialize)]
+struct BenchReport {
+    models_dir: PathBuf,
+    models: usize,
+    blobs: usize,
+    iterations: u32,
+    timings: Vec<Timing>,
+}
+
+/// A generated store in a temporary directory, removed on drop.
+struct TempStore(PathBuf);
+
+impl Drop for TempStore {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub(crate) fn run(models_dir: Option<PathBuf>, args: BenchArgs) -> Result<()> {
+    let mut _temp = None;
+    let models_dir = match args.synthetic {
+        Some(models) => {
+            let dir = match &args.dir {
+                Some(dir) => {
+                    if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()) {
+                        anyhow::bail!("{} is not empty", dir.display());
+                    }
+                    dir.clone()
+                }
+                None => {
+                    let dir =
+                        env::temp_dir().join(format!("ollama-file-find-bench-{}", process::id()));
+                    _temp = Some(TempStore(dir.clone()));
+                    dir
+                }
+            };
+            let started = Instant::now();
+            SyntheticStore {
+                models,
+                layers: args.layers,
+                shared_layers: args.shared_layers,
+                namespaces: args.namespaces,
+                layer_size: args.layer_size,
+                sparse: args.sparse,
+            }
+            .generate(&dir)?;
+            eprintln!(
+                "Generated {models} models in {} ({:.0} ms)",
+                dir.display(),
+                millis(started.elapsed())
+            );
+            dir
+        }
+        None => models_dir.unwrap_or_else(ollama_file_find::ollama_models_dir),
+    };
+    let paths = StorePaths::resolve(Some(models_dir.clone()))?;
+
+    let manifests = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let names = ScanArgs::new(&paths.manifests, &paths.blobs);
+    let verbose = ScanArgs::new(&paths.manifests, &paths.blobs).with_verbose(true);
+    let operations: [(&'static str, &dyn Fn()); 5] = [
+        ("scan", &|| drop(scan_manifests(&names))),
+        ("scan_verbose", &|| drop(scan_manifests(&verbose))),
+        ("manifests", &|| {
+            drop(manifests.manifests(HiddenPolicy::Include))
+        }),
+        ("blobs", &|| drop(blobs.blobs())),
+        ("gc_plan", &|| drop(gc_plan(&manifests, &blobs))),
+    ];
+    let timings = operations
+        .into_iter()
+        .map(|(operation, f)| time(operation, f, args.iterations))
+        .collect();
+
+    let report = BenchReport {
+        models_dir,
+        models: scan_manifests(&names).models.len(),
+        blobs: blobs.blobs()?.len(),
+        iterations: args.iterations,
+        timings,
+    };
+    if args.plain {
+        println!(
+            "{} models, {} blobs in {}, {} runs each",
+            report.models,
+            report.blobs,
+            report.models_dir.display(),
+            report.iterations
+        );
+        println!(
+            "{:<14}  {:>10}  {:>10}  {:>10}",
+            "OPERATION", "MIN", "MEDIAN", "MAX"
+        );
+        for t in &report.timings {
+            println!(
+                "{:<14}  {:>7.2} ms  {:>7.2} ms  {:>7.2} ms",
+                t.operation, t.min_ms, t.median_ms, t.max_ms
+            );
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// Run `f` once to warm caches, then `iterations` timed runs.
+fn time(operation: &'static str, f: &dyn Fn(), iterations: u32) -> Timing {
+    f();
+    let mut runs: Vec<f64> = (0..iterations)
+        .map(|_| {
+            let started = Instant::now();
+            f();
+            millis(started.elapsed())
+        })
+        .collect();
+    runs.sort_by(f64::total_cmp);
+    Timing {
+        operation,
+        min_ms: runs[0],
+        median_ms: runs[runs.len() / 2],
+        max_ms: runs[runs.len() - 1],
+    }
+}
+
+/// Milliseconds, rounded to the microsecond.
+fn millis(d: Duration) -> f64 {
+    (d.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
@@ -0,0 +1,497 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use ollama_file_find::{
+    BlobStatus, BlobStore, Digest, ManifestStore, ModelId, Seal, SealChange, copy_atomic,
+    map_per_device, sha256_file, write_atomic,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Result, StorePaths,
+    args::{BundleAction, BundleArgs, BundleInstallArgs},
+    config::Config,
+    format::human_bytes,
+    progress::Progress,
+    resume::{Done, ResumeLog, lock},
+};
+
+const SEAL_FILE: &str = "seal.json";
+const INSTALL_FILE: &str = "INSTALL.txt";
+/// Blobs copied so far; removed once the bundle is complete.
+const RESUME_FILE: &str = ".resume";
+
+#[derive(Serialize, Default)]
+struct InstallReport {
+    /// Manifest paths (relative to `manifests/`) written for the first time
+    installed: Vec<String>,
+    /// Manifests that differed and were overwritten (`--force`)
+    replaced: Vec<String>,
+    /// Manifests already present with the same content
+    unchanged: Vec<String>,
+    blobs_copied: usize,
+    bytes_copied: u64,
+}
+
+pub(crate) fn run(models_dir: Option<PathBuf>, config: &Config, args: BundleArgs) -> Result<()> {
+    if let Some(BundleAction::Install(install_args)) = args.action {
+        let models_dir = models_dir.unwrap_or_else(ollama_file_find::ollama_models_dir);
+        return install(&models_dir, config, install_args, args.plain);
+    }
+    let Some(out) = &args.output else {
+        anyhow::bail!("--output is required when creating a bundle");
+    };
+    let paths = StorePaths::resolve(models_dir)?;
+    let present = match &args.delta {
+        Some(inventory) => inventory_digests(inventory)?,
+        None => HashSet::new(),
+    };
+    create(
+        &paths,
+        &args.models,
+        out,
+        &present,
+        args.resume,
+        usize::from(args.jobs_per_device),
+    )
+}
+
+/// Just enough of a `list --verbose` record to know which blobs a machine has.
+#[derive(Deserialize)]
+struct InventoryModel {
+    #[serde(default)]
+    layers: Vec<InventoryBlob>,
+    config: Option<InventoryBlob>,
+    blob_paths: Option<Vec<InventoryBlob>>,
+}
+
+#[derive(Deserialize)]
+struct InventoryBlob {
+    digest: Digest,
+    status: Option<BlobStatus>,
+}
+
+/// Blobs the target already has, from its `list --verbose` JSON or a seal of its store.
+/// When blob details are present, only blobs in good shape count.
+fn inventory_digests(path: &Path) -> Result<HashSet<Digest>> {
+    let body = fs::read(path)?;
+    if let Ok(seal) = serde_json::from_slice::<Seal>(&body) {
+        return Ok(seal.blobs.into_keys().collect());
+    }
+    let models: Vec<InventoryModel> = serde_json::from_slice(&body).map_err(|e| {
+        anyhow::anyhow!(
+            "{}: expected `list --verbose` JSON or a seal: {e}",
+            path.display()
+        )
+    })?;
+    let mut digests = HashSet::new();
+    for m in models {
+        match m.blob_paths {
+            Some(blobs) => digests.extend(
+                blobs
+                    .into_iter()
+                    .filter(|b| b.status.is_none_or(|s| !s.is_problem()))
+                    .map(|b| b.digest),
+            ),
+            None => digests.extend(m.layers.into_iter().chain(m.config).map(|b| b.digest)),
+        }
+    }
+    Ok(digests)
+}
+
+fn create(
+    paths: &StorePaths,
+    models: &[String],
+    out: &Path,
+    present: &HashSet<Digest>,
+    resume: bool,
+    jobs_per_device: usize,
+) -> Result<()> {
+    let resuming = resume && out.join(RESUME_FILE).is_file();
+    if !resuming && fs::read_dir(out).is_ok_and(|mut entries| entries.next().is_some()) {
+        anyhow::bail!("{} is not empty", out.display());
+    }
+    let log = ResumeLog::open(&out.join(RESUME_FILE), "bundle", resume)?;
+    let store = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let bundle = ManifestStore::new(out.join("manifests"));
+    let bundle_blobs = BlobStore::new(out.join("blobs"));
+    fs::create_dir_all(bundle_blobs.root())?;
+
+    let mut names = Vec::new();
+    let mut copied: BTreeSet<Digest> = BTreeSet::new();
+    let mut omitted: BTreeSet<Digest> = BTreeSet::new();
+    let mut copies = Vec::new();
+    for name in models {
+        let id: ModelId = name.parse()?;
+        let found = store
+            .find(&id)
+            .ok_or_else(|| anyhow::anyhow!("model not found: {}", id.normalize()))?;
+        for layer in found.data.layers.iter().chain(found.data.config.iter()) {
+            if present.contains(&layer.digest) {
+                omitted.insert(layer.digest);
+            } else if copied.insert(layer.digest) {
+                let from = blobs.path_for(&layer.digest);
+                if !from.is_file() {
+                    anyhow::bail!(
+                        "{} references missing blob {}",
+                        id.normalize(),
+                        layer.digest
+                    );
+                }
+                copies.push((layer.digest, from, bundle_blobs.path_for(&layer.digest)));
+            }
+        }
+        let target = bundle
+            .root()
+            .join(found.path.strip_prefix(store.root()).unwrap_or(&found.path));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        copy_atomic(&found.path, &target)?;
+        names.push(id.normalize());
+    }
+
+    // Blobs may live on several disks (e.g. symlinked to a bigger drive); each disk gets
+    // its own copy workers so a slow one doesn't hold up the rest.
+    let log = Mutex::new(log);
+    let progress = Progress::start(
+        "copy",
+        copies.len(),
+        Some(
+            copies
+                .iter()
+                .map(|(_, from, _)| fs::metadata(from).map_or(0, |m| m.len()))
+                .sum(),
+        ),
+    );
+    let outcomes = map_per_device(
+        copies,
+        jobs_per_device,
+        |(_, from, _)| from.as_path(),
+        |(digest, from, to)| -> Result<bool> {
+            // The copy is atomic, so a recorded blob of the same size is whole.
+            let done = Done::of(&to);
+            if lock(&log).is_done(&digest, done) {
+                progress.step(digest, done.map_or(0, |d| d.size));
+                return Ok(true);
+            }
+            copy_atomic(&from, &to)?;
+            let size = fs::metadata(&to)?.len();
+            lock(&log).record(digest, Done { size, mtime: None })?;
+            progress.step(digest, size);
+            Ok(false)
+        },
+    );
+    progress.finish();
+    let mut resumed = 0;
+    for outcome in outcomes {
+        resumed += usize::from(outcome?);
+    }
+    let log = log.into_inner().unwrap_or_else(PoisonError::into_inner);
+
+    // Sealed against the source blobs so blobs left out of a delta are covered too.
+    let seal = Seal::compute(&bundle, &blobs)?;
+    let mut body = serde_json::to_vec_pretty(&seal)?;
+    body.push(b'\n');
+    write_atomic(&out.join(SEAL_FILE), &body)?;
+    write_atomic(
+        &out.join(INSTALL_FILE),
+        install_instructions(&names, &seal, omitted.len()).as_bytes(),
+    )?;
+    eprintln!(
+        "Bundled {} models and {} blobs in {}",
+        names.len(),
+        copied.len(),
+        out.display()
+    );
+    if resumed > 0 {
+        eprintln!("{resumed} of them were already copied by an earlier run");
+    }
+    if !omitted.is_empty() {
+        eprintln!("Left out {} blobs the target already has", omitted.len());
+    }
+    log.finish()?;
+    eprintln!("Store root: {}", seal.root);
+    Ok(())
+}
+
+fn install_instructions(names: &[String], seal: &Seal, omitted: usize) -> String {
+    let delta = if omitted == 0 {
+        String::new()
+    } else {
+        format!(
+            "\nThis is a delta bundle: {omitted} blobs were left out because the target\n\
+             already has them. Installing checks those against the seal as well.\n"
+        )
+    };
+    format!(
+        "Ollama model bundle: {}\n\
+         Store root: {}\n\
+         \n\
+         Copy this directory to the target machine, then run\n\
+         \n    ollama-file-find bundle install <this directory>\n\n\
+         which checks {SEAL_FILE} against the files and merges blobs, then manifests, into\n\
+         the local models directory (OLLAMA_MODELS, else ~/.ollama/models; override with\n\
+         --models-dir). Compare the store root above with the one published alongside\n\
+         the bundle, or pass it as --expect-root.\n\
+         \n\
+         Without ollama-file-find: copy blobs/ into the models directory first, then\n\
+         manifests/, so no manifest ever points at a blob that isn't there yet.\n\
+         {delta}",
+        names.join(", "),
+        seal.root
+    )
+}
+
+fn install(models_dir: &Path, config: &Config, args: BundleInstallArgs, plain: bool) -> Result<()> {
+    let bundle = ManifestStore::new(args.bundle.join("manifests"));
+    let bundle_blobs = BlobStore::new(args.bundle.join("blobs"));
+    let seal: Seal = serde_json::from_slice(&fs::read(args.bundle.join(SEAL_FILE))?)?;
+    // The seal travels with the bundle, so it is trusted no further than the files: its
+    // manifest paths must stay inside the manifests directory, and each blob must be
+    // sealed with its own digest as the content hash.
+    if let Some(model) = seal.models.keys().find(|m| !is_contained(m)) {
+        anyhow::bail!("bundle names a manifest outside the manifests directory: {model:?}");
+    }
+    if let Some((digest, content)) = seal.blobs.iter().find(|(d, c)| d != c) {
+        anyhow::bail!("blob {digest} is sealed with the content of {content}");
+    }
+    if let Some(expected) = &args.expect_root
+        && expected.parse::<Digest>()? != seal.root
+    {
+        anyhow::bail!("bundle root is {}, expected {expected}", seal.root);
+    }
+    let store = ManifestStore::new(models_dir.join("manifests"))
+        .with_backup_retention(config.retention.manifest_backups());
+    let blobs = BlobStore::new(models_dir.join("blobs"));
+    let mut changes = seal.verify(&bundle, &bundle_blobs)?;
+    // A delta bundle leaves out blobs the target should already have; accept those
+    // only if the local copy hashes to what was sealed.
+    changes.retain(|c| match c {
+        SealChange::BlobMissing { digest } => {
+            sha256_file(&blobs.path_for(digest)).ok() != seal.blobs.get(digest).copied()
+        }
+        _ => true,
+    });
+    if !changes.is_empty() {
+        eprintln!("{}", serde_json::to_string_pretty(&changes)?);
+        if changes
+            .iter()
+            .all(|c| matches!(c, SealChange::BlobMissing { .. }))
+        {
+            anyhow::bail!(
+                "{} blobs are neither in the bundle nor in {} (a delta built for another store?)",
+                changes.len(),
+                blobs.root().display()
+            );
+        }
+        anyhow::bail!("bundle does not match its seal ({} changes)", changes.len());
+    }
+
+    let mut report = InstallReport::default();
+    let mut conflicts = Vec::new();
+    for (model, sealed) in &seal.models {
+        let target = store.root().join(model);
+        match sha256_file(&target) {
+            Ok(d) if d == sealed.manifest => report.unchanged.push(model.clone()),
+            Ok(_) => conflicts.push(model.clone()),
+            Err(_) => report.installed.push(model.clone()),
+        }
+    }
+    if !conflicts.is_empty() && !args.force {
+        anyhow::bail!(
+            "already installed with different content (use --force to replace): {}",
+            conflicts.join(", ")
+        );
+    }
+    report.replaced = conflicts;
+
+    // Blobs before manifests, so no manifest ever refers to a blob not yet copied.
+    fs::create_dir_all(blobs.root())?;
+    // Left out of a delta bundle (and already checked above), or already present.
+    let copies: Vec<(&Digest, u64)> = seal
+        .blobs
+        .keys()
+        .filter_map(|digest| {
+            let size = fs::metadata(bundle_blobs.path_for(digest)).ok()?.len();
+            let present = fs::metadata(blobs.path_for(digest)).is_ok_and(|m| m.len() == size);
+            (!present).then_some((digest, size))
+        })
+        .collect();
+    let progress = Progress::start(
+        "copy",
+        copies.len(),
+        Some(copies.iter().map(|(_, size)| size).sum()),
+    );
+    for (digest, _) in copies {
+        let copied = copy_atomic(&bundle_blobs.path_for(digest), &blobs.path_for(digest))?;
+        report.bytes_copied += copied;
+        report.blobs_copied += 1;
+        progress.step(digest, copied);
+    }
+    progress.finish();
+    for model in report.installed.iter().chain(&report.replaced) {
+        let body = fs::read(bundle.root().join(model))?;
+        if let Some(backup) = store.write_manifest(&store.root().join(model), &body)? {
+            eprintln!("Previous manifest saved to {}", backup.path.display());
+        }
+    }
+
+    if plain {
+        for (models, what) in [
+            (&report.installed, "installed"),
+            (&report.replaced, "replaced"),
+            (&report.unchanged, "unchanged"),
+        ] {
+            for m in models {
+                println!("{m}\t{what}");
+            }
+        }
+        eprintln!(
+            "Copied {} blobs ({})",
+            report.blobs_copied,
+            human_bytes(report.bytes_copied)
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// Whether a `/`-separated manifest path from a seal names a file below the directory
+/// it is joined to: no `..`, no root or drive, no backslashes.
+fn is_contained(model: &str) -> bool {
+    !model.is_empty()
+        && !model.contains('\\')
+        && Path::new(model)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+
+    /// A bundle of `llama3:8b` from a fresh store, and an empty target models directory.
+    fn bundle(dir: &Path) -> (PathBuf, PathBuf) {
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .build(dir.join("source"))
+            .unwrap();
+        let paths = StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap();
+        let out = dir.join("bundle");
+        create(
+            &paths,
+            &["llama3:8b".to_string()],
+            &out,
+            &HashSet::new(),
+            false,
+            1,
+        )
+        .unwrap();
+        (out, dir.join("target"))
+    }
+
+    fn install_args(bundle: &Path) -> BundleInstallArgs {
+        BundleInstallArgs {
+            bundle: bundle.to_path_buf(),
+            expect_root: None,
+            force: false,
+        }
+    }
+
+    #[test]
+    fn test_install_rejects_mismatched_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let (out, target) = bundle(dir.path());
+        let weights = fs::read_dir(out.join("blobs"))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| fs::read(p).unwrap() == b"weights")
+            .unwrap();
+        fs::write(&weights, b"tampered").unwrap();
+
+        let err = install(&target, &Config::default(), install_args(&out), false).unwrap_err();
+        assert!(err.to_string().contains("does not match its seal"), "{err}");
+        assert!(!target.join("blobs").exists());
+        assert!(!target.join("manifests").exists());
+
+        // Resealing the swapped content under the old digest is caught as well.
+        let mut seal: Seal =
+            serde_json::from_slice(&fs::read(out.join(SEAL_FILE)).unwrap()).unwrap();
+        let digest = *seal
+            .blobs
+            .keys()
+            .find(|d| weights.ends_with(d.blob_file_name()))
+            .unwrap();
+        seal.blobs.insert(digest, sha256_file(&weights).unwrap());
+        fs::write(out.join(SEAL_FILE), serde_json::to_vec(&seal).unwrap()).unwrap();
+        let err = install(&target, &Config::default(), install_args(&out), false).unwrap_err();
+        assert!(
+            err.to_string().contains("is sealed with the content of"),
+            "{err}"
+        );
+        assert!(!target.join("blobs").exists());
+    }
+
+    #[test]
+    fn test_install_rejects_path_outside_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let (out, target) = bundle(dir.path());
+        let mut seal: Seal =
+            serde_json::from_slice(&fs::read(out.join(SEAL_FILE)).unwrap()).unwrap();
+        let (_, sealed) = seal.models.pop_first().unwrap();
+        // Would land in `<dir>/escaped/8b`, next to the target models directory.
+        seal.models.insert("../../escaped/8b".to_string(), sealed);
+        fs::write(out.join(SEAL_FILE), serde_json::to_vec(&seal).unwrap()).unwrap();
+
+        let err = install(&target, &Config::default(), install_args(&out), false).unwrap_err();
+        assert!(
+            err.to_string().contains("outside the manifests directory"),
+            "{err}"
+        );
+        assert!(!target.join("blobs").exists());
+        assert!(!dir.path().join("escaped").exists());
+
+        for bad in ["", "/etc/passwd", "a/../../b", "a\\..\\b"] {
+            assert!(!is_contained(bad), "{bad:?}");
+        }
+        assert!(is_contained("registry.ollama.ai/library/llama3/8b"));
+        assert!(is_contained("myregistry.local:5000/team/model/tag"));
+    }
+
+    #[test]
+    fn test_install_force_backs_up_replaced_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let (out, target) = bundle(dir.path());
+        let config = Config::default();
+        install(&target, &config, install_args(&out), false).unwrap();
+
+        let store = ManifestStore::new(target.join("manifests"));
+        let id: ModelId = "llama3:8b".parse().unwrap();
+        let manifest = store.path_for(&id);
+        let bundled = fs::read(&manifest).unwrap();
+        let edited = [bundled.as_slice(), b"\n"].concat();
+        fs::write(&manifest, &edited).unwrap();
+
+        let err = install(&target, &config, install_args(&out), false).unwrap_err();
+        assert!(err.to_string().contains("--force"), "{err}");
+        let args = BundleInstallArgs {
+            force: true,
+            ..install_args(&out)
+        };
+        install(&target, &config, args, false).unwrap();
+        assert_eq!(fs::read(&manifest).unwrap(), bundled);
+        let backups = store.backups(&id);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0].path).unwrap(), edited);
+    }
+}
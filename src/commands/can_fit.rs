@@ -0,0 +1,47 @@
+use std::process::ExitCode;
+
+use crate::{
+    Result, StorePaths,
+    args::CanFitArgs,
+    format::{human_bytes, parse_bytes},
+};
+
+pub(crate) fn run(paths: &StorePaths, args: CanFitArgs) -> Result<ExitCode> {
+    let needed = match parse_bytes(&args.target) {
+        Ok(bytes) => bytes,
+        Err(_) => model_download_bytes(paths, &args.target)?,
+    };
+    let available = fs4::available_space(&paths.blobs)?;
+    let required = needed.saturating_add(args.reserve);
+    let fits = required <= available;
+
+    if !args.quiet {
+        eprintln!(
+            "{}: need {} (+{} reserve), {} available on {}",
+            if fits { "fits" } else { "does not fit" },
+            human_bytes(needed),
+            human_bytes(args.reserve),
+            human_bytes(available),
+            paths.blobs.display()
+        );
+    }
+    Ok(if fits {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(feature = "network")]
+fn model_download_bytes(paths: &StorePaths, name: &str) -> Result<u64> {
+    use ollama_file_find::{BlobStore, ModelId, estimate_pull, registry::fetch_manifest};
+
+    let id: ModelId = name.parse()?;
+    let manifest = fetch_manifest(&id)?;
+    Ok(estimate_pull(&manifest, &BlobStore::new(&paths.blobs)).download_bytes)
+}
+
+#[cfg(not(feature = "network"))]
+fn model_download_bytes(_paths: &StorePaths, name: &str) -> Result<u64> {
+    anyhow::bail!("{name:?} is not a byte count; model sizes need the `network` feature")
+}
@@ -0,0 +1,185 @@
+use std::fmt::Write as _;
+
+use ollama_file_find::{
+    BlobStore, LayerKind, ManifestStore, ModelId, ModelLicense, Origin, Provenance, StoredManifest,
+    gguf::{GgufMetadata, GgufValue, read_gguf_file},
+    size_breakdown, write_atomic,
+};
+
+use crate::{Result, StorePaths, args::CardArgs, format::human_bytes};
+
+/// Largest template, system prompt, or params layer included; real ones are a few KiB.
+const MAX_TEXT_BYTES: u64 = 1 << 20;
+
+pub(crate) fn run(paths: &StorePaths, args: CardArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let Some(stored) = ManifestStore::new(&paths.manifests).find(&id) else {
+        anyhow::bail!("model not found: {}", id.normalize());
+    };
+    let card = render(&BlobStore::new(&paths.blobs), &stored)?;
+    match &args.output {
+        Some(path) => {
+            write_atomic(path, card.as_bytes())?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{card}"),
+    }
+    Ok(())
+}
+
+fn render(blobs: &BlobStore, stored: &StoredManifest) -> Result<String> {
+    let StoredManifest { id, data, .. } = stored;
+    let layers: Vec<_> = data.layers.iter().chain(data.config.iter()).collect();
+    let layer = |kind: LayerKind| layers.iter().find(|l| l.kind() == kind);
+    let text = |kind: LayerKind| {
+        layer(kind).and_then(|l| match blobs.read_text(&l.digest, MAX_TEXT_BYTES) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                eprintln!("Warning: {e}");
+                None
+            }
+        })
+    };
+    let provenance = Provenance::of(id, data);
+    let license = ModelLicense::read(&data.layers, blobs.root());
+    let gguf = layer(LayerKind::Weights).and_then(|l| {
+        read_gguf_file(&blobs.path_for(&l.digest))
+            .inspect_err(|e| eprintln!("Warning: no GGUF metadata: {e}"))
+            .ok()
+    });
+
+    let mut out = String::new();
+    writeln!(out, "# {}\n", id.normalize())?;
+    let mut summary = vec![
+        ("Registry", provenance.registry.clone()),
+        ("Namespace", provenance.namespace.clone()),
+        (
+            "Origin",
+            match provenance.origin {
+                Origin::OfficialLibrary => "official library",
+                Origin::CommunityNamespace => "community namespace",
+                Origin::ThirdPartyRegistry => "third-party registry",
+            }
+            .to_string(),
+        ),
+        (
+            "Signed",
+            if provenance.signed {
+                format!("yes ({} layers, not verified)", provenance.signatures.len())
+            } else {
+                "no".to_string()
+            },
+        ),
+    ];
+    if let Some(source) = &provenance.source {
+        summary.push(("Source", source.clone()));
+    }
+    let total: u64 = layers.iter().filter_map(|l| l.size).sum();
+    summary.push(("Size", human_bytes(total)));
+    if let Some(gguf) = &gguf {
+        if let Some(arch) = gguf.architecture() {
+            summary.push(("Architecture", arch.to_string()));
+        }
+        if let Some(ctx) = gguf.context_length() {
+            summary.push(("Context length", ctx.to_string()));
+        }
+    }
+    summary.push((
+        "License",
+        license
+            .family()
+            .map_or_else(|| "none".to_string(), |f| f.to_string()),
+    ));
+    if let Some(weights) = layer(LayerKind::Weights) {
+        summary.push(("Weights", format!("`{}`", weights.digest)));
+    }
+    table(
+        &mut out,
+        ["", ""],
+        summary.iter().map(|(k, v)| [k.to_string(), v.clone()]),
+    )?;
+
+    writeln!(out, "\n## Size\n")?;
+    table(
+        &mut out,
+        ["Layer", "Size"],
+        size_breakdown(layers.iter().copied())
+            .into_iter()
+            .map(|(kind, bytes)| [kind.to_string(), human_bytes(bytes)]),
+    )?;
+
+    if let Some(gguf) = &gguf {
+        writeln!(out, "\n## GGUF metadata\n")?;
+        gguf_table(&mut out, gguf)?;
+    }
+    if let Some(params) = text(LayerKind::Params) {
+        let pretty = serde_json::from_str::<serde_json::Value>(&params)
+            .and_then(|v| serde_json::to_string_pretty(&v))
+            .unwrap_or(params);
+        writeln!(out, "\n## Parameters\n")?;
+        fenced(&mut out, "json", &pretty)?;
+    }
+    if let Some(template) = text(LayerKind::Template) {
+        writeln!(out, "\n## Template\n")?;
+        fenced(&mut out, "", &template)?;
+    }
+    if let Some(system) = text(LayerKind::System) {
+        writeln!(out, "\n## System prompt\n")?;
+        fenced(&mut out, "", &system)?;
+    }
+    if !license.is_absent() {
+        writeln!(out, "\n## License\n")?;
+        for text in &license.texts {
+            fenced(&mut out, "", text)?;
+        }
+        for digest in &license.missing {
+            writeln!(out, "License layer `{digest}` is missing from the store.\n")?;
+        }
+    }
+    Ok(out)
+}
+
+fn gguf_table(out: &mut String, gguf: &GgufMetadata) -> Result<()> {
+    let rows = [
+        ["version".to_string(), gguf.version.to_string()],
+        ["tensor_count".to_string(), gguf.tensor_count.to_string()],
+    ];
+    let metadata = gguf.metadata.iter().map(|(key, value)| {
+        let value = match value {
+            GgufValue::Uint(n) => n.to_string(),
+            GgufValue::Int(n) => n.to_string(),
+            GgufValue::Float(x) => x.to_string(),
+            GgufValue::Bool(b) => b.to_string(),
+            GgufValue::String(s) => s.clone(),
+            GgufValue::Array { len } => format!("[{len} items]"),
+        };
+        [format!("`{key}`"), value]
+    });
+    table(out, ["Key", "Value"], rows.into_iter().chain(metadata))
+}
+
+fn table(
+    out: &mut String,
+    header: [&str; 2],
+    rows: impl IntoIterator<Item = [String; 2]>,
+) -> Result<()> {
+    writeln!(out, "| {} | {} |", header[0], header[1])?;
+    writeln!(out, "|---|---|")?;
+    for [k, v] in rows {
+        writeln!(out, "| {} | {} |", cell(&k), cell(&v))?;
+    }
+    Ok(())
+}
+
+/// Table cells can't hold pipes or line breaks.
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// A code block whose fence is longer than any run of backticks in `text`.
+fn fenced(out: &mut String, lang: &str, text: &str) -> Result<()> {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    writeln!(out, "{fence}{lang}\n{}\n{fence}", text.trim_end())?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+};
+
+use ollama_file_find::{BlobStore, Digest, LayerKind, ManifestStore, ModelId};
+
+use crate::{Result, StorePaths, args::CatBlobArgs};
+
+pub(crate) fn run(paths: &StorePaths, args: CatBlobArgs) -> Result<()> {
+    let blobs = BlobStore::new(&paths.blobs);
+    let digests: Vec<Digest> = match args.layer {
+        Some(kind) => {
+            let kind = LayerKind::from(kind);
+            let id: ModelId = args.target.parse()?;
+            let stored = ManifestStore::new(&paths.manifests)
+                .find(&id)
+                .ok_or_else(|| anyhow::anyhow!("model not found: {}", id.normalize()))?;
+            let digests: Vec<Digest> = stored
+                .data
+                .layers
+                .iter()
+                .chain(stored.data.config.iter())
+                .filter(|l| l.kind() == kind)
+                .map(|l| l.digest)
+                .collect();
+            if digests.is_empty() {
+                anyhow::bail!("{} has no {kind} layer", id.normalize());
+            }
+            digests
+        }
+        None => vec![blobs.resolve(&args.target)?],
+    };
+
+    let mut out = io::stdout().lock();
+    for digest in &digests {
+        let copied = if args.verify {
+            copy(blobs.open_verified(digest)?, &mut out)
+        } else {
+            let path = blobs.path_for(digest);
+            let file =
+                fs::File::open(&path).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+            copy(file, &mut out)
+        };
+        match copied {
+            // The reader (`head`, a pager) has seen enough.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            // A verified read reports a mismatch as `InvalidData` once the whole blob
+            // is through; the bytes are out, but the exit status says they are bad.
+            Err(e) => {
+                let _ = out.flush();
+                return Err(e.into());
+            }
+            Ok(()) => {}
+        }
+    }
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn copy(mut from: impl Read, to: &mut impl Write) -> io::Result<()> {
+    io::copy(&mut from, to).map(|_| ())
+}
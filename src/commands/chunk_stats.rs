@@ -0,0 +1,198 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+};
+
+use ollama_file_find::{
+    BlobStore, Digest, HiddenPolicy, ManifestStore, ModelId, StoredManifest, chunks::sample_chunks,
+};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::ChunkStatsArgs, format::human_bytes};
+
+#[derive(Serialize)]
+struct ChunkReport {
+    models: usize,
+    blobs: usize,
+    /// Bytes read from the distinct blobs
+    bytes: u64,
+    chunks: u64,
+    /// One chunk in this many was fingerprinted
+    sample_one_in: u64,
+    /// Estimated bytes left after chunk-level dedup across all blobs
+    unique_bytes: u64,
+    /// `bytes / unique_bytes`; 1.0 means chunking finds nothing blob dedup doesn't
+    dedup_ratio: f64,
+    /// Most similar model pairs
+    pairs: Vec<Pair>,
+}
+
+#[derive(Serialize)]
+struct Pair {
+    a: String,
+    b: String,
+    /// Estimated bytes the two models have in common
+    shared_bytes: u64,
+    /// Shared bytes as a fraction of the smaller model: what a delta from the other
+    /// would save when sending it
+    similarity: f64,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: ChunkStatsArgs) -> Result<()> {
+    let store = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let models: Vec<StoredManifest> = if args.models.is_empty() {
+        let (found, errors) = store.manifests(HiddenPolicy::Exclude);
+        for e in &errors {
+            eprintln!("Warning: {e}");
+        }
+        found
+    } else {
+        args.models
+            .iter()
+            .map(|name| {
+                let id: ModelId = name.parse()?;
+                store
+                    .find(&id)
+                    .ok_or_else(|| anyhow::anyhow!("model not found: {}", id.normalize()))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    // Fingerprints per distinct blob, with the length of every sampled chunk.
+    let mut lens: HashMap<u64, u32> = HashMap::new();
+    let mut per_blob: BTreeMap<Digest, HashSet<u64>> = BTreeMap::new();
+    let (mut bytes, mut chunks, mut sampled) = (0u64, 0u64, 0u64);
+    for m in &models {
+        for layer in m.data.layers.iter().chain(m.data.config.iter()) {
+            if per_blob.contains_key(&layer.digest) {
+                continue;
+            }
+            let path = blobs.path_for(&layer.digest);
+            let samples = match fs::File::open(&path).and_then(|f| sample_chunks(f, args.sample)) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Warning: {}: {e}", path.display());
+                    per_blob.insert(layer.digest, HashSet::new());
+                    continue;
+                }
+            };
+            bytes += samples.bytes;
+            chunks += samples.chunks;
+            let mut set = HashSet::new();
+            for c in samples.samples {
+                sampled += u64::from(c.len);
+                lens.insert(c.fingerprint, c.len);
+                set.insert(c.fingerprint);
+            }
+            per_blob.insert(layer.digest, set);
+        }
+    }
+    let unique_sampled: u64 = lens.values().map(|&l| u64::from(l)).sum();
+    // Sampled bytes stand for all bytes in proportion.
+    let scale = |n: u64| {
+        if sampled == 0 {
+            0
+        } else {
+            (n as f64 * bytes as f64 / sampled as f64).round() as u64
+        }
+    };
+
+    let model_sets: Vec<HashSet<u64>> = models
+        .iter()
+        .map(|m| {
+            m.data
+                .layers
+                .iter()
+                .chain(m.data.config.iter())
+                .filter_map(|l| per_blob.get(&l.digest))
+                .flatten()
+                .copied()
+                .collect()
+        })
+        .collect();
+    let sizes: Vec<u64> = model_sets
+        .iter()
+        .map(|s| s.iter().map(|fp| u64::from(lens[fp])).sum())
+        .collect();
+    let mut holders: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, set) in model_sets.iter().enumerate() {
+        for &fp in set {
+            holders.entry(fp).or_default().push(i);
+        }
+    }
+    let mut shared: HashMap<(usize, usize), u64> = HashMap::new();
+    for (fp, models) in &holders {
+        for (n, &a) in models.iter().enumerate() {
+            for &b in &models[n + 1..] {
+                *shared.entry((a, b)).or_default() += u64::from(lens[fp]);
+            }
+        }
+    }
+    let mut pairs: Vec<Pair> = shared
+        .into_iter()
+        .map(|((a, b), common)| {
+            let (a_name, b_name) = (models[a].id.normalize(), models[b].id.normalize());
+            let (a_name, b_name) = if a_name <= b_name {
+                (a_name, b_name)
+            } else {
+                (b_name, a_name)
+            };
+            Pair {
+                a: a_name,
+                b: b_name,
+                shared_bytes: scale(common),
+                similarity: common as f64 / sizes[a].min(sizes[b]).max(1) as f64,
+            }
+        })
+        .collect();
+    pairs.sort_by(|x, y| {
+        y.shared_bytes
+            .cmp(&x.shared_bytes)
+            .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+    });
+    pairs.truncate(args.top);
+
+    let unique_bytes = scale(unique_sampled);
+    let report = ChunkReport {
+        models: models.len(),
+        blobs: per_blob.len(),
+        bytes,
+        chunks,
+        sample_one_in: args.sample,
+        unique_bytes,
+        dedup_ratio: if unique_bytes == 0 {
+            1.0
+        } else {
+            bytes as f64 / unique_bytes as f64
+        },
+        pairs,
+    };
+    if args.plain {
+        println!(
+            "{} models, {} blobs, {} in {} chunks (1 in {} sampled)",
+            report.models,
+            report.blobs,
+            human_bytes(report.bytes),
+            report.chunks,
+            report.sample_one_in
+        );
+        println!(
+            "~{} unique after chunk dedup (ratio {:.2})",
+            human_bytes(report.unique_bytes),
+            report.dedup_ratio
+        );
+        for p in &report.pairs {
+            println!(
+                "{}\t{}\t{}\t{:.0}%",
+                p.a,
+                p.b,
+                human_bytes(p.shared_bytes),
+                p.similarity * 100.0
+            );
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use ollama_file_find::{ManifestStore, ModelId, clone_model};
+use serde_json::json;
+
+use crate::{Result, StorePaths, args::CloneArgs, config::Config};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: CloneArgs) -> Result<()> {
+    let src: ModelId = args.source.parse()?;
+    // A bare tag clones within the same model; anything with a name part is a full name.
+    let dst: ModelId = if args.target.contains([':', '/']) {
+        args.target.parse()?
+    } else if args.target.is_empty() || args.target.contains('\\') {
+        anyhow::bail!("invalid tag: {:?}", args.target);
+    } else {
+        ModelId {
+            tag: args.target.clone(),
+            ..src.clone()
+        }
+    };
+    if dst.normalize() == src.normalize() {
+        anyhow::bail!("source and target are the same model");
+    }
+    let manifests = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+    if !args.force && manifests.find(&dst).is_some() {
+        anyhow::bail!(
+            "{} already exists (use --force to replace it)",
+            dst.normalize()
+        );
+    }
+    let path = clone_model(&manifests, &src, &dst)?;
+
+    if args.plain {
+        println!(
+            "{}\t-> {}\t{}",
+            src.normalize(),
+            dst.normalize(),
+            path.display()
+        );
+    } else {
+        let report = json!({
+            "source": src.normalize(),
+            "target": dst.normalize(),
+            "manifest": path,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+    use crate::test_util::{backups, layer, parse, paths};
+
+    #[test]
+    fn test_clone_copies_manifest_and_force_backs_up_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"llama3")
+            .model("me/tuned:latest")
+            .layer(LayerKind::Weights, b"tuned")
+            .build(dir.path())
+            .unwrap();
+        let paths = paths(&store);
+        let config = Config::default();
+
+        run(&paths, &config, parse(&["llama3:8b", "8b-backup"])).unwrap();
+        assert_eq!(
+            fs::read(store.manifest_path("llama3:8b-backup")).unwrap(),
+            fs::read(store.manifest_path("llama3:8b")).unwrap()
+        );
+        assert_eq!(
+            layer(&store, "llama3:8b-backup", LayerKind::Weights).unwrap(),
+            b"llama3"
+        );
+        assert!(backups(&store, "llama3:8b-backup").is_empty());
+
+        let replaced = fs::read(store.manifest_path("me/tuned:latest")).unwrap();
+        assert!(run(&paths, &config, parse(&["llama3:8b", "me/tuned:latest"])).is_err());
+        run(
+            &paths,
+            &config,
+            parse(&["llama3:8b", "me/tuned:latest", "--force"]),
+        )
+        .unwrap();
+        assert_eq!(
+            layer(&store, "me/tuned:latest", LayerKind::Weights).unwrap(),
+            b"llama3"
+        );
+        let backups = backups(&store, "me/tuned:latest");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0].path).unwrap(), replaced);
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use ollama_file_find::{
+    BlobStore, Digest, LayerInfo, LayerKind, ManifestStore, ModelId, StoredManifest,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    Result, StorePaths,
+    args::CompareArgs,
+    format::{human_bytes, human_opt},
+};
+
+/// Metadata layers (templates, params, config) larger than this are not compared.
+const MAX_TEXT_BYTES: u64 = 1024 * 1024;
+
+#[derive(Serialize)]
+struct Comparison {
+    a: String,
+    b: String,
+    layers: LayerDiff,
+    /// Config keys whose values differ (`null` where a side lacks the key)
+    config: BTreeMap<String, [Value; 2]>,
+    /// Params keys whose values differ
+    params: BTreeMap<String, [Value; 2]>,
+    template: TextDiff,
+    system: TextDiff,
+    /// Unified diffs of the template, system, and params layers present in both models
+    /// and differing between them
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    unified: BTreeMap<&'static str, String>,
+}
+
+#[derive(Serialize, Default)]
+struct LayerDiff {
+    shared: Vec<LayerRef>,
+    only_a: Vec<LayerRef>,
+    only_b: Vec<LayerRef>,
+    shared_bytes: u64,
+    only_a_bytes: u64,
+    only_b_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct LayerRef {
+    digest: Digest,
+    kind: LayerKind,
+    size: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct TextDiff {
+    same: bool,
+    /// Both texts, only when they differ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    a: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b: Option<String>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: CompareArgs) -> Result<()> {
+    let store = ManifestStore::new(&paths.manifests);
+    let find = |name: &str| -> Result<StoredManifest> {
+        let id: ModelId = name.parse()?;
+        store
+            .find(&id)
+            .ok_or_else(|| anyhow::anyhow!("model not found: {}", id.normalize()))
+    };
+    let (a, b) = (find(&args.a)?, find(&args.b)?);
+    let blobs = BlobStore::new(&paths.blobs);
+
+    let template = (
+        layer_text(&blobs, &a, LayerKind::Template),
+        layer_text(&blobs, &b, LayerKind::Template),
+    );
+    let system = (
+        layer_text(&blobs, &a, LayerKind::System),
+        layer_text(&blobs, &b, LayerKind::System),
+    );
+    let params = (
+        layer_text(&blobs, &a, LayerKind::Params),
+        layer_text(&blobs, &b, LayerKind::Params),
+    );
+    let (a_name, b_name) = (a.id.normalize(), b.id.normalize());
+    let mut unified = BTreeMap::new();
+    for (label, (ta, tb)) in [
+        ("template", &template),
+        ("system", &system),
+        (
+            "params",
+            &(
+                params.0.as_deref().map(pretty_json),
+                params.1.as_deref().map(pretty_json),
+            ),
+        ),
+    ] {
+        if let (Some(ta), Some(tb)) = (ta, tb)
+            && ta != tb
+        {
+            unified.insert(label, unified_diff(ta, tb, &a_name, &b_name, label));
+        }
+    }
+
+    let comparison = Comparison {
+        layers: layer_diff(&a, &b),
+        config: key_diff(
+            a.data.config.as_ref().map(|l| text(&blobs, l)),
+            b.data.config.as_ref().map(|l| text(&blobs, l)),
+        ),
+        params: key_diff(params.0, params.1),
+        template: text_diff(template.0, template.1),
+        system: text_diff(system.0, system.1),
+        unified,
+        a: a_name,
+        b: b_name,
+    };
+
+    if args.diff {
+        for diff in comparison.unified.values() {
+            print!("{diff}");
+        }
+        return Ok(());
+    }
+    if args.plain {
+        print_summary(&comparison);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&comparison)?);
+    }
+    Ok(())
+}
+
+fn layer_diff(a: &StoredManifest, b: &StoredManifest) -> LayerDiff {
+    let all = |m: &StoredManifest| -> Vec<LayerInfo> {
+        m.data
+            .layers
+            .iter()
+            .chain(m.data.config.iter())
+            .cloned()
+            .collect()
+    };
+    let (a, b) = (all(a), all(b));
+    let digests =
+        |layers: &[LayerInfo]| -> HashSet<Digest> { layers.iter().map(|l| l.digest).collect() };
+    let (in_a, in_b) = (digests(&a), digests(&b));
+
+    let mut diff = LayerDiff::default();
+    let to_ref = |l: &LayerInfo| LayerRef {
+        digest: l.digest,
+        kind: l.kind(),
+        size: l.size,
+    };
+    for l in &a {
+        let size = l.size.unwrap_or(0);
+        if in_b.contains(&l.digest) {
+            diff.shared_bytes += size;
+            diff.shared.push(to_ref(l));
+        } else {
+            diff.only_a_bytes += size;
+            diff.only_a.push(to_ref(l));
+        }
+    }
+    for l in b.iter().filter(|l| !in_a.contains(&l.digest)) {
+        diff.only_b_bytes += l.size.unwrap_or(0);
+        diff.only_b.push(to_ref(l));
+    }
+    diff
+}
+
+fn text(blobs: &BlobStore, layer: &LayerInfo) -> String {
+    blobs
+        .read_text(&layer.digest, MAX_TEXT_BYTES)
+        .unwrap_or_else(|e| {
+            eprintln!("Warning: {e}");
+            String::new()
+        })
+}
+
+/// Text of the first layer of `kind`, if the model has one.
+fn layer_text(blobs: &BlobStore, m: &StoredManifest, kind: LayerKind) -> Option<String> {
+    m.data
+        .layers
+        .iter()
+        .find(|l| l.kind() == kind)
+        .map(|l| text(blobs, l))
+}
+
+/// Keys of two JSON objects whose values differ.
+fn key_diff(a: Option<String>, b: Option<String>) -> BTreeMap<String, [Value; 2]> {
+    let object = |text: Option<String>| -> Map<String, Value> {
+        match text.and_then(|t| serde_json::from_str(&t).ok()) {
+            Some(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    };
+    let (a, b) = (object(a), object(b));
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .filter_map(|k| {
+            let (va, vb) = (
+                a.get(k).cloned().unwrap_or(Value::Null),
+                b.get(k).cloned().unwrap_or(Value::Null),
+            );
+            (va != vb).then(|| (k.clone(), [va, vb]))
+        })
+        .collect()
+}
+
+fn text_diff(a: Option<String>, b: Option<String>) -> TextDiff {
+    if a == b {
+        TextDiff {
+            same: true,
+            a: None,
+            b: None,
+        }
+    } else {
+        TextDiff { same: false, a, b }
+    }
+}
+
+/// Params re-serialized with one key per line, so the diff shows individual settings.
+fn pretty_json(text: &str) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(v) => serde_json::to_string_pretty(&v).unwrap_or_else(|_| text.to_string()) + "\n",
+        Err(_) => text.to_string(),
+    }
+}
+
+fn unified_diff(a: &str, b: &str, a_name: &str, b_name: &str, label: &str) -> String {
+    similar::TextDiff::from_lines(a, b)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{a_name}/{label}"), &format!("{b_name}/{label}"))
+        .to_string()
+}
+
+fn print_summary(c: &Comparison) {
+    let l = &c.layers;
+    println!("{} vs {}", c.a, c.b);
+    println!(
+        "layers: {} shared ({}), {} only in {} ({}), {} only in {} ({})",
+        l.shared.len(),
+        human_bytes(l.shared_bytes),
+        l.only_a.len(),
+        c.a,
+        human_bytes(l.only_a_bytes),
+        l.only_b.len(),
+        c.b,
+        human_bytes(l.only_b_bytes)
+    );
+    for (side, layers) in [("-", &l.only_a), ("+", &l.only_b)] {
+        for layer in layers {
+            println!(
+                "  {side} {:<9} {}  {}",
+                layer.kind.to_string(),
+                layer.digest,
+                human_opt(layer.size)
+            );
+        }
+    }
+    for (label, keys) in [("config", &c.config), ("params", &c.params)] {
+        if keys.is_empty() {
+            println!("{label}: identical");
+        } else {
+            println!("{label}:");
+            for (k, [a, b]) in keys {
+                println!("  {k}: {a} -> {b}");
+            }
+        }
+    }
+    for (label, diff) in [("template", &c.template), ("system", &c.system)] {
+        println!(
+            "{label}: {}",
+            if diff.same { "identical" } else { "differs" }
+        );
+    }
+    for diff in c.unified.values() {
+        println!();
+        print!("{diff}");
+    }
+}
@@ -0,0 +1,47 @@
+use ollama_file_find::{HiddenPolicy, ManifestStore};
+
+use crate::{
+    Result, StorePaths,
+    args::CompleteNamesArgs,
+    commands::daemon::{default_socket, query},
+};
+
+pub(crate) fn run(models_dir: Option<std::path::PathBuf>, args: CompleteNamesArgs) -> Result<()> {
+    let prefix = args.prefix.as_deref().unwrap_or("");
+    // A running daemon already holds every name in memory. It only knows about the
+    // tags it was started with, so hidden tags always go through the walk.
+    let from_daemon = (!args.no_daemon && args.hidden.policy() == HiddenPolicy::Exclude)
+        .then(|| daemon_names(args.socket.unwrap_or_else(default_socket).as_path(), prefix))
+        .flatten();
+    let names = match from_daemon {
+        Some(names) => names,
+        None => walk_names(
+            &StorePaths::resolve(models_dir)?,
+            prefix,
+            args.hidden.policy(),
+        ),
+    };
+    for name in names {
+        println!("{name}");
+    }
+    Ok(())
+}
+
+/// Names from the daemon, or `None` if it is not running or answered unexpectedly.
+fn daemon_names(socket: &std::path::Path, prefix: &str) -> Option<Vec<String>> {
+    let reply = query(socket, &format!("complete {prefix}")).ok()?;
+    serde_json::from_value(reply).ok()
+}
+
+/// Names derived from manifest paths, without opening any manifest.
+fn walk_names(paths: &StorePaths, prefix: &str, hidden: HiddenPolicy) -> Vec<String> {
+    let (ids, _) = ManifestStore::new(&paths.manifests).ids(hidden);
+    let mut names: Vec<String> = ids
+        .iter()
+        .map(|id| id.normalize())
+        .filter(|n| n.starts_with(prefix))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+
+use ollama_file_find::{LicenseFamily, ModelLicense, ScanArgs, scan_manifests};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::ComplianceArgs};
+
+#[derive(Serialize, Default)]
+struct ComplianceReport {
+    /// Model names per detected license family
+    families: BTreeMap<LicenseFamily, Vec<String>>,
+    /// Models with a license layer whose blob is absent or unreadable
+    missing_license_blob: Vec<String>,
+    /// Models whose manifest declares no license layer
+    no_license: Vec<String>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: ComplianceArgs) -> Result<()> {
+    let outcome = scan_manifests(
+        &ScanArgs::new(&paths.manifests, &paths.blobs)
+            .with_hidden(args.hidden.policy())
+            .with_verbose(true),
+    );
+    for e in &outcome.errors {
+        eprintln!("Warning: {e}");
+    }
+
+    let mut report = ComplianceReport::default();
+    for m in &outcome.models {
+        let license = ModelLicense::read(m.layers.as_deref().unwrap_or_default(), &paths.blobs);
+        if license.is_absent() {
+            report.no_license.push(m.name.clone());
+            continue;
+        }
+        if !license.missing.is_empty() {
+            report.missing_license_blob.push(m.name.clone());
+        }
+        if let Some(family) = license.family() {
+            report
+                .families
+                .entry(family)
+                .or_default()
+                .push(m.name.clone());
+        }
+    }
+
+    if args.plain {
+        for (family, names) in &report.families {
+            println!("{family} ({})", names.len());
+            for name in names {
+                println!("  {name}");
+            }
+        }
+        for (label, names) in [
+            ("missing license blob", &report.missing_license_blob),
+            ("no license", &report.no_license),
+        ] {
+            if !names.is_empty() {
+                println!("{label} ({})", names.len());
+                for name in names {
+                    println!("  {name}");
+                }
+            }
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
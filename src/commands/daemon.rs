@@ -0,0 +1,242 @@
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+    thread,
+    time::Instant,
+};
+
+use interprocess::local_socket::{GenericFilePath, ListenerOptions, Stream, prelude::*};
+use ollama_file_find::{
+    BlobStatus, Digest, HiddenPolicy, ListedModel, OllamaHome, ScanArgs, find_model, scan_manifests,
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    Result, StorePaths,
+    args::DaemonArgs,
+    events::{self, now_secs},
+    watch::watch_store,
+};
+
+/// In-memory snapshot of the store answered from by the daemon's APIs.
+pub(crate) struct Index {
+    pub models: Vec<ListedModel>,
+    pub errors: Vec<String>,
+    /// Seconds since the epoch of the scan that produced this snapshot
+    pub scanned_at: u64,
+    /// Ollama's own directory (identity key, prompt history)
+    pub home: OllamaHome,
+}
+
+/// A referenced blob that is not in a usable state.
+#[derive(Serialize)]
+pub(crate) struct BlobProblem<'a> {
+    pub model: &'a str,
+    pub digest: Digest,
+    pub problem: BlobStatus,
+}
+
+impl Index {
+    pub(crate) fn build(paths: &StorePaths, hidden: HiddenPolicy) -> Self {
+        let outcome = scan_manifests(
+            &ScanArgs::new(&paths.manifests, &paths.blobs)
+                .with_hidden(hidden)
+                .with_verbose(true),
+        );
+        let models_dir = paths.manifests.parent().unwrap_or(&paths.manifests);
+        Index {
+            models: outcome.models,
+            errors: outcome.errors.iter().map(ToString::to_string).collect(),
+            scanned_at: now_secs(),
+            home: OllamaHome::inspect(&OllamaHome::for_models_dir(models_dir), models_dir),
+        }
+    }
+
+    /// Missing or size-mismatched blobs, for one model or (with `None`) all of them.
+    pub(crate) fn problems<'a>(&'a self, model: Option<&ListedModel>) -> Vec<BlobProblem<'a>> {
+        let models = self
+            .models
+            .iter()
+            .filter(|m| model.is_none_or(|wanted| wanted.name == m.name));
+        let mut out = Vec::new();
+        for m in models {
+            for b in m.blob_paths.iter().flatten() {
+                if !b.status.is_problem() {
+                    continue;
+                }
+                out.push(BlobProblem {
+                    model: &m.name,
+                    digest: b.digest,
+                    problem: b.status,
+                });
+            }
+        }
+        out
+    }
+
+    /// Answer one request line.
+    fn answer(&self, line: &str) -> Value {
+        let (cmd, arg) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .map_or((line.trim(), ""), |(c, a)| (c, a.trim()));
+        match cmd {
+            "list" => json!(self.models),
+            "which" => match find_model(&self.models, arg) {
+                Some(m) => json!({
+                    "name": m.name,
+                    "manifest_path": m.manifest_path,
+                    "primary_blob_path": m.primary_blob_path,
+                }),
+                None => json!({ "error": format!("model not found: {arg}") }),
+            },
+            "complete" => json!(self.complete(arg)),
+            "health" => self.health(),
+            _ => json!({ "error": format!("unknown command: {cmd}") }),
+        }
+    }
+
+    /// Sorted names starting with `prefix`.
+    pub(crate) fn complete(&self, prefix: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .models
+            .iter()
+            .map(|m| m.name.as_str())
+            .filter(|n| n.starts_with(prefix))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    pub(crate) fn health(&self) -> Value {
+        let problems = self.problems(None);
+        let mut by_status: BTreeMap<&str, usize> = BTreeMap::new();
+        for p in &problems {
+            *by_status.entry(p.problem.as_str()).or_default() += 1;
+        }
+        json!({
+            "ok": problems.is_empty() && self.errors.is_empty(),
+            "models": self.models.len(),
+            "blob_problems": by_status,
+            "problems": problems,
+            "errors": self.errors,
+            "scanned_at": self.scanned_at,
+            "ollama_home": self.home,
+        })
+    }
+}
+
+/// Default socket location: a file in the runtime dir on Unix, a named pipe on Windows.
+pub(crate) fn default_socket() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(r"\\.\pipe\ollama-file-find")
+    } else {
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("ollama-file-find.sock")
+    }
+}
+
+/// Send one request line to a running daemon and return its reply.
+pub(crate) fn query(socket: &Path, request: &str) -> Result<Value> {
+    let conn = Stream::connect(socket.to_fs_name::<GenericFilePath>()?)?;
+    let mut reader = BufReader::new(conn);
+    reader
+        .get_mut()
+        .write_all(format!("{}\n", request.trim()).as_bytes())?;
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+pub(crate) fn run(paths: &StorePaths, args: DaemonArgs) -> Result<()> {
+    if args.install_service {
+        return crate::service::install(paths.manifests.parent().unwrap_or(&paths.manifests));
+    }
+    let socket = args.socket.unwrap_or_else(default_socket);
+    let event_log = (!args.no_event_log).then(|| {
+        args.event_log
+            .unwrap_or_else(|| events::default_log_path(paths))
+    });
+    let index = Arc::new(RwLock::new(Index::build(paths, args.hidden.policy())));
+
+    let listener = ListenerOptions::new()
+        .name(socket.as_path().to_fs_name::<GenericFilePath>()?)
+        .try_overwrite(true)
+        .create_sync()?;
+    eprintln!("Listening on {}", socket.display());
+
+    #[cfg(feature = "grpc")]
+    let grpc_events = args
+        .grpc
+        .map(|addr| {
+            eprintln!("Serving gRPC on {addr}");
+            crate::grpc::spawn(addr, Arc::clone(&index))
+        })
+        .transpose()?;
+
+    let served = Arc::clone(&index);
+    thread::spawn(move || {
+        for conn in listener.incoming() {
+            match conn {
+                Ok(conn) => {
+                    let index = Arc::clone(&served);
+                    thread::spawn(move || serve_connection(conn, &index));
+                }
+                Err(e) => eprintln!("Warning: connection failed: {e}"),
+            }
+        }
+    });
+
+    watch_store(paths, args.poll, || {
+        let started = Instant::now();
+        let fresh = Index::build(paths, args.hidden.policy());
+        let count = fresh.models.len();
+        let mut current = index.write().unwrap_or_else(PoisonError::into_inner);
+        let changes = events::diff(&current.models, &fresh.models);
+        *current = fresh;
+        drop(current);
+        eprintln!("Reindexed {count} models in {:?}", started.elapsed());
+        for event in &changes {
+            eprintln!("  {} {}", event.kind, event.model);
+        }
+        if let Some(log) = &event_log
+            && let Err(e) = events::append_log(log, &changes)
+        {
+            eprintln!("Warning: could not write event log {}: {e}", log.display());
+        }
+        #[cfg(feature = "grpc")]
+        if let Some(tx) = &grpc_events {
+            for event in changes {
+                // No subscribers is not an error.
+                let _ = tx.send(event);
+            }
+        }
+    })
+}
+
+/// Answer newline-delimited requests until the client hangs up.
+fn serve_connection(conn: Stream, index: &RwLock<Index>) {
+    let mut reader = BufReader::new(conn);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        let reply = index
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .answer(&line);
+        let mut out = reply.to_string();
+        out.push('\n');
+        if reader.get_mut().write_all(out.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use ollama_file_find::{BlobStore, ManifestStore, TagGroup, TagVariant, tag_groups};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::DedupeTagsArgs,
+    config::Config,
+    format::human_bytes,
+    removal::{Failed, remove_models},
+};
+
+#[derive(Serialize)]
+struct DedupeReport {
+    groups: Vec<TagGroup>,
+    /// Sum of the groups' `freed_bytes`, once `prune --include-held --execute` has run
+    freed_bytes: u64,
+    /// Tags whose manifests were removed (none without `--execute`)
+    removed: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<Failed>,
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: DedupeTagsArgs) -> Result<()> {
+    let manifests = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+    let (groups, errors) = tag_groups(&manifests, &BlobStore::new(&paths.blobs), args.keep.into());
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+
+    let (removed, failed) = if args.execute {
+        remove_models(
+            &manifests,
+            groups.iter().flat_map(|g| &g.remove).map(|v| &v.name),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let report = DedupeReport {
+        freed_bytes: groups.iter().map(|g| g.freed_bytes).sum(),
+        groups,
+        removed,
+        failed,
+    };
+    if args.plain {
+        print_plain(&report, !args.execute);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    if !args.execute && report.groups.iter().any(|g| !g.remove.is_empty()) {
+        eprintln!("Nothing was removed; run again with --execute to remove these tags");
+    }
+    if !report.failed.is_empty() {
+        anyhow::bail!("{} tag(s) could not be removed", report.failed.len());
+    }
+    Ok(())
+}
+
+fn print_plain(report: &DedupeReport, dry_run: bool) {
+    let describe = |v: &TagVariant| {
+        format!(
+            "{} ({}, {})",
+            v.name,
+            v.quantization.as_deref().unwrap_or("?"),
+            human_bytes(v.size)
+        )
+    };
+    for g in &report.groups {
+        match &g.parameter_size {
+            Some(size) => println!("{} {size}", g.model),
+            None => println!("{}", g.model),
+        }
+        println!("  keep    {}", describe(&g.keep));
+        for v in &g.remove {
+            let verb = if report.removed.contains(&v.name) {
+                "removed"
+            } else {
+                "remove "
+            };
+            println!("  {verb} {}", describe(v));
+        }
+    }
+    for f in &report.failed {
+        eprintln!("Error: {}: {}", f.model, f.error);
+    }
+    let tags: usize = report.groups.iter().map(|g| g.remove.len()).sum();
+    println!(
+        "{tags} tag(s) in {} group(s), {} {}",
+        report.groups.len(),
+        human_bytes(report.freed_bytes),
+        if dry_run {
+            "would be freed"
+        } else {
+            "freed once `prune --include-held --execute` runs"
+        }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use ollama_file_find::{HiddenPolicy, LayerKind, ModelId, fixtures::FakeStore};
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: DedupeTagsArgs,
+    }
+
+    fn installed(manifests: &ManifestStore) -> Vec<String> {
+        let (ids, _) = manifests.ids(HiddenPolicy::Exclude);
+        ids.iter().map(ModelId::normalize).collect()
+    }
+
+    #[test]
+    fn test_dedupe_tags_removes_only_with_execute() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3.1:8b-instruct-q4_K_M")
+            .layer(LayerKind::Weights, b"q4 weights")
+            .model("llama3.1:8b-instruct-q8_0")
+            .layer(LayerKind::Weights, b"q8 weights, larger")
+            .build(dir.path().join("models"))
+            .unwrap();
+        let paths = StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap();
+        let manifests = store.manifests();
+        let dedupe = |argv: &[&str]| {
+            let argv = std::iter::once("dedupe-tags").chain(argv.iter().copied());
+            run(&paths, &Config::default(), Cli::parse_from(argv).args).unwrap();
+        };
+        let before = installed(&manifests);
+        assert_eq!(before.len(), 2);
+
+        dedupe(&["--plain"]);
+        assert_eq!(installed(&manifests), before);
+
+        dedupe(&["--plain", "--execute"]);
+        let after = installed(&manifests);
+        assert_eq!(after.len(), 1);
+        assert!(after[0].ends_with("llama3.1:8b-instruct-q8_0"), "{after:?}");
+        let removed: ModelId = "llama3.1:8b-instruct-q4_K_M".parse().unwrap();
+        assert_eq!(manifests.backups(&removed).len(), 1);
+    }
+}
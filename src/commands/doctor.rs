@@ -0,0 +1,99 @@
+use std::process::ExitCode;
+
+use ollama_file_find::{
+    CaseCollision, HiddenPolicy, ManifestStore, OllamaHome, case_collisions, is_case_insensitive,
+};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::DoctorArgs, format::human_bytes};
+
+#[derive(Serialize)]
+struct DoctorReport {
+    ok: bool,
+    /// Whether the manifests directory's filesystem ignores case (`null` if unknown)
+    case_insensitive: Option<bool>,
+    case_collisions: Vec<CaseCollision>,
+    ollama_home: OllamaHome,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: DoctorArgs) -> Result<ExitCode> {
+    let (manifests, errors) = ManifestStore::new(&paths.manifests).manifests(HiddenPolicy::Include);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let collisions = case_collisions(manifests.iter().map(|m| &m.id));
+    let models_dir = paths.manifests.parent().unwrap_or(&paths.manifests);
+    let report = DoctorReport {
+        ok: collisions.is_empty(),
+        case_insensitive: is_case_insensitive(&paths.manifests),
+        case_collisions: collisions,
+        ollama_home: OllamaHome::inspect(&OllamaHome::for_models_dir(models_dir), models_dir),
+    };
+
+    if args.plain {
+        let fs = match report.case_insensitive {
+            Some(true) => "case-insensitive",
+            Some(false) => "case-sensitive",
+            None => "unknown case sensitivity",
+        };
+        println!("filesystem: {fs}");
+        print_home(&report.ollama_home);
+        for c in &report.case_collisions {
+            println!(
+                "case collision ({}): {} -> {}",
+                format!("{:?}", c.level).to_lowercase(),
+                c.spellings.join(" vs "),
+                c.models.join(", ")
+            );
+        }
+        if !report.ok {
+            println!(
+                "these names resolve to the same files on macOS and Windows; rename or \
+                 remove all but one before using the store there"
+            );
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if report.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn print_home(home: &OllamaHome) {
+    if !home.exists {
+        println!("ollama home: {} (not found)", home.path.display());
+        return;
+    }
+    println!(
+        "ollama home: {}{}",
+        home.path.display(),
+        if home.holds_models {
+            ""
+        } else {
+            " (models are elsewhere)"
+        }
+    );
+    match &home.public_key {
+        Some(key) => println!(
+            "  public key: {} {}",
+            key.key_type,
+            key.fingerprint
+                .as_deref()
+                .unwrap_or("(fingerprint unavailable)")
+        ),
+        None => println!("  public key: none (created on first `ollama serve`)"),
+    }
+    if home.public_key.is_some() && !home.private_key {
+        println!("  private key: MISSING (registry pushes will fail)");
+    }
+    if let Some(h) = &home.history {
+        println!(
+            "  history: {} entries ({})",
+            h.entries,
+            human_bytes(h.bytes)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use ollama_file_find::{BlobSizes, Digest, ScanArgs, scan_manifests};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::DuArgs,
+    format::{human_bytes, human_opt},
+};
+
+#[derive(Serialize)]
+struct ModelUsage {
+    name: String,
+    sizes: BlobSizes,
+}
+
+#[derive(Serialize)]
+struct BlobUsage {
+    digest: Digest,
+    path: PathBuf,
+    sizes: BlobSizes,
+    /// Apparent minus allocated bytes (sparse regions or transparent compression)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    savings: Option<u64>,
+    /// Estimated apparent / compressed size (`--compressibility`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compress_ratio: Option<f64>,
+}
+
+/// Estimated effect of compressing the distinct blobs, from samples.
+#[derive(Serialize)]
+struct Compressibility {
+    zstd_level: i32,
+    /// Bytes actually read and compressed
+    sampled_bytes: u64,
+    /// Apparent size of the blobs probed
+    apparent: u64,
+    /// Estimated size once compressed
+    compressed: u64,
+    ratio: f64,
+}
+
+#[derive(Serialize)]
+struct DuReport {
+    models: Vec<ModelUsage>,
+    /// Usage of the distinct blobs referenced by the listed models
+    total: BlobSizes,
+    unique_blobs: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blobs: Option<Vec<BlobUsage>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compressibility: Option<Compressibility>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: DuArgs) -> Result<()> {
+    let outcome = scan_manifests(
+        &ScanArgs::new(&paths.manifests, &paths.blobs)
+            .with_hidden(args.hidden.policy())
+            .with_verbose(true),
+    );
+    for e in &outcome.errors {
+        eprintln!("Warning: {e}");
+    }
+
+    let mut seen = HashSet::new();
+    let mut total = BlobSizes::default();
+    let mut blobs = Vec::new();
+    let mut present = Vec::new();
+    let mut models = Vec::with_capacity(outcome.models.len());
+    for m in &outcome.models {
+        let mut sizes = BlobSizes::default();
+        for blob in m.blob_paths.iter().flatten() {
+            sizes.add(&blob.sizes);
+            if seen.insert(blob.digest) {
+                total.add(&blob.sizes);
+                if blob.status.is_present() {
+                    present.push((blob.digest, blob.path.clone()));
+                }
+                if args.blobs && blob.status.is_present() {
+                    blobs.push(BlobUsage {
+                        digest: blob.digest,
+                        path: blob.path.clone(),
+                        sizes: blob.sizes,
+                        savings: blob.sizes.allocation_savings().filter(|&s| s > 0),
+                        compress_ratio: None,
+                    });
+                }
+            }
+        }
+        models.push(ModelUsage {
+            name: m.name.clone(),
+            sizes,
+        });
+    }
+    let (compressibility, ratios) = probe::run(&args, &present)?;
+    for b in &mut blobs {
+        b.compress_ratio = ratios.get(&b.digest).copied();
+    }
+    let report = DuReport {
+        models,
+        total,
+        unique_blobs: seen.len(),
+        blobs: args.blobs.then_some(blobs),
+        compressibility,
+    };
+
+    if args.plain {
+        print_table(&report);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+fn print_table(report: &DuReport) {
+    let width = report
+        .models
+        .iter()
+        .map(|m| m.name.len())
+        .chain([5])
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{:<width$}  {:>10}  {:>10}  {:>10}",
+        "NAME", "DECLARED", "ACTUAL", "ON DISK"
+    );
+    let row = |name: &str, s: &BlobSizes| {
+        println!(
+            "{name:<width$}  {:>10}  {:>10}  {:>10}",
+            human_opt(s.declared),
+            human_opt(s.actual),
+            human_opt(s.physical)
+        );
+    };
+    for m in &report.models {
+        row(&m.name, &m.sizes);
+    }
+    row("TOTAL", &report.total);
+
+    if let Some(blobs) = &report.blobs {
+        println!();
+        println!(
+            "{:<19}  {:>10}  {:>10}  NOTE",
+            "BLOB", "APPARENT", "ALLOCATED"
+        );
+        for b in blobs {
+            let hex = b.digest.hex();
+            let note = match (b.savings, b.compress_ratio) {
+                (Some(_), _) => "sparse/compressed".to_string(),
+                (None, Some(r)) => format!("zstd ~{r:.2}x"),
+                (None, None) => String::new(),
+            };
+            println!(
+                "{:<19}  {:>10}  {:>10}  {note}",
+                &hex[..19],
+                human_opt(b.sizes.actual),
+                human_opt(b.sizes.physical)
+            );
+        }
+    }
+
+    let apparent = report.total.actual.unwrap_or(0);
+    match report.total.physical {
+        Some(physical) => println!(
+            "{} unique blobs, {} apparent, {} allocated ({} saved by sparse/compressed blobs)",
+            report.unique_blobs,
+            human_bytes(apparent),
+            human_bytes(physical),
+            human_bytes(report.total.allocation_savings().unwrap_or(0))
+        ),
+        None => println!(
+            "{} unique blobs, {} apparent",
+            report.unique_blobs,
+            human_bytes(apparent)
+        ),
+    }
+    if let Some(c) = &report.compressibility {
+        println!(
+            "zstd -{} would store {} as ~{} (ratio {:.2}, from {} sampled)",
+            c.zstd_level,
+            human_bytes(c.apparent),
+            human_bytes(c.compressed),
+            c.ratio,
+            human_bytes(c.sampled_bytes)
+        );
+    }
+}
+
+type Probed = (Option<Compressibility>, HashMap<Digest, f64>);
+
+#[cfg(not(feature = "zstd"))]
+mod probe {
+    use std::{collections::HashMap, path::PathBuf};
+
+    use ollama_file_find::Digest;
+
+    use super::Probed;
+    use crate::{Result, args::DuArgs};
+
+    pub(super) fn run(_: &DuArgs, _: &[(Digest, PathBuf)]) -> Result<Probed> {
+        Ok((None, HashMap::new()))
+    }
+}
+
+#[cfg(feature = "zstd")]
+mod probe {
+    use std::{
+        collections::HashMap,
+        fs::File,
+        io::{self, Read, Seek, SeekFrom},
+        path::{Path, PathBuf},
+    };
+
+    use ollama_file_find::Digest;
+
+    use super::{Compressibility, Probed};
+    use crate::{Result, args::DuArgs};
+
+    /// Compressed independently, as filesystems compress extents (btrfs uses 128 KiB).
+    const BLOCK: u64 = 128 * 1024;
+    /// Blocks read per blob, spread evenly over it.
+    const SAMPLES: u64 = 64;
+
+    /// Sample every blob if `--compressibility` was given, returning the totals and
+    /// each blob's ratio.
+    pub(super) fn run(args: &DuArgs, blobs: &[(Digest, PathBuf)]) -> Result<Probed> {
+        let mut ratios = HashMap::new();
+        if !args.compressibility {
+            return Ok((None, ratios));
+        }
+        let level = args.zstd_level;
+        let mut compressor = zstd::bulk::Compressor::new(level)?;
+        let (mut sampled, mut apparent, mut compressed) = (0u64, 0u64, 0u64);
+        for (digest, path) in blobs {
+            let (size, read, out) = match sample(path, &mut compressor) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Warning: {}: {e}", path.display());
+                    continue;
+                }
+            };
+            if read == 0 {
+                continue;
+            }
+            let ratio = read as f64 / out.max(1) as f64;
+            ratios.insert(*digest, ratio);
+            sampled += read;
+            apparent += size;
+            compressed += (size as f64 / ratio).round() as u64;
+        }
+        let totals = Compressibility {
+            zstd_level: level,
+            sampled_bytes: sampled,
+            apparent,
+            compressed,
+            ratio: if compressed == 0 {
+                1.0
+            } else {
+                apparent as f64 / compressed as f64
+            },
+        };
+        Ok((Some(totals), ratios))
+    }
+
+    /// File size, bytes sampled, and their compressed size.
+    fn sample(
+        path: &Path,
+        compressor: &mut zstd::bulk::Compressor<'_>,
+    ) -> io::Result<(u64, u64, u64)> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let blocks = size.div_ceil(BLOCK);
+        let offsets: Vec<u64> = if blocks <= SAMPLES {
+            (0..blocks).map(|i| i * BLOCK).collect()
+        } else {
+            (0..SAMPLES)
+                .map(|i| i * (blocks - 1) / (SAMPLES - 1) * BLOCK)
+                .collect()
+        };
+        let mut buf = Vec::with_capacity(BLOCK as usize);
+        let (mut read, mut out) = (0u64, 0u64);
+        for offset in offsets {
+            file.seek(SeekFrom::Start(offset))?;
+            buf.clear();
+            (&mut file).take(BLOCK).read_to_end(&mut buf)?;
+            read += buf.len() as u64;
+            // Filesystems keep a block uncompressed when compression doesn't help.
+            out += compressor.compress(&buf)?.len().min(buf.len()) as u64;
+        }
+        Ok((size, read, out))
+    }
+}
@@ -0,0 +1,64 @@
+//! Shared by the commands that rewrite one layer of a model in place (`set-param`, ...).
+
+use ollama_file_find::{
+    BlobStore, LayerEdit, LayerKind, ManifestStore, ModelId, remove_unreferenced, set_layer,
+};
+use serde_json::json;
+
+use crate::{Result, StorePaths, config::Config, format::human_opt};
+
+/// Swap the `kind` layer of `id` for `content` (or drop it), optionally delete the
+/// replaced blobs nothing else uses, and report what happened.
+pub(crate) fn apply(
+    paths: &StorePaths,
+    config: &Config,
+    id: &ModelId,
+    kind: LayerKind,
+    content: Option<&[u8]>,
+    gc: bool,
+    plain: bool,
+) -> Result<()> {
+    let manifests = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+    let blobs = BlobStore::new(&paths.blobs);
+    let edit = set_layer(&manifests, &blobs, id, kind, content)?;
+    let replaced: Vec<_> = edit
+        .old
+        .iter()
+        .map(|l| l.digest)
+        .filter(|d| edit.new.as_ref().is_none_or(|n| n.digest != *d))
+        .collect();
+    let removed = if gc {
+        remove_unreferenced(&manifests, &blobs, &replaced)?
+    } else {
+        Vec::new()
+    };
+
+    if plain {
+        print_plain(&edit);
+        for digest in &removed {
+            println!("  removed blob {digest}");
+        }
+    } else {
+        let report = json!({ "edit": edit, "removed_blobs": removed });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+fn print_plain(edit: &LayerEdit) {
+    match &edit.new {
+        Some(layer) => println!(
+            "{}\t{} -> {} ({})",
+            edit.model,
+            edit.kind,
+            layer.digest,
+            human_opt(layer.size)
+        ),
+        None if edit.old.is_empty() => println!("{}\tno {} layer", edit.model, edit.kind),
+        None => println!("{}\t{} removed", edit.model, edit.kind),
+    }
+    if let Some(backup) = &edit.backup {
+        println!("  previous manifest: {}", backup.path.display());
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use ollama_file_find::{BlobStore, LayerKind, ManifestStore, ModelId, StoredManifest};
+
+use crate::{
+    Result, StorePaths,
+    args::{EnvArgs, Shell},
+};
+
+/// Layer files exported, by variable suffix; only the first layer of each kind counts.
+const LAYER_VARS: [(&str, LayerKind); 7] = [
+    ("PATH", LayerKind::Weights),
+    ("TEMPLATE", LayerKind::Template),
+    ("SYSTEM", LayerKind::System),
+    ("PARAMS", LayerKind::Params),
+    ("LICENSE", LayerKind::License),
+    ("PROJECTOR", LayerKind::Projector),
+    ("ADAPTER", LayerKind::Adapter),
+];
+
+pub(crate) fn run(paths: &StorePaths, args: EnvArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let Some(StoredManifest { id, path, data }) = ManifestStore::new(&paths.manifests).find(&id)
+    else {
+        anyhow::bail!("model not found: {}", id.normalize());
+    };
+    let shell = args.shell.unwrap_or_else(Shell::detect);
+    let blobs = BlobStore::new(&paths.blobs);
+
+    println!("{}", shell.export("OFF_MODEL_NAME", &id.normalize()));
+    println!("{}", shell.export("OFF_MODEL_MANIFEST", &lossy(&path)));
+    println!("{}", shell.export("OFF_MODEL_DIR", &lossy(blobs.root())));
+    for (suffix, kind) in LAYER_VARS {
+        let var = format!("OFF_MODEL_{suffix}");
+        let blob = data
+            .layers
+            .iter()
+            .find(|l| l.kind() == kind)
+            .map(|l| blobs.path_for(&l.digest));
+        match blob {
+            Some(blob) if blob.is_file() => println!("{}", shell.export(&var, &lossy(&blob))),
+            // Unset rather than skip, so a variable from an earlier model doesn't linger.
+            missing => {
+                if let Some(blob) = missing {
+                    eprintln!("Warning: {kind} blob missing: {}", blob.display());
+                }
+                println!("{}", shell.unset(&var));
+            }
+        }
+    }
+    if args.cd {
+        println!("{}", shell.cd(&lossy(blobs.root())));
+    }
+    Ok(())
+}
+
+fn lossy(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl Shell {
+    /// `fish` if `$SHELL` says so, PowerShell on Windows, else POSIX `sh` syntax.
+    fn detect() -> Self {
+        match std::env::var("SHELL") {
+            Ok(shell) if shell.ends_with("fish") => Shell::Fish,
+            _ if cfg!(windows) => Shell::Powershell,
+            _ => Shell::Posix,
+        }
+    }
+
+    fn quote(self, value: &str) -> String {
+        match self {
+            Shell::Posix => format!("'{}'", value.replace('\'', r"'\''")),
+            Shell::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+            Shell::Powershell => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    fn export(self, var: &str, value: &str) -> String {
+        let value = self.quote(value);
+        match self {
+            Shell::Posix => format!("export {var}={value}"),
+            Shell::Fish => format!("set -gx {var} {value}"),
+            Shell::Powershell => format!("$env:{var} = {value}"),
+        }
+    }
+
+    fn unset(self, var: &str) -> String {
+        match self {
+            Shell::Posix => format!("unset {var}"),
+            Shell::Fish => format!("set -e {var}"),
+            Shell::Powershell => format!("Remove-Item Env:{var} -ErrorAction SilentlyContinue"),
+        }
+    }
+
+    fn cd(self, dir: &str) -> String {
+        match self {
+            Shell::Posix | Shell::Fish => format!("cd {}", self.quote(dir)),
+            Shell::Powershell => format!("Set-Location {}", self.quote(dir)),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use ollama_file_find::{BlobStore, ModelId, estimate_pull, registry::fetch_manifest};
+
+use crate::{Result, StorePaths, args::EstimateArgs, format::human_bytes};
+
+pub(crate) fn run(paths: &StorePaths, args: EstimateArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let manifest = fetch_manifest(&id)?;
+    let estimate = estimate_pull(&manifest, &BlobStore::new(&paths.blobs));
+
+    if args.plain {
+        let present = estimate.layers.iter().filter(|l| l.present).count();
+        println!(
+            "{}: {} to download of {} total ({} already present, {present}/{} blobs shared)",
+            id.normalize(),
+            human_bytes(estimate.download_bytes),
+            human_bytes(estimate.total_bytes),
+            human_bytes(estimate.present_bytes),
+            estimate.layers.len()
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(&estimate)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use crate::{
+    Result, StorePaths,
+    args::EventsArgs,
+    events::{default_log_path, now_secs, read_log},
+};
+
+pub(crate) fn run(paths: impl FnOnce() -> Result<StorePaths>, args: EventsArgs) -> Result<()> {
+    let log = match args.log {
+        Some(log) => log,
+        None => default_log_path(&paths()?),
+    };
+    let since = args
+        .since
+        .map_or(0, |d| now_secs().saturating_sub(d.as_secs()));
+    let events = read_log(&log, since)?;
+
+    if args.plain {
+        for e in &events {
+            println!("{}\t{}\t{}", e.timestamp, e.kind, e.model);
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&events)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use ollama_file_find::{
+    BlobStore, ManifestStore, ModelId, StoredManifest, copy_atomic, write_atomic,
+};
+
+use crate::{Result, StorePaths, args::ExportArgs, format::human_bytes};
+
+pub(crate) fn run(paths: &StorePaths, args: ExportArgs) -> Result<()> {
+    let store = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let id: ModelId = args.model.parse()?;
+    let found = store
+        .find(&id)
+        .ok_or_else(|| anyhow::anyhow!("model not found: {}", id.normalize()))?;
+
+    if let Some(dir) = &args.target.docker_context {
+        docker_context(&store, &blobs, &id, &found, dir)?;
+    }
+    #[cfg(feature = "network")]
+    if let Some(format) = args.target.nix {
+        nix(&store, &blobs, &id, &found, format)?;
+    }
+    Ok(())
+}
+
+/// Lay out `dir` as a build context: `blobs/` and `manifests/` mirror the store, and the
+/// Dockerfile copies every blob in a layer of its own.
+fn docker_context(
+    store: &ManifestStore,
+    blobs: &BlobStore,
+    id: &ModelId,
+    found: &StoredManifest,
+    dir: &Path,
+) -> Result<()> {
+    if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        anyhow::bail!("{} is not empty", dir.display());
+    }
+    let context_blobs = dir.join("blobs");
+    fs::create_dir_all(&context_blobs)?;
+
+    let name = id.normalize();
+    let mut dockerfile = format!(
+        "# syntax=docker/dockerfile:1\n\
+         # {name}, written by `ollama-file-find export --docker-context`.\n\
+         # Every blob is a `COPY --link` layer of its own, which depends only on the blob\n\
+         # and where it goes, so images sharing a blob share that layer in the build cache\n\
+         # and in registries.\n\
+         ARG BASE_IMAGE=ollama/ollama\n\
+         FROM ${{BASE_IMAGE}}\n\
+         ARG MODELS_DIR=/root/.ollama/models\n\
+         ENV OLLAMA_MODELS=${{MODELS_DIR}}\n"
+    );
+    let mut seen = Vec::new();
+    let mut bytes = 0;
+    for layer in found.data.config.iter().chain(&found.data.layers) {
+        if seen.contains(&layer.digest) {
+            continue;
+        }
+        seen.push(layer.digest);
+        let from = blobs.path_for(&layer.digest);
+        if !from.is_file() {
+            anyhow::bail!("{name} references missing blob {}", layer.digest);
+        }
+        let file = from.file_name().unwrap_or_default().to_string_lossy();
+        let to = context_blobs.join(&*file);
+        // Docker does not follow symlinks out of the context, so link or copy the file.
+        if fs::hard_link(&from, &to).is_err() {
+            copy_atomic(&from, &to)?;
+        }
+        bytes += fs::metadata(&to)?.len();
+        writeln!(
+            dockerfile,
+            "COPY --link blobs/{file} ${{MODELS_DIR}}/blobs/{file}"
+        )?;
+    }
+
+    let relative = found.path.strip_prefix(store.root()).unwrap_or(&found.path);
+    let manifest = dir.join("manifests").join(relative);
+    if let Some(parent) = manifest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    copy_atomic(&found.path, &manifest)?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    writeln!(
+        dockerfile,
+        "COPY --link manifests/{relative} ${{MODELS_DIR}}/manifests/{relative}"
+    )?;
+    write_atomic(&dir.join("Dockerfile"), dockerfile.as_bytes())?;
+
+    eprintln!(
+        "Wrote {} blobs ({}) and a Dockerfile to {}; build with:",
+        seen.len(),
+        human_bytes(bytes),
+        dir.display()
+    );
+    println!(
+        "docker build -t ollama-{} {}",
+        image_tag(&name),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// A model as fixed-output fetches: everything a Nix expression needs to rebuild the
+/// model's part of a models directory.
+#[cfg(feature = "network")]
+#[derive(serde::Serialize)]
+struct NixModel {
+    model: String,
+    /// Derivation name
+    name: String,
+    blobs: Vec<NixBlob>,
+    manifest: NixManifest,
+}
+
+#[cfg(feature = "network")]
+#[derive(serde::Serialize)]
+struct NixBlob {
+    /// Path in the models directory, `blobs/sha256-<hex>`
+    name: String,
+    url: String,
+    /// Hex SHA-256, as `fetchurl` takes it
+    sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    /// The local copy, when present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<std::path::PathBuf>,
+}
+
+#[cfg(feature = "network")]
+#[derive(serde::Serialize)]
+struct NixManifest {
+    /// Path in the models directory, `manifests/<host>/<namespace>/<model>/<tag>`
+    name: String,
+    /// The installed manifest, byte for byte (registries may serve another encoding)
+    content: String,
+}
+
+#[cfg(feature = "network")]
+fn nix(
+    store: &ManifestStore,
+    blobs: &BlobStore,
+    id: &ModelId,
+    found: &StoredManifest,
+    format: crate::args::NixFormat,
+) -> Result<()> {
+    use ollama_file_find::registry::blob_url;
+
+    let mut model = NixModel {
+        model: id.normalize(),
+        name: format!("ollama-{}", image_tag(&id.normalize())),
+        blobs: Vec::new(),
+        manifest: NixManifest {
+            name: format!(
+                "manifests/{}",
+                found
+                    .path
+                    .strip_prefix(store.root())
+                    .unwrap_or(&found.path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            ),
+            content: fs::read_to_string(&found.path)?,
+        },
+    };
+    for layer in found.data.config.iter().chain(&found.data.layers) {
+        if model.blobs.iter().any(|b| b.sha256 == layer.digest.hex()) {
+            continue;
+        }
+        let path = blobs.path_for(&layer.digest);
+        model.blobs.push(NixBlob {
+            name: format!("blobs/sha256-{}", layer.digest.hex()),
+            url: blob_url(id, &layer.digest),
+            sha256: layer.digest.hex(),
+            size: layer.size,
+            path: path.is_file().then_some(path),
+        });
+    }
+
+    match format {
+        crate::args::NixFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&model)?);
+        }
+        crate::args::NixFormat::Expr => print!("{}", nix_expression(&model)),
+    }
+    Ok(())
+}
+
+/// `linkFarm` of the fetched blobs and the manifest: a read-only models directory.
+#[cfg(feature = "network")]
+fn nix_expression(model: &NixModel) -> String {
+    let mut out = format!(
+        "# {}, written by `ollama-file-find export --nix`.\n\
+         # Every blob is a fixed-output fetch pinned by its SHA-256. The result is a models\n\
+         # directory: `callPackage` this file and point OLLAMA_MODELS at the output.\n\
+         {{ fetchurl, linkFarm, writeText }}:\n\
+         linkFarm {} [\n",
+        model.model,
+        nix_string(&model.name)
+    );
+    for blob in &model.blobs {
+        // `fetchurl` would name the path after the URL, and `:` is not allowed there.
+        let _ = write!(
+            out,
+            "  {{\n    name = {};\n    path = fetchurl {{\n      name = {};\n      \
+             url = {};\n      sha256 = {};\n    }};\n  }}\n",
+            nix_string(&blob.name),
+            nix_string(blob.name.trim_start_matches("blobs/")),
+            nix_string(&blob.url),
+            nix_string(&blob.sha256),
+        );
+    }
+    let _ = write!(
+        out,
+        "  {{\n    name = {};\n    path = writeText \"manifest\" {};\n  }}\n]\n",
+        nix_string(&model.manifest.name),
+        nix_string(&model.manifest.content),
+    );
+    out
+}
+
+/// A double-quoted Nix string literal.
+#[cfg(feature = "network")]
+fn nix_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{escaped}\"")
+}
+
+/// A form of a model name that is a valid Docker repository and Nix store name: `hf.co/Org/M:Q4` -> `hf.co-org-m-q4`.
+fn image_tag(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,76 @@
+use std::{fs, path::PathBuf};
+
+use ollama_file_find::{ScanArgs, scan_manifests};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::ExportStaticArgs, events::now_secs};
+
+#[derive(Serialize)]
+struct IndexEntry<'a> {
+    name: &'a str,
+    total_size: Option<u64>,
+    /// Path of the model's JSON file, relative to the output directory
+    file: String,
+}
+
+#[derive(Serialize)]
+struct StaticIndex<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generated_at: Option<u64>,
+    models: Vec<IndexEntry<'a>>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: ExportStaticArgs) -> Result<()> {
+    let mut outcome = scan_manifests(
+        &ScanArgs::new(&paths.manifests, &paths.blobs)
+            .with_hidden(args.hidden.policy())
+            .with_verbose(true),
+    );
+    for e in &outcome.errors {
+        eprintln!("Warning: {e}");
+    }
+
+    if args.stable_fields {
+        for m in &mut outcome.models {
+            m.strip_volatile();
+        }
+    }
+
+    let mut entries = Vec::with_capacity(outcome.models.len());
+    for m in &outcome.models {
+        // Mirror the manifests tree so every model gets a unique, URL-friendly path.
+        let rel = m.manifest_path.strip_prefix(&paths.manifests)?;
+        let mut file = PathBuf::from("models").join(rel);
+        file.as_mut_os_string().push(".json");
+        let dest = args.dir.join(&file);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dest, serde_json::to_vec_pretty(m)?)?;
+        entries.push(IndexEntry {
+            name: &m.name,
+            total_size: m.total_size,
+            file: file
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        });
+    }
+
+    let index = StaticIndex {
+        generated_at: (!args.stable_fields).then(now_secs),
+        models: entries,
+    };
+    fs::create_dir_all(&args.dir)?;
+    fs::write(
+        args.dir.join("index.json"),
+        serde_json::to_vec_pretty(&index)?,
+    )?;
+    eprintln!(
+        "Wrote {} model files and index.json to {}",
+        index.models.len(),
+        args.dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use ollama_file_find::{BlobStore, HiddenPolicy, ManifestStore, ModelId};
+use serde_json::{Map, Value};
+
+use crate::{Result, StorePaths, args::FactsArgs};
+
+pub(crate) fn run(paths: &StorePaths, args: FactsArgs) -> Result<()> {
+    let manifests = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+
+    let (ids, errors) = manifests.ids(HiddenPolicy::Exclude);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let installed: HashSet<String> = ids.iter().map(ModelId::normalize).collect();
+    let entries = if paths.blobs.is_dir() {
+        blobs.blobs()?
+    } else {
+        Vec::new()
+    };
+    let store = paths.manifests.parent().unwrap_or(&paths.manifests);
+
+    let prefix = &args.prefix;
+    let mut facts = Map::new();
+    let mut set = |key: String, value: Value| {
+        facts.insert(format!("{prefix}_{key}"), value);
+    };
+    set("store_path".into(), store.display().to_string().into());
+    set("model_count".into(), installed.len().into());
+    set("blob_count".into(), entries.len().into());
+    set(
+        "total_bytes".into(),
+        entries.iter().map(|b| b.size).sum::<u64>().into(),
+    );
+    for name in &args.models {
+        let normalized = name.parse::<ModelId>()?.normalize();
+        set(
+            format!("has_{}", key_part(&normalized)),
+            installed.contains(&normalized).into(),
+        );
+    }
+
+    if args.strings {
+        for value in facts.values_mut() {
+            if !value.is_string() {
+                *value = Value::String(value.to_string());
+            }
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&facts)?);
+    Ok(())
+}
+
+/// `hf.co/Org/Model:Q4_K_M` -> `hf_co_org_model_q4_k_m`, usable as a variable name.
+fn key_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use ollama_file_find::{
+    BlobStore, Fit, HiddenPolicy, LayerKind, ManifestStore, MemoryEstimate, StoredManifest,
+    gguf::read_gguf_file,
+};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::FitArgs, format::human_bytes};
+
+/// Ollama's context window when a model sets none.
+const DEFAULT_CONTEXT: u64 = 4096;
+
+#[derive(Serialize)]
+struct FitReport {
+    vram: u64,
+    /// Full fits first, then partial (most layers on the GPU first), then the rest; by
+    /// quality per GB within each
+    models: Vec<ModelFit>,
+}
+
+#[derive(Serialize)]
+struct ModelFit {
+    name: String,
+    #[serde(flatten)]
+    fit: Fit,
+    #[serde(flatten)]
+    estimate: MemoryEstimate,
+    /// Rough capability score: log2(1 + billions of parameters), discounted for
+    /// quantization below 8 bits per weight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality: Option<f64>,
+    /// `quality` per GB of memory needed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality_per_gb: Option<f64>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: FitArgs) -> Result<()> {
+    let blobs = BlobStore::new(&paths.blobs);
+    let (manifests, errors) = ManifestStore::new(&paths.manifests).manifests(HiddenPolicy::Exclude);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+
+    let mut models = Vec::new();
+    for stored in &manifests {
+        let Some(weights) = stored
+            .data
+            .layers
+            .iter()
+            .find(|l| l.kind() == LayerKind::Weights)
+        else {
+            continue;
+        };
+        let path = blobs.path_for(&weights.digest);
+        let meta = match read_gguf_file(&path) {
+            Ok(meta) => meta,
+            Err(e) => {
+                eprintln!("Warning: {}: {e}", stored.id.normalize());
+                continue;
+            }
+        };
+        let size = std::fs::metadata(&path).map_or(weights.size.unwrap_or(0), |m| m.len());
+        let context = args
+            .ctx
+            .or_else(|| num_ctx(&blobs, stored))
+            .unwrap_or(DEFAULT_CONTEXT);
+        let estimate = MemoryEstimate::new(&meta, size, context);
+        let quality = quality(&estimate);
+        #[allow(clippy::cast_precision_loss)]
+        let quality_per_gb = quality.map(|q| q / (estimate.total as f64 / 1e9));
+        models.push(ModelFit {
+            name: stored.id.normalize(),
+            fit: estimate.fit(args.vram),
+            estimate,
+            quality,
+            quality_per_gb,
+        });
+    }
+    models.sort_by(|a, b| {
+        rank(&a.fit)
+            .cmp(&rank(&b.fit))
+            .then_with(|| {
+                // Unknown quality last.
+                match (a.quality_per_gb, b.quality_per_gb) {
+                    (Some(a), Some(b)) => b.total_cmp(&a),
+                    (a, b) => b.is_some().cmp(&a.is_some()),
+                }
+            })
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    if args.plain {
+        for m in &models {
+            let fit = match m.fit {
+                Fit::Full => "full".to_string(),
+                Fit::Partial { gpu_layers, layers } => format!("partial {gpu_layers}/{layers}"),
+                Fit::None => "none".to_string(),
+            };
+            println!("{}\t{fit}\t{}", m.name, human_bytes(m.estimate.total));
+        }
+    } else {
+        let report = FitReport {
+            vram: args.vram,
+            models,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// The model's `num_ctx` parameter, if it sets one.
+fn num_ctx(blobs: &BlobStore, stored: &StoredManifest) -> Option<u64> {
+    let layer = stored
+        .data
+        .layers
+        .iter()
+        .find(|l| l.kind() == LayerKind::Params)?;
+    let text = blobs.read_text(&layer.digest, 1 << 20).ok()?;
+    serde_json::from_str::<serde_json::Value>(&text)
+        .ok()?
+        .get("num_ctx")?
+        .as_u64()
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn quality(estimate: &MemoryEstimate) -> Option<f64> {
+    let billions = estimate.parameters? as f64 / 1e9;
+    // Share of full-precision quality kept at each quantization level, roughly as
+    // perplexity comparisons of llama.cpp quants show it.
+    let retention = match estimate.bits_per_weight {
+        Some(b) if b >= 7.5 => 1.0,
+        Some(b) if b >= 5.5 => 0.98,
+        Some(b) if b >= 4.5 => 0.95,
+        Some(b) if b >= 3.5 => 0.88,
+        Some(b) if b >= 2.5 => 0.75,
+        Some(_) => 0.6,
+        None => 0.9,
+    };
+    Some((1.0 + billions).log2() * retention)
+}
+
+/// Sort key: full fits, then partial ones by the share (per mille) of layers left on
+/// the CPU, then none.
+fn rank(fit: &Fit) -> (u8, u64) {
+    match *fit {
+        Fit::Full => (0, 0),
+        Fit::Partial { gpu_layers, layers } => (1, 1000 - gpu_layers * 1000 / layers),
+        Fit::None => (2, 0),
+    }
+}
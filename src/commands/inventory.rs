@@ -0,0 +1,115 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use ollama_file_find::{
+    BlobStore, ManifestStore, Seal,
+    signature::{DetachedSignature, TrustStore, load_signing_key},
+    write_atomic,
+};
+use serde_json::json;
+
+use crate::{
+    Result, StorePaths,
+    args::{InventoryAction, InventoryArgs, InventorySignArgs, InventoryVerifyArgs},
+    commands::seal::print_changes,
+    config::Config,
+};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: InventoryArgs) -> Result<ExitCode> {
+    match args.action {
+        InventoryAction::Sign(sign_args) => sign(paths, config, sign_args),
+        InventoryAction::Verify(verify_args) => verify(paths, config, verify_args),
+    }
+}
+
+fn sign(paths: &StorePaths, config: &Config, args: InventorySignArgs) -> Result<ExitCode> {
+    let Some(key_path) = &config.inventory.signing_key else {
+        anyhow::bail!("no [inventory] signing_key in the config file");
+    };
+    let key = load_signing_key(key_path)?;
+    let inventory = inventory_path(&args.output, args.inventory)?;
+
+    let seal = Seal::compute(
+        &ManifestStore::new(&paths.manifests),
+        &BlobStore::new(&paths.blobs),
+    )?;
+    let mut body = serde_json::to_vec_pretty(&seal)?;
+    body.push(b'\n');
+    let signature = DetachedSignature::sign(&key, &body);
+    let mut sig_body = serde_json::to_vec_pretty(&signature)?;
+    sig_body.push(b'\n');
+    write_atomic(&inventory, &body)?;
+    write_atomic(&args.output, &sig_body)?;
+    eprintln!(
+        "Inventory of {} models and {} blobs in {}, signed by {} in {}",
+        seal.models.len(),
+        seal.blobs.len(),
+        inventory.display(),
+        signature.key,
+        args.output.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(paths: &StorePaths, config: &Config, args: InventoryVerifyArgs) -> Result<ExitCode> {
+    let trust = match (&config.inventory.trust_store, &config.inventory.signing_key) {
+        (Some(dir), _) => TrustStore::load(dir)?,
+        (None, Some(key)) => TrustStore::of_signing_key(&load_signing_key(key)?, "signing_key"),
+        (None, None) => {
+            anyhow::bail!("no [inventory] trust_store or signing_key in the config file")
+        }
+    };
+    let inventory = inventory_path(&args.signature, args.inventory)?;
+    let signature: DetachedSignature = serde_json::from_slice(&fs::read(&args.signature)?)?;
+    let body = fs::read(&inventory)?;
+    let signers = signature.signers(&body, &trust);
+
+    let seal: Seal = serde_json::from_slice(&body)?;
+    let changes = seal.verify(
+        &ManifestStore::new(&paths.manifests),
+        &BlobStore::new(&paths.blobs),
+    )?;
+    let ok = !signers.is_empty() && changes.is_empty();
+
+    if args.plain {
+        match signers.first() {
+            Some(k) => println!("signature\tvalid\t{} ({})", k.name, k.fingerprint),
+            None => println!("signature\tinvalid\t{}", signature.key),
+        }
+        print_changes(&changes);
+    } else {
+        let report = json!({
+            "ok": ok,
+            "signature": {
+                "valid": !signers.is_empty(),
+                "key": signature.key,
+                "signed_by": signers.iter().map(|k| &k.name).collect::<Vec<_>>(),
+            },
+            "changes": changes,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// The inventory next to a signature file: given explicitly, or the signature's path
+/// without `.sig`.
+fn inventory_path(signature: &Path, explicit: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(path) = explicit {
+        return Ok(path);
+    }
+    match signature.extension() {
+        Some(ext) if ext == "sig" => Ok(signature.with_extension("")),
+        _ => anyhow::bail!(
+            "{} does not end in .sig; name the inventory with --inventory",
+            signature.display()
+        ),
+    }
+}
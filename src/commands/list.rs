@@ -0,0 +1,203 @@
+use ollama_file_find::{
+    BlobStore, Collation, HiddenPolicy, ListedModel, ManifestStore, ModelLicense, ScanArgs,
+    StoreSummary, case_collisions, scan_manifests,
+};
+
+use crate::{
+    Result, StorePaths,
+    args::ListArgs,
+    format::human_bytes,
+    output::{OutputFormat, emit, render_models},
+    watch::watch_store,
+};
+
+pub(crate) fn run(paths: &StorePaths, api_urls: &[String], args: ListArgs) -> Result<()> {
+    let mut last = render(paths, api_urls, &args)?;
+    emit(args.output.as_deref(), &last)?;
+    if !args.watch {
+        return Ok(());
+    }
+    // Listings on stdout follow each other; JSON arrays are self-delimiting, YAML gets a
+    // document marker and line formats an empty line.
+    let separator: &[u8] = match output_format(&args) {
+        _ if args.summary || args.output.is_some() => b"",
+        OutputFormat::Json => b"",
+        OutputFormat::Yaml => b"---\n",
+        OutputFormat::Ndjson | OutputFormat::Csv | OutputFormat::Plain | OutputFormat::Table => {
+            b"\n"
+        }
+    };
+    watch_store(paths, args.poll, || match render(paths, api_urls, &args) {
+        Ok(bytes) if bytes != last => {
+            let emitted = emit(args.output.as_deref(), &[separator, &bytes].concat());
+            if let Err(e) = emitted {
+                eprintln!("Warning: {e}");
+            }
+            last = bytes;
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: {e}"),
+    })
+}
+
+/// Verbose details cannot be expressed as a plain name list, so --plain yields to JSON.
+fn output_format(args: &ListArgs) -> OutputFormat {
+    args.format
+        .or(args.table.then_some(OutputFormat::Table))
+        .or_else(|| {
+            args.output
+                .as_deref()
+                .and_then(OutputFormat::from_extension)
+        })
+        .unwrap_or(if args.plain && !args.verbose {
+            OutputFormat::Plain
+        } else {
+            OutputFormat::Json
+        })
+}
+
+/// One listing (or the `--summary` line) as it would be written out.
+fn render(
+    paths: &StorePaths,
+    #[cfg_attr(not(feature = "network"), allow(unused_variables))] api_urls: &[String],
+    args: &ListArgs,
+) -> Result<Vec<u8>> {
+    let &ListArgs {
+        summary,
+        hidden,
+        verbose,
+        timings,
+        canonical,
+        stable_fields,
+        ref license_contains,
+        only_broken,
+        only_ok,
+        lenient,
+        collate,
+        #[cfg(feature = "network")]
+        running,
+        ..
+    } = args;
+
+    if summary {
+        return summary_line(paths, hidden.policy()).map(|line| format!("{line}\n").into_bytes());
+    }
+
+    // License and health filtering need the layer and blob details, which only
+    // verbose scans keep. The table needs just the digest, size, and modification time.
+    let format = output_format(args);
+    let filtered = license_contains.is_some() || only_broken || only_ok;
+    let mut outcome = scan_manifests(
+        &ScanArgs::new(&paths.manifests, &paths.blobs)
+            .with_hidden(hidden.policy())
+            .with_verbose(verbose || filtered)
+            .with_summary(format == OutputFormat::Table)
+            .with_lenient(lenient)
+            .with_collation(collate.into()),
+    );
+
+    for e in &outcome.errors {
+        eprintln!("Warning: {e}");
+    }
+    if timings || verbose {
+        eprintln!("Scan: {}", outcome.stats);
+    }
+    for m in outcome.models.iter().filter(|m| !m.repairs.is_empty()) {
+        let repairs: Vec<_> = m.repairs.iter().map(ToString::to_string).collect();
+        eprintln!(
+            "Warning: repaired {}: {}",
+            m.manifest_path.display(),
+            repairs.join(", ")
+        );
+    }
+    for c in case_collisions(outcome.models.iter().map(|m| &m.model_id)) {
+        eprintln!(
+            "Warning: {} differ only by case and will mis-resolve on macOS/Windows \
+             (see `ollama-file-find doctor`)",
+            c.spellings.join(" and ")
+        );
+    }
+
+    if let Some(needle) = &license_contains {
+        outcome.models.retain(|m| {
+            ModelLicense::read(m.layers.as_deref().unwrap_or_default(), &paths.blobs)
+                .contains(needle)
+        });
+    }
+    if only_broken || only_ok {
+        outcome
+            .models
+            .retain(|m| m.is_broken() == Some(only_broken));
+    }
+    if filtered && !verbose && format != OutputFormat::Table {
+        outcome.models = outcome
+            .models
+            .into_iter()
+            .map(|m| ListedModel {
+                repairs: m.repairs,
+                ..ListedModel::new(m.model_id, m.manifest_path)
+            })
+            .collect();
+    }
+
+    #[cfg(feature = "network")]
+    if running {
+        use ollama_file_find::api::{mark_running, running_models};
+        let mut loaded = Vec::new();
+        let mut reached = false;
+        for url in api_urls {
+            match running_models(url) {
+                Ok(found) => {
+                    reached = true;
+                    loaded.extend(found);
+                }
+                Err(e) => eprintln!("Warning: running state unknown for {url}: {e}"),
+            }
+        }
+        // With no endpoint reachable, "not running" would be a guess.
+        if reached {
+            mark_running(&mut outcome.models, &loaded);
+        }
+    }
+
+    if stable_fields {
+        for m in &mut outcome.models {
+            m.strip_volatile();
+        }
+    }
+    if canonical {
+        for m in &mut outcome.models {
+            m.canonicalize();
+        }
+        // Names can coincide (3- and 4-component layouts); the path breaks ties.
+        let collation = Collation::from(collate);
+        outcome.models.sort_by(|a, b| {
+            collation
+                .compare(&a.name, &b.name)
+                .then_with(|| a.manifest_path.cmp(&b.manifest_path))
+        });
+    }
+
+    render_models(&outcome.models, format, canonical)
+}
+
+fn summary_line(paths: &StorePaths, hidden: HiddenPolicy) -> Result<String> {
+    let (summary, errors) = StoreSummary::compute(
+        &ManifestStore::new(&paths.manifests),
+        &BlobStore::new(&paths.blobs),
+        hidden,
+    )?;
+    let mut line = format!(
+        "{} models · {} ({} unique) · {} broken · {} orphaned",
+        summary.models,
+        human_bytes(summary.total_bytes),
+        human_bytes(summary.unique_bytes),
+        summary.broken,
+        human_bytes(summary.orphaned_bytes)
+    );
+    // Unreadable manifests would otherwise go unnoticed in a one-line status.
+    if !errors.is_empty() {
+        line.push_str(&format!(" · {} unreadable", errors.len()));
+    }
+    Ok(line)
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use ollama_file_find::{BlobClass, BlobStore, Digest, ManifestStore, classify_file, gc_plan};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::ListBlobsArgs,
+    events::{default_log_path, former_owners},
+    format::human_bytes,
+};
+
+/// Model names shown per blob unless `--full-refs` is given.
+const REFS_SHOWN: usize = 5;
+
+#[derive(Serialize)]
+struct BlobRow {
+    digest: Digest,
+    path: PathBuf,
+    size: u64,
+    class: BlobClass,
+    /// Whether any manifest (hidden tags included) references the blob
+    referenced: bool,
+    /// Unreferenced, but a manifest backup still does
+    held: bool,
+    /// Unreferenced, but a pin still does
+    pinned: bool,
+    /// Number of models referencing the blob
+    ref_count: usize,
+    /// Those models, the first few only without `--full-refs`
+    refs: Vec<String>,
+    /// For unreferenced blobs, models the event log says used to reference them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    formerly: Vec<String>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: ListBlobsArgs) -> Result<()> {
+    let plan = gc_plan(
+        &ManifestStore::new(&paths.manifests),
+        &BlobStore::new(&paths.blobs),
+    )?;
+    for e in &plan.errors {
+        eprintln!("Warning: {e}");
+    }
+    let referenced: HashSet<Digest> = plan.reachable.iter().map(|b| b.digest).collect();
+    let held: HashSet<Digest> = plan.held.iter().map(|b| b.digest).collect();
+    let pinned: HashSet<Digest> = plan.pinned.iter().map(|b| b.digest).collect();
+    let history = if plan.held.is_empty() && plan.pinned.is_empty() && plan.unreachable.is_empty() {
+        Default::default()
+    } else {
+        former_owners(&default_log_path(paths))
+    };
+
+    let mut rows = Vec::new();
+    for blob in plan
+        .reachable
+        .iter()
+        .chain(&plan.held)
+        .chain(&plan.pinned)
+        .chain(&plan.unreachable)
+    {
+        let class = classify_file(&blob.path).unwrap_or_else(|e| {
+            eprintln!("Warning: {e}");
+            BlobClass::Unknown
+        });
+        if args.class.is_some_and(|wanted| wanted != class) {
+            continue;
+        }
+        let refs = plan.referrers(&blob.digest);
+        let is_referenced = referenced.contains(&blob.digest);
+        let formerly = match history.get(&blob.digest) {
+            Some(models) if !is_referenced => capped(models, args.full_refs),
+            _ => Vec::new(),
+        };
+        rows.push(BlobRow {
+            digest: blob.digest,
+            path: blob.path.clone(),
+            size: blob.size,
+            class,
+            referenced: is_referenced,
+            held: held.contains(&blob.digest),
+            pinned: pinned.contains(&blob.digest),
+            ref_count: refs.len(),
+            refs: capped(refs, args.full_refs),
+            formerly,
+        });
+    }
+    rows.sort_by_key(|r| r.digest);
+
+    if args.plain {
+        for r in &rows {
+            let owners = if r.referenced {
+                format!("\t{}", joined(&r.refs, r.ref_count))
+            } else if r.formerly.is_empty() {
+                String::new()
+            } else {
+                format!("\twas {}", r.formerly.join(", "))
+            };
+            println!(
+                "{}\t{:>10}\t{}{}{owners}",
+                r.digest,
+                human_bytes(r.size),
+                r.class,
+                if r.referenced {
+                    ""
+                } else if r.held {
+                    "\theld"
+                } else if r.pinned {
+                    "\tpinned"
+                } else {
+                    "\torphan"
+                }
+            );
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    }
+    Ok(())
+}
+
+/// The first few of `models`, or all of them with `full`.
+pub(crate) fn capped(models: &[String], full: bool) -> Vec<String> {
+    let shown = if full { models.len() } else { REFS_SHOWN };
+    models.iter().take(shown).cloned().collect()
+}
+
+/// `a, b, c` followed by `+N more` when `shown` is a prefix of `total` names.
+pub(crate) fn joined(shown: &[String], total: usize) -> String {
+    let mut out = shown.join(", ");
+    if total > shown.len() {
+        out.push_str(&format!(" +{} more", total - shown.len()));
+    }
+    out
+}
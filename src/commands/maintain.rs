@@ -0,0 +1,316 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{Mutex, PoisonError},
+};
+
+use ollama_file_find::{Digest, HiddenPolicy, map_per_device, sha256_file, write_atomic};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Result, StorePaths,
+    args::MaintainArgs,
+    commands::{daemon::Index, orphans},
+    config::Config,
+    events::now_secs,
+    format::{human_bytes, human_duration},
+    progress::Progress,
+    resume::{Done, ResumeLog, lock},
+};
+
+/// What the last completed run left behind, so `--every` / `--if-due` can decide.
+#[derive(Serialize, Deserialize)]
+struct State {
+    last_run: u64,
+    ok: bool,
+}
+
+#[derive(Serialize)]
+struct MaintainReport {
+    ran_at: u64,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scrub: Option<ScrubReport>,
+    prune: PruneSummary,
+    health: Value,
+}
+
+#[derive(Serialize, Default)]
+struct ScrubReport {
+    blobs_checked: usize,
+    bytes_checked: u64,
+    /// Blobs skipped because an interrupted run had already checked them (`--resume`)
+    #[serde(skip_serializing_if = "is_zero")]
+    blobs_resumed: usize,
+    /// Blobs whose content no longer matches their digest
+    corrupt: Vec<CorruptBlob>,
+}
+
+#[derive(Serialize)]
+struct CorruptBlob {
+    digest: Digest,
+    actual: Digest,
+    path: PathBuf,
+    models: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct PruneSummary {
+    dry_run: bool,
+    removed: usize,
+    freed_bytes: u64,
+    retained: usize,
+    errors: Vec<String>,
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: MaintainArgs) -> Result<ExitCode> {
+    let state_path = args.state.clone().unwrap_or_else(default_state_path);
+    let last = load_state(&state_path)?;
+    let now = now_secs();
+    let next_due = match (&args.every, args.if_due, &last) {
+        (Some(schedule), _, Some(last)) => schedule.next_after(last.last_run),
+        (_, Some(interval), Some(last)) => Some(last.last_run + interval.as_secs()),
+        _ => None,
+    };
+    if let Some(next) = next_due
+        && next > now
+    {
+        if args.plain {
+            println!("not due for {}", human_duration(next - now));
+        } else {
+            let skipped = serde_json::json!({
+                "due": false,
+                "next_due": next,
+                "last_run": last.map(|l| l.last_run),
+            });
+            println!("{skipped}");
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let index = Index::build(paths, HiddenPolicy::Include);
+    let health = index.health();
+    let scrub = if args.no_scrub {
+        None
+    } else {
+        // Next to the state file, so separate stores (`--state`) don't share one.
+        let log = ResumeLog::open(&state_path.with_extension("resume"), "scrub", args.resume)?;
+        let log = Mutex::new(log);
+        let report = scrub(&index, &log, usize::from(args.jobs_per_device))?;
+        log.into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .finish()?;
+        Some(report)
+    };
+    let prune = prune(paths, config, args.dry_run)?;
+    let ok = health["ok"].as_bool().unwrap_or(false)
+        && scrub.as_ref().is_none_or(|s| s.corrupt.is_empty())
+        && prune.errors.is_empty();
+    let report = MaintainReport {
+        ran_at: now,
+        ok,
+        scrub,
+        prune,
+        health,
+    };
+
+    if let Some(parent) = state_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let state = State { last_run: now, ok };
+    write_atomic(&state_path, &serde_json::to_vec(&state)?)?;
+
+    if args.plain {
+        print_plain(&report);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Re-hash every referenced blob that is present and the right size (missing and
+/// truncated blobs are already reported by the health check; cloud placeholders are
+/// skipped so they aren't downloaded). Intact blobs are recorded in `log` as they are
+/// checked, and ones it already holds unchanged are skipped. Each disk holding blobs is
+/// scrubbed by its own `jobs_per_device` workers.
+fn scrub(index: &Index, log: &Mutex<ResumeLog>, jobs_per_device: usize) -> Result<ScrubReport> {
+    let mut blobs: BTreeMap<Digest, (PathBuf, Vec<String>)> = BTreeMap::new();
+    for m in &index.models {
+        for b in m.blob_paths.iter().flatten() {
+            if b.status.is_problem() || b.offline {
+                continue;
+            }
+            blobs
+                .entry(b.digest)
+                .or_insert_with(|| (b.path.clone(), Vec::new()))
+                .1
+                .push(m.name.clone());
+        }
+    }
+    let sizes: Vec<u64> = blobs
+        .values()
+        .map(|(path, _)| fs::metadata(path).map_or(0, |m| m.len()))
+        .collect();
+    let progress = Progress::start("scrub", sizes.len(), Some(sizes.iter().sum()));
+    let outcomes = map_per_device(
+        blobs.into_iter().zip(sizes).collect(),
+        jobs_per_device,
+        |((_, (path, _)), _)| path.as_path(),
+        |((digest, (path, models)), size)| {
+            let outcome = scrub_blob(log, digest, path, models);
+            progress.step(digest, size);
+            outcome
+        },
+    );
+    progress.finish();
+    let mut report = ScrubReport::default();
+    for outcome in outcomes {
+        match outcome? {
+            Scrubbed::Resumed => report.blobs_resumed += 1,
+            Scrubbed::Intact(size) => {
+                report.blobs_checked += 1;
+                report.bytes_checked += size;
+            }
+            Scrubbed::Corrupt(size, corrupt) => {
+                report.blobs_checked += 1;
+                report.bytes_checked += size;
+                report.corrupt.push(corrupt);
+            }
+            Scrubbed::Unreadable(e) => eprintln!("Warning: {e}"),
+        }
+    }
+    Ok(report)
+}
+
+fn scrub_blob(
+    log: &Mutex<ResumeLog>,
+    digest: Digest,
+    path: PathBuf,
+    models: Vec<String>,
+) -> Result<Scrubbed> {
+    let before = Done::of(&path);
+    if lock(log).is_done(&digest, before) {
+        return Ok(Scrubbed::Resumed);
+    }
+    let actual = match sha256_file(&path) {
+        Ok(actual) => actual,
+        Err(e) => return Ok(Scrubbed::Unreadable(e)),
+    };
+    let size = fs::metadata(&path).map_or(0, |m| m.len());
+    if actual != digest {
+        let corrupt = CorruptBlob {
+            digest,
+            actual,
+            path,
+            models,
+        };
+        return Ok(Scrubbed::Corrupt(size, corrupt));
+    }
+    if let Some(before) = before {
+        lock(log).record(digest, before)?;
+    }
+    Ok(Scrubbed::Intact(size))
+}
+
+/// What became of one blob in [`scrub`].
+enum Scrubbed {
+    Resumed,
+    Intact(u64),
+    Corrupt(u64, CorruptBlob),
+    Unreadable(ollama_file_find::Error),
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Remove orphans outside the `[retention]` window from the config file.
+fn prune(paths: &StorePaths, config: &Config, dry_run: bool) -> Result<PruneSummary> {
+    let (plan, _) = orphans::plan(paths, config, None, false)?;
+    let retained = plan.retained.len();
+    if dry_run {
+        return Ok(PruneSummary {
+            dry_run,
+            removed: plan.unreachable.len(),
+            freed_bytes: plan.unreachable_bytes(),
+            retained,
+            errors: Vec::new(),
+        });
+    }
+    let report = plan.execute()?;
+    Ok(PruneSummary {
+        dry_run,
+        removed: report.removed.len(),
+        freed_bytes: report.freed_bytes,
+        retained,
+        errors: report.errors.iter().map(ToString::to_string).collect(),
+    })
+}
+
+fn print_plain(report: &MaintainReport) {
+    let problems = report.health["problems"].as_array().map_or(0, Vec::len);
+    let errors = report.health["errors"].as_array().map_or(0, Vec::len);
+    println!(
+        "health: {} models, {problems} blob problems, {errors} manifest errors",
+        report.health["models"]
+    );
+    if let Some(scrub) = &report.scrub {
+        println!(
+            "scrub: {} blobs ({}) checked{}, {} corrupt",
+            scrub.blobs_checked,
+            human_bytes(scrub.bytes_checked),
+            if scrub.blobs_resumed == 0 {
+                String::new()
+            } else {
+                format!(" ({} done by an earlier run)", scrub.blobs_resumed)
+            },
+            scrub.corrupt.len()
+        );
+        for c in &scrub.corrupt {
+            println!("  corrupt {} ({})", c.digest, c.models.join(", "));
+        }
+    }
+    let prune = &report.prune;
+    println!(
+        "prune: {} orphans {}, {} {}, {} kept by retention",
+        prune.removed,
+        if prune.dry_run { "prunable" } else { "removed" },
+        human_bytes(prune.freed_bytes),
+        if prune.dry_run {
+            "would be freed"
+        } else {
+            "freed"
+        },
+        prune.retained
+    );
+    for e in &prune.errors {
+        println!("  error: {e}");
+    }
+    println!("{}", if report.ok { "ok" } else { "PROBLEMS FOUND" });
+}
+
+fn load_state(path: &Path) -> Result<Option<State>> {
+    match fs::read(path) {
+        Ok(body) => Ok(Some(
+            serde_json::from_slice(&body)
+                .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?,
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn default_state_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("ollama-file-find")
+        .join("maintain.json")
+}
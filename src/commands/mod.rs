@@ -0,0 +1,54 @@
+pub(crate) mod apply;
+pub(crate) mod backup;
+pub(crate) mod bench;
+pub(crate) mod bundle;
+pub(crate) mod can_fit;
+pub(crate) mod card;
+pub(crate) mod cat_blob;
+pub(crate) mod chunk_stats;
+pub(crate) mod clone;
+pub(crate) mod compare;
+pub(crate) mod complete_names;
+pub(crate) mod compliance;
+pub(crate) mod daemon;
+pub(crate) mod dedupe_tags;
+pub(crate) mod doctor;
+pub(crate) mod du;
+pub(crate) mod edit;
+pub(crate) mod env;
+#[cfg(feature = "network")]
+pub(crate) mod estimate;
+pub(crate) mod events;
+pub(crate) mod export;
+pub(crate) mod export_static;
+pub(crate) mod facts;
+pub(crate) mod fit;
+#[cfg(feature = "signatures")]
+pub(crate) mod inventory;
+pub(crate) mod list;
+pub(crate) mod list_blobs;
+pub(crate) mod maintain;
+#[cfg(all(feature = "fuse", unix))]
+pub(crate) mod mount;
+pub(crate) mod orphans;
+pub(crate) mod owners;
+#[cfg(feature = "peer")]
+pub(crate) mod peer;
+pub(crate) mod pin;
+pub(crate) mod policy;
+pub(crate) mod provenance;
+pub(crate) mod prune;
+pub(crate) mod quota;
+pub(crate) mod recover;
+pub(crate) mod restore_manifest;
+pub(crate) mod sandbox;
+pub(crate) mod seal;
+pub(crate) mod search;
+pub(crate) mod serve;
+pub(crate) mod set_param;
+pub(crate) mod set_system;
+pub(crate) mod set_template;
+pub(crate) mod show;
+pub(crate) mod strip_layer;
+pub(crate) mod verify;
+pub(crate) mod verify_manifests;
@@ -0,0 +1,338 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::{OsStr, OsString},
+    fs::{self, File},
+    os::unix::fs::{FileExt, MetadataExt},
+    path::PathBuf,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use fuser::{
+    Errno, FileAttr, FileHandle, FileType, Filesystem, FopenFlags, Generation, INodeNo, LockOwner,
+    MountOption, OpenAccMode, OpenFlags, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty,
+    ReplyEntry, ReplyOpen, Request,
+};
+use ollama_file_find::{BlobStore, HiddenPolicy, LayerKind, ManifestStore};
+
+use crate::{Result, StorePaths, args::MountArgs};
+
+/// The tree never changes while mounted, so the kernel may cache it for a while.
+const TTL: Duration = Duration::from_secs(60);
+
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_stop(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn run(paths: &StorePaths, args: MountArgs) -> Result<()> {
+    let fs = StoreFs::build(paths)?;
+    eprintln!(
+        "Mounting {} model(s) read-only at {}; Ctrl-C unmounts",
+        fs.models,
+        args.dir.display()
+    );
+    let mut config = fuser::Config::default();
+    config.mount_options.extend([
+        MountOption::RO,
+        MountOption::NoExec,
+        MountOption::FSName("ollama".to_string()),
+        MountOption::Subtype("ollama-file-find".to_string()),
+    ]);
+    if args.allow_other {
+        config.acl = fuser::SessionACL::All;
+    }
+    let session = fuser::spawn_mount(fs, &args.dir, &config)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", args.dir.display()))?;
+
+    // SAFETY: the handler only stores to an atomic.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGTERM,
+            request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    // Also stop when someone else unmounts the directory.
+    while !STOP.load(Ordering::SeqCst) && !session.guard.is_finished() {
+        thread::sleep(Duration::from_millis(200));
+    }
+    if session.guard.is_finished() {
+        session.join()?;
+    } else {
+        session.umount_and_join()?;
+    }
+    Ok(())
+}
+
+enum Node {
+    Dir(BTreeMap<OsString, INodeNo>),
+    File {
+        path: PathBuf,
+        size: u64,
+        mtime: SystemTime,
+    },
+}
+
+/// A read-only view of the store: `<name>.gguf` for each model's weights and a
+/// `<name>/` folder with its other layers, where `name` is the model name split on `/`
+/// into directories (`llama3:8b.gguf`, `team/model:tag/template`).
+struct StoreFs {
+    /// Indexed by inode number minus one; the root is inode 1
+    nodes: Vec<Node>,
+    models: usize,
+    open: Mutex<HashMap<u64, File>>,
+    next_handle: AtomicU64,
+    uid: u32,
+    gid: u32,
+    mounted_at: SystemTime,
+}
+
+impl StoreFs {
+    fn build(paths: &StorePaths) -> Result<Self> {
+        let owner = fs::metadata(&paths.manifests)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", paths.manifests.display()))?;
+        let mut fs = StoreFs {
+            nodes: vec![Node::Dir(BTreeMap::new())],
+            models: 0,
+            open: Mutex::new(HashMap::new()),
+            next_handle: AtomicU64::new(1),
+            uid: owner.uid(),
+            gid: owner.gid(),
+            mounted_at: SystemTime::now(),
+        };
+        let blobs = BlobStore::new(&paths.blobs);
+        let (manifests, errors) =
+            ManifestStore::new(&paths.manifests).manifests(HiddenPolicy::Exclude);
+        for e in &errors {
+            eprintln!("Warning: {e}");
+        }
+        for stored in &manifests {
+            let name = stored.id.normalize();
+            let mut parts: Vec<&str> = name.split('/').collect();
+            let Some(leaf) = parts.pop() else { continue };
+            let dir = parts
+                .iter()
+                .fold(INodeNo::ROOT, |dir, part| fs.dir(dir, OsStr::new(part)));
+            let folder = fs.dir(dir, OsStr::new(leaf));
+            let mut seen: HashMap<String, usize> = HashMap::new();
+            for layer in &stored.data.layers {
+                let kind = layer.kind();
+                let path = blobs.path_for(&layer.digest);
+                if kind == LayerKind::Weights {
+                    fs.file(dir, format!("{leaf}.gguf"), path);
+                    continue;
+                }
+                let count = seen.entry(kind.to_string()).or_default();
+                *count += 1;
+                let mut file = match *count {
+                    1 => kind.to_string(),
+                    n => format!("{kind}-{n}"),
+                };
+                if matches!(kind, LayerKind::Adapter | LayerKind::Projector) {
+                    file.push_str(".gguf");
+                }
+                fs.file(folder, file, path);
+            }
+            fs.models += 1;
+        }
+        Ok(fs)
+    }
+
+    fn node(&self, ino: INodeNo) -> Option<&Node> {
+        let index = usize::try_from(u64::from(ino)).ok()?.checked_sub(1)?;
+        self.nodes.get(index)
+    }
+
+    fn push(&mut self, node: Node) -> INodeNo {
+        self.nodes.push(node);
+        INodeNo(self.nodes.len() as u64)
+    }
+
+    /// The directory `name` under `parent`, created if needed.
+    fn dir(&mut self, parent: INodeNo, name: &OsStr) -> INodeNo {
+        if let Some(Node::Dir(children)) = self.node(parent)
+            && let Some(&ino) = children.get(name)
+            && matches!(self.node(ino), Some(Node::Dir(_)))
+        {
+            return ino;
+        }
+        let ino = self.push(Node::Dir(BTreeMap::new()));
+        self.link(parent, name.to_os_string(), ino);
+        ino
+    }
+
+    /// A file backed by the blob at `path`; missing blobs are left out with a warning.
+    fn file(&mut self, parent: INodeNo, name: String, path: PathBuf) {
+        let meta = match fs::metadata(&path) {
+            Ok(meta) => meta,
+            Err(e) => {
+                eprintln!("Warning: skipping {name}: {}: {e}", path.display());
+                return;
+            }
+        };
+        let ino = self.push(Node::File {
+            path,
+            size: meta.len(),
+            mtime: meta.modified().unwrap_or(self.mounted_at),
+        });
+        self.link(parent, name.into(), ino);
+    }
+
+    fn link(&mut self, parent: INodeNo, name: OsString, ino: INodeNo) {
+        let index = u64::from(parent) as usize - 1;
+        if let Node::Dir(children) = &mut self.nodes[index] {
+            children.insert(name, ino);
+        }
+    }
+
+    fn attr(&self, ino: INodeNo) -> Option<FileAttr> {
+        let (kind, size, mtime, perm, nlink) = match self.node(ino)? {
+            Node::Dir(_) => (FileType::Directory, 0, self.mounted_at, 0o555, 2),
+            Node::File { size, mtime, .. } => (FileType::RegularFile, *size, *mtime, 0o444, 1),
+        };
+        Some(FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 1 << 20,
+            flags: 0,
+        })
+    }
+}
+
+impl Filesystem for StoreFs {
+    fn lookup(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        let found = match self.node(parent) {
+            Some(Node::Dir(children)) => children.get(name).and_then(|&ino| self.attr(ino)),
+            _ => None,
+        };
+        match found {
+            Some(attr) => reply.entry(&TTL, &attr, Generation(0)),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn getattr(&self, _req: &Request, ino: INodeNo, _fh: Option<FileHandle>, reply: ReplyAttr) {
+        match self.attr(ino) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(Errno::ENOENT),
+        }
+    }
+
+    fn readdir(
+        &self,
+        _req: &Request,
+        ino: INodeNo,
+        _fh: FileHandle,
+        offset: u64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(Node::Dir(children)) = self.node(ino) else {
+            reply.error(Errno::ENOTDIR);
+            return;
+        };
+        let entries = [
+            (ino, FileType::Directory, OsStr::new(".")),
+            (ino, FileType::Directory, OsStr::new("..")),
+        ]
+        .into_iter()
+        .chain(children.iter().map(|(name, &child)| {
+            let kind = match self.node(child) {
+                Some(Node::Dir(_)) => FileType::Directory,
+                _ => FileType::RegularFile,
+            };
+            (child, kind, name.as_os_str())
+        }));
+        for (i, (child, kind, name)) in entries.enumerate().skip(offset as usize) {
+            if reply.add(child, i as u64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn open(&self, _req: &Request, ino: INodeNo, flags: OpenFlags, reply: ReplyOpen) {
+        if flags.acc_mode() != OpenAccMode::O_RDONLY {
+            reply.error(Errno::EROFS);
+            return;
+        }
+        let Some(Node::File { path, .. }) = self.node(ino) else {
+            reply.error(Errno::EISDIR);
+            return;
+        };
+        match File::open(path) {
+            Ok(file) => {
+                let fh = self.next_handle.fetch_add(1, Ordering::Relaxed);
+                self.open.lock().unwrap().insert(fh, file);
+                // Blobs never change under a digest, so cached pages stay valid.
+                reply.opened(FileHandle(fh), FopenFlags::FOPEN_KEEP_CACHE);
+            }
+            Err(e) => reply.error(e.into()),
+        }
+    }
+
+    fn read(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        offset: u64,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<LockOwner>,
+        reply: ReplyData,
+    ) {
+        let open = self.open.lock().unwrap();
+        let Some(file) = open.get(&u64::from(fh)) else {
+            reply.error(Errno::EBADF);
+            return;
+        };
+        let mut buf = vec![0; size as usize];
+        let mut filled = 0;
+        while filled < buf.len() {
+            match file.read_at(&mut buf[filled..], offset + filled as u64) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    reply.error(e.into());
+                    return;
+                }
+            }
+        }
+        reply.data(&buf[..filled]);
+    }
+
+    fn release(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        _flags: OpenFlags,
+        _lock_owner: Option<LockOwner>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.open.lock().unwrap().remove(&u64::from(fh));
+        reply.ok();
+    }
+}
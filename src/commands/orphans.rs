@@ -0,0 +1,189 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use ollama_file_find::{BlobEntry, BlobStore, Digest, GcPlan, ManifestStore, StrayFile, gc_plan};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::OrphansArgs,
+    commands::list_blobs::capped,
+    config::Config,
+    events::{default_log_path, former_owners, now_secs},
+    format::{human_bytes, human_duration},
+};
+
+#[derive(Serialize)]
+struct Orphan<'a> {
+    digest: Digest,
+    path: &'a PathBuf,
+    size: u64,
+    /// Seconds since the blob was last modified
+    age: Option<u64>,
+    /// Kept by the retention window
+    retained: bool,
+    /// Still referenced by a manifest backup, so kept by default
+    held: bool,
+    /// Still referenced by a pin, so always kept
+    pinned: bool,
+    /// Models the event log says used to reference the blob
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    formerly: Vec<String>,
+}
+
+impl<'a> Orphan<'a> {
+    fn new(b: &'a BlobEntry, retained: bool, held: bool, pinned: bool, now: u64) -> Self {
+        Orphan {
+            digest: b.digest,
+            path: &b.path,
+            size: b.size,
+            age: b.mtime.map(|m| now.saturating_sub(m)),
+            retained,
+            held,
+            pinned,
+            formerly: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct OrphansReport<'a> {
+    orphans: Vec<Orphan<'a>>,
+    /// Bytes `prune` would free
+    prunable_bytes: u64,
+    retained_bytes: u64,
+    /// Bytes only backups reference (`prune --include-held --execute` frees them)
+    held_bytes: u64,
+    /// Bytes only pins reference (`pin --remove` lets them go)
+    pinned_bytes: u64,
+    /// Retention window in seconds, if any
+    keep_newer_than: Option<u64>,
+    /// Digests referenced by manifests but missing from the blob store
+    missing: &'a [Digest],
+    /// Files in the blobs directory that aren't blobs: partial downloads and strays
+    stray: &'a [StrayFile],
+    stray_bytes: u64,
+    errors: Vec<String>,
+}
+
+/// Mark-and-sweep the store and apply the retention window: `keep_newer` if given,
+/// else the config's `[retention]` setting. With `include_held`, blobs only backups
+/// reference are treated as orphans too; pinned ones never are.
+pub(crate) fn plan(
+    paths: &StorePaths,
+    config: &Config,
+    keep_newer: Option<Duration>,
+    include_held: bool,
+) -> Result<(GcPlan, Option<Duration>)> {
+    let mut plan = gc_plan(
+        &ManifestStore::new(&paths.manifests),
+        &BlobStore::new(&paths.blobs),
+    )?;
+    if include_held {
+        plan.release_held();
+    }
+    let keep = keep_newer.or(config.retention.keep_orphans_newer_than.map(|d| d.0));
+    if let Some(keep) = keep {
+        plan.retain_newer_than(keep, now_secs());
+    }
+    Ok((plan, keep))
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: OrphansArgs) -> Result<()> {
+    let (plan, keep) = plan(paths, config, args.keep_newer, false)?;
+    let now = now_secs();
+    let mut orphans: Vec<Orphan> = plan
+        .unreachable
+        .iter()
+        .map(|b| Orphan::new(b, false, false, false, now))
+        .chain(
+            plan.retained
+                .iter()
+                .map(|b| Orphan::new(b, true, false, false, now)),
+        )
+        .chain(
+            plan.held
+                .iter()
+                .map(|b| Orphan::new(b, false, true, false, now)),
+        )
+        .chain(
+            plan.pinned
+                .iter()
+                .map(|b| Orphan::new(b, false, false, true, now)),
+        )
+        .collect();
+    let history: HashMap<Digest, Vec<String>> = if orphans.is_empty() {
+        HashMap::new()
+    } else {
+        former_owners(&default_log_path(paths))
+    };
+    for o in &mut orphans {
+        if let Some(models) = history.get(&o.digest) {
+            o.formerly = capped(models, args.full_refs);
+        }
+    }
+    orphans.sort_by_key(|o| o.digest);
+    let report = OrphansReport {
+        orphans,
+        prunable_bytes: plan.unreachable_bytes(),
+        retained_bytes: plan.retained.iter().map(|b| b.size).sum(),
+        held_bytes: plan.held_bytes(),
+        pinned_bytes: plan.pinned_bytes(),
+        keep_newer_than: keep.map(|d| d.as_secs()),
+        missing: &plan.missing,
+        stray: &plan.stray,
+        stray_bytes: plan.stray_bytes(),
+        errors: plan.errors.iter().map(ToString::to_string).collect(),
+    };
+
+    if args.plain {
+        for o in &report.orphans {
+            println!(
+                "{}\t{}\t{}{}{}",
+                o.digest,
+                human_bytes(o.size),
+                o.age.map_or_else(|| "-".to_string(), human_duration),
+                if o.retained {
+                    "\tretained"
+                } else if o.held {
+                    "\theld"
+                } else if o.pinned {
+                    "\tpinned"
+                } else {
+                    ""
+                },
+                if o.formerly.is_empty() {
+                    String::new()
+                } else {
+                    format!("\twas {}", o.formerly.join(", "))
+                }
+            );
+        }
+        for f in report.stray {
+            println!(
+                "{}\t{}\t{}\t{}",
+                f.path.display(),
+                human_bytes(f.size),
+                f.mtime.map_or_else(
+                    || "-".to_string(),
+                    |m| human_duration(now.saturating_sub(m))
+                ),
+                if f.partial_of.is_some() {
+                    "partial"
+                } else {
+                    "stray"
+                }
+            );
+        }
+        println!(
+            "{} prunable, {} retained, {} held by backups, {} pinned, {} in partial or stray files",
+            human_bytes(report.prunable_bytes),
+            human_bytes(report.retained_bytes),
+            human_bytes(report.held_bytes),
+            human_bytes(report.pinned_bytes),
+            human_bytes(report.stray_bytes)
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
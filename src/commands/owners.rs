@@ -0,0 +1,72 @@
+use ollama_file_find::{BlobStore, Digest, HiddenPolicy, LayerKind, ManifestStore};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::OwnersArgs};
+
+#[derive(Serialize)]
+struct Owners {
+    digest: Digest,
+    /// Whether the blob file is present
+    present: bool,
+    /// Models (hidden tags included) referencing the blob, with the layer's role
+    models: Vec<Owner>,
+    /// Pinned models whose blobs include it (see `pin`)
+    pins: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Owner {
+    model: String,
+    kind: LayerKind,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: OwnersArgs) -> Result<()> {
+    let manifests = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let digest = blobs.resolve(&args.digest)?;
+
+    let (stored, errors) = manifests.manifests(HiddenPolicy::Include);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let mut models: Vec<Owner> = stored
+        .iter()
+        .flat_map(|m| {
+            m.data
+                .layers
+                .iter()
+                .chain(m.data.config.iter())
+                .filter(|l| l.digest == digest)
+                .map(|l| Owner {
+                    model: m.id.normalize(),
+                    kind: l.kind(),
+                })
+        })
+        .collect();
+    models.sort_by(|a, b| a.model.cmp(&b.model));
+    let pins = manifests
+        .pins()?
+        .into_iter()
+        .filter(|p| p.digests.contains(&digest))
+        .map(|p| p.model)
+        .collect();
+    let owners = Owners {
+        digest,
+        present: blobs.path_for(&digest).is_file(),
+        models,
+        pins,
+    };
+
+    if args.plain {
+        println!("{}", owners.digest);
+        for o in &owners.models {
+            println!("{}\t{}", o.model, o.kind);
+        }
+        for p in &owners.pins {
+            println!("{p}\tpinned");
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&owners)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Read},
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use ollama_file_find::{
+    BlobStore, Digest, ManifestData, ManifestStore, ModelId, VerifiedReader, sha256_digest,
+};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::{PeerArgs, PullFromPeerArgs, ServeArgs},
+    config::Config,
+    format::human_bytes,
+    progress::Progress,
+};
+
+/// mDNS service type peers advertise; the HTTP API is the one `serve` provides.
+const SERVICE_TYPE: &str = "_ollama-blobs._tcp.local.";
+
+#[derive(Serialize)]
+struct PullReport {
+    model: String,
+    /// Peer the manifest and blobs came from
+    peer: SocketAddr,
+    manifest_digest: Digest,
+    /// Peers that answered with this manifest; every one that answered must agree
+    peers_agreeing: usize,
+    blobs_fetched: usize,
+    bytes_fetched: u64,
+    blobs_present: usize,
+}
+
+/// Advertise this store on the LAN and serve it until interrupted.
+pub(crate) fn run(paths: &StorePaths, args: PeerArgs) -> Result<()> {
+    let blobs = BlobStore::new(&paths.blobs).blobs()?;
+    let host = hostname();
+    let mdns = ServiceDaemon::new()?;
+    let ip: Vec<IpAddr> = if args.listen.ip().is_unspecified() {
+        Vec::new()
+    } else {
+        vec![args.listen.ip()]
+    };
+    let mut service = ServiceInfo::new(
+        SERVICE_TYPE,
+        &host,
+        &format!("{host}.local."),
+        &ip[..],
+        args.listen.port(),
+        &[
+            ("version", env!("CARGO_PKG_VERSION")),
+            ("blobs", &blobs.len().to_string()),
+            ("inventory", "/blobs"),
+        ][..],
+    )?;
+    if ip.is_empty() {
+        service = service.enable_addr_auto();
+    }
+    mdns.register(service)?;
+    eprintln!(
+        "Advertising {} blobs as {host} ({SERVICE_TYPE})",
+        blobs.len()
+    );
+    let served = super::serve::run(
+        paths,
+        ServeArgs {
+            listen: args.listen,
+            threads: args.threads,
+            expose_paths: false,
+        },
+    );
+    let _ = mdns.shutdown();
+    served
+}
+
+/// Works on an empty models directory, so a fresh machine can be seeded from a peer.
+pub(crate) fn pull(
+    models_dir: Option<PathBuf>,
+    config: &Config,
+    args: PullFromPeerArgs,
+) -> Result<()> {
+    let models_dir = models_dir.unwrap_or_else(ollama_file_find::ollama_models_dir);
+    let id: ModelId = args.model.parse()?;
+    let name = id.normalize();
+    let peers = match args.peer {
+        Some(peer) => vec![peer],
+        None => discover(args.wait)?,
+    };
+    if peers.is_empty() {
+        anyhow::bail!("no peers found on the LAN (try --peer host:port or a longer --wait)");
+    }
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_connect(Some(Duration::from_secs(2)))
+        .timeout_recv_response(Some(Duration::from_secs(5)))
+        .build()
+        .into();
+    // Peers are not authenticated, so a manifest is only trusted when every peer that
+    // has the model serves the same bytes; blobs then come from the quickest to answer.
+    let mut answers: Vec<(Duration, SocketAddr, Vec<u8>)> = Vec::new();
+    for peer in peers {
+        let started = Instant::now();
+        let url = format!("http://{peer}/manifests/{}", name.replace(':', "%3A"));
+        let Ok(mut response) = agent.get(&url).call() else {
+            continue;
+        };
+        let Ok(body) = response
+            .body_mut()
+            .with_config()
+            .limit(1 << 20)
+            .read_to_vec()
+        else {
+            continue;
+        };
+        answers.push((started.elapsed(), peer, body));
+    }
+    let peers_agreeing = answers.len();
+    let (peer, body) = agreed_manifest(&name, answers)?;
+    let manifest_digest = sha256_digest(&body);
+    let manifest: ManifestData = serde_json::from_slice(&body)?;
+    eprintln!(
+        "Pulling {name} (manifest {manifest_digest}) from {peer}; \
+         {peers_agreeing} peer(s) agreed on it"
+    );
+
+    let blobs = BlobStore::new(models_dir.join("blobs"));
+    fs::create_dir_all(blobs.root())?;
+    let mut report = PullReport {
+        model: name,
+        peer,
+        manifest_digest,
+        peers_agreeing,
+        blobs_fetched: 0,
+        bytes_fetched: 0,
+        blobs_present: 0,
+    };
+    let digests: BTreeSet<_> = manifest
+        .layers
+        .iter()
+        .chain(manifest.config.iter())
+        .map(|l| (l.digest, l.size))
+        .collect();
+    let mut fetch = Vec::new();
+    for (digest, size) in digests {
+        if fs::metadata(blobs.path_for(&digest)).is_ok_and(|m| size.is_none_or(|s| m.len() == s)) {
+            report.blobs_present += 1;
+        } else {
+            fetch.push((digest, size));
+        }
+    }
+    let progress = Progress::start(
+        "download",
+        fetch.len(),
+        fetch.iter().map(|(_, size)| *size).sum(),
+    );
+    for (digest, _) in fetch {
+        let path = blobs.path_for(&digest);
+        let url = format!("http://{peer}/blobs/{digest}");
+        let response = agent.get(&url).call()?;
+        let mut reader = VerifiedReader::new(
+            response.into_body().into_reader(),
+            digest,
+            url.clone().into(),
+        );
+        report.bytes_fetched += download(&mut reader, &path)?;
+        report.blobs_fetched += 1;
+        progress.step(digest, reader.bytes_read());
+        progress.note(format_args!(
+            "  {digest} ({})",
+            human_bytes(reader.bytes_read())
+        ));
+    }
+    progress.finish();
+
+    // Blobs first, so the manifest never points at something missing.
+    let store = ManifestStore::new(models_dir.join("manifests"))
+        .with_backup_retention(config.retention.manifest_backups());
+    if let Some(backup) = store.write_manifest(&store.path_for(&id), &body)? {
+        eprintln!("Previous manifest saved to {}", backup.path.display());
+    }
+    if args.plain {
+        println!(
+            "{}\t{}\t{} blobs fetched ({})\t{} already present",
+            report.model,
+            report.peer,
+            report.blobs_fetched,
+            human_bytes(report.bytes_fetched),
+            report.blobs_present
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// The manifest every peer answered with, and the quickest of them to download from.
+/// Fails when no peer answered or any two served different bytes.
+fn agreed_manifest(
+    name: &str,
+    mut answers: Vec<(Duration, SocketAddr, Vec<u8>)>,
+) -> Result<(SocketAddr, Vec<u8>)> {
+    answers.sort_by_key(|(elapsed, ..)| *elapsed);
+    let Some((_, _, body)) = answers.first() else {
+        anyhow::bail!("no peer has {name}");
+    };
+    if answers.iter().any(|(_, _, other)| other != body) {
+        let served: Vec<String> = answers
+            .iter()
+            .map(|(_, peer, body)| format!("{peer} has {}", sha256_digest(body)))
+            .collect();
+        anyhow::bail!(
+            "peers disagree on the manifest of {name} ({}); pick one with --peer",
+            served.join(", ")
+        );
+    }
+    let (_, peer, body) = answers.swap_remove(0);
+    Ok((peer, body))
+}
+
+/// Stream into a temporary sibling of `path` and rename it into place only once the
+/// content has been verified.
+fn download(reader: &mut impl Read, path: &Path) -> Result<u64> {
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(format!(".peer-{}", std::process::id()));
+    let tmp = path.with_file_name(tmp_name);
+    let copied = fs::File::create(&tmp)
+        .and_then(|mut file| {
+            let n = io::copy(reader, &mut file)?;
+            file.sync_all()?;
+            Ok(n)
+        })
+        .and_then(|n| fs::rename(&tmp, path).map(|()| n));
+    if copied.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(copied?)
+}
+
+/// Browse for peers for `wait`, returning every resolved address (IPv4 first).
+fn discover(wait: Duration) -> Result<Vec<SocketAddr>> {
+    let mdns = ServiceDaemon::new()?;
+    let events = mdns.browse(SERVICE_TYPE)?;
+    let deadline = Instant::now() + wait;
+    let own = hostname();
+    let mut peers = BTreeSet::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = events.recv_timeout(left) else {
+            break;
+        };
+        if let ServiceEvent::ServiceResolved(service) = event {
+            if service.fullname.starts_with(&format!("{own}.")) {
+                continue;
+            }
+            for ip in service.get_addresses() {
+                let ip = ip.to_ip_addr();
+                peers.insert((ip.is_ipv6(), SocketAddr::new(ip, service.get_port())));
+            }
+        }
+    }
+    let _ = mdns.shutdown();
+    Ok(peers.into_iter().map(|(_, addr)| addr).collect())
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| format!("ollama-file-find-{}", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agreed_manifest() {
+        let (near, far): (SocketAddr, SocketAddr) = (
+            "10.0.0.2:11435".parse().unwrap(),
+            "10.0.0.3:11435".parse().unwrap(),
+        );
+        let answer = |ms, peer, body: &[u8]| (Duration::from_millis(ms), peer, body.to_vec());
+
+        let (peer, body) = agreed_manifest(
+            "llama3:8b",
+            vec![answer(30, far, b"{}"), answer(10, near, b"{}")],
+        )
+        .unwrap();
+        assert_eq!((peer, body.as_slice()), (near, &b"{}"[..]));
+
+        let err = agreed_manifest(
+            "llama3:8b",
+            vec![answer(10, near, b"{}"), answer(30, far, b"{ }")],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("peers disagree"), "{err}");
+        assert!(agreed_manifest("llama3:8b", Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use ollama_file_find::{ManifestStore, ModelId};
+use serde_json::json;
+
+use crate::{Result, StorePaths, args::PinArgs};
+
+pub(crate) fn run(paths: &StorePaths, args: PinArgs) -> Result<()> {
+    let store = ManifestStore::new(&paths.manifests);
+
+    let Some(model) = args.model else {
+        let pins = store.pins()?;
+        if args.plain {
+            for p in &pins {
+                let copy = p
+                    .copy
+                    .as_ref()
+                    .map_or_else(|| "-".to_string(), |c| c.display().to_string());
+                println!("{}\t{}\t{copy}", p.model, p.pinned_at);
+            }
+        } else {
+            println!("{}", serde_json::to_string_pretty(&pins)?);
+        }
+        return Ok(());
+    };
+    let id: ModelId = model.parse()?;
+
+    if args.remove {
+        let removed = store.unpin(&id)?;
+        if args.plain {
+            let verb = if removed.is_some() {
+                "unpinned"
+            } else {
+                "was not pinned:"
+            };
+            println!("{verb} {}", id.normalize());
+        } else {
+            let report = json!({ "model": id.normalize(), "unpinned": removed });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        return Ok(());
+    }
+
+    let pin = store.pin(&id, args.link)?;
+    if args.plain {
+        print!("pinned {} ({} blobs)", pin.model, pin.digests.len());
+        match &pin.copy {
+            Some(copy) => println!(", manifest linked at {}", copy.display()),
+            None => println!(),
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&pin)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ollama_file_find::{LayerKind, ManifestStore, fixtures::FakeStore};
+
+    use super::*;
+    use crate::{
+        commands::prune,
+        config::Config,
+        removal::remove_models,
+        test_util::{backups, layer, parse, paths},
+    };
+
+    #[test]
+    fn test_pinned_blobs_survive_removal_and_prune_include_held() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"pinned weights")
+            .config(br#"{"model_format":"gguf"}"#)
+            .model("phi3:mini")
+            .layer(LayerKind::Weights, b"backed-up weights")
+            .build(dir.path())
+            .unwrap();
+        let paths = paths(&store);
+        let before = fs::read(store.manifest_path("llama3:8b")).unwrap();
+
+        run(&paths, parse(&["llama3:8b", "--link"])).unwrap();
+        let pins = store.manifests().pins().unwrap();
+        let copy = pins[0].copy.clone().unwrap();
+        assert_eq!(fs::read(&copy).unwrap(), before);
+
+        let manifests = ManifestStore::new(&paths.manifests);
+        let id: ModelId = "llama3:8b".parse().unwrap();
+        let names = ["llama3:8b".to_string(), "phi3:mini".to_string()];
+        let (removed, failed) = remove_models(&manifests, &names);
+        assert_eq!(removed.len(), 2);
+        assert!(failed.is_empty());
+        let backups = backups(&store, "llama3:8b");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0].path).unwrap(), before);
+
+        let argv = ["--keep-newer", "0s", "--include-held", "--execute"];
+        prune::run(&paths, &Config::default(), parse(&argv)).unwrap();
+        assert!(!store.blob_path(b"backed-up weights").exists());
+        assert_eq!(
+            fs::read(store.blob_path(b"pinned weights")).unwrap(),
+            b"pinned weights"
+        );
+        assert!(store.blob_path(br#"{"model_format":"gguf"}"#).is_file());
+        // The linked copy still brings the whole model back.
+        manifests.restore_pinned(&id).unwrap();
+        assert_eq!(
+            layer(&store, "llama3:8b", LayerKind::Weights).unwrap(),
+            b"pinned weights"
+        );
+        fs::remove_file(store.manifest_path("llama3:8b")).unwrap();
+
+        run(&paths, parse(&["llama3:8b", "--remove"])).unwrap();
+        prune::run(&paths, &Config::default(), parse(&argv)).unwrap();
+        assert!(!store.blob_path(b"pinned weights").exists());
+    }
+}
@@ -0,0 +1,203 @@
+use std::process::ExitCode;
+
+use ollama_file_find::{
+    DEFAULT_HOST, LIBRARY_NAMESPACE, ManifestData, ManifestStore, ModelId, ModelLicense,
+};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::{PolicyAction, PolicyArgs, PolicyCheckArgs},
+    config::{Config, PolicyConfig},
+    format::{human_bytes, human_opt},
+};
+
+/// License layers larger than this are not fetched from registries.
+#[cfg(feature = "network")]
+const MAX_LICENSE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Serialize)]
+struct Verdict {
+    model: String,
+    /// Where the metadata came from: `local`, `upstream`, or `name` when the name
+    /// alone was denied
+    source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    allowed: bool,
+    violations: Vec<Violation>,
+}
+
+#[derive(Serialize)]
+struct Violation {
+    rule: &'static str,
+    message: String,
+}
+
+/// Metadata the rules are evaluated against.
+struct Subject {
+    source: &'static str,
+    manifest: ManifestData,
+    licenses: Vec<String>,
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: PolicyArgs) -> Result<ExitCode> {
+    match args.action {
+        PolicyAction::Check(check) => run_check(paths, &config.policy, check, args.plain),
+    }
+}
+
+fn run_check(
+    paths: &StorePaths,
+    policy: &PolicyConfig,
+    args: PolicyCheckArgs,
+    plain: bool,
+) -> Result<ExitCode> {
+    let id: ModelId = args.model.parse()?;
+    let mut violations = name_violations(policy, &id);
+    let local = local_subject(paths, &id)?;
+    // A name that is already denied needs no registry round trip.
+    let subject = match local {
+        Some(s) => Some(s),
+        None if !violations.is_empty() => None,
+        None if args.local => anyhow::bail!("{} is not installed locally", id.normalize()),
+        None => Some(upstream_subject(&id)?),
+    };
+    let mut size = None;
+    if let Some(subject) = &subject {
+        let total = subject
+            .manifest
+            .layers
+            .iter()
+            .chain(subject.manifest.config.iter())
+            .filter_map(|l| l.size)
+            .sum();
+        violations.extend(content_violations(policy, total, &subject.licenses));
+        size = Some(total);
+    }
+    let verdict = Verdict {
+        model: id.normalize(),
+        source: subject.as_ref().map_or("name", |s| s.source),
+        size,
+        allowed: violations.is_empty(),
+        violations,
+    };
+
+    if plain {
+        println!(
+            "{}: {} ({}, {})",
+            verdict.model,
+            if verdict.allowed { "allowed" } else { "denied" },
+            human_opt(verdict.size),
+            verdict.source
+        );
+        for v in &verdict.violations {
+            println!("  {}: {}", v.rule, v.message);
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&verdict)?);
+    }
+    Ok(if verdict.allowed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Rules decided by the model name alone.
+fn name_violations(policy: &PolicyConfig, id: &ModelId) -> Vec<Violation> {
+    let mut out = Vec::new();
+    let host = id.host.as_deref().unwrap_or(DEFAULT_HOST);
+    if !policy.allowed_hosts.is_empty() && !policy.allowed_hosts.iter().any(|h| h == host) {
+        out.push(Violation {
+            rule: "allowed_hosts",
+            message: format!("host {host} is not allowed"),
+        });
+    }
+    let namespace = id.namespace.as_deref().unwrap_or(LIBRARY_NAMESPACE);
+    if !policy.allowed_namespaces.is_empty()
+        && !policy.allowed_namespaces.iter().any(|n| n == namespace)
+    {
+        out.push(Violation {
+            rule: "allowed_namespaces",
+            message: format!("namespace {namespace} is not allowed"),
+        });
+    }
+    out
+}
+
+/// Rules that need the manifest and license text.
+fn content_violations(policy: &PolicyConfig, size: u64, licenses: &[String]) -> Vec<Violation> {
+    let mut out = Vec::new();
+    if let Some(max) = policy.max_model_size
+        && size > max.0
+    {
+        out.push(Violation {
+            rule: "max_model_size",
+            message: format!("{} exceeds {}", human_bytes(size), human_bytes(max.0)),
+        });
+    }
+    for blocked in &policy.blocked_licenses {
+        let needle = blocked.to_lowercase();
+        if licenses.iter().any(|l| l.to_lowercase().contains(&needle)) {
+            out.push(Violation {
+                rule: "blocked_licenses",
+                message: format!("license mentions {blocked:?}"),
+            });
+        }
+    }
+    out
+}
+
+/// The installed manifest for `id` (hidden tags included), if any.
+fn local_subject(paths: &StorePaths, id: &ModelId) -> Result<Option<Subject>> {
+    let Some(found) = ManifestStore::new(&paths.manifests).find(id) else {
+        return Ok(None);
+    };
+    let license = ModelLicense::read(&found.data.layers, &paths.blobs);
+    for digest in &license.missing {
+        eprintln!("Warning: license blob {digest} is missing");
+    }
+    Ok(Some(Subject {
+        source: "local",
+        manifest: found.data,
+        licenses: license.texts,
+    }))
+}
+
+#[cfg(feature = "network")]
+fn upstream_subject(id: &ModelId) -> Result<Subject> {
+    use ollama_file_find::registry::{fetch_blob, fetch_manifest};
+
+    let manifest = fetch_manifest(id)?;
+    let mut licenses = Vec::new();
+    for digest in license_layers(&manifest) {
+        let bytes = fetch_blob(id, &digest, MAX_LICENSE_BYTES)?;
+        licenses.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Ok(Subject {
+        source: "upstream",
+        manifest,
+        licenses,
+    })
+}
+
+#[cfg(not(feature = "network"))]
+fn upstream_subject(id: &ModelId) -> Result<Subject> {
+    anyhow::bail!(
+        "{} is not installed locally; checking upstream needs the `network` feature",
+        id.normalize()
+    )
+}
+
+/// Digests of license layers small enough to download.
+#[cfg(feature = "network")]
+fn license_layers(manifest: &ManifestData) -> Vec<ollama_file_find::Digest> {
+    manifest
+        .layers
+        .iter()
+        .filter(|l| l.kind() == ollama_file_find::LayerKind::License)
+        .filter(|l| l.size.is_none_or(|s| s <= MAX_LICENSE_BYTES))
+        .map(|l| l.digest)
+        .collect()
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeSet;
+
+use ollama_file_find::{HiddenPolicy, ManifestStore, ModelId, Origin, Provenance};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::ProvenanceArgs};
+
+#[derive(Serialize)]
+struct ProvenanceEntry {
+    name: String,
+    provenance: Provenance,
+    /// Present with `--check-registry`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream: Option<Upstream>,
+}
+
+/// How the local manifest compares to the one its registry publishes today.
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+#[cfg_attr(not(feature = "network"), allow(dead_code))]
+enum Upstream {
+    /// Same layers and config
+    Match,
+    /// The tag now points at different content (or was re-pushed)
+    Differs,
+    /// Could not be fetched (unreachable, private, or removed upstream)
+    Unavailable { error: String },
+}
+
+pub(crate) fn run(paths: &StorePaths, args: ProvenanceArgs) -> Result<()> {
+    let (manifests, errors) = ManifestStore::new(&paths.manifests).manifests(HiddenPolicy::Exclude);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let wanted: BTreeSet<String> = args
+        .models
+        .iter()
+        .map(|m| m.parse::<ModelId>().map(|id| id.normalize()))
+        .collect::<std::result::Result<_, _>>()?;
+    let registry = args.registry.as_deref().map(str::to_lowercase);
+
+    let mut entries = Vec::new();
+    for m in &manifests {
+        let name = m.id.normalize();
+        if !wanted.is_empty() && !wanted.contains(&name) {
+            continue;
+        }
+        let provenance = Provenance::of(&m.id, &m.data);
+        if (args.third_party && provenance.origin == Origin::OfficialLibrary)
+            || (args.unsigned && provenance.signed)
+            || registry
+                .as_ref()
+                .is_some_and(|r| *r != provenance.registry.to_lowercase())
+        {
+            continue;
+        }
+        #[cfg(feature = "network")]
+        let upstream = args.check_registry.then(|| upstream(m));
+        #[cfg(not(feature = "network"))]
+        let upstream = None;
+        entries.push(ProvenanceEntry {
+            name,
+            provenance,
+            upstream,
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if args.plain {
+        for e in &entries {
+            let p = &e.provenance;
+            let origin = match p.origin {
+                Origin::OfficialLibrary => "official",
+                Origin::CommunityNamespace => "community",
+                Origin::ThirdPartyRegistry => "third-party",
+            };
+            let mut line = format!(
+                "{}\t{origin}\t{}\t{}",
+                e.name,
+                p.registry,
+                if p.signed { "signed" } else { "unsigned" }
+            );
+            match &e.upstream {
+                Some(Upstream::Match) => line.push_str("\tupstream: match"),
+                Some(Upstream::Differs) => line.push_str("\tupstream: DIFFERS"),
+                Some(Upstream::Unavailable { error }) => {
+                    line.push_str(&format!("\tupstream: unavailable ({error})"));
+                }
+                None => {}
+            }
+            if let Some(source) = &p.source {
+                line.push_str(&format!("\tsource: {source}"));
+            }
+            println!("{line}");
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "network")]
+fn upstream(m: &ollama_file_find::StoredManifest) -> Upstream {
+    let digests = |manifest: &ollama_file_find::ManifestData| -> BTreeSet<_> {
+        manifest
+            .layers
+            .iter()
+            .chain(manifest.config.iter())
+            .map(|l| l.digest)
+            .collect()
+    };
+    match ollama_file_find::registry::fetch_manifest(&m.id) {
+        Ok(remote) if digests(&remote) == digests(&m.data) => Upstream::Match,
+        Ok(_) => Upstream::Differs,
+        Err(e) => Upstream::Unavailable {
+            error: e.to_string(),
+        },
+    }
+}
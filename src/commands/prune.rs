@@ -0,0 +1,177 @@
+use crate::{
+    Result, StorePaths,
+    args::PruneArgs,
+    commands::{
+        list_blobs::{capped, joined},
+        orphans::plan,
+    },
+    config::Config,
+    events::{default_log_path, former_owners, now_secs},
+    format::{human_bytes, human_duration},
+};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: PruneArgs) -> Result<()> {
+    let (mut plan, _) = plan(paths, config, args.keep_newer, args.include_held)?;
+    if args.partials {
+        plan.sweep_partials_older_than(args.partials_older_than, now_secs());
+    }
+    let recent_partials = plan.stray.iter().filter(|f| f.partial_of.is_some()).count();
+    if args.partials && recent_partials > 0 {
+        eprintln!(
+            "Keeping {recent_partials} partial file(s) of downloads written to in the last {}",
+            human_duration(args.partials_older_than.as_secs())
+        );
+    }
+    if !plan.held.is_empty() {
+        eprintln!(
+            "Keeping {} blob(s) only backups reference ({}); --include-held removes them",
+            plan.held.len(),
+            human_bytes(plan.held_bytes())
+        );
+    }
+    if !plan.pinned.is_empty() {
+        eprintln!(
+            "Keeping {} blob(s) only pins reference ({}); unpin the models to remove them",
+            plan.pinned.len(),
+            human_bytes(plan.pinned_bytes())
+        );
+    }
+    if !plan.retained.is_empty() {
+        eprintln!(
+            "Keeping {} recent orphan(s) ({})",
+            plan.retained.len(),
+            human_bytes(plan.retained.iter().map(|b| b.size).sum())
+        );
+    }
+
+    if !args.execute {
+        let history = if plan.unreachable.is_empty() {
+            Default::default()
+        } else {
+            former_owners(&default_log_path(paths))
+        };
+        for b in &plan.unreachable {
+            let was = history
+                .get(&b.digest)
+                .map(|models| format!(", was {}", joined(&capped(models, false), models.len())))
+                .unwrap_or_default();
+            println!(
+                "would remove {} ({}{was})",
+                b.path.display(),
+                human_bytes(b.size)
+            );
+        }
+        for f in &plan.partials {
+            println!(
+                "would remove partial {} ({})",
+                f.path.display(),
+                human_bytes(f.size)
+            );
+        }
+        println!(
+            "{} blob(s){}; would free {}",
+            plan.unreachable.len(),
+            partials_note(plan.partials.len()),
+            human_bytes(plan.unreachable_bytes())
+        );
+        if !plan.unreachable.is_empty() || !plan.partials.is_empty() {
+            eprintln!("Nothing was deleted; run again with --execute to remove these");
+        }
+        return Ok(());
+    }
+
+    let report = plan.execute()?;
+    for b in &report.removed {
+        println!("removed {} ({})", b.path.display(), human_bytes(b.size));
+    }
+    for f in &report.removed_partials {
+        println!(
+            "removed partial {} ({})",
+            f.path.display(),
+            human_bytes(f.size)
+        );
+    }
+    for e in &report.errors {
+        eprintln!("Warning: {e}");
+    }
+    println!(
+        "{} blob(s){} removed, {} freed",
+        report.removed.len(),
+        partials_note(report.removed_partials.len()),
+        human_bytes(report.freed_bytes)
+    );
+    Ok(())
+}
+
+fn partials_note(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!(" and {count} partial file(s)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use clap::Parser;
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: PruneArgs,
+    }
+
+    fn prune(paths: &StorePaths, argv: &[&str]) {
+        let argv = ["prune", "--keep-newer", "0s"]
+            .into_iter()
+            .chain(argv.iter().copied());
+        run(paths, &Config::default(), Cli::parse_from(argv).args).unwrap();
+    }
+
+    /// Two tags sharing their weights, a model with weights of its own, and an orphan.
+    fn store(dir: &std::path::Path) -> (FakeStore, StorePaths) {
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"shared")
+            .model("llama3:latest")
+            .layer(LayerKind::Weights, b"shared")
+            .model("phi3:mini")
+            .layer(LayerKind::Weights, b"phi3")
+            .orphan(b"orphan")
+            .build(dir.join("models"))
+            .unwrap();
+        let paths = StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap();
+        (store, paths)
+    }
+
+    #[test]
+    fn test_prune_only_lists_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let (store, paths) = store(dir.path());
+        fs::remove_file(store.manifest_path("phi3:mini")).unwrap();
+
+        prune(&paths, &[]);
+        prune(&paths, &["--dry-run"]);
+        assert!(store.blob_path(b"orphan").exists());
+        assert!(store.blob_path(b"phi3").exists());
+    }
+
+    #[test]
+    fn test_prune_execute_keeps_shared_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let (store, paths) = store(dir.path());
+        fs::remove_file(store.manifest_path("llama3:latest")).unwrap();
+        fs::remove_file(store.manifest_path("phi3:mini")).unwrap();
+
+        prune(&paths, &["--execute"]);
+        assert!(!store.blob_path(b"orphan").exists());
+        assert!(!store.blob_path(b"phi3").exists());
+        // Still referenced by `llama3:8b`.
+        assert!(store.blob_path(b"shared").exists());
+    }
+}
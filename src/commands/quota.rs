@@ -0,0 +1,118 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    process::ExitCode,
+};
+
+use ollama_file_find::{Digest, HiddenPolicy, ManifestStore};
+use serde::Serialize;
+
+use crate::{
+    Result, StorePaths,
+    args::{QuotaAction, QuotaArgs},
+    config::Config,
+    format::{human_bytes, human_opt},
+};
+
+#[derive(Serialize)]
+struct NamespaceUsage {
+    namespace: String,
+    /// Declared bytes of the distinct blobs the namespace's models reference
+    used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u64>,
+    over: bool,
+    models: usize,
+    /// Biggest model in the namespace, the usual candidate for removal
+    largest: Option<ModelSize>,
+}
+
+#[derive(Serialize)]
+struct ModelSize {
+    name: String,
+    size: u64,
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: QuotaArgs) -> Result<ExitCode> {
+    let check = matches!(args.action, Some(QuotaAction::Check));
+    if check && config.quota.is_empty() {
+        eprintln!("Warning: no [quota] limits configured");
+    }
+
+    let mut usage = namespace_usage(paths);
+    for u in &mut usage {
+        u.limit = config.quota.get(&u.namespace).map(|l| l.0);
+        u.over = u.limit.is_some_and(|l| u.used > l);
+    }
+    if check {
+        usage.retain(|u| u.over);
+    }
+
+    if args.plain {
+        for u in &usage {
+            let hint = match (&u.largest, u.over) {
+                (Some(m), true) => format!("  (largest: {}, {})", m.name, human_bytes(m.size)),
+                _ => String::new(),
+            };
+            println!(
+                "{}\t{} of {}{}{hint}",
+                u.namespace,
+                human_bytes(u.used),
+                human_opt(u.limit),
+                if u.over { "\tOVER" } else { "" }
+            );
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+    }
+
+    Ok(if check && usage.iter().any(|u| u.over) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Usage of every namespace present in the store. A blob shared by two models counts
+/// once per namespace, since removing either namespace alone would not free it.
+fn namespace_usage(paths: &StorePaths) -> Vec<NamespaceUsage> {
+    // Hidden tags pin blobs too.
+    let mut by_ns: BTreeMap<String, (HashSet<Digest>, NamespaceUsage)> = BTreeMap::new();
+    let errors = ManifestStore::new(&paths.manifests).visit(HiddenPolicy::Include, |id, m| {
+        // Validate every digest before counting anything, so a bad manifest is skipped whole.
+        let layers = m
+            .entries()
+            .map(|l| Ok((l.digest()?, l.size.unwrap_or(0))))
+            .collect::<ollama_file_find::Result<Vec<_>>>()?;
+        let ns = id.qualified_namespace();
+        let (seen, usage) = by_ns.entry(ns.clone()).or_insert_with(|| {
+            let usage = NamespaceUsage {
+                namespace: ns,
+                used: 0,
+                limit: None,
+                over: false,
+                models: 0,
+                largest: None,
+            };
+            (HashSet::new(), usage)
+        });
+        let mut model_size = 0;
+        for (digest, size) in layers {
+            model_size += size;
+            if seen.insert(digest) {
+                usage.used += size;
+            }
+        }
+        usage.models += 1;
+        if usage.largest.as_ref().is_none_or(|l| model_size > l.size) {
+            usage.largest = Some(ModelSize {
+                name: id.normalize(),
+                size: model_size,
+            });
+        }
+        Ok(())
+    });
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    by_ns.into_values().map(|(_, u)| u).collect()
+}
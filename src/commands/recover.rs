@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use ollama_file_find::{BlobStore, Digest, ManifestStore, ModelId, gc_plan, recovery_candidates};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::RecoverArgs, config::Config, format::human_bytes};
+
+#[derive(Serialize)]
+struct Recovered<'a> {
+    digest: Digest,
+    size: u64,
+    /// `general.name` from the GGUF metadata
+    gguf_name: Option<&'a str>,
+    architecture: Option<&'a str>,
+    /// Name the model is (or would be) available under
+    model: String,
+    /// Manifest written, when `--write` was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest: Option<String>,
+}
+
+/// A pinned model whose manifest is gone but whose linked copy is not.
+#[derive(Serialize)]
+struct Unpinned {
+    model: String,
+    pinned_copy: PathBuf,
+    /// Manifest put back, when `--write` was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Entry<'a> {
+    Pinned(Unpinned),
+    Orphan(Recovered<'a>),
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: RecoverArgs) -> Result<()> {
+    let manifests = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+    let blobs = BlobStore::new(&paths.blobs);
+
+    let mut pinned = Vec::new();
+    for pin in manifests.pins()? {
+        let Some(copy) = pin.copy else { continue };
+        let id: ModelId = pin.model.parse()?;
+        if manifests.path_for(&id).exists() {
+            continue;
+        }
+        if !copy.exists() {
+            eprintln!(
+                "Warning: {} is pinned but its linked manifest {} is gone",
+                pin.model,
+                copy.display()
+            );
+            continue;
+        }
+        let manifest = if args.write {
+            Some(manifests.restore_pinned(&id)?.display().to_string())
+        } else {
+            None
+        };
+        pinned.push(Unpinned {
+            model: pin.model,
+            pinned_copy: copy,
+            manifest,
+        });
+    }
+
+    let plan = gc_plan(&manifests, &blobs)?;
+    let candidates = recovery_candidates(&plan.unreachable, &manifests);
+
+    let mut out = Vec::with_capacity(candidates.len());
+    for c in &candidates {
+        let manifest = if args.write {
+            Some(c.write(&manifests, &blobs)?.display().to_string())
+        } else {
+            None
+        };
+        out.push(Recovered {
+            digest: c.blob.digest,
+            size: c.blob.size,
+            gguf_name: c.metadata.name(),
+            architecture: c.metadata.architecture(),
+            model: c.id.normalize(),
+            manifest,
+        });
+    }
+
+    if args.plain {
+        let verb = if args.write {
+            "recovered"
+        } else {
+            "would recover"
+        };
+        for p in &pinned {
+            println!("{verb} pinned {} from {}", p.model, p.pinned_copy.display());
+        }
+        for r in &out {
+            println!(
+                "{verb} {} as {} ({}, {})",
+                r.digest,
+                r.model,
+                r.architecture.unwrap_or("unknown arch"),
+                human_bytes(r.size)
+            );
+        }
+        if out.is_empty() && pinned.is_empty() {
+            println!("no orphaned GGUF blobs or removed pinned manifests found");
+        } else if !args.write {
+            println!("run with --write to create the manifests");
+        }
+    } else {
+        let entries: Vec<Entry> = pinned
+            .into_iter()
+            .map(Entry::Pinned)
+            .chain(out.into_iter().map(Entry::Orphan))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::time::SystemTime;
+
+use ollama_file_find::{BlobStore, ManifestStore, ModelId};
+use serde_json::json;
+
+use crate::{
+    Result, StorePaths, args::RestoreManifestArgs, config::Config, format::human_duration,
+};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: RestoreManifestArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let store = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+
+    if args.list {
+        let backups = store.backups(&id);
+        if args.plain {
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            for b in &backups {
+                println!(
+                    "{}\t{} ago\t{}",
+                    b.taken_at,
+                    human_duration(now.saturating_sub(b.taken_at)),
+                    b.path.display()
+                );
+            }
+        } else {
+            println!("{}", serde_json::to_string_pretty(&backups)?);
+        }
+        return Ok(());
+    }
+
+    let restored = store.restore_backup(&id, args.at)?;
+    // Blobs may have been pruned since the backup was taken.
+    let blobs = BlobStore::new(&paths.blobs);
+    let missing: Vec<String> = store
+        .find(&id)
+        .map(|m| {
+            m.data
+                .layers
+                .iter()
+                .chain(m.data.config.iter())
+                .filter(|l| !blobs.path_for(&l.digest).exists())
+                .map(|l| l.digest.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    for digest in &missing {
+        eprintln!("Warning: restored manifest references missing blob {digest}");
+    }
+    if args.plain {
+        println!(
+            "{}\trestored from {}\t{}",
+            id.normalize(),
+            restored.taken_at,
+            restored.path.display()
+        );
+    } else {
+        let report = json!({
+            "model": id.normalize(),
+            "restored": restored,
+            "missing_blobs": missing,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+    use crate::{
+        commands::set_system,
+        test_util::{backups, layer, parse, paths},
+    };
+
+    #[test]
+    fn test_restore_manifest_undoes_edit_and_backs_up_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::System, b"You are helpful.")
+            .build(dir.path())
+            .unwrap();
+        let paths = paths(&store);
+        let config = Config::default();
+        let original = fs::read(store.manifest_path("llama3:8b")).unwrap();
+        set_system::run(&paths, &config, parse(&["llama3:8b", "Be terse."])).unwrap();
+        let edited = fs::read(store.manifest_path("llama3:8b")).unwrap();
+
+        run(&paths, &config, parse(&["llama3:8b"])).unwrap();
+        assert_eq!(
+            fs::read(store.manifest_path("llama3:8b")).unwrap(),
+            original
+        );
+        assert_eq!(
+            layer(&store, "llama3:8b", LayerKind::System).unwrap(),
+            b"You are helpful."
+        );
+        let backups = backups(&store, "llama3:8b");
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read(&backups[1].path).unwrap(), edited);
+    }
+}
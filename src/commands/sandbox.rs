@@ -0,0 +1,226 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ollama_file_find::{BlobStore, HiddenPolicy, ManifestStore, ModelId, copy_atomic};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Result, StorePaths,
+    args::{SandboxAction, SandboxArgs, SandboxCreateArgs, SandboxDestroyArgs},
+    events::now_secs,
+};
+
+/// Written into every sandbox; `destroy` refuses directories without it.
+const MARKER: &str = ".ollama-file-find-sandbox.json";
+
+#[derive(Serialize, Deserialize)]
+struct Marker {
+    /// Models directory the sandbox was made from
+    source: PathBuf,
+    created_at: u64,
+    /// How blobs were shared: `hardlink`, `symlink`, or `copy`
+    blobs: String,
+}
+
+pub(crate) fn run(models_dir: Option<PathBuf>, args: SandboxArgs) -> Result<()> {
+    match args.action {
+        SandboxAction::Create(create_args) => {
+            create(&StorePaths::resolve(models_dir)?, create_args)
+        }
+        SandboxAction::Destroy(destroy_args) => destroy(destroy_args),
+    }
+}
+
+/// Copy manifests (small, and rewritten rather than edited in place by Ollama) and
+/// hardlink blobs, so the sandbox can be changed freely without touching the store.
+fn create(paths: &StorePaths, args: SandboxCreateArgs) -> Result<()> {
+    let dir = match args.dir {
+        Some(dir) => dir,
+        None => std::env::temp_dir().join(format!("ollama-sandbox-{}", std::process::id())),
+    };
+    if fs::read_dir(&dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        anyhow::bail!("{} is not empty", dir.display());
+    }
+    let store = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let (manifests, errors) = store.manifests(HiddenPolicy::Include);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let wanted: BTreeSet<String> = args
+        .models
+        .iter()
+        .map(|m| m.parse::<ModelId>().map(|id| id.normalize()))
+        .collect::<std::result::Result<_, _>>()?;
+    let manifests: Vec<_> = manifests
+        .into_iter()
+        .filter(|m| wanted.is_empty() || wanted.contains(&m.id.normalize()))
+        .collect();
+    if manifests.len() < wanted.len() {
+        let found: BTreeSet<_> = manifests.iter().map(|m| m.id.normalize()).collect();
+        let missing: Vec<_> = wanted.difference(&found).cloned().collect();
+        anyhow::bail!("model not found: {}", missing.join(", "));
+    }
+
+    let sandbox = ManifestStore::new(dir.join("manifests"));
+    let sandbox_blobs = BlobStore::new(dir.join("blobs"));
+    fs::create_dir_all(sandbox.root())?;
+    fs::create_dir_all(sandbox_blobs.root())?;
+    let mut method = "hardlink";
+    let mut linked = BTreeSet::new();
+    for m in &manifests {
+        for layer in m.data.layers.iter().chain(m.data.config.iter()) {
+            let from = blobs.path_for(&layer.digest);
+            if !linked.insert(layer.digest) || !from.is_file() {
+                continue;
+            }
+            method = share(&from, &sandbox_blobs.path_for(&layer.digest), method)?;
+        }
+        let target = sandbox
+            .root()
+            .join(m.path.strip_prefix(store.root()).unwrap_or(&m.path));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        copy_atomic(&m.path, &target)?;
+    }
+    let marker = Marker {
+        source: paths.blobs.parent().unwrap_or(&paths.blobs).to_path_buf(),
+        created_at: now_secs(),
+        blobs: method.to_string(),
+    };
+    fs::write(dir.join(MARKER), serde_json::to_vec_pretty(&marker)?)?;
+
+    let dir = fs::canonicalize(&dir)?;
+    eprintln!(
+        "Sandbox with {} models and {} blobs ({method}s) in {}",
+        manifests.len(),
+        linked.len(),
+        dir.display()
+    );
+    if method != "hardlink" {
+        eprintln!(
+            "Warning: the sandbox is on another filesystem; blobs were {}",
+            if method == "symlink" {
+                "symlinked (removing them from the store breaks the sandbox)"
+            } else {
+                "copied"
+            }
+        );
+    }
+    eprintln!("Restart `ollama serve` with this set to use it:");
+    if cfg!(windows) {
+        println!("$env:OLLAMA_MODELS = \"{}\"", dir.display());
+    } else {
+        println!("export OLLAMA_MODELS='{}'", dir.display());
+    }
+    Ok(())
+}
+
+/// Hardlink `from` to `to`, falling back (for the rest of the run) to a symlink or a
+/// copy when the sandbox is on another filesystem. Returns the method used.
+fn share(from: &Path, to: &Path, method: &'static str) -> Result<&'static str> {
+    if method == "hardlink" {
+        match fs::hard_link(from, to) {
+            Ok(()) => return Ok(method),
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+            Err(e) => return Err(anyhow::anyhow!("linking {}: {e}", from.display())),
+        }
+    }
+    #[cfg(unix)]
+    if method != "copy" {
+        std::os::unix::fs::symlink(fs::canonicalize(from)?, to)?;
+        return Ok("symlink");
+    }
+    copy_atomic(from, to)?;
+    Ok("copy")
+}
+
+fn destroy(args: SandboxDestroyArgs) -> Result<()> {
+    let dir = match args.dir {
+        Some(dir) => dir,
+        None => std::env::var_os("OLLAMA_MODELS")
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow::anyhow!("no sandbox given and OLLAMA_MODELS is not set"))?,
+    };
+    let marker: Marker = match fs::read(dir.join(MARKER)) {
+        Ok(body) => serde_json::from_slice(&body)?,
+        Err(_) => anyhow::bail!(
+            "{} is not a sandbox (no {MARKER}); refusing to delete it",
+            dir.display()
+        ),
+    };
+    fs::remove_dir_all(&dir)?;
+    eprintln!(
+        "Removed sandbox {} (made from {})",
+        dir.display(),
+        marker.source.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ollama_file_find::{LayerKind, fixtures::FakeStore};
+
+    use super::*;
+
+    fn store(dir: &Path) -> FakeStore {
+        FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .build(dir.join("models"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_destroy_refuses_non_sandbox() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path());
+        let models_dir = store.models_dir().to_path_buf();
+        let err = destroy(SandboxDestroyArgs {
+            dir: Some(models_dir.clone()),
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("not a sandbox"), "{err}");
+        assert!(store.manifest_path("llama3:8b").exists());
+
+        // A marker that isn't one of ours is refused as well.
+        fs::write(models_dir.join(MARKER), b"not json").unwrap();
+        assert!(
+            destroy(SandboxDestroyArgs {
+                dir: Some(models_dir)
+            })
+            .is_err()
+        );
+        assert!(store.blob_path(b"weights").exists());
+    }
+
+    #[test]
+    fn test_destroy_sandbox_keeps_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path());
+        let paths = StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap();
+        let sandbox = dir.path().join("sandbox");
+        create(
+            &paths,
+            SandboxCreateArgs {
+                models: Vec::new(),
+                dir: Some(sandbox.clone()),
+            },
+        )
+        .unwrap();
+        assert!(sandbox.join(MARKER).exists());
+
+        destroy(SandboxDestroyArgs {
+            dir: Some(sandbox.clone()),
+        })
+        .unwrap();
+        assert!(!sandbox.exists());
+        assert!(store.manifest_path("llama3:8b").exists());
+        assert_eq!(fs::read(store.blob_path(b"weights")).unwrap(), b"weights");
+    }
+}
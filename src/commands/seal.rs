@@ -0,0 +1,69 @@
+use std::{fs, process::ExitCode};
+
+use ollama_file_find::{BlobStore, Digest, ManifestStore, Seal, SealChange, write_atomic};
+
+use crate::{
+    Result, StorePaths,
+    args::{SealAction, SealArgs},
+};
+
+pub(crate) fn run(paths: &StorePaths, args: SealArgs) -> Result<ExitCode> {
+    let manifests = ManifestStore::new(&paths.manifests);
+    let blobs = BlobStore::new(&paths.blobs);
+    let Some(SealAction::Verify(verify)) = args.action else {
+        let seal = Seal::compute(&manifests, &blobs)?;
+        let mut body = serde_json::to_vec_pretty(&seal)?;
+        body.push(b'\n');
+        match &args.output {
+            Some(out) => {
+                write_atomic(out, &body)?;
+                eprintln!(
+                    "Sealed {} models and {} blobs in {}",
+                    seal.models.len(),
+                    seal.blobs.len(),
+                    out.display()
+                );
+            }
+            None => print!("{}", String::from_utf8(body)?),
+        }
+        // The root is what gets published alongside the bundle.
+        eprintln!("Store root: {}", seal.root);
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let seal: Seal = serde_json::from_slice(&fs::read(&verify.seal)?)?;
+    let mut changes = seal.verify(&manifests, &blobs)?;
+    if let Some(expected) = &verify.expect_root {
+        let expected: Digest = expected.parse()?;
+        if expected != seal.root && !changes.contains(&SealChange::SealTampered) {
+            changes.insert(0, SealChange::SealTampered);
+        }
+    }
+
+    if args.plain {
+        print_changes(&changes);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    }
+    Ok(if changes.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// One line per change, as `seal verify --plain` prints them.
+pub(crate) fn print_changes(changes: &[SealChange]) {
+    for c in changes {
+        match c {
+            SealChange::SealTampered => println!("seal\ttampered"),
+            SealChange::ModelAdded { model } => println!("{model}\tadded"),
+            SealChange::ModelRemoved { model } => println!("{model}\tremoved"),
+            SealChange::ManifestChanged { model } => println!("{model}\tchanged"),
+            SealChange::BlobMissing { digest } => println!("{digest}\tmissing"),
+            SealChange::BlobChanged { digest, found } => {
+                println!("{digest}\tchanged\tfound {found}");
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use ollama_file_find::{BlobStore, LayerKind, LicenseFamily, ManifestStore, StoredManifest};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Result, StorePaths, args::SearchArgs, config::Config};
+
+/// Metadata blobs (templates, params, config) larger than this are not indexed.
+const MAX_INDEXED_BYTES: u64 = 256 * 1024;
+
+/// Searchable text of one model, lowercased, split by where it came from.
+struct Document {
+    name: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+#[derive(Serialize)]
+struct Hit {
+    name: String,
+    /// Number of fields matching at least one term
+    score: usize,
+    /// Fields that matched, in index order
+    matched: Vec<&'static str>,
+}
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: SearchArgs) -> Result<()> {
+    let terms: Vec<String> = args
+        .query
+        .iter()
+        .flat_map(|q| q.split_whitespace())
+        .map(str::to_lowercase)
+        .collect();
+    if terms.is_empty() {
+        anyhow::bail!("empty query");
+    }
+
+    let (stored, errors) = ManifestStore::new(&paths.manifests).manifests(args.hidden.policy());
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let blobs = BlobStore::new(&paths.blobs);
+    let index: Vec<Document> = stored
+        .iter()
+        .map(|m| Document::build(m, &blobs, config))
+        .collect();
+
+    let mut hits: Vec<Hit> = index.iter().filter_map(|d| d.matches(&terms)).collect();
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+
+    if args.plain {
+        for h in &hits {
+            println!("{}\t{}", h.name, h.matched.join(","));
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+    }
+    Ok(())
+}
+
+impl Document {
+    fn build(m: &StoredManifest, blobs: &BlobStore, config: &Config) -> Self {
+        let name = m.id.normalize();
+        let mut fields = vec![("name", name.to_lowercase())];
+        let mut push = |field, text: String| {
+            if !text.is_empty() {
+                fields.push((field, text.to_lowercase()));
+            }
+        };
+
+        if let Some(cfg) = &m.data.config
+            && let Ok(text) = blobs.read_text(&cfg.digest, MAX_INDEXED_BYTES)
+        {
+            push("config", json_text(&text));
+        }
+        for layer in &m.data.layers {
+            let kind = layer.kind();
+            let wanted = matches!(
+                kind,
+                LayerKind::Template | LayerKind::System | LayerKind::Params | LayerKind::License
+            );
+            if !wanted {
+                continue;
+            }
+            let Ok(text) = blobs.read_text(&layer.digest, MAX_INDEXED_BYTES) else {
+                continue;
+            };
+            match kind {
+                LayerKind::Template => {
+                    push("template", format!("{} {text}", template_styles(&text)));
+                }
+                LayerKind::System => push("system", text),
+                LayerKind::Params => push("params", params_text(&text)),
+                // Only the family: full license text would match almost any word.
+                _ => push("license", LicenseFamily::detect(&text).to_string()),
+            }
+        }
+        let annotations: Vec<String> = m
+            .data
+            .annotations
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect();
+        push("annotations", annotations.join(" "));
+        push(
+            "labels",
+            config.labels_for(&m.id).collect::<Vec<_>>().join(" "),
+        );
+
+        Document { name, fields }
+    }
+
+    /// A hit if every term occurs in some field.
+    fn matches(&self, terms: &[String]) -> Option<Hit> {
+        let mut matched = Vec::new();
+        for term in terms {
+            let fields: Vec<_> = self
+                .fields
+                .iter()
+                .filter(|(_, text)| text.contains(term.as_str()))
+                .map(|(field, _)| *field)
+                .collect();
+            if fields.is_empty() {
+                return None;
+            }
+            for f in fields {
+                if !matched.contains(&f) {
+                    matched.push(f);
+                }
+            }
+        }
+        Some(Hit {
+            name: self.name.clone(),
+            score: matched.len(),
+            matched,
+        })
+    }
+}
+
+/// `key value` pairs of a JSON object, so keys and values are both searchable.
+fn json_text(text: &str) -> String {
+    match serde_json::from_str::<Value>(text) {
+        Ok(Value::Object(map)) => map
+            .iter()
+            .map(|(k, v)| match v {
+                Value::String(s) => format!("{k} {s}"),
+                other => format!("{k} {other}"),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => text.to_string(),
+    }
+}
+
+/// Params text plus the context window in `128k` form, as people usually write it.
+fn params_text(text: &str) -> String {
+    let mut out = json_text(text);
+    if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(text)
+        && let Some(ctx) = map.get("num_ctx").and_then(Value::as_u64)
+    {
+        out.push_str(&format!(" context {}k", ctx / 1024));
+    }
+    out
+}
+
+/// Names of the chat formats a template uses, recognized by their control tokens.
+fn template_styles(template: &str) -> String {
+    const STYLES: &[(&str, &str)] = &[
+        ("<|im_start|>", "chatml"),
+        ("<|start_header_id|>", "llama3"),
+        ("[INST]", "inst mistral llama2"),
+        ("<start_of_turn>", "gemma"),
+        ("<|user|>", "zephyr phi3"),
+        ("### Instruction", "alpaca"),
+        (".Tools", "tools"),
+    ];
+    STYLES
+        .iter()
+        .filter(|(token, _)| template.contains(token))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,273 @@
+use std::{fs, io::Read, io::Seek, io::SeekFrom, path::Path, sync::Arc, thread};
+
+use ollama_file_find::{
+    BlobStore, Digest, MANIFEST_MEDIA_TYPE, ManifestStore, ModelId, ScanArgs, scan_manifests,
+};
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+use crate::{Result, StorePaths, args::ServeArgs};
+
+pub(crate) fn run(paths: &StorePaths, args: ServeArgs) -> Result<()> {
+    let server = Arc::new(Server::http(args.listen).map_err(|e| anyhow::anyhow!("{e}"))?);
+    eprintln!(
+        "Serving {} on http://{} (GET /models, /manifests/<model>, /blobs, /blobs/<digest>)",
+        paths.blobs.display(),
+        args.listen
+    );
+    let expose_paths = args.expose_paths;
+    let workers: Vec<_> = (0..args.threads)
+        .map(|_| {
+            let (server, paths) = (Arc::clone(&server), paths.clone());
+            thread::spawn(move || {
+                while let Ok(request) = server.recv() {
+                    let method = request.method().clone();
+                    let url = request.url().to_string();
+                    let status = handle(&paths, expose_paths, request);
+                    if let Err(e) = status {
+                        eprintln!("Warning: {method} {url}: {e}");
+                    }
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+/// A model as `GET /models` lists it: without filesystem paths unless asked for, since
+/// they tell every client on the network how the server's disks are laid out.
+#[derive(Serialize)]
+struct ServedModel<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    model_id: &'a ModelId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest_path: Option<&'a Path>,
+}
+
+fn handle(paths: &StorePaths, expose_paths: bool, request: Request) -> std::io::Result<()> {
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        return request
+            .respond(text(405, "method not allowed").with_header(header("Allow", "GET, HEAD")));
+    }
+    let url = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if url == "/models" {
+        let outcome = scan_manifests(&ScanArgs::new(&paths.manifests, &paths.blobs));
+        let models: Vec<ServedModel> = outcome
+            .models
+            .iter()
+            .map(|m| ServedModel {
+                name: &m.name,
+                model_id: &m.model_id,
+                manifest_path: expose_paths.then_some(m.manifest_path.as_path()),
+            })
+            .collect();
+        return request.respond(json(&models));
+    }
+    if url == "/blobs" {
+        let inventory: Vec<_> = BlobStore::new(&paths.blobs)
+            .blobs()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|b| serde_json::json!({ "digest": b.digest, "size": b.size }))
+            .collect();
+        return request.respond(json(&inventory));
+    }
+    if let Some(name) = url.strip_prefix("/manifests/") {
+        let name = name
+            .replace("%3A", ":")
+            .replace("%3a", ":")
+            .replace("%2F", "/");
+        let found = name
+            .parse::<ModelId>()
+            .ok()
+            .and_then(|id| ManifestStore::new(&paths.manifests).find(&id));
+        return match found.and_then(|m| fs::read(m.path).ok()) {
+            Some(body) => request.respond(
+                Response::from_data(body).with_header(header("Content-Type", MANIFEST_MEDIA_TYPE)),
+            ),
+            None => request.respond(text(404, "model not found")),
+        };
+    }
+    let Some(name) = url.strip_prefix("/blobs/") else {
+        return request.respond(text(404, "not found"));
+    };
+    // Accept both the manifest form (`sha256:…`) and the file name form (`sha256-…`).
+    let digest = name
+        .replace("%3A", ":")
+        .replace("%3a", ":")
+        .parse::<Digest>()
+        .ok()
+        .or_else(|| Digest::from_blob_file_name(name));
+    let Some(digest) = digest else {
+        return request.respond(text(400, "invalid digest"));
+    };
+    serve_blob(&BlobStore::new(&paths.blobs), digest, request)
+}
+
+fn serve_blob(blobs: &BlobStore, digest: Digest, request: Request) -> std::io::Result<()> {
+    let Ok(mut file) = fs::File::open(blobs.path_for(&digest)) else {
+        return request.respond(text(404, "blob not found"));
+    };
+    let size = file.metadata()?.len();
+    // Blobs are content-addressed, so the digest is a strong validator.
+    let etag = format!("\"{digest}\"");
+    let headers = [
+        header("ETag", &etag),
+        header("Accept-Ranges", "bytes"),
+        header("Cache-Control", "public, max-age=31536000, immutable"),
+    ];
+    let request_header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str().to_string())
+    };
+
+    if request_header("If-None-Match").is_some_and(|v| etag_matches(&v, &etag)) {
+        let mut response = Response::empty(304);
+        for h in headers {
+            response.add_header(h);
+        }
+        return request.respond(response);
+    }
+    // A range only applies to the representation the client already has part of.
+    let range = request_header("Range")
+        .filter(|_| request_header("If-Range").is_none_or(|v| v.trim() == etag));
+    let (status, start, len) = match range.map(|r| parse_range(&r, size)) {
+        None | Some(RangeRequest::Ignored) => (200, 0, size),
+        Some(RangeRequest::Satisfiable(start, end)) => (206, start, end - start + 1),
+        Some(RangeRequest::Unsatisfiable) => {
+            let response = text(416, "range not satisfiable")
+                .with_header(header("Content-Range", &format!("bytes */{size}")));
+            return request.respond(response);
+        }
+    };
+
+    file.seek(SeekFrom::Start(start))?;
+    let mut response = Response::new(
+        StatusCode(status),
+        headers.to_vec(),
+        file.take(len),
+        usize::try_from(len).ok(),
+        None,
+    )
+    .with_header(header("Content-Type", "application/octet-stream"));
+    if status == 206 {
+        response.add_header(header(
+            "Content-Range",
+            &format!("bytes {start}-{}/{size}", start + len - 1),
+        ));
+    }
+    request.respond(response)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RangeRequest {
+    /// Serve the whole blob (unsupported unit, several ranges, or not a valid range)
+    Ignored,
+    /// Inclusive byte range
+    Satisfiable(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parse a single `bytes=` range (`a-b`, `a-`, or `-suffix`) against a blob of `size`.
+/// A header that isn't a valid range is ignored and the whole blob served, as RFC 9110
+/// requires; only a valid range that starts past the end is unsatisfiable.
+fn parse_range(value: &str, size: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Ignored;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Ignored;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return RangeRequest::Ignored;
+    };
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        return match last.parse::<u64>() {
+            Ok(suffix) if suffix > 0 && size > 0 => {
+                RangeRequest::Satisfiable(size.saturating_sub(suffix), size - 1)
+            }
+            Ok(_) => RangeRequest::Unsatisfiable,
+            Err(_) => RangeRequest::Ignored,
+        };
+    }
+    let Ok(first) = first.parse::<u64>() else {
+        return RangeRequest::Ignored;
+    };
+    let last = match last.parse::<u64>() {
+        _ if last.is_empty() => None,
+        Ok(last) if last >= first => Some(last),
+        _ => return RangeRequest::Ignored,
+    };
+    if first >= size {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Satisfiable(first, last.map_or(size - 1, |l| l.min(size - 1)))
+}
+
+/// Whether an `If-None-Match` list names `etag` (or is `*`).
+fn etag_matches(value: &str, etag: &str) -> bool {
+    value
+        .split(',')
+        .map(|t| t.trim().trim_start_matches("W/"))
+        .any(|t| t == "*" || t == etag)
+}
+
+fn json(value: &impl serde::Serialize) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_data(serde_json::to_vec(value).unwrap_or_default())
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn text(status: u16, body: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body).with_status_code(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        use RangeRequest::{Ignored, Satisfiable, Unsatisfiable};
+        for (header, expected) in [
+            ("bytes=0-99", Satisfiable(0, 99)),
+            ("bytes=10-", Satisfiable(10, 999)),
+            ("bytes=-100", Satisfiable(900, 999)),
+            ("bytes=-5000", Satisfiable(0, 999)),
+            ("bytes=500-5000", Satisfiable(500, 999)),
+            (" bytes= 1 - 2 ", Satisfiable(1, 2)),
+            ("bytes=5-5", Satisfiable(5, 5)),
+            ("bytes=1000-", Unsatisfiable),
+            ("bytes=1000-2000", Unsatisfiable),
+            ("bytes=-0", Unsatisfiable),
+            ("bytes=0-1,5-6", Ignored),
+            ("bytes=5-3", Ignored),
+            ("bytes=abc-", Ignored),
+            ("bytes=1-x", Ignored),
+            ("bytes=-x", Ignored),
+            ("bytes=-", Ignored),
+            ("bytes=42", Ignored),
+            ("items=0-1", Ignored),
+        ] {
+            assert_eq!(parse_range(header, 1000), expected, "{header}");
+        }
+        assert_eq!(parse_range("bytes=-1", 0), Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), Unsatisfiable);
+    }
+}
@@ -0,0 +1,139 @@
+use ollama_file_find::{BlobStore, LayerKind, ManifestStore, ModelId};
+use serde_json::{Map, Value};
+
+use crate::{Result, StorePaths, args::SetParamArgs, commands::edit, config::Config};
+
+/// Parameters `ollama` understands in a Modelfile `PARAMETER` line or the params layer.
+const KNOWN_PARAMS: &[&str] = &[
+    "num_ctx",
+    "num_batch",
+    "num_gpu",
+    "num_thread",
+    "num_keep",
+    "num_predict",
+    "seed",
+    "stop",
+    "temperature",
+    "top_k",
+    "top_p",
+    "min_p",
+    "typical_p",
+    "repeat_last_n",
+    "repeat_penalty",
+    "presence_penalty",
+    "frequency_penalty",
+    "penalize_newline",
+    "mirostat",
+    "mirostat_tau",
+    "mirostat_eta",
+    "use_mmap",
+];
+
+/// Largest params layer read back; real ones are a few hundred bytes.
+const MAX_PARAMS_BYTES: u64 = 1 << 20;
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: SetParamArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let Some(stored) = ManifestStore::new(&paths.manifests).find(&id) else {
+        anyhow::bail!("model not found: {}", id.normalize());
+    };
+    let blobs = BlobStore::new(&paths.blobs);
+    let mut params: Map<String, Value> = match stored
+        .data
+        .layers
+        .iter()
+        .find(|l| LayerKind::from_media_type(l.media_type.as_str()) == LayerKind::Params)
+    {
+        Some(layer) => serde_json::from_str(&blobs.read_text(&layer.digest, MAX_PARAMS_BYTES)?)?,
+        None => Map::new(),
+    };
+
+    for key in &args.unset {
+        if params.remove(key).is_none() {
+            eprintln!("Warning: {key} was not set");
+        }
+    }
+    // `stop` may be given several times; the first occurrence replaces the old list.
+    let mut stops: Option<Vec<Value>> = None;
+    for assignment in &args.params {
+        let Some((key, value)) = assignment.split_once('=') else {
+            anyhow::bail!("expected KEY=VALUE, got {assignment:?}");
+        };
+        let key = key.trim();
+        if !KNOWN_PARAMS.contains(&key) {
+            eprintln!("Warning: {key} is not a parameter ollama knows; it may be ignored");
+        }
+        if key == "stop" {
+            stops.get_or_insert_with(Vec::new).push(Value::from(value));
+        } else {
+            params.insert(key.to_string(), parse_value(value));
+        }
+    }
+    if let Some(stops) = stops {
+        params.insert("stop".to_string(), Value::Array(stops));
+    }
+
+    // An empty params layer is dropped rather than written.
+    let content = (!params.is_empty())
+        .then(|| serde_json::to_vec(&params))
+        .transpose()?;
+    edit::apply(
+        paths,
+        config,
+        &id,
+        LayerKind::Params,
+        content.as_deref(),
+        args.gc,
+        args.plain,
+    )
+}
+
+/// Numbers, booleans, and JSON arrays keep their type; anything else is a string.
+fn parse_value(raw: &str) -> Value {
+    match serde_json::from_str::<Value>(raw.trim()) {
+        Ok(v @ (Value::Number(_) | Value::Bool(_) | Value::Array(_))) => v,
+        _ => Value::from(raw),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ollama_file_find::fixtures::FakeStore;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{backups, layer, parse, paths};
+
+    #[test]
+    fn test_set_param_replaces_params_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Params, br#"{"temperature":0.8,"top_k":40}"#)
+            .build(dir.path())
+            .unwrap();
+        let argv = [
+            "llama3:8b",
+            "temperature=0.5",
+            "stop=<|eot_id|>",
+            "--unset",
+            "top_k",
+        ];
+        let before = fs::read(store.manifest_path("llama3:8b")).unwrap();
+
+        run(&paths(&store), &Config::default(), parse(&argv)).unwrap();
+        let params = layer(&store, "llama3:8b", LayerKind::Params).unwrap();
+        let params: Value = serde_json::from_slice(&params).unwrap();
+        assert_eq!(params, json!({"temperature": 0.5, "stop": ["<|eot_id|>"]}));
+        assert_eq!(
+            layer(&store, "llama3:8b", LayerKind::Weights).unwrap(),
+            b"weights"
+        );
+        let backups = backups(&store, "llama3:8b");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0].path).unwrap(), before);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{fs, io::Read};
+
+use ollama_file_find::{LayerKind, ModelId};
+
+use crate::{Result, StorePaths, args::SetSystemArgs, commands::edit, config::Config};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: SetSystemArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let prompt = match (&args.prompt, &args.file) {
+        (Some(prompt), _) => Some(prompt.clone()),
+        (None, Some(file)) if file.as_os_str() == "-" => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            Some(text)
+        }
+        (None, Some(file)) => Some(fs::read_to_string(file)?),
+        (None, None) => None,
+    };
+    if prompt.as_deref().is_some_and(|p| p.trim().is_empty()) {
+        anyhow::bail!("system prompt is empty (use --clear to remove it)");
+    }
+    edit::apply(
+        paths,
+        config,
+        &id,
+        LayerKind::System,
+        prompt.as_deref().map(str::as_bytes),
+        args.gc,
+        args.plain,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use ollama_file_find::fixtures::FakeStore;
+
+    use super::*;
+    use crate::test_util::{backups, layer, parse, paths};
+
+    #[test]
+    fn test_set_system_replaces_and_clears_system_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::System, b"You are helpful.")
+            .build(dir.path())
+            .unwrap();
+        let paths = paths(&store);
+        let before = fs::read(store.manifest_path("llama3:8b")).unwrap();
+
+        run(
+            &paths,
+            &Config::default(),
+            parse(&["llama3:8b", "Be terse."]),
+        )
+        .unwrap();
+        assert_eq!(
+            layer(&store, "llama3:8b", LayerKind::System).unwrap(),
+            b"Be terse."
+        );
+        let backups_after_set = backups(&store, "llama3:8b");
+        assert_eq!(backups_after_set.len(), 1);
+        assert_eq!(fs::read(&backups_after_set[0].path).unwrap(), before);
+
+        let edited = fs::read(store.manifest_path("llama3:8b")).unwrap();
+        run(&paths, &Config::default(), parse(&["llama3:8b", "--clear"])).unwrap();
+        assert!(layer(&store, "llama3:8b", LayerKind::System).is_none());
+        assert_eq!(
+            layer(&store, "llama3:8b", LayerKind::Weights).unwrap(),
+            b"weights"
+        );
+        let backups = backups(&store, "llama3:8b");
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read(&backups[1].path).unwrap(), edited);
+    }
+}
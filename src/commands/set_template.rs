@@ -0,0 +1,64 @@
+use std::{fs, io::Read};
+
+use ollama_file_find::{LayerKind, ModelId};
+
+use crate::{Result, StorePaths, args::SetTemplateArgs, commands::edit, config::Config};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: SetTemplateArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let template = if args.file.as_os_str() == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(&args.file)?
+    };
+    if template.trim().is_empty() {
+        anyhow::bail!("template is empty");
+    }
+    if template.matches("{{").count() != template.matches("}}").count() {
+        eprintln!("Warning: unbalanced {{{{ }}}} in the template; ollama may fail to load it");
+    }
+    edit::apply(
+        paths,
+        config,
+        &id,
+        LayerKind::Template,
+        Some(template.as_bytes()),
+        args.gc,
+        args.plain,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use ollama_file_find::fixtures::FakeStore;
+
+    use super::*;
+    use crate::test_util::{backups, layer, parse, paths};
+
+    #[test]
+    fn test_set_template_replaces_template_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Template, b"{{ .Prompt }}")
+            .build(dir.path().join("models"))
+            .unwrap();
+        let file = dir.path().join("template");
+        let template = "<|user|>{{ .Prompt }}<|assistant|>";
+        fs::write(&file, template).unwrap();
+        let before = fs::read(store.manifest_path("llama3:8b")).unwrap();
+
+        let argv = ["llama3:8b", "--file", file.to_str().unwrap()];
+        run(&paths(&store), &Config::default(), parse(&argv)).unwrap();
+        assert_eq!(
+            layer(&store, "llama3:8b", LayerKind::Template).unwrap(),
+            template.as_bytes()
+        );
+        let backups = backups(&store, "llama3:8b");
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0].path).unwrap(), before);
+    }
+}
@@ -0,0 +1,160 @@
+use std::fs;
+
+use ollama_file_find::{
+    BlobStore, LayerKind, ListedModel, ManifestStore, ModelId, StoredManifest,
+    gguf::{GgufValue, HeaderReader, read_gguf},
+};
+
+use crate::{
+    Result, StorePaths,
+    args::ShowArgs,
+    format::{human_bytes, human_opt},
+};
+
+/// Largest template or system prompt printed; real ones are a few KiB.
+const MAX_TEXT_BYTES: u64 = 1 << 20;
+
+pub(crate) fn run(paths: &StorePaths, api_urls: &[String], args: ShowArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let Some(StoredManifest { id, path, data }) = ManifestStore::new(&paths.manifests).find(&id)
+    else {
+        anyhow::bail!("model not found: {}", id.normalize());
+    };
+    if args.web_url {
+        return print_web_url(&id, &args);
+    }
+    let blobs = BlobStore::new(&paths.blobs);
+    let text_kind = match (args.template, args.system) {
+        (true, _) => Some(LayerKind::Template),
+        (_, true) => Some(LayerKind::System),
+        _ => None,
+    };
+    if let Some(kind) = text_kind {
+        let Some(layer) = data
+            .layers
+            .iter()
+            .find(|l| LayerKind::from_media_type(l.media_type.as_str()) == kind)
+        else {
+            anyhow::bail!("{} has no {kind} layer", id.normalize());
+        };
+        print!("{}", blobs.read_text(&layer.digest, MAX_TEXT_BYTES)?);
+        return Ok(());
+    }
+    if args.gguf {
+        let Some(layer) = data.layers.iter().find(|l| l.kind() == LayerKind::Weights) else {
+            anyhow::bail!("{} has no weights layer", id.normalize());
+        };
+        let path = blobs.path_for(&layer.digest);
+        let file = fs::File::open(&path).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+        let mut reader = HeaderReader::new(file, args.gguf_limit);
+        let metadata =
+            read_gguf(&mut reader).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+        if args.plain {
+            println!("version: {}", metadata.version);
+            println!("tensors: {}", metadata.tensor_count);
+            for (key, value) in &metadata.metadata {
+                match value {
+                    GgufValue::Array { len } => println!("{key}: [{len} items]"),
+                    GgufValue::String(s) => println!("{key}: {s:?}"),
+                    GgufValue::Uint(n) => println!("{key}: {n}"),
+                    GgufValue::Int(n) => println!("{key}: {n}"),
+                    GgufValue::Float(x) => println!("{key}: {x}"),
+                    GgufValue::Bool(b) => println!("{key}: {b}"),
+                }
+            }
+        } else {
+            println!("{}", serde_json::to_string_pretty(&metadata)?);
+        }
+        eprintln!(
+            "Read {} of {}",
+            human_bytes(reader.bytes_fetched()),
+            human_opt(layer.size)
+        );
+        return Ok(());
+    }
+    let model = ListedModel::new(id, path).into_verbose(data, blobs.root());
+
+    if args.warmup {
+        for url in api_urls {
+            println!("{}", warmup_command(url, &model.name, &args.keep_alive));
+        }
+        return Ok(());
+    }
+    if args.plain {
+        print_plain(&model);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&model)?);
+    }
+    Ok(())
+}
+
+/// A `curl` call that makes the server load the model without generating anything
+/// (an empty prompt) and keep it loaded for `keep_alive`.
+fn warmup_command(api_url: &str, name: &str, keep_alive: &str) -> String {
+    // A bare number is seconds to Ollama; anything else is a duration string.
+    let keep_alive = match keep_alive.parse::<i64>() {
+        Ok(secs) => serde_json::Value::from(secs),
+        Err(_) => serde_json::Value::from(keep_alive),
+    };
+    let body = format!(
+        r#"{{"model":{},"keep_alive":{keep_alive}}}"#,
+        serde_json::Value::from(name)
+    );
+    format!("curl -s {api_url}/api/generate -d '{body}'")
+}
+
+fn print_web_url(id: &ModelId, args: &ShowArgs) -> Result<()> {
+    let Some(url) = id.web_url() else {
+        anyhow::bail!(
+            "{} is not from ollama.com (registry {})",
+            id.normalize(),
+            id.display_host().unwrap_or_default()
+        );
+    };
+    #[cfg(feature = "network")]
+    let description = if args.description {
+        ollama_file_find::registry::fetch_description(id)?
+    } else {
+        None
+    };
+    #[cfg(not(feature = "network"))]
+    let description: Option<String> = None;
+    if args.plain {
+        println!("{url}");
+        if let Some(description) = &description {
+            println!("{description}");
+        }
+    } else {
+        let mut out = serde_json::json!({ "name": id.normalize(), "url": url });
+        if let Some(description) = description {
+            out["description"] = description.into();
+        }
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    }
+    Ok(())
+}
+
+fn print_plain(model: &ListedModel) {
+    println!("name: {}", model.name);
+    println!("manifest: {}", model.manifest_path.display());
+    println!("size: {}", human_opt(model.total_size));
+    if let Some(path) = &model.primary_blob_path {
+        println!("weights: {}", path.display());
+    }
+    println!("layers:");
+    for b in model.blob_paths.iter().flatten() {
+        println!(
+            "  {:<10} {:>10}  {}{}",
+            LayerKind::from_media_type(b.media_type.as_str()).to_string(),
+            b.sizes
+                .declared
+                .map_or_else(|| "?".to_string(), human_bytes),
+            b.digest,
+            if b.status.is_problem() {
+                format!("  ({})", b.status)
+            } else {
+                String::new()
+            }
+        );
+    }
+}
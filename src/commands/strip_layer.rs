@@ -0,0 +1,161 @@
+use ollama_file_find::{
+    BlobStore, LayerKind, ManifestStore, ModelId, remove_unreferenced, strip_layers,
+};
+use serde_json::json;
+
+use crate::{Result, StorePaths, args::StripLayerArgs, config::Config, format::human_opt};
+
+pub(crate) fn run(paths: &StorePaths, config: &Config, args: StripLayerArgs) -> Result<()> {
+    let id: ModelId = args.model.parse()?;
+    let manifests = ManifestStore::new(&paths.manifests)
+        .with_backup_retention(config.retention.manifest_backups());
+    if let Some(tag) = &args.tag
+        && (tag.is_empty() || tag.contains(['/', '\\', ':']))
+    {
+        anyhow::bail!("invalid tag: {tag:?}");
+    }
+    let target = args.tag.as_ref().map(|tag| ModelId {
+        tag: tag.clone(),
+        ..id.clone()
+    });
+    if let Some(target) = &target
+        && !args.force
+        && manifests.find(target).is_some()
+    {
+        anyhow::bail!(
+            "{} already exists (use --force to replace it)",
+            target.normalize()
+        );
+    }
+    let kinds: Vec<LayerKind> = args.kinds.iter().map(|&k| k.into()).collect();
+    let Some(stored) = manifests.find(&id) else {
+        anyhow::bail!("model not found: {}", id.normalize());
+    };
+    if !stored
+        .data
+        .layers
+        .iter()
+        .any(|l| kinds.contains(&LayerKind::from_media_type(l.media_type.as_str())))
+    {
+        anyhow::bail!("{} has no layer of the given types", id.normalize());
+    }
+
+    let stripped = strip_layers(&manifests, &id, &kinds, target.as_ref())?;
+    let removed_blobs = if args.gc {
+        let digests: Vec<_> = stripped.removed.iter().map(|l| l.digest).collect();
+        remove_unreferenced(&manifests, &BlobStore::new(&paths.blobs), &digests)?
+    } else {
+        Vec::new()
+    };
+
+    if args.plain {
+        println!("{}\t-> {}", stripped.model, stripped.target);
+        for l in &stripped.removed {
+            println!(
+                "  stripped {:<10} {:>10}  {}",
+                LayerKind::from_media_type(l.media_type.as_str()).to_string(),
+                human_opt(l.size),
+                l.digest
+            );
+        }
+        if let Some(backup) = &stripped.backup {
+            println!("  previous manifest: {}", backup.path.display());
+        }
+        for digest in &removed_blobs {
+            println!("  removed blob {digest}");
+        }
+    } else {
+        let report = json!({ "stripped": stripped, "removed_blobs": removed_blobs });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use ollama_file_find::{HiddenPolicy, fixtures::FakeStore, sha256_file};
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: StripLayerArgs,
+    }
+
+    fn args(argv: &[&str]) -> clap::error::Result<StripLayerArgs> {
+        Cli::try_parse_from(std::iter::once("test").chain(argv.iter().copied())).map(|c| c.args)
+    }
+
+    fn store(dir: &std::path::Path) -> (FakeStore, StorePaths) {
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Template, b"{{ .Prompt }}")
+            .layer(LayerKind::License, b"license")
+            .build(dir.join("models"))
+            .unwrap();
+        let paths = StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap();
+        (store, paths)
+    }
+
+    #[test]
+    fn test_strip_in_place_and_restore_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let (store, paths) = store(dir.path());
+        let manifest = store.manifest_path("llama3:8b");
+        let original = sha256_file(&manifest).unwrap();
+        let config_digest = |m: &ManifestStore| {
+            let (all, _) = m.manifests(HiddenPolicy::Include);
+            all[0].data.config.as_ref().map(|c| c.digest)
+        };
+        let before = config_digest(&store.manifests());
+
+        run(
+            &paths,
+            &Config::default(),
+            args(&["llama3:8b", "--type", "template,license"]).unwrap(),
+        )
+        .unwrap();
+        // Rewritten through a temporary file renamed into place: nothing else is left
+        // next to the manifest, and it parses with only the weights left.
+        let siblings: Vec<_> = std::fs::read_dir(manifest.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(siblings, [manifest.file_name().unwrap()]);
+        let manifests = store.manifests();
+        let id: ModelId = "llama3:8b".parse().unwrap();
+        let stripped = manifests.find(&id).unwrap();
+        assert_eq!(stripped.data.layers.len(), 1);
+        assert_eq!(config_digest(&manifests), before);
+        assert!(store.blob_path(b"license").exists());
+
+        // The replaced manifest was backed up and can be put back byte for byte.
+        assert_eq!(manifests.backups(&id).len(), 1);
+        manifests.restore_backup(&id, None).unwrap();
+        assert_eq!(sha256_file(&manifest).unwrap(), original);
+        assert_eq!(manifests.find(&id).unwrap().data.layers.len(), 3);
+    }
+
+    #[test]
+    fn test_config_and_weights_cannot_be_stripped() {
+        for kind in ["config", "model", "weights"] {
+            assert!(args(&["llama3:8b", "--type", kind]).is_err(), "{kind}");
+        }
+        // Stripping whatever the manifest does not have leaves it alone.
+        let dir = tempfile::tempdir().unwrap();
+        let (store, paths) = store(dir.path());
+        let manifest = store.manifest_path("llama3:8b");
+        let original = sha256_file(&manifest).unwrap();
+        let err = run(
+            &paths,
+            &Config::default(),
+            args(&["llama3:8b", "--type", "params"]).unwrap(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no layer"), "{err}");
+        assert_eq!(sha256_file(&manifest).unwrap(), original);
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+    process::ExitCode,
+};
+
+#[cfg(feature = "signatures")]
+use ollama_file_find::signature::{
+    SignatureReport, SignatureStatus, TrustStore, load_attestations, verify_signatures,
+};
+use ollama_file_find::{
+    BlobStore, Digest, HiddenPolicy, ManifestStore, ModelId, StoredManifest, is_offline,
+    map_per_device, sha256_file,
+};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::VerifyArgs, format::human_bytes, progress::Progress};
+
+#[derive(Serialize)]
+struct ChecksumReport {
+    ok: bool,
+    blobs_checked: usize,
+    bytes_checked: u64,
+    blobs: Vec<BlobCheck>,
+}
+
+#[derive(Serialize)]
+struct BlobCheck {
+    digest: Digest,
+    path: PathBuf,
+    /// Models (of those checked) referencing the blob
+    models: Vec<String>,
+    status: CheckStatus,
+    /// What the content hashes to, when it doesn't match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual: Option<Digest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    /// Content no longer matches the digest
+    Corrupt,
+    Missing,
+    Unreadable,
+    /// A cloud placeholder; hashing it would download it, so it is left unchecked
+    Offline,
+}
+
+impl CheckStatus {
+    fn failed(self) -> bool {
+        matches!(
+            self,
+            CheckStatus::Corrupt | CheckStatus::Missing | CheckStatus::Unreadable
+        )
+    }
+}
+
+pub(crate) fn run(paths: &StorePaths, args: VerifyArgs) -> Result<ExitCode> {
+    let wanted: BTreeSet<String> = args
+        .models
+        .iter()
+        .map(|m| m.parse::<ModelId>().map(|id| id.normalize()))
+        .collect::<std::result::Result<_, _>>()?;
+    let (manifests, errors) = ManifestStore::new(&paths.manifests).manifests(HiddenPolicy::Exclude);
+    for e in &errors {
+        eprintln!("Warning: {e}");
+    }
+    let manifests: Vec<(String, StoredManifest)> = manifests
+        .into_iter()
+        .map(|m| (m.id.normalize(), m))
+        .filter(|(name, _)| wanted.is_empty() || wanted.contains(name))
+        .collect();
+    let found: BTreeSet<&String> = manifests.iter().map(|(name, _)| name).collect();
+    let missing: Vec<&str> = wanted
+        .iter()
+        .filter(|name| !found.contains(name))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!("model not found: {}", missing.join(", "));
+    }
+
+    #[cfg(feature = "signatures")]
+    if args.signatures {
+        return signatures(paths, &args, &manifests);
+    }
+    checksums(paths, &args, &manifests, errors.is_empty())
+}
+
+/// Stream every referenced blob through SHA-256 and compare with its digest. Unreadable
+/// manifests fail the run too, since their blobs went unchecked.
+fn checksums(
+    paths: &StorePaths,
+    args: &VerifyArgs,
+    manifests: &[(String, StoredManifest)],
+    manifests_ok: bool,
+) -> Result<ExitCode> {
+    let store = BlobStore::new(&paths.blobs);
+    let mut blobs: BTreeMap<Digest, Vec<String>> = BTreeMap::new();
+    for (name, m) in manifests {
+        for layer in m.data.config.iter().chain(&m.data.layers) {
+            let models = blobs.entry(layer.digest).or_default();
+            if !models.contains(name) {
+                models.push(name.clone());
+            }
+        }
+    }
+    let items: Vec<(Digest, Vec<String>, PathBuf, Option<fs::Metadata>)> = blobs
+        .into_iter()
+        .map(|(digest, models)| {
+            let path = store.path_for(&digest);
+            let meta = fs::metadata(&path).ok().filter(fs::Metadata::is_file);
+            (digest, models, path, meta)
+        })
+        .collect();
+    let total_bytes = items
+        .iter()
+        .filter_map(|(.., meta)| meta.as_ref())
+        .filter(|meta| !is_offline(meta))
+        .map(fs::Metadata::len)
+        .sum();
+    let progress = Progress::start("verify", items.len(), Some(total_bytes));
+    let checks = map_per_device(
+        items,
+        usize::from(args.jobs_per_device),
+        |(_, _, path, _)| path.as_path(),
+        |(digest, models, path, meta)| {
+            let size = meta.as_ref().map_or(0, fs::Metadata::len);
+            let check = check_blob(digest, models, path, meta);
+            progress.step(digest, size);
+            (check, size)
+        },
+    );
+    progress.finish();
+
+    let mut report = ChecksumReport {
+        ok: manifests_ok,
+        blobs_checked: 0,
+        bytes_checked: 0,
+        blobs: Vec::with_capacity(checks.len()),
+    };
+    for (check, size) in checks {
+        if matches!(check.status, CheckStatus::Ok | CheckStatus::Corrupt) {
+            report.blobs_checked += 1;
+            report.bytes_checked += size;
+        }
+        report.ok &= !check.status.failed();
+        report.blobs.push(check);
+    }
+
+    if args.plain {
+        for b in &report.blobs {
+            let status = match b.status {
+                CheckStatus::Ok => "ok",
+                CheckStatus::Corrupt => "CORRUPT",
+                CheckStatus::Missing => "MISSING",
+                CheckStatus::Unreadable => "UNREADABLE",
+                CheckStatus::Offline => "offline",
+            };
+            let mut line = format!("{status}\t{}\t{}", b.digest, b.models.join(", "));
+            if let Some(actual) = &b.actual {
+                line.push_str(&format!("\thashes to {actual}"));
+            }
+            if let Some(error) = &b.error {
+                line.push_str(&format!("\t{error}"));
+            }
+            println!("{line}");
+        }
+        let failed = report.blobs.iter().filter(|b| b.status.failed()).count();
+        println!(
+            "{} blob(s) hashed ({}), {failed} failed",
+            report.blobs_checked,
+            human_bytes(report.bytes_checked)
+        );
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if report.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn check_blob(
+    digest: Digest,
+    models: Vec<String>,
+    path: PathBuf,
+    meta: Option<fs::Metadata>,
+) -> BlobCheck {
+    let mut check = BlobCheck {
+        digest,
+        path,
+        models,
+        status: CheckStatus::Ok,
+        actual: None,
+        error: None,
+    };
+    match meta {
+        None => check.status = CheckStatus::Missing,
+        Some(meta) if is_offline(&meta) => check.status = CheckStatus::Offline,
+        Some(_) => match sha256_file(&check.path) {
+            Ok(actual) if actual == digest => {}
+            Ok(actual) => {
+                check.status = CheckStatus::Corrupt;
+                check.actual = Some(actual);
+            }
+            Err(e) => {
+                check.status = CheckStatus::Unreadable;
+                check.error = Some(e.to_string());
+            }
+        },
+    }
+    check
+}
+
+#[cfg(feature = "signatures")]
+#[derive(Serialize)]
+struct VerifyReport {
+    ok: bool,
+    /// Fingerprints of the keys in the trust store
+    trusted_keys: Vec<String>,
+    models: Vec<ModelSignatures>,
+}
+
+#[cfg(feature = "signatures")]
+#[derive(Serialize)]
+struct ModelSignatures {
+    name: String,
+    #[serde(flatten)]
+    report: SignatureReport,
+}
+
+#[cfg(feature = "signatures")]
+fn signatures(
+    paths: &StorePaths,
+    args: &VerifyArgs,
+    manifests: &[(String, StoredManifest)],
+) -> Result<ExitCode> {
+    let Some(trust_store) = &args.trust_store else {
+        anyhow::bail!("--signatures needs --trust-store");
+    };
+    let trust = TrustStore::load(trust_store)?;
+    if trust.keys.is_empty() {
+        anyhow::bail!("no public keys in {}", trust_store.display());
+    }
+    let external = match &args.attestations {
+        Some(dir) => {
+            let (found, errors) = load_attestations(dir)?;
+            for e in &errors {
+                eprintln!("Warning: {e}");
+            }
+            found
+        }
+        None => Vec::new(),
+    };
+
+    let blobs = BlobStore::new(&paths.blobs);
+    let mut models: Vec<ModelSignatures> = manifests
+        .iter()
+        .map(|(name, m)| ModelSignatures {
+            name: name.clone(),
+            report: verify_signatures(&m.data, &blobs, &external, &trust),
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    let report = VerifyReport {
+        ok: models
+            .iter()
+            .all(|m| m.report.status == SignatureStatus::Verified),
+        trusted_keys: trust
+            .keys
+            .iter()
+            .map(|k| format!("{} {}", k.fingerprint, k.name))
+            .collect(),
+        models,
+    };
+
+    if args.plain {
+        for m in &report.models {
+            let r = &m.report;
+            let status = match r.status {
+                SignatureStatus::Verified => "verified",
+                SignatureStatus::Partial => "PARTIAL",
+                SignatureStatus::Untrusted => "UNTRUSTED",
+                SignatureStatus::Unsigned => "UNSIGNED",
+            };
+            let mut line = format!("{}\t{status}", m.name);
+            if !r.signers.is_empty() {
+                line.push_str(&format!("\tby {}", r.signers.join(", ")));
+            }
+            if !r.uncovered.is_empty() {
+                line.push_str(&format!("\t{} blobs not covered", r.uncovered.len()));
+            }
+            println!("{line}");
+            for p in &r.problems {
+                println!("  problem: {p}");
+            }
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(if report.ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
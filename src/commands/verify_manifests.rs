@@ -0,0 +1,137 @@
+use std::{collections::BTreeMap, fs, path::Path, process::ExitCode};
+
+use ollama_file_find::{Digest, Error, HiddenPolicy, ManifestStore, sha256_file, write_atomic};
+use serde::Serialize;
+
+use crate::{Result, StorePaths, args::VerifyManifestsArgs};
+
+/// Recorded digests, keyed by path relative to the manifests directory.
+type DigestIndex = BTreeMap<String, Digest>;
+
+#[derive(Serialize)]
+struct Problem {
+    /// Path relative to the manifests directory
+    manifest: String,
+    /// `empty`, `truncated`, `invalid`, `media_type`, `changed`, `unrecorded`, `missing`, or
+    /// `unreadable`
+    problem: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+pub(crate) fn run(paths: &StorePaths, args: VerifyManifestsArgs) -> Result<ExitCode> {
+    let root = &paths.manifests;
+    let (stored, errors) = ManifestStore::new(root).manifests(HiddenPolicy::Include);
+    let rel = |p: &Path| {
+        p.strip_prefix(root)
+            .unwrap_or(p)
+            .iter()
+            .map(|c| c.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+
+    let mut problems = Vec::new();
+    for e in errors {
+        let (manifest, problem, detail) = match e {
+            Error::EmptyManifest(p) => (rel(&p), "empty", None),
+            Error::TruncatedManifest(p) => (rel(&p), "truncated", None),
+            Error::Json { path, source } => (rel(&path), "invalid", Some(source.to_string())),
+            Error::Io { path, source } => (rel(&path), "unreadable", Some(source.to_string())),
+            other => (String::new(), "unreadable", Some(other.to_string())),
+        };
+        problems.push(Problem {
+            manifest,
+            problem,
+            detail,
+        });
+    }
+
+    // Any string loads as a media type, so ones that aren't `type/subtype` are flagged here.
+    for m in &stored {
+        for layer in m.data.config.iter().chain(&m.data.layers) {
+            if !layer.media_type.is_well_formed() {
+                problems.push(Problem {
+                    manifest: rel(&m.path),
+                    problem: "media_type",
+                    detail: Some(format!(
+                        "{} has media type {:?}",
+                        layer.digest,
+                        layer.media_type.as_str()
+                    )),
+                });
+            }
+        }
+    }
+
+    let mut digests = DigestIndex::new();
+    for m in &stored {
+        match sha256_file(&m.path) {
+            Ok(d) => {
+                digests.insert(rel(&m.path), d);
+            }
+            Err(e) => problems.push(Problem {
+                manifest: rel(&m.path),
+                problem: "unreadable",
+                detail: Some(e.to_string()),
+            }),
+        }
+    }
+
+    if let Some(index_path) = &args.index {
+        let recorded: DigestIndex = serde_json::from_slice(&fs::read(index_path)?)?;
+        for (manifest, expected) in &recorded {
+            let problem = match digests.get(manifest) {
+                Some(actual) if actual == expected => continue,
+                Some(actual) => Problem {
+                    manifest: manifest.clone(),
+                    problem: "changed",
+                    detail: Some(format!("expected {expected}, found {actual}")),
+                },
+                // Already reported if it exists but failed to parse.
+                None if problems.iter().any(|p| &p.manifest == manifest) => continue,
+                None => Problem {
+                    manifest: manifest.clone(),
+                    problem: "missing",
+                    detail: None,
+                },
+            };
+            problems.push(problem);
+        }
+        for manifest in digests.keys().filter(|m| !recorded.contains_key(*m)) {
+            problems.push(Problem {
+                manifest: manifest.clone(),
+                problem: "unrecorded",
+                detail: None,
+            });
+        }
+    }
+
+    if let Some(out) = &args.write_index {
+        let mut body = serde_json::to_vec_pretty(&digests)?;
+        body.push(b'\n');
+        write_atomic(out, &body)?;
+        eprintln!(
+            "Recorded {} manifest digests in {}",
+            digests.len(),
+            out.display()
+        );
+    }
+
+    problems.sort_by(|a, b| a.manifest.cmp(&b.manifest));
+    if args.plain {
+        for p in &problems {
+            match &p.detail {
+                Some(d) => println!("{}\t{}\t{d}", p.manifest, p.problem),
+                None => println!("{}\t{}", p.manifest, p.problem),
+            }
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&problems)?);
+    }
+    Ok(if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use ollama_file_find::ModelId;
+use serde::Deserialize;
+
+use crate::{
+    Result,
+    format::{parse_bytes, parse_duration},
+};
+
+/// Settings read from `config.toml`.
+///
+/// ```toml
+/// [quota]
+/// library = "200GB"
+/// myteam = "100GB"
+///
+/// [policy]
+/// max_model_size = "50GB"
+/// allowed_namespaces = ["library", "myteam"]
+/// blocked_licenses = ["non-commercial"]
+///
+/// [labels]
+/// "llama3.1:8b" = ["prod", "chat"]
+///
+/// [retention]
+/// keep_orphans_newer_than = "7d"
+/// manifest_backups = 10
+///
+/// [inventory]
+/// signing_key = "/etc/ollama-file-find/inventory.pem"
+/// trust_store = "/etc/ollama-file-find/trusted"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Maximum bytes per namespace
+    pub quota: BTreeMap<String, ByteLimit>,
+    /// Rules evaluated by `policy check`
+    pub policy: PolicyConfig,
+    /// Free-form labels per model name, matched by `search`
+    pub labels: BTreeMap<String, Vec<String>>,
+    pub retention: RetentionConfig,
+    pub inventory: InventoryConfig,
+}
+
+/// Keys for `inventory sign` / `inventory verify`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct InventoryConfig {
+    /// ed25519 private key in PEM (`openssl genpkey -algorithm ed25519`)
+    pub signing_key: Option<PathBuf>,
+    /// Directory of public keys (`*.pub`, `*.pem`) whose signatures `verify` accepts;
+    /// without it, only the public half of `signing_key`
+    pub trust_store: Option<PathBuf>,
+}
+
+/// What `prune` keeps even though no manifest references it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RetentionConfig {
+    /// Orphaned blobs modified more recently than this are kept
+    pub keep_orphans_newer_than: Option<DurationValue>,
+    /// Copies kept per model under `manifests/.bak/` when a manifest is overwritten
+    pub manifest_backups: Option<usize>,
+}
+
+impl RetentionConfig {
+    pub(crate) fn manifest_backups(&self) -> usize {
+        self.manifest_backups
+            .unwrap_or(ollama_file_find::DEFAULT_BACKUP_RETENTION)
+    }
+}
+
+/// Admission rules for models; empty lists allow anything.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PolicyConfig {
+    /// Largest total size (all layers) of a single model
+    pub max_model_size: Option<ByteLimit>,
+    /// Namespaces models may come from (`library` for official models)
+    pub allowed_namespaces: Vec<String>,
+    /// Registry hosts models may come from
+    pub allowed_hosts: Vec<String>,
+    /// Case-insensitive phrases that must not appear in a model's license
+    pub blocked_licenses: Vec<String>,
+}
+
+/// A byte count written either as an integer or a size string (`200GB`, `512MiB`).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ByteLimit(pub u64);
+
+impl<'de> Deserialize<'de> for ByteLimit {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bytes(u64),
+            Text(String),
+        }
+        match Raw::deserialize(d)? {
+            Raw::Bytes(n) => Ok(ByteLimit(n)),
+            Raw::Text(s) => parse_bytes(&s)
+                .map(ByteLimit)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// A duration written like `90m`, `24h`, or `7d`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DurationValue(pub Duration);
+
+impl<'de> Deserialize<'de> for DurationValue {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(d)?;
+        parse_duration(&s)
+            .map(DurationValue)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Config {
+    /// Load `path`, or the default location when `None`. A missing default file yields
+    /// an empty config; a missing explicit file is an error.
+    pub(crate) fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(p) => (p.to_path_buf(), true),
+            None => match default_config_path() {
+                Some(p) => (p, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Config::default());
+            }
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+}
+
+impl Config {
+    /// Labels configured for a model, whichever way its name was written in the file.
+    pub(crate) fn labels_for(&self, id: &ModelId) -> impl Iterator<Item = &str> {
+        let wanted = id.normalize();
+        self.labels
+            .iter()
+            .filter(move |(name, _)| {
+                name.parse::<ModelId>()
+                    .is_ok_and(|parsed| parsed.normalize() == wanted)
+            })
+            .flat_map(|(_, labels)| labels.iter().map(String::as_str))
+    }
+}
+
+/// `<config dir>/ollama-file-find/config.toml`.
+pub(crate) fn default_config_path() -> Option<std::path::PathBuf> {
+    dirs::config_dir().map(|d| d.join("ollama-file-find").join("config.toml"))
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+/// A five-field cron schedule (`minute hour day-of-month month day-of-week`), evaluated
+/// in UTC. Fields take `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`), and
+/// comma-separated lists; `@hourly`, `@daily`, `@weekly`, and `@monthly` are accepted.
+#[derive(Debug, Clone)]
+pub(crate) struct Schedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Day-of-month and day-of-week both restricted: either may match, as in cron.
+    either_day: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expanded = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 cron fields, got {:?}", s));
+        };
+        // Sunday may be written as 0 or 7.
+        let weekdays = field(weekday, 0, 7)?;
+        let weekdays = (weekdays | (weekdays >> 7)) & 0x7f;
+        Ok(Schedule {
+            minutes: field(minute, 0, 59)?,
+            hours: u32::try_from(field(hour, 0, 23)?).unwrap_or(0),
+            days: u32::try_from(field(day, 1, 31)?).unwrap_or(0),
+            months: u16::try_from(field(month, 1, 12)?).unwrap_or(0),
+            weekdays: u8::try_from(weekdays).unwrap_or(0),
+            either_day: day != "*" && weekday != "*",
+        })
+    }
+}
+
+impl Schedule {
+    /// First scheduled minute strictly after `after` (seconds since the epoch).
+    pub(crate) fn next_after(&self, after: u64) -> Option<u64> {
+        let start = after / 60 + 1;
+        let (mut day, mut minute) = (start / 1440, start % 1440);
+        // Every valid schedule fires within a leap cycle of days.
+        for _ in 0..366 * 8 {
+            if self.matches_day(day)
+                && let Some(m) = (minute..1440).find(|m| {
+                    self.hours & (1 << (m / 60)) != 0 && self.minutes & (1 << (m % 60)) != 0
+                })
+            {
+                return Some((day * 1440 + m) * 60);
+            }
+            day += 1;
+            minute = 0;
+        }
+        None
+    }
+
+    fn matches_day(&self, days_since_epoch: u64) -> bool {
+        let (month, day) = month_day(days_since_epoch);
+        // 1970-01-01 was a Thursday.
+        let weekday = (days_since_epoch + 4) % 7;
+        let by_date = self.days & (1 << day) != 0;
+        let by_weekday = self.weekdays & (1 << weekday) != 0;
+        let day_ok = if self.either_day {
+            by_date || by_weekday
+        } else {
+            by_date && by_weekday
+        };
+        day_ok && self.months & (1 << month) != 0
+    }
+}
+
+/// Bit set of the values one field allows.
+fn field(spec: &str, min: u64, max: u64) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u64>()
+                    .ok()
+                    .filter(|&s| s > 0)
+                    .ok_or_else(|| format!("invalid step in {part:?}"))?,
+            ),
+            None => (part, 1),
+        };
+        let number = |s: &str| {
+            s.parse::<u64>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("{s:?} is not in {min}-{max}"))
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            r => match r.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                // `5/15` means from 5 to the end in steps of 15.
+                None if step > 1 => (number(r)?, max),
+                None => (number(r)?, number(r)?),
+            },
+        };
+        if lo > hi {
+            return Err(format!("empty range {range:?}"));
+        }
+        for v in (lo..=hi).step_by(usize::try_from(step).unwrap_or(usize::MAX)) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+/// Month (1-12) and day of month for a count of days since 1970-01-01 (proleptic
+/// Gregorian, after Howard Hinnant's `civil_from_days`).
+fn month_day(days: u64) -> (u64, u64) {
+    let z = days + 719_468;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
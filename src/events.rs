@@ -0,0 +1,209 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use ollama_file_find::{Digest, ListedModel};
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, StorePaths};
+
+/// A change to the set of installed models, derived by diffing two scans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoreEvent {
+    /// Seconds since the epoch
+    pub timestamp: u64,
+    pub kind: StoreEventKind,
+    pub model: String,
+    /// Blobs the model stopped referencing: all of them when removed, the replaced
+    /// layers when changed (absent from logs written by older versions)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub digests: Vec<Digest>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StoreEventKind {
+    Added,
+    Removed,
+    /// Same name, different layers (e.g. re-pulled or edited)
+    Changed,
+}
+
+impl fmt::Display for StoreEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StoreEventKind::Added => "added",
+            StoreEventKind::Removed => "removed",
+            StoreEventKind::Changed => "changed",
+        })
+    }
+}
+
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Digests that make up a model's content; equal fingerprints mean nothing changed.
+fn fingerprint(m: &ListedModel) -> Vec<Digest> {
+    m.layers
+        .iter()
+        .flatten()
+        .chain(m.config.iter())
+        .map(|l| l.digest)
+        .collect()
+}
+
+/// Events turning the `old` model list into the `new` one, ordered by model name.
+pub(crate) fn diff(old: &[ListedModel], new: &[ListedModel]) -> Vec<StoreEvent> {
+    let timestamp = now_secs();
+    let event = |kind, model: &str, digests| StoreEvent {
+        timestamp,
+        kind,
+        model: model.to_string(),
+        digests,
+    };
+    let before: HashMap<&str, &ListedModel> = old.iter().map(|m| (m.name.as_str(), m)).collect();
+    let mut events = Vec::new();
+    let mut seen = HashSet::with_capacity(new.len());
+    for m in new {
+        seen.insert(m.name.as_str());
+        match before.get(m.name.as_str()) {
+            None => events.push(event(StoreEventKind::Added, &m.name, Vec::new())),
+            Some(prev) if fingerprint(prev) != fingerprint(m) => {
+                let current = fingerprint(m);
+                let dropped = fingerprint(prev)
+                    .into_iter()
+                    .filter(|d| !current.contains(d))
+                    .collect();
+                events.push(event(StoreEventKind::Changed, &m.name, dropped));
+            }
+            Some(_) => {}
+        }
+    }
+    for m in old {
+        if !seen.contains(&m.name.as_str()) {
+            events.push(event(StoreEventKind::Removed, &m.name, fingerprint(m)));
+        }
+    }
+    events.sort_by(|a, b| a.model.cmp(&b.model));
+    events
+}
+
+/// Default location of a store's append-only event log. Each models directory gets its
+/// own file, named by a hash of its absolute path, so events of different stores (and
+/// of sandboxes) are never mixed up.
+pub(crate) fn default_log_path(paths: &StorePaths) -> PathBuf {
+    let root = paths.manifests.parent().unwrap_or(&paths.manifests);
+    let root = fs::canonicalize(root)
+        .or_else(|_| std::path::absolute(root))
+        .unwrap_or_else(|_| root.to_path_buf());
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("ollama-file-find")
+        .join("events")
+        .join(format!(
+            "{:016x}.jsonl",
+            fnv1a(root.as_os_str().as_encoded_bytes())
+        ))
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` stays the same across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Append events to a JSONL log, creating it (and its directory) on first use.
+pub(crate) fn append_log(path: &Path, events: &[StoreEvent]) -> Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut buf = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut buf, event)?;
+        buf.push(b'\n');
+    }
+    // One write per batch keeps concurrent appenders from interleaving lines.
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&buf)?;
+    Ok(())
+}
+
+/// Events recorded at or after `since` (seconds since the epoch). Lines that fail to
+/// parse, such as a torn final write, are skipped.
+pub(crate) fn read_log(path: &Path, since: u64) -> Result<Vec<StoreEvent>> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut out = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(event) = serde_json::from_str::<StoreEvent>(&line?)
+            && event.timestamp >= since
+        {
+            out.push(event);
+        }
+    }
+    Ok(out)
+}
+
+/// Models that used to reference each blob according to the log at `path`, oldest
+/// first. Empty when there is no log; unreadable logs are warned about and ignored,
+/// since this is only a hint.
+pub(crate) fn former_owners(path: &Path) -> HashMap<Digest, Vec<String>> {
+    let events = read_log(path, 0).unwrap_or_else(|e| {
+        eprintln!("Warning: could not read event log {}: {e}", path.display());
+        Vec::new()
+    });
+    let mut owners: HashMap<Digest, Vec<String>> = HashMap::new();
+    for event in events {
+        for digest in event.digests {
+            let models = owners.entry(digest).or_default();
+            if !models.contains(&event.model) {
+                models.push(event.model.clone());
+            }
+        }
+    }
+    owners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_log_path_per_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = |name: &str| {
+            let models = dir.path().join(name);
+            fs::create_dir_all(models.join("manifests")).unwrap();
+            StorePaths {
+                manifests: models.join("manifests"),
+                blobs: models.join("blobs"),
+            }
+        };
+        let (a, b) = (store("a"), store("b"));
+        assert_ne!(default_log_path(&a), default_log_path(&b));
+        // Another spelling of the same directory shares the log.
+        let a_again = StorePaths {
+            manifests: dir.path().join("b/../a/manifests"),
+            blobs: dir.path().join("a/blobs"),
+        };
+        assert_eq!(default_log_path(&a), default_log_path(&a_again));
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, PoisonError, RwLock},
+    thread,
+};
+
+use ollama_file_find::{BlobStatus, ListedModel, find_model};
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use tonic::{Request, Response, Status, transport::Server};
+
+use crate::{
+    Result,
+    commands::daemon::Index,
+    events::{StoreEvent, StoreEventKind},
+};
+
+mod pb {
+    tonic::include_proto!("ollama_file_find.v1");
+}
+
+use pb::model_store_server::{ModelStore, ModelStoreServer};
+
+type RpcResult<T> = std::result::Result<Response<T>, Status>;
+
+struct Service {
+    index: Arc<RwLock<Index>>,
+    events: broadcast::Sender<StoreEvent>,
+}
+
+/// Start the gRPC server on its own runtime thread; send store events into the
+/// returned channel to stream them to `WatchEvents` subscribers.
+pub(crate) fn spawn(
+    addr: SocketAddr,
+    index: Arc<RwLock<Index>>,
+) -> Result<broadcast::Sender<StoreEvent>> {
+    let (events, _) = broadcast::channel(256);
+    let service = Service {
+        index,
+        events: events.clone(),
+    };
+    let runtime = tokio::runtime::Runtime::new()?;
+    thread::spawn(move || {
+        let server = Server::builder()
+            .add_service(ModelStoreServer::new(service))
+            .serve(addr);
+        if let Err(e) = runtime.block_on(server) {
+            eprintln!("Warning: gRPC server stopped: {e}");
+        }
+    });
+    Ok(events)
+}
+
+impl Service {
+    fn index(&self) -> std::sync::RwLockReadGuard<'_, Index> {
+        self.index.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[tonic::async_trait]
+impl ModelStore for Service {
+    async fn list_models(
+        &self,
+        _request: Request<pb::ListModelsRequest>,
+    ) -> RpcResult<pb::ListModelsResponse> {
+        let models = self.index().models.iter().map(to_pb).collect();
+        Ok(Response::new(pb::ListModelsResponse { models }))
+    }
+
+    async fn get_model(&self, request: Request<pb::GetModelRequest>) -> RpcResult<pb::Model> {
+        let name = request.into_inner().name;
+        let index = self.index();
+        find_model(&index.models, &name)
+            .map(|m| Response::new(to_pb(m)))
+            .ok_or_else(|| Status::not_found(format!("model not found: {name}")))
+    }
+
+    async fn verify(&self, request: Request<pb::VerifyRequest>) -> RpcResult<pb::VerifyResponse> {
+        let name = request.into_inner().name;
+        let index = self.index();
+        let model = if name.is_empty() {
+            None
+        } else {
+            let found = find_model(&index.models, &name)
+                .ok_or_else(|| Status::not_found(format!("model not found: {name}")))?;
+            Some(found)
+        };
+        let problems: Vec<_> = index
+            .problems(model)
+            .into_iter()
+            .map(|p| pb::BlobProblem {
+                model: p.model.to_string(),
+                digest: p.digest.to_string(),
+                problem: p.problem.to_string(),
+            })
+            .collect();
+        Ok(Response::new(pb::VerifyResponse {
+            ok: problems.is_empty(),
+            problems,
+        }))
+    }
+
+    type WatchEventsStream =
+        Pin<Box<dyn Stream<Item = std::result::Result<pb::StoreEvent, Status>> + Send>>;
+
+    async fn watch_events(
+        &self,
+        _request: Request<pb::WatchEventsRequest>,
+    ) -> RpcResult<Self::WatchEventsStream> {
+        // Subscribers that fall behind skip the events they missed.
+        let stream = BroadcastStream::new(self.events.subscribe())
+            .filter_map(|event| event.ok().map(|e| Ok(event_to_pb(&e))));
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+fn to_pb(m: &ListedModel) -> pb::Model {
+    let path = |p: &std::path::Path| p.display().to_string();
+    pb::Model {
+        name: m.name.clone(),
+        host: m.model_id.host.clone(),
+        namespace: m.model_id.namespace.clone(),
+        model: m.model_id.model.clone(),
+        tag: m.model_id.tag.clone(),
+        manifest_path: path(&m.manifest_path),
+        total_size: m.total_size,
+        mtime: m.mtime,
+        primary_blob_path: m.primary_blob_path.as_deref().map(path),
+        blobs: m
+            .blob_paths
+            .iter()
+            .flatten()
+            .map(|b| pb::Blob {
+                digest: b.digest.to_string(),
+                media_type: b.media_type.to_string(),
+                declared_size: b.sizes.declared,
+                actual_size: b.sizes.actual,
+                physical_size: b.sizes.physical,
+                path: path(&b.path),
+                offline: b.offline,
+                status: status_to_pb(b.status).into(),
+                primary: b.primary,
+            })
+            .collect(),
+    }
+}
+
+fn status_to_pb(s: BlobStatus) -> pb::BlobStatus {
+    match s {
+        BlobStatus::Ok => pb::BlobStatus::Ok,
+        BlobStatus::Missing => pb::BlobStatus::Missing,
+        BlobStatus::Empty => pb::BlobStatus::Empty,
+        BlobStatus::Truncated => pb::BlobStatus::Truncated,
+        BlobStatus::SizeMismatch => pb::BlobStatus::SizeMismatch,
+        BlobStatus::Unverified => pb::BlobStatus::Unverified,
+    }
+}
+
+fn event_to_pb(e: &StoreEvent) -> pb::StoreEvent {
+    let kind = match e.kind {
+        StoreEventKind::Added => pb::store_event::Kind::Added,
+        StoreEventKind::Removed => pb::store_event::Kind::Removed,
+        StoreEventKind::Changed => pb::store_event::Kind::Changed,
+    };
+    pb::StoreEvent {
+        timestamp: e.timestamp,
+        kind: kind.into(),
+        model: e.model.clone(),
+    }
+}
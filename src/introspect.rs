@@ -0,0 +1,211 @@
+//! `--help-json`: the command and flag tree as JSON, so GUIs and TUIs wrapping the binary
+//! can build their forms from it and pick up new subcommands without changes.
+
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use serde::Serialize;
+
+use crate::{Result, args::Args};
+
+#[derive(Serialize)]
+struct CommandInfo {
+    name: String,
+    /// Full invocation, e.g. `ollama-file-find-cli policy check`
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    about: Option<String>,
+    usage: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    args: Vec<ArgInfo>,
+    /// Sets of arguments of which one is required, or at most one may be given
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupInfo>,
+    /// Whether a subcommand must be given
+    subcommand_required: bool,
+    subcommands: Vec<CommandInfo>,
+}
+
+#[derive(Serialize)]
+struct ArgInfo {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short: Option<char>,
+    positional: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+    /// `flag` (takes no value), `count` (repeatable flag), `set` (one value), or `append`
+    /// (repeatable)
+    action: &'static str,
+    required: bool,
+    /// Accepted by every subcommand below this one too
+    global: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    value_names: Vec<String>,
+    /// Values per occurrence; `max` is absent when unbounded
+    num_args: NumArgs,
+    /// Hint for pickers: `file_path`, `dir_path`, `any_path`, `url`, ...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value_hint: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    possible_values: Vec<PossibleValueInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    default_values: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    conflicts_with: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct NumArgs {
+    min: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct PossibleValueInfo {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+}
+
+#[derive(Serialize)]
+struct GroupInfo {
+    id: String,
+    args: Vec<String>,
+    required: bool,
+    multiple: bool,
+}
+
+/// If `--help-json` is on the command line, print the tree for the subcommand named
+/// before it (the whole CLI when none is) and return true. Checked before parsing, so
+/// the required arguments of that subcommand need not be given.
+pub(crate) fn print_if_requested() -> Result<bool> {
+    let args: Vec<String> = std::env::args_os()
+        .skip(1)
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    let Some(end) = args.iter().position(|a| a == "--help-json") else {
+        return Ok(false);
+    };
+    let mut cmd = Args::command();
+    cmd.build();
+    let mut cmd = &mut cmd;
+    for word in &args[..end] {
+        let pos = cmd
+            .get_subcommands()
+            .position(|s| s.get_name() == word || s.get_all_aliases().any(|a| a == word));
+        if let Some(pos) = pos {
+            cmd = cmd.get_subcommands_mut().nth(pos).expect("found above");
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&describe(cmd))?);
+    Ok(true)
+}
+
+fn describe(cmd: &mut Command) -> CommandInfo {
+    let usage = cmd.render_usage().to_string();
+    let usage = usage.trim_start_matches("Usage: ").to_owned();
+    let args = cmd
+        .get_arguments()
+        .filter(|a| !a.is_hide_set())
+        .filter_map(|a| describe_arg(cmd, a))
+        .collect();
+    let groups = cmd
+        .get_groups()
+        .map(|g| GroupInfo {
+            id: g.get_id().to_string(),
+            args: g.get_args().map(ToString::to_string).collect(),
+            required: g.is_required_set(),
+            // `is_multiple` takes `&mut self`.
+            multiple: g.clone().is_multiple(),
+        })
+        // Derive adds an unconstrained group per flattened struct.
+        .filter(|g| g.required || !g.multiple)
+        .collect();
+    let subcommands = cmd
+        .get_subcommands_mut()
+        .filter(|s| !s.is_hide_set() && s.get_name() != "help")
+        .map(describe)
+        .collect();
+    CommandInfo {
+        name: cmd.get_name().to_owned(),
+        path: cmd
+            .get_bin_name()
+            .unwrap_or_else(|| cmd.get_name())
+            .to_owned(),
+        about: cmd.get_about().map(ToString::to_string),
+        usage,
+        aliases: cmd.get_visible_aliases().map(str::to_owned).collect(),
+        args,
+        groups,
+        subcommand_required: cmd.is_subcommand_required_set(),
+        subcommands,
+    }
+}
+
+/// `None` for clap's own `--help` and `--version`.
+fn describe_arg(cmd: &Command, arg: &Arg) -> Option<ArgInfo> {
+    let action = match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse => "flag",
+        ArgAction::Count => "count",
+        ArgAction::Set => "set",
+        ArgAction::Append => "append",
+        _ => return None,
+    };
+    let range = arg.get_num_args().unwrap_or_default();
+    let hint = format!("{:?}", arg.get_value_hint());
+    Some(ArgInfo {
+        id: arg.get_id().to_string(),
+        long: arg.get_long().map(str::to_owned),
+        short: arg.get_short(),
+        positional: arg.is_positional(),
+        help: arg.get_help().map(ToString::to_string),
+        action,
+        required: arg.is_required_set(),
+        global: arg.is_global_set(),
+        value_names: arg
+            .get_value_names()
+            .into_iter()
+            .flatten()
+            .map(ToString::to_string)
+            .collect(),
+        num_args: NumArgs {
+            min: range.min_values(),
+            max: (range.max_values() != usize::MAX).then_some(range.max_values()),
+        },
+        value_hint: (hint != "Unknown").then(|| snake_case(&hint)),
+        possible_values: arg
+            .get_possible_values()
+            .into_iter()
+            .filter(|v| !v.is_hide_set())
+            .map(|v| PossibleValueInfo {
+                name: v.get_name().to_owned(),
+                help: v.get_help().map(ToString::to_string),
+            })
+            .collect(),
+        default_values: arg
+            .get_default_values()
+            .iter()
+            .map(|v| v.to_string_lossy().into_owned())
+            .collect(),
+        conflicts_with: cmd
+            .get_arg_conflicts_with(arg)
+            .into_iter()
+            .map(|a| a.get_id().to_string())
+            .collect(),
+    })
+}
+
+/// `FilePath` -> `file_path`.
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
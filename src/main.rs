@@ -1,47 +1,152 @@
 type Result<T> = std::result::Result<T, anyhow::Error>;
 
 mod args;
-use args::Args;
+use args::{Args, Command};
+
+mod commands;
+mod config;
+mod cron;
+mod events;
+mod format;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod introspect;
+mod nice;
+mod output;
+mod progress;
+mod removal;
+mod resume;
+mod service;
+mod table;
+#[cfg(test)]
+mod test_util;
+mod watch;
+
+use std::{path::PathBuf, process::ExitCode};
 
 use clap::Parser;
-use ollama_file_find::{ScanArgs, ollama_models_dir, scan_manifests};
+use config::Config;
+use ollama_file_find::{api_url, ollama_api_url, ollama_models_dir};
+
+/// Resolved `manifests/` and `blobs/` roots of the models directory.
+#[derive(Clone)]
+pub(crate) struct StorePaths {
+    pub manifests: PathBuf,
+    pub blobs: PathBuf,
+}
+
+impl StorePaths {
+    pub(crate) fn resolve(models_dir: Option<PathBuf>) -> Result<Self> {
+        let models_dir = models_dir.unwrap_or_else(ollama_models_dir);
+        let manifests = models_dir.join("manifests");
+        let blobs = models_dir.join("blobs");
+        if !manifests.is_dir() {
+            anyhow::bail!("Manifests directory not found: {}", manifests.display());
+        }
+        Ok(StorePaths { manifests, blobs })
+    }
+}
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
+    if introspect::print_if_requested()? {
+        return Ok(ExitCode::SUCCESS);
+    }
     let Args {
-        plain,
-        include_hidden,
-        verbose,
+        command,
+        list,
         models_dir,
+        config,
+        api_urls,
+        nice,
+        max_hash_rate,
+        progress,
+        help_json: _,
     } = Args::parse();
 
-    let models_dir = models_dir.unwrap_or_else(ollama_models_dir);
-    let manifests_root = models_dir.join("manifests");
-    let blobs_root = models_dir.join("blobs");
-
-    if !manifests_root.is_dir() {
-        anyhow::bail!(
-            "Manifests directory not found: {}",
-            manifests_root.display()
-        );
+    if nice && let Err(e) = nice::lower_priority() {
+        eprintln!("Warning: could not lower process priority: {e}");
     }
+    let hash_rate = max_hash_rate.or(nice.then_some(nice::DEFAULT_NICE_HASH_RATE));
+    ollama_file_find::set_hash_rate_limit(hash_rate);
+    progress::set_mode(progress);
 
-    let outcome = scan_manifests(
-        &ScanArgs::new(manifests_root, blobs_root)
-            .with_include_hidden(include_hidden)
-            .with_verbose(verbose),
-    );
-
-    for e in &outcome.errors {
-        eprintln!("Warning: {e}");
-    }
+    // Resolved lazily: some commands never touch the store.
+    let paths = || StorePaths::resolve(models_dir.clone());
+    let config = || Config::load(config.as_deref());
+    let api_urls: Vec<String> = if api_urls.is_empty() {
+        vec![ollama_api_url()]
+    } else {
+        api_urls.iter().map(|u| api_url(u)).collect()
+    };
 
-    if plain && !verbose {
-        for m in &outcome.models {
-            println!("{}", m.name);
+    match command.unwrap_or(Command::List(list)) {
+        Command::List(args) => commands::list::run(&paths()?, &api_urls, args)?,
+        Command::Du(args) => commands::du::run(&paths()?, args)?,
+        Command::Daemon(args) => commands::daemon::run(&paths()?, args)?,
+        // Does not resolve the store up front: an explicit --log needs none.
+        Command::Events(args) => commands::events::run(paths, args)?,
+        Command::ExportStatic(args) => commands::export_static::run(&paths()?, args)?,
+        #[cfg(feature = "network")]
+        Command::Estimate(args) => commands::estimate::run(&paths()?, args)?,
+        Command::CanFit(args) => return commands::can_fit::run(&paths()?, args),
+        // Does not resolve the store up front: the daemon may answer without it.
+        Command::CompleteNames(args) => commands::complete_names::run(models_dir, args)?,
+        Command::Quota(args) => return commands::quota::run(&paths()?, &config()?, args),
+        Command::Policy(args) => return commands::policy::run(&paths()?, &config()?, args),
+        Command::Compliance(args) => commands::compliance::run(&paths()?, args)?,
+        Command::Search(args) => commands::search::run(&paths()?, &config()?, args)?,
+        Command::Compare(args) => commands::compare::run(&paths()?, args)?,
+        Command::Orphans(args) => commands::orphans::run(&paths()?, &config()?, args)?,
+        Command::Prune(args) => commands::prune::run(&paths()?, &config()?, args)?,
+        Command::Sandbox(args) => commands::sandbox::run(models_dir, args)?,
+        Command::Maintain(args) => return commands::maintain::run(&paths()?, &config()?, args),
+        Command::Recover(args) => commands::recover::run(&paths()?, &config()?, args)?,
+        Command::ListBlobs(args) => commands::list_blobs::run(&paths()?, args)?,
+        Command::VerifyManifests(args) => return commands::verify_manifests::run(&paths()?, args),
+        Command::Show(args) => commands::show::run(&paths()?, &api_urls, args)?,
+        Command::Provenance(args) => commands::provenance::run(&paths()?, args)?,
+        Command::Verify(args) => return commands::verify::run(&paths()?, args),
+        Command::Doctor(args) => return commands::doctor::run(&paths()?, args),
+        // Does not resolve the store up front: synthetic runs generate their own.
+        Command::Bench(args) => commands::bench::run(models_dir, args)?,
+        Command::Seal(args) => return commands::seal::run(&paths()?, args),
+        Command::Bundle(args) => commands::bundle::run(models_dir, &config()?, args)?,
+        Command::ChunkStats(args) => commands::chunk_stats::run(&paths()?, args)?,
+        Command::Serve(args) => commands::serve::run(&paths()?, args)?,
+        #[cfg(feature = "peer")]
+        Command::Peer(args) => commands::peer::run(&paths()?, args)?,
+        #[cfg(feature = "peer")]
+        Command::PullFromPeer(args) => commands::peer::pull(models_dir, &config()?, args)?,
+        Command::Backup(args) => commands::backup::run(&paths()?, args)?,
+        Command::Restore(args) => commands::backup::restore(
+            &models_dir.unwrap_or_else(ollama_file_find::ollama_models_dir),
+            &config()?,
+            args,
+        )?,
+        Command::RestoreManifest(args) => {
+            commands::restore_manifest::run(&paths()?, &config()?, args)?
         }
-    } else {
-        println!("{}", serde_json::to_string_pretty(&outcome.models)?);
+        Command::SetParam(args) => commands::set_param::run(&paths()?, &config()?, args)?,
+        Command::SetTemplate(args) => commands::set_template::run(&paths()?, &config()?, args)?,
+        Command::SetSystem(args) => commands::set_system::run(&paths()?, &config()?, args)?,
+        Command::StripLayer(args) => commands::strip_layer::run(&paths()?, &config()?, args)?,
+        Command::Clone(args) => commands::clone::run(&paths()?, &config()?, args)?,
+        Command::Pin(args) => commands::pin::run(&paths()?, args)?,
+        Command::Owners(args) => commands::owners::run(&paths()?, args)?,
+        Command::CatBlob(args) => commands::cat_blob::run(&paths()?, args)?,
+        Command::Card(args) => commands::card::run(&paths()?, args)?,
+        #[cfg(feature = "signatures")]
+        Command::Inventory(args) => return commands::inventory::run(&paths()?, &config()?, args),
+        Command::Apply(args) => {
+            return commands::apply::run(&paths()?, &config()?, &api_urls[0], args);
+        }
+        Command::Facts(args) => commands::facts::run(&paths()?, args)?,
+        Command::Export(args) => commands::export::run(&paths()?, args)?,
+        Command::Fit(args) => commands::fit::run(&paths()?, args)?,
+        Command::DedupeTags(args) => commands::dedupe_tags::run(&paths()?, &config()?, args)?,
+        #[cfg(all(feature = "fuse", unix))]
+        Command::Mount(args) => commands::mount::run(&paths()?, args)?,
+        Command::Env(args) => commands::env::run(&paths()?, args)?,
     }
-
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,53 @@
+//! `--nice`: run at background CPU and I/O priority so long hashes and copies don't
+//! compete with inference on the same machine.
+
+use std::io;
+
+/// Hash throughput cap applied by `--nice` unless `--max-hash-rate` is given.
+pub(crate) const DEFAULT_NICE_HASH_RATE: u64 = 64 << 20;
+
+/// Lower this process to the lowest CPU priority and the idle I/O class (Linux), or
+/// background mode (Windows, which covers both).
+pub(crate) fn lower_priority() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        // SAFETY: plain syscall on this process; no pointers involved.
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        #[cfg(target_os = "linux")]
+        {
+            const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+            const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+            const IOPRIO_CLASS_SHIFT: u32 = 13;
+            // SAFETY: ioprio_set takes integer arguments only; 0 is this process.
+            let set = unsafe {
+                libc::syscall(
+                    libc::SYS_ioprio_set,
+                    IOPRIO_WHO_PROCESS,
+                    0,
+                    IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+                )
+            };
+            if set != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::System::Threading::{
+            GetCurrentProcess, PROCESS_MODE_BACKGROUND_BEGIN, SetPriorityClass,
+        };
+        // SAFETY: the pseudo handle of the current process is always valid.
+        if unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
@@ -0,0 +1,60 @@
+//! Scan, GC marking and manifest loading over a generated store.
+//!
+//! `OFF_BENCH_MODELS` overrides the number of models (default 2000).
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use ollama_file_find::{
+    BlobStore, HiddenPolicy, ManifestStore, ScanArgs, SyntheticStore, gc_plan, scan_manifests,
+};
+
+fn store_benches(c: &mut Criterion) {
+    let models = std::env::var("OFF_BENCH_MODELS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(2000);
+    let dir = tempfile::tempdir().expect("temp dir");
+    SyntheticStore {
+        models,
+        sparse: true,
+        ..SyntheticStore::default()
+    }
+    .generate(dir.path())
+    .expect("generate store");
+    let manifests = dir.path().join("manifests");
+    let blobs = dir.path().join("blobs");
+
+    let mut group = c.benchmark_group(format!("{models}_models"));
+    group.sample_size(20);
+    group.bench_function("scan", |b| {
+        b.iter(|| black_box(scan_manifests(&ScanArgs::new(&manifests, &blobs))));
+    });
+    group.bench_function("scan_verbose", |b| {
+        let args = ScanArgs::new(&manifests, &blobs).with_verbose(true);
+        b.iter(|| black_box(scan_manifests(&args)));
+    });
+    group.bench_function("gc_plan", |b| {
+        let (m, s) = (ManifestStore::new(&manifests), BlobStore::new(&blobs));
+        b.iter(|| black_box(gc_plan(&m, &s)));
+    });
+    group.bench_function("manifests_owned", |b| {
+        let store = ManifestStore::new(&manifests);
+        b.iter(|| black_box(store.manifests(HiddenPolicy::Include)));
+    });
+    group.bench_function("manifests_borrowed", |b| {
+        let store = ManifestStore::new(&manifests);
+        b.iter(|| {
+            let mut layers = 0;
+            let errors = store.visit(HiddenPolicy::Include, |_, m| {
+                layers += m.layers.len();
+                Ok(())
+            });
+            black_box((layers, errors))
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, store_benches);
+criterion_main!(benches);
@@ -0,0 +1,180 @@
+//! Talk to a running Ollama server (as opposed to [`crate::registry`], which talks to
+//! model registries).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, ListedModel, ModelId, Result, RuntimeState};
+
+/// One entry of `GET /api/ps`: a model currently loaded in memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningModel {
+    pub name: String,
+    #[serde(default)]
+    pub model: String,
+    /// Bytes in memory (system RAM plus VRAM)
+    #[serde(default)]
+    pub size: u64,
+    /// Bytes of `size` held in GPU memory
+    #[serde(default)]
+    pub size_vram: u64,
+    /// Manifest digest (hex, without the `sha256:` prefix)
+    #[serde(default)]
+    pub digest: String,
+    /// When the server will unload it (RFC 3339)
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// Base URL of the server that reported it (filled in by [`running_models`])
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct PsResponse {
+    #[serde(default)]
+    models: Vec<RunningModel>,
+}
+
+/// Models the server at `api_url` has loaded (`GET /api/ps`).
+pub fn running_models(api_url: &str) -> Result<Vec<RunningModel>> {
+    let url = format!("{}/api/ps", api_url.trim_end_matches('/'));
+    let api_err = |e: ureq::Error| Error::Api {
+        url: url.clone(),
+        message: e.to_string(),
+    };
+    let response: PsResponse = ureq::get(&url)
+        .call()
+        .map_err(api_err)?
+        .body_mut()
+        .read_json()
+        .map_err(api_err)?;
+    let endpoint = api_url.trim_end_matches('/');
+    Ok(response
+        .models
+        .into_iter()
+        .map(|r| RunningModel {
+            endpoint: endpoint.to_string(),
+            ..r
+        })
+        .collect())
+}
+
+/// One entry of `GET /api/tags`: an installed model, as `ollama list` shows it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledModel {
+    pub name: String,
+    #[serde(default)]
+    pub model: String,
+    /// Declared size of the layers and config blob
+    #[serde(default)]
+    pub size: u64,
+    /// Manifest digest (hex, without the `sha256:` prefix)
+    #[serde(default)]
+    pub digest: String,
+    /// Manifest mtime (RFC 3339)
+    #[serde(default)]
+    pub modified_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<InstalledModel>,
+}
+
+/// Models the server at `api_url` has installed (`GET /api/tags`).
+pub fn installed_models(api_url: &str) -> Result<Vec<InstalledModel>> {
+    let url = format!("{}/api/tags", api_url.trim_end_matches('/'));
+    let api_err = |e: ureq::Error| Error::Api {
+        url: url.clone(),
+        message: e.to_string(),
+    };
+    let response: TagsResponse = ureq::get(&url)
+        .call()
+        .map_err(api_err)?
+        .body_mut()
+        .read_json()
+        .map_err(api_err)?;
+    Ok(response.models)
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Have the server at `api_url` pull `model` (`POST /api/pull`, not streamed), blocking
+/// until the download is complete.
+pub fn pull_model(api_url: &str, model: &str) -> Result<()> {
+    let url = format!("{}/api/pull", api_url.trim_end_matches('/'));
+    let api_err = |message: String| Error::Api {
+        url: url.clone(),
+        message,
+    };
+    let response: PullResponse = ureq::post(&url)
+        .send_json(serde_json::json!({ "model": model, "stream": false }))
+        .map_err(|e| api_err(e.to_string()))?
+        .body_mut()
+        .read_json()
+        .map_err(|e| api_err(e.to_string()))?;
+    match response.error {
+        Some(error) => Err(api_err(error)),
+        None if response.status == "success" => Ok(()),
+        None => Err(api_err(format!(
+            "pull ended with status {:?}",
+            response.status
+        ))),
+    }
+}
+
+/// Set [`ListedModel::runtime`] on every model: `running` with its memory footprint
+/// when it appears in `running` (from any endpoint), not running otherwise.
+pub fn mark_running(models: &mut [ListedModel], running: &[RunningModel]) {
+    let loaded: Vec<(String, &RunningModel)> = running
+        .iter()
+        .filter_map(|r| Some((r.name.parse::<ModelId>().ok()?.normalize(), r)))
+        .collect();
+    for m in models {
+        let mut found = loaded.iter().filter(|(name, _)| *name == m.name);
+        m.runtime = Some(match found.next() {
+            Some((_, r)) => RuntimeState {
+                running: true,
+                endpoints: std::iter::once(&r.endpoint)
+                    .chain(found.map(|(_, r)| &r.endpoint))
+                    .filter(|e| !e.is_empty())
+                    .cloned()
+                    .collect(),
+                size_in_memory: Some(r.size),
+                size_vram: Some(r.size_vram),
+                expires_at: r.expires_at.clone(),
+            },
+            None => RuntimeState::default(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_running() {
+        let mut models: Vec<ListedModel> = ["llama3:8b", "apple/OpenELM:1b"]
+            .iter()
+            .map(|n| ListedModel::new(n.parse().unwrap(), "manifest"))
+            .collect();
+        let loaded: Vec<RunningModel> = serde_json::from_str(
+            r#"[{"name":"registry.ollama.ai/library/llama3:8b","size":10,"size_vram":8,"endpoint":"http://a:11434"},
+                {"name":"llama3:8b","size":10,"size_vram":0,"endpoint":"http://b:11434"}]"#,
+        )
+        .unwrap();
+        mark_running(&mut models, &loaded);
+        let runtime = models[0].runtime.as_ref().unwrap();
+        assert!(runtime.running);
+        assert_eq!(runtime.size_vram, Some(8));
+        assert_eq!(runtime.endpoints, ["http://a:11434", "http://b:11434"]);
+        assert!(!models[1].runtime.as_ref().unwrap().running);
+    }
+}
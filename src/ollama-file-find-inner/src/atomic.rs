@@ -0,0 +1,60 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{Error, Result};
+
+/// Write `contents` to `path` so readers see either the old file or the complete new
+/// one: write a temporary sibling, flush it to disk, then rename it into place.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    replace_with(path, |f| f.write_all(contents))
+}
+
+/// Copy `from` to `to` with the same guarantee as [`write_atomic`], streaming rather
+/// than reading the source into memory. Returns the number of bytes copied.
+pub fn copy_atomic(from: &Path, to: &Path) -> Result<u64> {
+    let mut src = File::open(from).map_err(|source| Error::Io {
+        path: from.to_path_buf(),
+        source,
+    })?;
+    let mut copied = 0;
+    replace_with(to, |f| {
+        copied = io::copy(&mut src, f)?;
+        Ok(())
+    })?;
+    Ok(copied)
+}
+
+fn replace_with(path: &Path, fill: impl FnOnce(&mut File) -> io::Result<()>) -> Result<()> {
+    let io_err = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().ok_or_else(|| {
+        io_err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path has no file name",
+        ))
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp = dir.join(tmp_name);
+
+    let result = File::create(&tmp)
+        .and_then(|mut f| {
+            fill(&mut f)?;
+            f.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result.map_err(io_err)
+}
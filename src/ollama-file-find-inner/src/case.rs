@@ -0,0 +1,145 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::ModelId;
+
+/// Which path component of a manifest name differs only by case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameLevel {
+    Host,
+    Namespace,
+    Model,
+    Tag,
+}
+
+/// Manifest paths that are distinct on a case-sensitive filesystem but name the same
+/// directory or file on a case-insensitive one (macOS and Windows by default), e.g.
+/// `Apple/Model` and `apple/model`. Ollama resolves such names to whichever spelling
+/// the filesystem happens to return.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseCollision {
+    /// The shallowest component where the spellings diverge
+    pub level: NameLevel,
+    /// The colliding path prefixes as written on disk, e.g. `registry.ollama.ai/Apple`
+    pub spellings: Vec<String>,
+    /// Normalized names of every model under any of the spellings
+    pub models: Vec<String>,
+}
+
+/// Group model ids whose manifest paths differ only by case. Each clash is reported
+/// once, at the shallowest level it occurs (a namespace clash is not repeated for
+/// every model inside it).
+#[must_use]
+pub fn case_collisions<'a>(ids: impl IntoIterator<Item = &'a ModelId>) -> Vec<CaseCollision> {
+    // Per level: folded prefix -> (spellings seen, models under them).
+    let mut groups: BTreeMap<(NameLevel, String), (BTreeSet<String>, BTreeSet<String>)> =
+        BTreeMap::new();
+    for id in ids {
+        let name = id.normalize();
+        let mut prefix = String::new();
+        let parts = [
+            (NameLevel::Host, id.host.as_deref()),
+            (NameLevel::Namespace, id.namespace.as_deref()),
+            (NameLevel::Model, Some(id.model.as_str())),
+            (NameLevel::Tag, Some(id.tag.as_str())),
+        ];
+        for (level, part) in parts {
+            let Some(part) = part else { continue };
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(part);
+            let (spellings, models) = groups.entry((level, prefix.to_lowercase())).or_default();
+            spellings.insert(prefix.clone());
+            models.insert(name.clone());
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, (spellings, _))| {
+            // Spellings that differ only in a parent are reported at the parent's level.
+            let parents: BTreeSet<_> = spellings
+                .iter()
+                .map(|s| s.rsplit_once('/').map(|(parent, _)| parent))
+                .collect();
+            parents.len() < spellings.len()
+        })
+        .map(|((level, _), (spellings, models))| CaseCollision {
+            level,
+            spellings: spellings.into_iter().collect(),
+            models: models.into_iter().collect(),
+        })
+        .collect()
+}
+
+/// Whether the filesystem holding `dir` ignores case, found by looking up `dir` with
+/// the case of its last component flipped. `None` when the name has no letters or the
+/// directory does not exist.
+#[must_use]
+pub fn is_case_insensitive(dir: &Path) -> Option<bool> {
+    let meta = fs::metadata(dir).ok()?;
+    let name = dir.file_name()?.to_str()?;
+    let flipped: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect();
+    if flipped == name {
+        return None;
+    }
+    let Ok(other) = fs::metadata(dir.with_file_name(flipped)) else {
+        return Some(false);
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(meta.ino() == other.ino() && meta.dev() == other.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (meta, other);
+        Some(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_collisions() {
+        let ids: Vec<ModelId> = [
+            "Apple/Model:latest",
+            "apple/model:latest",
+            "apple/other:latest",
+            "llama3:8B",
+            "llama3:8b",
+            "phi4:latest",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+        let found = case_collisions(&ids);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].level, NameLevel::Namespace);
+        assert_eq!(
+            found[0].spellings,
+            ["registry.ollama.ai/Apple", "registry.ollama.ai/apple"]
+        );
+        assert_eq!(found[0].models.len(), 3);
+        assert_eq!(found[1].level, NameLevel::Tag);
+        assert_eq!(found[1].models, ["llama3:8B", "llama3:8b"]);
+    }
+}
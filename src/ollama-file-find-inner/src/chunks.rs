@@ -0,0 +1,139 @@
+//! Content-defined chunking with fingerprint sampling, to estimate how much of one blob
+//! reappears in another before deciding on dedup or delta transfer.
+//!
+//! Boundaries come from a gear rolling hash, so an insertion early in a file only moves
+//! the chunks around it and later chunks still line up. Only chunks whose fingerprint
+//! is a multiple of the sampling rate are kept; because the choice depends on content,
+//! two files keep the same sample of the chunks they share, and byte counts scaled by
+//! the rate estimate the full ones.
+
+use std::io::{self, Read};
+
+/// Chunks are cut at the first gear hash with these low bits clear (64 KiB on average).
+const BOUNDARY_MASK: u64 = (1 << 16) - 1;
+const MIN_CHUNK: usize = 16 * 1024;
+const MAX_CHUNK: usize = 256 * 1024;
+
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64, for a fixed table of well-mixed values
+    let mut table = [0; 256];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// A sampled chunk: 64-bit FNV-1a fingerprint of its bytes and its length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkSample {
+    pub fingerprint: u64,
+    pub len: u32,
+}
+
+/// Result of [`sample_chunks`].
+#[derive(Debug, Clone, Default)]
+pub struct ChunkSamples {
+    /// Bytes read
+    pub bytes: u64,
+    /// Chunks cut
+    pub chunks: u64,
+    /// The kept chunks, in file order
+    pub samples: Vec<ChunkSample>,
+}
+
+/// Chunk everything `reader` yields, keeping about one chunk in `one_in` (1 keeps all).
+pub fn sample_chunks(mut reader: impl Read, one_in: u64) -> io::Result<ChunkSamples> {
+    let one_in = one_in.max(1);
+    let mut out = ChunkSamples::default();
+    let mut buf = vec![0; 1 << 20];
+    let (mut gear, mut fnv, mut len) = (0u64, FNV_OFFSET, 0usize);
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        out.bytes += n as u64;
+        for &b in &buf[..n] {
+            gear = (gear << 1).wrapping_add(GEAR[b as usize]);
+            fnv = (fnv ^ u64::from(b)).wrapping_mul(FNV_PRIME);
+            len += 1;
+            if (len >= MIN_CHUNK && gear & BOUNDARY_MASK == 0) || len >= MAX_CHUNK {
+                out.cut(fnv, len, one_in);
+                (gear, fnv, len) = (0, FNV_OFFSET, 0);
+            }
+        }
+    }
+    if len > 0 {
+        out.cut(fnv, len, one_in);
+    }
+    Ok(out)
+}
+
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+impl ChunkSamples {
+    fn cut(&mut self, fingerprint: u64, len: usize, one_in: u64) {
+        self.chunks += 1;
+        if fingerprint.is_multiple_of(one_in) {
+            self.samples.push(ChunkSample {
+                fingerprint,
+                len: len as u32,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunks_resync_after_insertion() {
+        let original = noise(4 << 20, 1);
+        let mut edited = b"a few inserted bytes".to_vec();
+        edited.extend_from_slice(&original);
+
+        let a = sample_chunks(&original[..], 1).unwrap();
+        let b = sample_chunks(&edited[..], 1).unwrap();
+        assert_eq!(a.bytes, original.len() as u64);
+        assert_eq!(
+            a.samples.iter().map(|c| u64::from(c.len)).sum::<u64>(),
+            a.bytes
+        );
+        assert!(a.chunks > 16, "{} chunks", a.chunks);
+
+        let seen: HashSet<_> = a.samples.iter().collect();
+        let shared = b.samples.iter().filter(|c| seen.contains(c)).count();
+        // Only the first chunk differs.
+        assert_eq!(shared, b.samples.len() - 1);
+
+        let unrelated = sample_chunks(&noise(1 << 20, 2)[..], 1).unwrap();
+        assert!(!unrelated.samples.iter().any(|c| seen.contains(c)));
+    }
+}
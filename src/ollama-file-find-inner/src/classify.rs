@@ -0,0 +1,144 @@
+use std::{fmt, fs, io::Read, path::Path};
+
+use serde::Serialize;
+
+use crate::{Error, Result, gguf::is_gguf};
+
+/// How many leading bytes [`classify_file`] samples.
+pub const CLASSIFY_SAMPLE_BYTES: usize = 4096;
+
+/// Content type of a blob, guessed from its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobClass {
+    Gguf,
+    Safetensors,
+    Json,
+    Text,
+    Zip,
+    /// Empty file
+    Empty,
+    Unknown,
+}
+
+impl fmt::Display for BlobClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BlobClass::Gguf => "gguf",
+            BlobClass::Safetensors => "safetensors",
+            BlobClass::Json => "json",
+            BlobClass::Text => "text",
+            BlobClass::Zip => "zip",
+            BlobClass::Empty => "empty",
+            BlobClass::Unknown => "unknown",
+        })
+    }
+}
+
+impl std::str::FromStr for BlobClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "gguf" => BlobClass::Gguf,
+            "safetensors" => BlobClass::Safetensors,
+            "json" => BlobClass::Json,
+            "text" => BlobClass::Text,
+            "zip" => BlobClass::Zip,
+            "empty" => BlobClass::Empty,
+            "unknown" => BlobClass::Unknown,
+            other => return Err(format!("unknown blob class {other:?}")),
+        })
+    }
+}
+
+impl BlobClass {
+    /// Classify a sample taken from the start of a blob.
+    #[must_use]
+    pub fn detect(sample: &[u8]) -> Self {
+        if sample.is_empty() {
+            return BlobClass::Empty;
+        }
+        if is_gguf(sample) {
+            return BlobClass::Gguf;
+        }
+        if sample.starts_with(b"PK\x03\x04") {
+            return BlobClass::Zip;
+        }
+        // safetensors: little-endian u64 header length, then the JSON header itself.
+        if sample.len() > 8 && sample[8] == b'{' {
+            let header_len = u64::from_le_bytes(sample[..8].try_into().expect("8 bytes"));
+            if header_len > 1 && header_len < 100 * 1024 * 1024 {
+                return BlobClass::Safetensors;
+            }
+        }
+        if let Some(text) = as_text(sample) {
+            return if looks_like_json(text) {
+                BlobClass::Json
+            } else {
+                BlobClass::Text
+            };
+        }
+        BlobClass::Unknown
+    }
+}
+
+/// An object opening with a key (`{"`) or empty (`{}`), or an array. Go templates
+/// (`{{ .Prompt }}`) also start with a brace, so one is not enough.
+fn looks_like_json(text: &str) -> bool {
+    let mut chars = text.trim_start().chars();
+    match chars.next() {
+        Some('[') => true,
+        Some('{') => matches!(chars.find(|c| !c.is_whitespace()), Some('"' | '}')),
+        _ => false,
+    }
+}
+
+/// The sample as text, if it is UTF-8 (allowing a character cut off at the end)
+/// without control characters other than whitespace.
+fn as_text(sample: &[u8]) -> Option<&str> {
+    let text = match std::str::from_utf8(sample) {
+        Ok(t) => t,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&sample[..e.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    text.chars()
+        .all(|c| !c.is_control() || c.is_whitespace())
+        .then_some(text)
+}
+
+/// Sample the start of a file and classify it.
+pub fn classify_file(path: &Path) -> Result<BlobClass> {
+    let mut sample = Vec::with_capacity(CLASSIFY_SAMPLE_BYTES);
+    fs::File::open(path)
+        .and_then(|f| {
+            f.take(CLASSIFY_SAMPLE_BYTES as u64)
+                .read_to_end(&mut sample)
+        })
+        .map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    Ok(BlobClass::detect(&sample))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_blob_class() {
+        let mut safetensors = 20u64.to_le_bytes().to_vec();
+        safetensors.extend_from_slice(br#"{"__metadata__":{}}"#);
+        assert_eq!(BlobClass::detect(b"GGUF\x03\0\0\0"), BlobClass::Gguf);
+        assert_eq!(BlobClass::detect(&safetensors), BlobClass::Safetensors);
+        assert_eq!(BlobClass::detect(b"  {\"a\":1}"), BlobClass::Json);
+        assert_eq!(BlobClass::detect(b"{{ .Prompt }}"), BlobClass::Text);
+        assert_eq!(BlobClass::detect(b"MIT License\n"), BlobClass::Text);
+        assert_eq!(BlobClass::detect(b"PK\x03\x04rest"), BlobClass::Zip);
+        assert_eq!(BlobClass::detect(b""), BlobClass::Empty);
+        assert_eq!(BlobClass::detect(&[0, 159, 146, 150]), BlobClass::Unknown);
+    }
+}
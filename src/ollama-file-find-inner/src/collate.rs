@@ -0,0 +1,100 @@
+use std::cmp::Ordering;
+
+/// How model names are ordered in scan results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Collation {
+    /// Byte-wise (`llama3.10` before `llama3.2`)
+    #[default]
+    Ascii,
+    /// Runs of digits compare by value (`llama3.2` before `llama3.10`)
+    Natural,
+}
+
+impl Collation {
+    #[must_use]
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Ascii => a.cmp(b),
+            Collation::Natural => natural_cmp(a, b),
+        }
+    }
+}
+
+/// Compare digit runs numerically and everything else byte-wise. Numbers of equal value
+/// but different zero padding (`01`, `1`) fall back to byte order, so the result is
+/// still a total order consistent with equality.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a.as_bytes(), b.as_bytes());
+    while let (Some(&cx), Some(&cy)) = (x.first(), y.first()) {
+        let ord = if cx.is_ascii_digit() && cy.is_ascii_digit() {
+            let (nx, rx) = split_digits(x);
+            let (ny, ry) = split_digits(y);
+            x = rx;
+            y = ry;
+            let (tx, ty) = (trim_zeros(nx), trim_zeros(ny));
+            tx.len().cmp(&ty.len()).then_with(|| tx.cmp(ty))
+        } else {
+            x = &x[1..];
+            y = &y[1..];
+            cx.cmp(&cy)
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    x.len().cmp(&y.len()).then_with(|| {
+        if x.is_empty() {
+            a.cmp(b)
+        } else {
+            Ordering::Equal
+        }
+    })
+}
+
+fn split_digits(s: &[u8]) -> (&[u8], &[u8]) {
+    s.split_at(
+        s.iter()
+            .position(|c| !c.is_ascii_digit())
+            .unwrap_or(s.len()),
+    )
+}
+
+fn trim_zeros(s: &[u8]) -> &[u8] {
+    let start = s.iter().position(|&c| c != b'0').unwrap_or(s.len());
+    &s[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_collation() {
+        let mut names = vec![
+            "llama3.10:8b",
+            "llama3:8b",
+            "llama3.1:70b",
+            "llama3.1:8b",
+            "llama3.2:1b",
+            "phi4:latest",
+            "qwen2.5:007b",
+            "qwen2.5:7b",
+        ];
+        names.sort_by(|a, b| Collation::Natural.compare(a, b));
+        assert_eq!(
+            names,
+            [
+                "llama3.1:8b",
+                "llama3.1:70b",
+                "llama3.2:1b",
+                "llama3.10:8b",
+                "llama3:8b",
+                "phi4:latest",
+                "qwen2.5:007b",
+                "qwen2.5:7b",
+            ]
+        );
+        assert_eq!(Collation::Natural.compare("a10", "a10"), Ordering::Equal);
+        assert_eq!(Collation::Ascii.compare("a10", "a9"), Ordering::Less);
+    }
+}
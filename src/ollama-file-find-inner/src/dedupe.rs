@@ -0,0 +1,315 @@
+//! Finding tags of one model that are different quantizations (or sizes on disk) of the
+//! same weights, e.g. `llama3.1:8b-instruct-q4_K_M` next to `llama3.1:8b-instruct-q8_0`,
+//! and choosing which one to keep.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    time::UNIX_EPOCH,
+};
+
+use serde::Serialize;
+
+use crate::{
+    BlobStore, Digest, Error, HiddenPolicy, LayerKind, ManifestStore, ModelId, StoredManifest,
+};
+
+/// Largest config blob read for its `file_type` and `model_type`.
+const MAX_CONFIG_BYTES: u64 = 1 << 20;
+
+/// Which tag of a [`TagGroup`] is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepPolicy {
+    /// The most bits per weight (`fp16` over `q8_0` over `q4_K_M`); the newest on a tie
+    HighestQuant,
+    /// The most recently pulled or created
+    Latest,
+    /// The least bytes on disk
+    Smallest,
+}
+
+/// One tag in a [`TagGroup`].
+#[derive(Debug, Clone, Serialize)]
+pub struct TagVariant {
+    pub name: String,
+    /// `file_type` from the config blob, else the quantization named in the tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<String>,
+    /// Declared size of all layers
+    pub size: u64,
+    /// Manifest modification time (seconds since the epoch)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<u64>,
+    #[serde(skip)]
+    weights: Option<Digest>,
+    #[serde(skip)]
+    layers: Vec<Digest>,
+}
+
+/// Tags of one model with the same parameter count but different weights, and the one
+/// [`KeepPolicy`] picks among them.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagGroup {
+    /// Model name without the tag (`llama3.1`, `team/model`)
+    pub model: String,
+    /// Parameter count shared by the tags (`8B`), from the config blob or the tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter_size: Option<String>,
+    pub keep: TagVariant,
+    /// Tags to remove; aliases of `keep` (same weights blob) are never listed, nor, with
+    /// [`KeepPolicy::HighestQuant`], tags whose quantization is unknown
+    pub remove: Vec<TagVariant>,
+    /// Bytes of blobs that no manifest references once every group's `remove` tags are
+    /// gone (blobs shared between groups are counted in each)
+    pub freed_bytes: u64,
+}
+
+/// Group every visible model's tags by model name and parameter count, pick one tag per
+/// group with `policy`, and list the others for removal. Models whose parameter count
+/// can't be told from the config blob or the tag are only grouped with each other.
+/// Groups that would remove nothing are left out.
+pub fn tag_groups(
+    manifests: &ManifestStore,
+    blobs: &BlobStore,
+    policy: KeepPolicy,
+) -> (Vec<TagGroup>, Vec<Error>) {
+    // Hidden tags are never grouped, but the blobs they reference are not freed.
+    let (stored, errors) = manifests.manifests(HiddenPolicy::Include);
+    let (visible, _) = manifests.ids(HiddenPolicy::Exclude);
+    let visible: HashSet<String> = visible.iter().map(ModelId::normalize).collect();
+    let mut by_model: BTreeMap<(String, Option<String>), Vec<TagVariant>> = BTreeMap::new();
+    for m in stored
+        .iter()
+        .filter(|m| visible.contains(&m.id.normalize()))
+    {
+        let (config_quant, config_size) = config_labels(blobs, m);
+        let name = m.id.normalize();
+        let model = name
+            .rsplit_once(':')
+            .map_or(name.as_str(), |(model, _)| model)
+            .to_string();
+        let parameter_size = config_size.or_else(|| tag_size(&m.id.tag));
+        let variant = TagVariant {
+            quantization: config_quant.or_else(|| tag_quantization(&m.id.tag)),
+            size: m.data.layers.iter().filter_map(|l| l.size).sum(),
+            mtime: fs::metadata(&m.path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            weights: m
+                .data
+                .layers
+                .iter()
+                .find(|l| l.kind() == LayerKind::Weights)
+                .map(|l| l.digest),
+            layers: referenced(m).collect(),
+            name,
+        };
+        by_model
+            .entry((model, parameter_size))
+            .or_default()
+            .push(variant);
+    }
+
+    let mut groups: Vec<TagGroup> = by_model
+        .into_iter()
+        .filter_map(|((model, parameter_size), mut variants)| {
+            variants.sort_by(|a, b| a.name.cmp(&b.name));
+            let best = (0..variants.len()).max_by(|&i, &j| {
+                let (a, b) = (&variants[i], &variants[j]);
+                match policy {
+                    KeepPolicy::HighestQuant => quant_bits(a)
+                        .total_cmp(&quant_bits(b))
+                        .then(a.mtime.cmp(&b.mtime)),
+                    KeepPolicy::Latest => a.mtime.cmp(&b.mtime),
+                    KeepPolicy::Smallest => b.size.cmp(&a.size),
+                }
+            })?;
+            let keep = variants.remove(best);
+            // Ranking by quantization says nothing about tags whose quantization is unknown.
+            let remove: Vec<TagVariant> = variants
+                .into_iter()
+                .filter(|v| v.weights != keep.weights)
+                .filter(|v| policy != KeepPolicy::HighestQuant || quant_bits(v) > 0.0)
+                .collect();
+            (!remove.is_empty()).then_some(TagGroup {
+                model,
+                parameter_size,
+                keep,
+                remove,
+                freed_bytes: 0,
+            })
+        })
+        .collect();
+
+    let removed: HashSet<&str> = groups
+        .iter()
+        .flat_map(|g| g.remove.iter().map(|v| v.name.as_str()))
+        .collect();
+    let still_referenced: HashSet<Digest> = stored
+        .iter()
+        .filter(|m| !removed.contains(m.id.normalize().as_str()))
+        .flat_map(referenced)
+        .collect();
+    for group in &mut groups {
+        let freed: HashSet<Digest> = group
+            .remove
+            .iter()
+            .flat_map(|v| v.layers.iter().copied())
+            .filter(|d| !still_referenced.contains(d))
+            .collect();
+        group.freed_bytes = freed
+            .iter()
+            .filter_map(|d| fs::metadata(blobs.path_for(d)).ok())
+            .map(|meta| meta.len())
+            .sum();
+    }
+    (groups, errors)
+}
+
+/// Digests of the config blob and every layer.
+fn referenced(m: &StoredManifest) -> impl Iterator<Item = Digest> + '_ {
+    m.data.config.iter().chain(&m.data.layers).map(|l| l.digest)
+}
+
+/// `file_type` and `model_type` from the config blob Ollama writes (`"Q4_K_M"`, `"8.0B"`).
+fn config_labels(blobs: &BlobStore, m: &StoredManifest) -> (Option<String>, Option<String>) {
+    let Some(config) = m
+        .data
+        .config
+        .as_ref()
+        .and_then(|c| blobs.read_text(&c.digest, MAX_CONFIG_BYTES).ok())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+    else {
+        return (None, None);
+    };
+    let field = |key: &str| {
+        config
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    };
+    (
+        field("file_type").map(str::to_string),
+        field("model_type").and_then(normalize_size),
+    )
+}
+
+/// The part of a tag naming a quantization (`8b-instruct-q4_K_M` -> `q4_K_M`).
+fn tag_quantization(tag: &str) -> Option<String> {
+    tag.split('-')
+        .find(|part| label_bits(part).is_some())
+        .map(str::to_string)
+}
+
+/// The part of a tag naming a parameter count (`8b-instruct-q4_K_M` -> `8B`).
+fn tag_size(tag: &str) -> Option<String> {
+    tag.split('-').find_map(normalize_size)
+}
+
+/// `8.0B`, `8b`, and `8B` all as `8B`; `135m` as `135M`.
+fn normalize_size(label: &str) -> Option<String> {
+    let unit = label.chars().last()?.to_ascii_uppercase();
+    if !matches!(unit, 'B' | 'M') {
+        return None;
+    }
+    let n: f64 = label[..label.len() - 1].parse().ok()?;
+    (n > 0.0).then(|| format!("{n}{unit}"))
+}
+
+fn quant_bits(v: &TagVariant) -> f64 {
+    v.quantization
+        .as_deref()
+        .and_then(label_bits)
+        .unwrap_or(0.0)
+}
+
+/// Rough bits per weight of a llama.cpp quantization name, for ordering only.
+fn label_bits(label: &str) -> Option<f64> {
+    let label = label.to_ascii_lowercase();
+    match label.as_str() {
+        "f32" | "fp32" => return Some(32.0),
+        "f16" | "fp16" | "bf16" => return Some(16.0),
+        _ => {}
+    }
+    let (rest, importance) = match label.strip_prefix("iq") {
+        Some(rest) => (rest, -0.2),
+        None => (label.strip_prefix('q')?, 0.0),
+    };
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let bits: f64 = rest[..digits].parse().ok()?;
+    let variant = match &rest[digits..] {
+        "_k_l" => 0.5,
+        "_k_m" | "_k" => 0.4,
+        "_1" => 0.2,
+        "_k_s" => 0.1,
+        _ => 0.0,
+    };
+    Some(bits + variant + importance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::FakeStore;
+
+    #[test]
+    fn test_tag_groups() {
+        const CONFIG: &str = r#"{"file_type":"Q4_K_M","model_type":"8.0B"}"#;
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3.1:8b-instruct-q4_K_M")
+            .layer(LayerKind::Weights, b"q4 weights")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .model("llama3.1:latest")
+            .layer(LayerKind::Weights, b"q4 weights")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .config(CONFIG)
+            .model("llama3.1:8b-instruct-q8_0")
+            .layer(LayerKind::Weights, b"q8 weights, larger")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .model("llama3.1:70b-instruct-q4_0")
+            .layer(LayerKind::Weights, b"70b weights")
+            .model("phi4")
+            .layer(LayerKind::Weights, b"phi weights")
+            .build(dir.path())
+            .unwrap();
+
+        let (groups, errors) =
+            tag_groups(&store.manifests(), &store.blobs(), KeepPolicy::HighestQuant);
+        assert!(errors.is_empty());
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.model, "llama3.1");
+        assert_eq!(group.parameter_size.as_deref(), Some("8B"));
+        assert_eq!(group.keep.name, "llama3.1:8b-instruct-q8_0");
+        let removed: Vec<&str> = group.remove.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(removed, ["llama3.1:8b-instruct-q4_K_M", "llama3.1:latest"]);
+        // The template and default config stay with the kept tag; the q4 weights and
+        // the config only `latest` has go.
+        assert_eq!(
+            group.freed_bytes,
+            (b"q4 weights".len() + CONFIG.len()) as u64
+        );
+
+        let (groups, _) = tag_groups(&store.manifests(), &store.blobs(), KeepPolicy::Smallest);
+        assert_eq!(groups[0].remove.len(), 1);
+        assert_eq!(groups[0].remove[0].name, "llama3.1:8b-instruct-q8_0");
+    }
+
+    #[test]
+    fn test_label_bits() {
+        let order = [
+            "iq2_xs", "q3_K_S", "q4_0", "q4_K_M", "q5_K_M", "q8_0", "fp16",
+        ];
+        for pair in order.windows(2) {
+            assert!(label_bits(pair[0]) < label_bits(pair[1]), "{pair:?}");
+        }
+        assert_eq!(label_bits("instruct"), None);
+        assert_eq!(normalize_size("8.0B").as_deref(), Some("8B"));
+        assert_eq!(normalize_size("instruct"), None);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Error, hash::hex};
+
+/// Hash algorithm of a content digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DigestAlgorithm {
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    /// Name used in digests (`sha256`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A content digest (`sha256:<64 hex>`), stored as raw bytes so that layer lists and
+/// blob sets of large stores stay small and compare cheaply. Serialized in manifest form.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Digest {
+    algorithm: DigestAlgorithm,
+    bytes: [u8; 32],
+}
+
+impl Digest {
+    #[must_use]
+    pub const fn sha256(bytes: [u8; 32]) -> Self {
+        Digest {
+            algorithm: DigestAlgorithm::Sha256,
+            bytes,
+        }
+    }
+
+    #[must_use]
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+
+    /// Lowercase hex of the hash, without the algorithm prefix.
+    #[must_use]
+    pub fn hex(&self) -> String {
+        hex(&self.bytes)
+    }
+
+    /// Name of the blob file holding this content (`sha256-<hex>`).
+    #[must_use]
+    pub fn blob_file_name(&self) -> String {
+        format!("{}-{}", self.algorithm, self.hex())
+    }
+
+    /// Path of the blob file under a `blobs` directory (the file may not exist).
+    #[must_use]
+    pub fn blob_path(&self, blobs_root: &Path) -> PathBuf {
+        blobs_root.join(self.blob_file_name())
+    }
+
+    /// Parse a blob file name (`sha256-<hex>`); `None` for partial downloads and
+    /// unrelated files.
+    #[must_use]
+    pub fn from_blob_file_name(name: &str) -> Option<Self> {
+        let hex = name.strip_prefix("sha256-")?;
+        decode_hex(hex).map(Digest::sha256)
+    }
+
+    /// The blob an interrupted `ollama pull` was downloading, from the name of one of
+    /// its partial files (`sha256-<hex>-partial`, or `-partial-<n>` for a chunk).
+    #[must_use]
+    pub fn from_partial_file_name(name: &str) -> Option<Self> {
+        let (hex, rest) = name.strip_prefix("sha256-")?.split_once("-partial")?;
+        let chunk = rest.strip_prefix('-').unwrap_or(rest);
+        if !chunk.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        decode_hex(hex).map(Digest::sha256)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let nibble = |c: u8| char::from(c).to_digit(16);
+    let mut out = [0u8; 32];
+    for (byte, pair) in out.iter_mut().zip(hex.chunks_exact(2)) {
+        let (hi, lo) = (nibble(pair[0])?, nibble(pair[1])?);
+        *byte = u8::try_from(hi << 4 | lo).ok()?;
+    }
+    Some(out)
+}
+
+impl FromStr for Digest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix("sha256:")
+            .and_then(decode_hex)
+            .map(Digest::sha256)
+            .ok_or_else(|| Error::InvalidDigest(s.to_string()))
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.hex())
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: std::borrow::Cow<'de, str> = Deserialize::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_round_trip() {
+        let text = format!("sha256:{}", "0123456789abcdef".repeat(4));
+        let d: Digest = text.parse().unwrap();
+        assert_eq!(d.to_string(), text);
+        assert_eq!(d.as_bytes()[..2], [0x01, 0x23]);
+        assert_eq!(
+            d.blob_path(Path::new("/b")),
+            Path::new("/b").join(format!("sha256-{}", d.hex()))
+        );
+        assert_eq!(Digest::from_blob_file_name(&d.blob_file_name()), Some(d));
+        assert_eq!(
+            serde_json::from_str::<Digest>(&format!("\"{text}\"")).unwrap(),
+            d
+        );
+
+        for bad in ["sha256:ab", "md5:00", &text[7..], &text.replace('a', "g")] {
+            assert!(bad.parse::<Digest>().is_err(), "{bad}");
+        }
+        assert_eq!(Digest::from_blob_file_name("sha256-ab.partial"), None);
+
+        let name = d.blob_file_name();
+        assert_eq!(Digest::from_partial_file_name(&name), None);
+        for partial in [format!("{name}-partial"), format!("{name}-partial-12")] {
+            assert_eq!(
+                Digest::from_partial_file_name(&partial),
+                Some(d),
+                "{partial}"
+            );
+        }
+        assert_eq!(
+            Digest::from_partial_file_name(&format!("{name}-partial-x")),
+            None
+        );
+    }
+}
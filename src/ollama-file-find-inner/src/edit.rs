@@ -0,0 +1,218 @@
+//! Offline edits to an installed model: swap the content of one metadata layer
+//! (params, template, system prompt, ...) by writing a new blob and rewriting the
+//! manifest to point at it.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::{
+    BlobStore, Error, LayerInfo, LayerKind, ManifestBackup, ManifestStore, MediaType, ModelId,
+    Result,
+};
+
+/// What [`set_layer`] changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct LayerEdit {
+    pub model: String,
+    pub kind: LayerKind,
+    /// Layers of that kind before the edit (usually one)
+    pub old: Vec<LayerInfo>,
+    /// The layer now in the manifest; `None` when the edit removed it
+    pub new: Option<LayerInfo>,
+    pub manifest: PathBuf,
+    /// Copy of the manifest as it was, if it changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<ManifestBackup>,
+}
+
+/// Replace the layers of `kind` in the manifest of `id` with a single layer holding
+/// `content` (at the position of the first one, or appended), or drop them when
+/// `content` is `None`.
+///
+/// The blob is written before the manifest, so the manifest never references something
+/// missing, and the manifest write keeps a backup (see
+/// [`ManifestStore::write_manifest`]). Old blobs are left in place; pass their digests
+/// to [`crate::remove_unreferenced`] to delete the ones nothing else uses.
+pub fn set_layer(
+    manifests: &ManifestStore,
+    blobs: &BlobStore,
+    id: &ModelId,
+    kind: LayerKind,
+    content: Option<&[u8]>,
+) -> Result<LayerEdit> {
+    let stored = manifests
+        .find(id)
+        .ok_or_else(|| Error::ModelNotFound(id.normalize()))?;
+    let mut data = stored.data;
+
+    let new = match content {
+        Some(bytes) => Some(LayerInfo {
+            digest: blobs.write_blob(bytes)?,
+            media_type: MediaType::new(kind.media_type()),
+            size: Some(bytes.len() as u64),
+        }),
+        None => None,
+    };
+    let is_kind = |l: &LayerInfo| LayerKind::from_media_type(l.media_type.as_str()) == kind;
+    let position = data.layers.iter().position(is_kind);
+    let old: Vec<LayerInfo> = data.layers.iter().filter(|l| is_kind(l)).cloned().collect();
+    data.layers.retain(|l| !is_kind(l));
+    if let Some(layer) = &new {
+        let at = position.unwrap_or(data.layers.len());
+        data.layers.insert(at, layer.clone());
+    }
+
+    let body = serde_json::to_vec(&data).map_err(|source| Error::Json {
+        path: stored.path.clone(),
+        source,
+    })?;
+    let backup = manifests.write_manifest(&stored.path, &body)?;
+    Ok(LayerEdit {
+        model: stored.id.normalize(),
+        kind,
+        old,
+        new,
+        manifest: stored.path,
+        backup,
+    })
+}
+
+/// Copy the manifest of `from` byte for byte to `to`, so both names share every blob
+/// (no extra disk) and the same manifest digest. An existing, different manifest at
+/// `to` is backed up first. Returns the new manifest path.
+pub fn clone_model(manifests: &ManifestStore, from: &ModelId, to: &ModelId) -> Result<PathBuf> {
+    let stored = manifests
+        .find(from)
+        .ok_or_else(|| Error::ModelNotFound(from.normalize()))?;
+    let body = std::fs::read(&stored.path).map_err(|source| Error::Io {
+        path: stored.path.clone(),
+        source,
+    })?;
+    let path = manifests.path_for(to);
+    manifests.write_manifest(&path, &body)?;
+    Ok(path)
+}
+
+/// What [`strip_layers`] wrote.
+#[derive(Debug, Clone, Serialize)]
+pub struct StrippedModel {
+    pub model: String,
+    /// Name the stripped manifest was written under (the model itself, or a new tag)
+    pub target: String,
+    pub removed: Vec<LayerInfo>,
+    pub manifest: PathBuf,
+    /// Copy of the manifest that was replaced, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<ManifestBackup>,
+}
+
+/// Write the manifest of `id` without its layers of the given kinds, in place or under
+/// `target` (e.g. the same model with another tag). Blobs are not touched, so the
+/// original keeps working when a new tag is written.
+pub fn strip_layers(
+    manifests: &ManifestStore,
+    id: &ModelId,
+    kinds: &[LayerKind],
+    target: Option<&ModelId>,
+) -> Result<StrippedModel> {
+    let stored = manifests
+        .find(id)
+        .ok_or_else(|| Error::ModelNotFound(id.normalize()))?;
+    let mut data = stored.data;
+    let (removed, kept) = data
+        .layers
+        .into_iter()
+        .partition(|l| kinds.contains(&LayerKind::from_media_type(l.media_type.as_str())));
+    data.layers = kept;
+
+    let path = match target {
+        Some(target) => manifests.path_for(target),
+        None => stored.path.clone(),
+    };
+    let body = serde_json::to_vec(&data).map_err(|source| Error::Json {
+        path: path.clone(),
+        source,
+    })?;
+    let backup = manifests.write_manifest(&path, &body)?;
+    Ok(StrippedModel {
+        model: stored.id.normalize(),
+        target: target.unwrap_or(&stored.id).normalize(),
+        removed,
+        manifest: path,
+        backup,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::FakeStore;
+
+    #[test]
+    fn test_set_layer() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Params, br#"{"temperature":0.8}"#)
+            .layer(LayerKind::License, b"license")
+            .build(dir.path())
+            .unwrap();
+        let (manifests, blobs) = (store.manifests(), store.blobs());
+        let id: ModelId = "llama3:8b".parse().unwrap();
+        let kinds = || -> Vec<LayerKind> {
+            let m = manifests.find(&id).unwrap();
+            m.data
+                .layers
+                .iter()
+                .map(|l| LayerKind::from_media_type(l.media_type.as_str()))
+                .collect()
+        };
+
+        let edit = set_layer(&manifests, &blobs, &id, LayerKind::Params, Some(b"{}")).unwrap();
+        assert_eq!(edit.old.len(), 1);
+        assert!(edit.backup.is_some());
+        let new = edit.new.unwrap();
+        assert_eq!(blobs.read_text(&new.digest, 16).unwrap(), "{}");
+        // Replaced in place.
+        assert_eq!(
+            kinds(),
+            [LayerKind::Weights, LayerKind::Params, LayerKind::License]
+        );
+
+        set_layer(
+            &manifests,
+            &blobs,
+            &id,
+            LayerKind::Template,
+            Some(b"{{ .Prompt }}"),
+        )
+        .unwrap();
+        set_layer(&manifests, &blobs, &id, LayerKind::License, None).unwrap();
+        assert_eq!(
+            kinds(),
+            [LayerKind::Weights, LayerKind::Params, LayerKind::Template]
+        );
+
+        let slim: ModelId = "llama3:8b-slim".parse().unwrap();
+        let stripped = strip_layers(
+            &manifests,
+            &id,
+            &[LayerKind::Params, LayerKind::Template],
+            Some(&slim),
+        )
+        .unwrap();
+        assert_eq!(stripped.removed.len(), 2);
+        assert!(stripped.backup.is_none());
+        assert_eq!(manifests.find(&slim).unwrap().data.layers.len(), 1);
+        assert_eq!(kinds().len(), 3);
+
+        let snapshot: ModelId = "llama3:snapshot".parse().unwrap();
+        let path = clone_model(&manifests, &id, &snapshot).unwrap();
+        assert_eq!(
+            std::fs::read(path).unwrap(),
+            std::fs::read(manifests.path_for(&id)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Estimating the memory a model needs to run, from its GGUF metadata, and whether that
+//! fits a GPU. The numbers follow llama.cpp's layout (weights, an f16 KV cache, and a
+//! compute buffer) but are estimates: runtimes differ by a few hundred MiB.
+
+use serde::Serialize;
+
+use crate::gguf::GgufMetadata;
+
+/// Fixed cost of a GPU runtime (CUDA/Metal context, scratch) on top of the model.
+pub const RUNTIME_OVERHEAD: u64 = 512 << 20;
+
+/// Bytes per element of the KV cache (f16, the default).
+const KV_BYTES_PER_ELEMENT: u64 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryEstimate {
+    /// Size of the weights file
+    pub weights: u64,
+    /// K and V cache for `context` tokens
+    pub kv_cache: u64,
+    /// Runtime overhead and compute buffer
+    pub overhead: u64,
+    pub total: u64,
+    pub context: u64,
+    /// Transformer blocks, the unit of partial offload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layers: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<u64>,
+    /// Average bits per parameter of the weights file (quantization level)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bits_per_weight: Option<f64>,
+}
+
+/// How a model fits in a given amount of VRAM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "fit", rename_all = "snake_case")]
+pub enum Fit {
+    /// Everything on the GPU
+    Full,
+    /// `gpu_layers` of `layers` on the GPU, the rest on the CPU (slower)
+    Partial { gpu_layers: u64, layers: u64 },
+    /// Not even one layer fits next to the runtime overhead
+    None,
+}
+
+impl MemoryEstimate {
+    /// Estimate for running a model whose weights file is `weights` bytes with a
+    /// context window of `context` tokens. Without the attention metadata the KV cache
+    /// is taken as zero.
+    #[must_use]
+    pub fn new(meta: &GgufMetadata, weights: u64, context: u64) -> Self {
+        let layers = meta.arch_u64("block_count");
+        let embedding = meta.arch_u64("embedding_length");
+        let heads = meta.arch_u64("attention.head_count");
+        // Grouped-query attention caches fewer heads; the key may be per-layer (an
+        // array), in which case all heads are assumed.
+        let kv_heads = meta.arch_u64("attention.head_count_kv").or(heads);
+        let head_dim = embedding.zip(heads).and_then(|(e, h)| e.checked_div(h));
+        let key_len = meta.arch_u64("attention.key_length").or(head_dim);
+        let value_len = meta.arch_u64("attention.value_length").or(head_dim);
+        let kv_cache = match (layers, kv_heads, key_len, value_len) {
+            (Some(l), Some(h), Some(k), Some(v)) => {
+                l * context * h * (k + v) * KV_BYTES_PER_ELEMENT
+            }
+            _ => 0,
+        };
+        // Activations for a batch, roughly one f32 row per token of context.
+        let compute = embedding.map_or(0, |e| e * context * 4);
+        let overhead = RUNTIME_OVERHEAD + compute;
+        let parameters = meta.parameter_count();
+        #[allow(clippy::cast_precision_loss)]
+        let bits_per_weight = parameters
+            .filter(|&p| p > 0)
+            .map(|p| weights as f64 * 8.0 / p as f64);
+        MemoryEstimate {
+            weights,
+            kv_cache,
+            overhead,
+            total: weights + kv_cache + overhead,
+            context,
+            layers,
+            parameters,
+            bits_per_weight,
+        }
+    }
+
+    /// Where the model would run with `vram` bytes of GPU memory. Partial offload puts
+    /// whole layers (their weights and KV cache) on the GPU.
+    #[must_use]
+    pub fn fit(&self, vram: u64) -> Fit {
+        if self.total <= vram {
+            return Fit::Full;
+        }
+        let Some(layers) = self.layers.filter(|&l| l > 0) else {
+            return Fit::None;
+        };
+        let per_layer = (self.weights + self.kv_cache).div_ceil(layers);
+        let gpu_layers = (vram.saturating_sub(self.overhead) / per_layer.max(1)).min(layers);
+        if gpu_layers == 0 {
+            Fit::None
+        } else {
+            Fit::Partial { gpu_layers, layers }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::gguf::GgufValue;
+
+    #[test]
+    fn test_estimate_and_fit() {
+        // Llama 3 8B's shape: 32 layers, 4096 wide, 32 heads of which 8 are cached.
+        let metadata: BTreeMap<String, GgufValue> = [
+            ("general.architecture", GgufValue::String("llama".into())),
+            ("general.size_label", GgufValue::String("8B".into())),
+            ("llama.block_count", GgufValue::Uint(32)),
+            ("llama.embedding_length", GgufValue::Uint(4096)),
+            ("llama.attention.head_count", GgufValue::Uint(32)),
+            ("llama.attention.head_count_kv", GgufValue::Uint(8)),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+        let meta = GgufMetadata {
+            version: 3,
+            tensor_count: 0,
+            metadata,
+        };
+        let gib = 1u64 << 30;
+        let weights = 4_920_000_000;
+        let estimate = MemoryEstimate::new(&meta, weights, 8192);
+        // 32 layers * 8192 tokens * 8 heads * (128 + 128) * 2 bytes = 1 GiB.
+        assert_eq!(estimate.kv_cache, gib);
+        assert_eq!(estimate.parameters, Some(8_000_000_000));
+        assert!((estimate.bits_per_weight.unwrap() - 4.92).abs() < 0.01);
+
+        assert_eq!(estimate.fit(24 * gib), Fit::Full);
+        assert!(matches!(
+            estimate.fit(4 * gib),
+            Fit::Partial { gpu_layers, layers: 32 } if gpu_layers > 0 && gpu_layers < 32
+        ));
+        assert_eq!(estimate.fit(gib / 4), Fit::None);
+    }
+}
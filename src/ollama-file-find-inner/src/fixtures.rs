@@ -0,0 +1,247 @@
+//! Builders for small, realistic model stores on disk, for tests here and in downstream
+//! crates (feature `test-util`).
+//!
+//! ```
+//! use ollama_file_find::{LayerKind, fixtures::FakeStore};
+//!
+//! let dir = tempfile::tempdir().unwrap();
+//! let store = FakeStore::builder()
+//!     .model("llama3:8b")
+//!     .layer(LayerKind::Weights, b"GGUF...")
+//!     .layer(LayerKind::Template, "{{ .Prompt }}")
+//!     .model("phi4")
+//!     .layer(LayerKind::Weights, b"GGUF")
+//!     .orphan(b"left over")
+//!     .build(dir.path())
+//!     .unwrap();
+//! assert!(store.manifest_path("llama3:8b").is_file());
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    BlobStore, Digest, Error, LayerInfo, LayerKind, ManifestData, ManifestStore, MediaType,
+    ModelId, Result, hash::sha256_digest, write_atomic,
+};
+
+/// Config blob written for models that don't set one.
+const DEFAULT_CONFIG: &str =
+    r#"{"model_format":"gguf","model_family":"llama","architecture":"amd64","os":"linux"}"#;
+
+/// A models directory written by [`FakeStoreBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct FakeStore {
+    root: PathBuf,
+}
+
+impl FakeStore {
+    #[must_use]
+    pub fn builder() -> FakeStoreBuilder {
+        FakeStoreBuilder::default()
+    }
+
+    /// The models directory (parent of `manifests/` and `blobs/`).
+    #[must_use]
+    pub fn models_dir(&self) -> &Path {
+        &self.root
+    }
+
+    #[must_use]
+    pub fn manifests(&self) -> ManifestStore {
+        ManifestStore::new(self.root.join("manifests"))
+    }
+
+    #[must_use]
+    pub fn blobs(&self) -> BlobStore {
+        BlobStore::new(self.root.join("blobs"))
+    }
+
+    /// Manifest file of a model added to the builder.
+    ///
+    /// # Panics
+    /// If `name` is not a valid model name.
+    #[must_use]
+    pub fn manifest_path(&self, name: &str) -> PathBuf {
+        let id: ModelId = name.parse().expect("valid model name");
+        self.manifests().path_for(&id)
+    }
+
+    /// Blob file holding `content`, as written by the builder.
+    #[must_use]
+    pub fn blob_path(&self, content: impl AsRef<[u8]>) -> PathBuf {
+        self.blobs().path_for(&sha256_digest(content.as_ref()))
+    }
+}
+
+#[derive(Debug, Default)]
+struct FakeModel {
+    name: String,
+    layers: Vec<(LayerKind, Vec<u8>)>,
+    config: Option<Vec<u8>>,
+}
+
+/// Collects models, layers, and stray blobs; nothing touches the disk until
+/// [`FakeStoreBuilder::build`]. Blob digests are the real SHA-256 of their content, and
+/// layers with equal content share one blob, as in a real store.
+#[derive(Debug, Default)]
+pub struct FakeStoreBuilder {
+    models: Vec<FakeModel>,
+    orphans: Vec<Vec<u8>>,
+}
+
+impl FakeStoreBuilder {
+    /// Start a model; following [`layer`](Self::layer) and [`config`](Self::config) calls
+    /// apply to it. Names are parsed like user input (`phi4` is `phi4:latest`).
+    #[must_use]
+    pub fn model(mut self, name: &str) -> Self {
+        self.models.push(FakeModel {
+            name: name.to_string(),
+            ..FakeModel::default()
+        });
+        self
+    }
+
+    /// Add a layer to the current model.
+    ///
+    /// # Panics
+    /// If no model has been started.
+    #[must_use]
+    pub fn layer(mut self, kind: LayerKind, content: impl AsRef<[u8]>) -> Self {
+        self.current()
+            .layers
+            .push((kind, content.as_ref().to_vec()));
+        self
+    }
+
+    /// Replace the current model's config blob (a small generic one by default).
+    ///
+    /// # Panics
+    /// If no model has been started.
+    #[must_use]
+    pub fn config(mut self, content: impl AsRef<[u8]>) -> Self {
+        self.current().config = Some(content.as_ref().to_vec());
+        self
+    }
+
+    /// A blob no manifest references.
+    #[must_use]
+    pub fn orphan(mut self, content: impl AsRef<[u8]>) -> Self {
+        self.orphans.push(content.as_ref().to_vec());
+        self
+    }
+
+    fn current(&mut self) -> &mut FakeModel {
+        self.models
+            .last_mut()
+            .expect("call FakeStoreBuilder::model before adding layers")
+    }
+
+    /// Write the store under `models_dir`, which is created if needed.
+    pub fn build(self, models_dir: impl AsRef<Path>) -> Result<FakeStore> {
+        let store = FakeStore {
+            root: models_dir.as_ref().to_path_buf(),
+        };
+        let (manifests, blobs) = (store.manifests(), store.blobs());
+        fs::create_dir_all(blobs.root()).map_err(|source| Error::Io {
+            path: blobs.root().to_path_buf(),
+            source,
+        })?;
+        for content in &self.orphans {
+            write_blob(&blobs, content)?;
+        }
+        for model in &self.models {
+            let id: ModelId = model.name.parse()?;
+            let layers = model
+                .layers
+                .iter()
+                .map(|(kind, content)| {
+                    Ok(layer(
+                        write_blob(&blobs, content)?,
+                        kind.media_type(),
+                        content,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let config = model.config.as_deref().unwrap_or(DEFAULT_CONFIG.as_bytes());
+            let config = layer(
+                write_blob(&blobs, config)?,
+                LayerKind::Config.media_type(),
+                config,
+            );
+
+            let path = manifests.path_for(&id);
+            let body =
+                serde_json::to_vec(&ManifestData::new(config, layers)).map_err(|source| {
+                    Error::Json {
+                        path: path.clone(),
+                        source,
+                    }
+                })?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|source| Error::Io {
+                    path: parent.to_path_buf(),
+                    source,
+                })?;
+            }
+            write_atomic(&path, &body)?;
+        }
+        Ok(store)
+    }
+}
+
+fn layer(digest: Digest, media_type: &str, content: &[u8]) -> LayerInfo {
+    LayerInfo {
+        digest,
+        media_type: MediaType::new(media_type),
+        size: Some(content.len() as u64),
+    }
+}
+
+fn write_blob(blobs: &BlobStore, content: &[u8]) -> Result<Digest> {
+    let digest = sha256_digest(content);
+    let path = blobs.path_for(&digest);
+    if !path.exists() {
+        write_atomic(&path, content)?;
+    }
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ScanArgs, gc_plan, scan_manifests, sha256_file};
+
+    #[test]
+    fn test_fake_store_builder() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights-a")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .model("hf.co/org/phi:q4")
+            .layer(LayerKind::Weights, b"weights-b")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .config(br#"{"model_family":"phi"}"#)
+            .orphan(b"stray")
+            .build(dir.path())
+            .unwrap();
+
+        let outcome = scan_manifests(
+            &ScanArgs::new(store.manifests().root(), store.blobs().root()).with_verbose(true),
+        );
+        let names: Vec<_> = outcome.models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["hf.co/org/phi:q4", "llama3:8b"]);
+        assert!(outcome.models.iter().all(|m| m.is_broken() == Some(false)));
+
+        let plan = gc_plan(&store.manifests(), &store.blobs()).unwrap();
+        // Two weights, one shared template, the default and the custom config.
+        assert_eq!(plan.reachable.len(), 5);
+        assert_eq!(plan.unreachable.len(), 1);
+        let stray = store.blob_path(b"stray");
+        assert_eq!(plan.unreachable[0].path, stray);
+        assert_eq!(sha256_file(&stray).unwrap(), plan.unreachable[0].digest);
+    }
+}
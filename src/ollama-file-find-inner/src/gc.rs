@@ -0,0 +1,389 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    time::Duration,
+};
+
+use crate::{
+    BlobEntry, BlobStore, Digest, Error, HiddenPolicy, ManifestStore, Result, StrayFile,
+    models::ManifestDataRef,
+};
+
+/// Result of marking every digest referenced by a manifest and sweeping the blob store.
+///
+/// Nothing is deleted until [`GcPlan::execute`] is called, so callers can present the
+/// plan for confirmation first.
+#[derive(Debug)]
+pub struct GcPlan {
+    /// Blobs referenced by at least one manifest (hidden tags included)
+    pub reachable: Vec<BlobEntry>,
+    /// Blobs only a manifest backup under [`crate::BACKUP_DIR`] still references. Kept
+    /// unless [`GcPlan::release_held`] is called
+    pub held: Vec<BlobEntry>,
+    /// Blobs only a pin (see [`ManifestStore::pin`]) still references. Always kept;
+    /// unpin the model to let them go
+    pub pinned: Vec<BlobEntry>,
+    /// Blobs nothing points to; these are what [`GcPlan::execute`] deletes
+    pub unreachable: Vec<BlobEntry>,
+    /// Unreachable blobs kept by [`GcPlan::retain_newer_than`]
+    pub retained: Vec<BlobEntry>,
+    /// Digests referenced by a manifest but absent from the blob store
+    pub missing: Vec<Digest>,
+    /// Files in the blobs directory that aren't complete blobs (partial downloads and
+    /// anything else); not deleted unless moved to `partials`
+    pub stray: Vec<StrayFile>,
+    /// Partial downloads [`GcPlan::execute`] deletes too; see
+    /// [`GcPlan::sweep_partials_older_than`]
+    pub partials: Vec<StrayFile>,
+    /// Models (hidden tags included) referencing each digest in `reachable` and
+    /// `missing`, sorted by name; see [`GcPlan::referrers`]
+    pub references: HashMap<Digest, Vec<String>>,
+    /// Manifests that could not be read; any of them may reference "unreachable" blobs
+    pub errors: Vec<Error>,
+}
+
+/// Outcome of [`GcPlan::execute`].
+#[derive(Debug, Default)]
+pub struct GcReport {
+    pub removed: Vec<BlobEntry>,
+    pub removed_partials: Vec<StrayFile>,
+    /// Bytes of the removed blobs and partial downloads
+    pub freed_bytes: u64,
+    /// Removal failures; the corresponding blobs are left in place
+    pub errors: Vec<Error>,
+}
+
+/// Delete those of `digests` that no manifest (hidden tags included) or recovery copy
+/// references, e.g. the layers an edit just replaced, and return the ones removed.
+/// Blobs still held by a backup or a pin are left alone. Refuses, like
+/// [`GcPlan::execute`], when any manifest could not be read.
+pub fn remove_unreferenced(
+    manifests: &ManifestStore,
+    blobs: &BlobStore,
+    digests: &[Digest],
+) -> Result<Vec<Digest>> {
+    let (marked, errors) = mark(manifests);
+    if !errors.is_empty() {
+        return Err(Error::GcUnsafe(errors.len()));
+    }
+    let (pinned, held) = mark_held(manifests)?;
+    let mut removed = Vec::new();
+    for digest in digests {
+        let path = blobs.path_for(digest);
+        if marked.contains_key(digest)
+            || held.contains(digest)
+            || pinned.contains(digest)
+            || removed.contains(digest)
+            || !path.is_file()
+        {
+            continue;
+        }
+        fs::remove_file(&path).map_err(|source| Error::Io { path, source })?;
+        removed.push(*digest);
+    }
+    Ok(removed)
+}
+
+/// Mark every digest referenced by a manifest, then sweep the blob store into
+/// reachable, pinned, held (referenced only by backups), and unreachable sets.
+pub fn gc_plan(manifests: &ManifestStore, blobs: &BlobStore) -> Result<GcPlan> {
+    let (references, errors) = mark(manifests);
+    let (pinned_digests, held_digests) = mark_held(manifests)?;
+
+    let mut marked: HashSet<Digest> = references.keys().copied().collect();
+    let mut reachable = Vec::new();
+    let mut held = Vec::new();
+    let mut pinned = Vec::new();
+    let mut unreachable = Vec::new();
+    for blob in blobs.blobs()? {
+        if marked.remove(&blob.digest) {
+            reachable.push(blob);
+        } else if pinned_digests.contains(&blob.digest) {
+            pinned.push(blob);
+        } else if held_digests.contains(&blob.digest) {
+            held.push(blob);
+        } else {
+            unreachable.push(blob);
+        }
+    }
+    let mut missing: Vec<Digest> = marked.into_iter().collect();
+    missing.sort_unstable();
+
+    Ok(GcPlan {
+        reachable,
+        held,
+        pinned,
+        unreachable,
+        retained: Vec::new(),
+        missing,
+        stray: blobs.stray_files()?,
+        partials: Vec::new(),
+        references,
+        errors,
+    })
+}
+
+/// Digests referenced by any manifest, with the models referencing them. Hidden tags
+/// still hold on to their blobs, so they are always marked.
+fn mark(manifests: &ManifestStore) -> (HashMap<Digest, Vec<String>>, Vec<Error>) {
+    let mut marked: HashMap<Digest, Vec<String>> = HashMap::new();
+    let errors = manifests.visit(HiddenPolicy::Include, |id, m| {
+        let name = id.normalize();
+        for layer in m.entries() {
+            let models = marked.entry(layer.digest()?).or_default();
+            if models.last() != Some(&name) {
+                models.push(name.clone());
+            }
+        }
+        Ok(())
+    });
+    for models in marked.values_mut() {
+        models.sort_unstable();
+    }
+    (marked, errors)
+}
+
+/// Digests referenced by pins and the manifest copies under [`crate::PIN_DIR`], then
+/// those referenced by the backups under [`crate::BACKUP_DIR`]. Copies that don't parse
+/// are skipped rather than reported: they could not be restored anyway. An unreadable
+/// pin list is an error, since the blobs it pins would otherwise look unreachable.
+fn mark_held(manifests: &ManifestStore) -> Result<(HashSet<Digest>, HashSet<Digest>)> {
+    let mut pinned: HashSet<Digest> = manifests
+        .pins()?
+        .into_iter()
+        .flat_map(|p| p.digests)
+        .collect();
+    let mut held = HashSet::new();
+    for (dir, marked) in [
+        (crate::PIN_DIR, &mut pinned),
+        (crate::BACKUP_DIR, &mut held),
+    ] {
+        let files = walkdir::WalkDir::new(manifests.root().join(dir))
+            .follow_links(false)
+            .into_iter()
+            .filter_map(std::result::Result::ok)
+            .filter(|e| e.file_type().is_file());
+        for entry in files {
+            let Ok(body) = fs::read(entry.path()) else {
+                continue;
+            };
+            let Ok(m) = crate::parse_manifest::<ManifestDataRef>(entry.path(), &body) else {
+                continue;
+            };
+            marked.extend(m.entries().filter_map(|l| l.digest().ok()));
+        }
+    }
+    Ok((pinned, held))
+}
+
+impl GcPlan {
+    /// Models referencing `digest`, sorted by name; empty for held and unreachable blobs.
+    #[must_use]
+    pub fn referrers(&self, digest: &Digest) -> &[String] {
+        self.references.get(digest).map_or(&[], Vec::as_slice)
+    }
+
+    /// Total bytes held by reachable blobs.
+    #[must_use]
+    pub fn reachable_bytes(&self) -> u64 {
+        self.reachable.iter().map(|b| b.size).sum()
+    }
+
+    /// Total bytes held by blobs only backups reference.
+    #[must_use]
+    pub fn held_bytes(&self) -> u64 {
+        self.held.iter().map(|b| b.size).sum()
+    }
+
+    /// Total bytes of the blobs only pins reference.
+    #[must_use]
+    pub fn pinned_bytes(&self) -> u64 {
+        self.pinned.iter().map(|b| b.size).sum()
+    }
+
+    /// Move the held blobs to `unreachable`, so [`GcPlan::execute`] deletes them too
+    /// (and the backups referencing them can no longer be restored whole). Pinned blobs
+    /// stay.
+    pub fn release_held(&mut self) {
+        self.unreachable.append(&mut self.held);
+    }
+
+    /// Total bytes of the stray files.
+    #[must_use]
+    pub fn stray_bytes(&self) -> u64 {
+        self.stray.iter().map(|f| f.size).sum()
+    }
+
+    /// Total bytes that [`GcPlan::execute`] would free.
+    #[must_use]
+    pub fn unreachable_bytes(&self) -> u64 {
+        self.unreachable.iter().map(|b| b.size).sum::<u64>()
+            + self.partials.iter().map(|f| f.size).sum::<u64>()
+    }
+
+    /// Move partial downloads not written to for `min_age` before `now` (seconds since
+    /// the epoch) from `stray` to `partials`, so [`GcPlan::execute`] deletes them. Ollama
+    /// resumes a pull from its partial files, so a download is only swept when none of
+    /// its files (`-partial`, `-partial-0`, …) is recent; files without a known mtime
+    /// count as recent.
+    pub fn sweep_partials_older_than(&mut self, min_age: Duration, now: u64) {
+        let cutoff = now.saturating_sub(min_age.as_secs());
+        let active: HashSet<Digest> = self
+            .stray
+            .iter()
+            .filter(|f| f.mtime.is_none_or(|m| m > cutoff))
+            .filter_map(|f| f.partial_of)
+            .collect();
+        let (sweep, keep): (Vec<_>, Vec<_>) = std::mem::take(&mut self.stray)
+            .into_iter()
+            .partition(|f| f.partial_of.is_some_and(|d| !active.contains(&d)));
+        self.stray = keep;
+        self.partials.extend(sweep);
+    }
+
+    /// Move unreachable blobs modified less than `min_age` before `now` (seconds since
+    /// the epoch) to `retained`: they may belong to a pull in progress or to a model
+    /// removed by mistake. Blobs without a known mtime are retained too.
+    pub fn retain_newer_than(&mut self, min_age: Duration, now: u64) {
+        let cutoff = now.saturating_sub(min_age.as_secs());
+        let (keep, sweep): (Vec<_>, Vec<_>) = std::mem::take(&mut self.unreachable)
+            .into_iter()
+            .partition(|b| b.mtime.is_none_or(|m| m > cutoff));
+        self.retained.extend(keep);
+        self.unreachable = sweep;
+    }
+
+    /// Delete every unreachable blob, and the partial downloads in `partials`.
+    ///
+    /// Refuses to run when any manifest failed to parse during marking, since its
+    /// references are unknown and the sweep could remove live data.
+    pub fn execute(self) -> Result<GcReport> {
+        if !self.errors.is_empty() {
+            return Err(Error::GcUnsafe(self.errors.len()));
+        }
+        let mut report = GcReport::default();
+        for blob in self.unreachable {
+            match fs::remove_file(&blob.path) {
+                Ok(()) => {
+                    report.freed_bytes += blob.size;
+                    report.removed.push(blob);
+                }
+                Err(source) => report.errors.push(Error::Io {
+                    path: blob.path,
+                    source,
+                }),
+            }
+        }
+        for partial in self.partials {
+            match fs::remove_file(&partial.path) {
+                Ok(()) => {
+                    report.freed_bytes += partial.size;
+                    report.removed_partials.push(partial);
+                }
+                Err(source) => report.errors.push(Error::Io {
+                    path: partial.path,
+                    source,
+                }),
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gc_plan_marks_hidden_and_sweeps_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifests = dir.path().join("manifests");
+        let blobs = dir.path().join("blobs");
+        let live = format!("sha256:{}", "a".repeat(64));
+        let hidden = format!("sha256:{}", "b".repeat(64));
+        let orphan = format!("sha256:{}", "c".repeat(64));
+        let backed_up = format!("sha256:{}", "d".repeat(64));
+        for (path, digest) in [
+            ("library/m/latest", &live),
+            ("library/m/.staging", &hidden),
+            (".bak/library/m/latest/1760000000", &backed_up),
+        ] {
+            let path = manifests.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let body = format!(
+                r#"{{"layers":[{{"digest":"{digest}","mediaType":"application/vnd.ollama.image.model","size":1}}]}}"#
+            );
+            fs::write(path, body).unwrap();
+        }
+        fs::create_dir_all(&blobs).unwrap();
+        for digest in [&live, &hidden, &orphan, &backed_up] {
+            fs::write(crate::digest_to_blob_path(&blobs, digest), b"x").unwrap();
+        }
+        let partial = format!("sha256-{}-partial-0", "e".repeat(64));
+        fs::write(blobs.join(&partial), b"half").unwrap();
+
+        let plan = gc_plan(&ManifestStore::new(&manifests), &BlobStore::new(&blobs)).unwrap();
+        assert_eq!(plan.reachable.len(), 2);
+        assert_eq!(plan.unreachable.len(), 1);
+        assert_eq!(plan.unreachable[0].digest.to_string(), orphan);
+        assert_eq!(plan.held[0].digest.to_string(), backed_up);
+        assert!(plan.missing.is_empty());
+        assert_eq!(plan.stray.len(), 1);
+        assert_eq!(plan.stray_bytes(), 4);
+        assert!(plan.stray[0].partial_of.is_some());
+        let partial_mtime = plan.stray[0].mtime.unwrap();
+        let live_digest: Digest = live.parse().unwrap();
+        assert_eq!(plan.referrers(&live_digest), ["m:latest"]);
+        assert!(plan.referrers(&plan.unreachable[0].digest).is_empty());
+
+        let now = plan.unreachable[0].mtime.unwrap();
+        let mut recent = gc_plan(&ManifestStore::new(&manifests), &BlobStore::new(&blobs)).unwrap();
+        recent.retain_newer_than(Duration::from_secs(3600), now);
+        assert!(recent.unreachable.is_empty());
+        assert_eq!(recent.retained.len(), 1);
+
+        let report = plan.execute().unwrap();
+        assert_eq!(report.freed_bytes, 1);
+        assert!(blobs.join(&partial).exists());
+        assert!(!crate::digest_to_blob_path(&blobs, &orphan).exists());
+        assert!(crate::digest_to_blob_path(&blobs, &live).exists());
+        assert!(crate::digest_to_blob_path(&blobs, &backed_up).exists());
+
+        let mut released =
+            gc_plan(&ManifestStore::new(&manifests), &BlobStore::new(&blobs)).unwrap();
+        released.release_held();
+        released.sweep_partials_older_than(Duration::from_secs(60), partial_mtime + 30);
+        assert!(released.partials.is_empty());
+        released.sweep_partials_older_than(Duration::from_secs(60), partial_mtime + 60);
+        assert_eq!(released.partials.len(), 1);
+        let report = released.execute().unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed_partials.len(), 1);
+        assert!(!crate::digest_to_blob_path(&blobs, &backed_up).exists());
+        assert!(!blobs.join(&partial).exists());
+    }
+
+    #[test]
+    fn test_corrupt_pins_remove_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::fixtures::FakeStore::builder()
+            .model("llama3:8b")
+            .layer(crate::LayerKind::Weights, b"weights")
+            .build(dir.path())
+            .unwrap();
+        let (manifests, blobs) = (store.manifests(), store.blobs());
+        let id: crate::ModelId = "llama3:8b".parse().unwrap();
+        manifests.pin(&id, false).unwrap();
+        fs::remove_file(store.manifest_path("llama3:8b")).unwrap();
+        fs::write(
+            manifests.root().join(crate::PIN_DIR).join("pins.json"),
+            b"{ not json",
+        )
+        .unwrap();
+
+        assert!(gc_plan(&manifests, &blobs).is_err());
+        let weights = crate::hash::sha256_digest(b"weights");
+        assert!(remove_unreferenced(&manifests, &blobs, &[weights]).is_err());
+        assert!(store.blob_path(b"weights").exists());
+    }
+}
@@ -0,0 +1,628 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{Error, Result};
+
+/// First four bytes of every GGUF file.
+pub const GGUF_MAGIC: [u8; 4] = *b"GGUF";
+
+/// Strings longer than this are treated as corruption rather than allocated.
+const MAX_STRING_LEN: u64 = 16 * 1024 * 1024;
+
+/// How much of a file [`read_gguf_file`] reads at most looking for the end of the
+/// metadata. The largest vocabularies (token and merge arrays) take a few tens of MiB.
+pub const DEFAULT_HEADER_LIMIT: u64 = 256 << 20;
+
+/// Size of a [`HeaderReader`]'s first read; each later one doubles, up to
+/// [`MAX_HEADER_READ`].
+const FIRST_HEADER_READ: usize = 1 << 20;
+const MAX_HEADER_READ: usize = 16 << 20;
+
+/// How much of a file [`GgufSummary::read`] reads at most: one [`HeaderReader`] read.
+const SUMMARY_READ_LIMIT: u64 = FIRST_HEADER_READ as u64;
+
+/// A metadata value. Arrays (token lists, merges) are skipped and only their length
+/// kept, since they can be megabytes long and are rarely what callers want.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum GgufValue {
+    Uint(u64),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Array { len: u64 },
+}
+
+/// Header and key/value metadata of a GGUF file (tensor infos are not read).
+#[derive(Debug, Clone, Serialize)]
+pub struct GgufMetadata {
+    pub version: u32,
+    pub tensor_count: u64,
+    pub metadata: BTreeMap<String, GgufValue>,
+}
+
+impl GgufMetadata {
+    #[must_use]
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.metadata.get(key)? {
+            GgufValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        match *self.metadata.get(key)? {
+            GgufValue::Uint(n) => Some(n),
+            GgufValue::Int(n) => u64::try_from(n).ok(),
+            _ => None,
+        }
+    }
+
+    /// `general.architecture`, e.g. `llama`.
+    #[must_use]
+    pub fn architecture(&self) -> Option<&str> {
+        self.get_str("general.architecture")
+    }
+
+    /// Human name of the model (`general.name`, else `general.basename`).
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.get_str("general.name")
+            .or_else(|| self.get_str("general.basename"))
+    }
+
+    /// Quantization of the weights named from `general.file_type` the way llama.cpp and
+    /// Ollama name it (`Q4_K_M`, `F16`); `None` when absent or a type this crate does
+    /// not know.
+    #[must_use]
+    pub fn file_type(&self) -> Option<&'static str> {
+        Some(match self.get_u64("general.file_type")? {
+            0 => "F32",
+            1 => "F16",
+            2 => "Q4_0",
+            3 => "Q4_1",
+            4 => "Q4_1_F16",
+            7 => "Q8_0",
+            8 => "Q5_0",
+            9 => "Q5_1",
+            10 => "Q2_K",
+            11 => "Q3_K_S",
+            12 => "Q3_K_M",
+            13 => "Q3_K_L",
+            14 => "Q4_K_S",
+            15 => "Q4_K_M",
+            16 => "Q5_K_S",
+            17 => "Q5_K_M",
+            18 => "Q6_K",
+            19 => "IQ2_XXS",
+            20 => "IQ2_XS",
+            21 => "Q2_K_S",
+            22 => "IQ3_XS",
+            23 => "IQ3_XXS",
+            24 => "IQ1_S",
+            25 => "IQ4_NL",
+            26 => "IQ3_S",
+            27 => "IQ3_M",
+            28 => "IQ2_S",
+            29 => "IQ2_M",
+            30 => "IQ4_XS",
+            31 => "IQ1_M",
+            32 => "BF16",
+            33 => "Q4_0_4_4",
+            34 => "Q4_0_4_8",
+            35 => "Q4_0_8_8",
+            36 => "TQ1_0",
+            37 => "TQ2_0",
+            _ => return None,
+        })
+    }
+
+    /// `<arch>.context_length`.
+    #[must_use]
+    pub fn context_length(&self) -> Option<u64> {
+        self.arch_u64("context_length")
+    }
+
+    /// An architecture-specific number, e.g. `block_count` for `llama.block_count`.
+    #[must_use]
+    pub fn arch_u64(&self, key: &str) -> Option<u64> {
+        self.get_u64(&format!("{}.{key}", self.architecture()?))
+    }
+
+    /// Number of parameters: `general.parameter_count` when present, else read from
+    /// `general.size_label` (`8B`, `135M`, `8x7B`).
+    #[must_use]
+    pub fn parameter_count(&self) -> Option<u64> {
+        if let Some(n) = self.get_u64("general.parameter_count") {
+            return Some(n);
+        }
+        let label = self.get_str("general.size_label")?.trim();
+        let (experts, size) = match label.split_once(['x', 'X']) {
+            Some((experts, size)) => (experts.parse::<f64>().ok()?, size),
+            None => (1.0, label),
+        };
+        let unit = match size.chars().last()?.to_ascii_uppercase() {
+            'K' => 1e3,
+            'M' => 1e6,
+            'B' => 1e9,
+            'T' => 1e12,
+            _ => return None,
+        };
+        let n: f64 = size[..size.len() - 1].parse().ok()?;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some((experts * n * unit) as u64)
+    }
+}
+
+/// The header fields that tell local models apart when deciding which to keep.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GgufSummary {
+    /// `general.architecture`, e.g. `llama`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub architecture: Option<String>,
+    /// See [`GgufMetadata::parameter_count`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter_count: Option<u64>,
+    /// See [`GgufMetadata::file_type`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<String>,
+    /// Context window the model was trained with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+}
+
+impl GgufSummary {
+    #[must_use]
+    pub fn from_metadata(meta: &GgufMetadata) -> Self {
+        GgufSummary {
+            architecture: meta.architecture().map(str::to_string),
+            parameter_count: meta.parameter_count(),
+            quantization: meta.file_type().map(str::to_string),
+            context_length: meta.context_length(),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.architecture.is_some()
+            && self.parameter_count.is_some()
+            && self.quantization.is_some()
+            && self.context_length.is_some()
+    }
+
+    /// Whether `key` can hold a summary field: `general.*`, or `<arch>.*` once the
+    /// architecture is known.
+    fn is_summary_key(meta: &GgufMetadata, key: &str) -> bool {
+        key.starts_with("general.")
+            || meta
+                .architecture()
+                .and_then(|arch| key.strip_prefix(arch))
+                .is_some_and(|rest| rest.starts_with('.'))
+    }
+
+    /// Read the summary from a GGUF file. Reading stops once every field is known or at
+    /// the first key that can't hold one (llama.cpp's converters write the `general.`
+    /// and architecture keys before the tokenizer vocabulary), and never goes past the
+    /// first read of a [`HeaderReader`]. `None` when the file is not a readable GGUF
+    /// file or its header has none of the fields.
+    #[must_use]
+    pub fn read(path: &Path) -> Option<Self> {
+        let file = fs::File::open(path).ok()?;
+        let meta = read_gguf_until(HeaderReader::new(file, SUMMARY_READ_LIMIT), |meta, key| {
+            !GgufSummary::from_metadata(meta).is_complete()
+                && GgufSummary::is_summary_key(meta, key)
+        })
+        .ok()?;
+        let summary = GgufSummary::from_metadata(&meta);
+        (summary != GgufSummary::default()).then_some(summary)
+    }
+}
+
+/// Whether `bytes` start with the GGUF magic.
+#[must_use]
+pub fn is_gguf(bytes: &[u8]) -> bool {
+    bytes.starts_with(&GGUF_MAGIC)
+}
+
+/// Read the GGUF header and metadata from a file, without touching tensor data and
+/// without reading more than [`DEFAULT_HEADER_LIMIT`] bytes.
+pub fn read_gguf_file(path: &Path) -> Result<GgufMetadata> {
+    let file = fs::File::open(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    read_gguf(HeaderReader::new(file, DEFAULT_HEADER_LIMIT))
+}
+
+/// Reader over the start of a file for [`read_gguf`] that fetches it in a few large
+/// reads (1 MiB first, each next one twice as large, up to 16 MiB) rather than many
+/// small ones, and fails once `limit` bytes have been fetched. On network filesystems
+/// such as SSHFS every read is a round trip, so the metadata of a 40 GB model comes in
+/// one or two requests, and a damaged header can't walk the parser into tensor data.
+pub struct HeaderReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+    fetched: u64,
+    next_read: usize,
+    limit: u64,
+}
+
+impl<R: Read> HeaderReader<R> {
+    pub fn new(inner: R, limit: u64) -> Self {
+        HeaderReader {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+            fetched: 0,
+            next_read: FIRST_HEADER_READ,
+            limit,
+        }
+    }
+
+    /// Bytes fetched from the underlying reader so far.
+    #[must_use]
+    pub fn bytes_fetched(&self) -> u64 {
+        self.fetched
+    }
+
+    fn refill(&mut self) -> io::Result<()> {
+        let left = self.limit.saturating_sub(self.fetched);
+        if left == 0 {
+            return Err(io::Error::other(format!(
+                "GGUF metadata extends past the first {} bytes",
+                self.limit
+            )));
+        }
+        let want = usize::try_from(left).map_or(self.next_read, |l| l.min(self.next_read));
+        self.buf.resize(want, 0);
+        let mut got = 0;
+        while got < want {
+            match self.inner.read(&mut self.buf[got..]) {
+                Ok(0) => break,
+                Ok(n) => got += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.buf.truncate(got);
+        self.pos = 0;
+        self.fetched += got as u64;
+        self.next_read = (self.next_read * 2).min(MAX_HEADER_READ);
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for HeaderReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            self.refill()?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Read the GGUF header and metadata from the start of a stream.
+pub fn read_gguf(reader: impl Read) -> Result<GgufMetadata> {
+    read_gguf_until(reader, |_, _| true)
+}
+
+/// [`read_gguf`], but stopping early with the metadata read so far when `more`, given
+/// the next key before its value is read, says it is not needed.
+fn read_gguf_until(
+    reader: impl Read,
+    more: impl Fn(&GgufMetadata, &str) -> bool,
+) -> Result<GgufMetadata> {
+    let mut r = Reader {
+        inner: reader,
+        version: 0,
+    };
+    let mut magic = [0u8; 4];
+    r.fill(&mut magic)?;
+    if magic != GGUF_MAGIC {
+        return Err(Error::Gguf("not a GGUF file (bad magic)".to_string()));
+    }
+    r.version = r.u32()?;
+    if !(1..=3).contains(&r.version) {
+        return Err(Error::Gguf(format!(
+            "unsupported GGUF version {}",
+            r.version
+        )));
+    }
+    let tensor_count = r.count()?;
+    let kv_count = r.count()?;
+    let mut meta = GgufMetadata {
+        version: r.version,
+        tensor_count,
+        metadata: BTreeMap::new(),
+    };
+    for _ in 0..kv_count {
+        let key = r.string()?;
+        if !more(&meta, &key) {
+            break;
+        }
+        let ty = r.u32()?;
+        let value = r.value(ty)?;
+        meta.metadata.insert(key, value);
+    }
+    Ok(meta)
+}
+
+struct Reader<R> {
+    inner: R,
+    version: u32,
+}
+
+impl<R: Read> Reader<R> {
+    fn fill(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.read_exact(buf).map_err(|e| {
+            Error::Gguf(if e.kind() == io::ErrorKind::UnexpectedEof {
+                format!("truncated header: {e}")
+            } else {
+                e.to_string()
+            })
+        })
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.fill(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    /// Counts and lengths are 32-bit in version 1, 64-bit afterwards.
+    fn count(&mut self) -> Result<u64> {
+        if self.version == 1 {
+            self.u32().map(u64::from)
+        } else {
+            self.u64()
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.count()?;
+        if len > MAX_STRING_LEN {
+            return Err(Error::Gguf(format!("string of {len} bytes")));
+        }
+        let mut buf = vec![0u8; usize::try_from(len).unwrap_or(usize::MAX)];
+        self.fill(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    fn skip(&mut self, n: u64) -> Result<()> {
+        let copied = std::io::copy(&mut (&mut self.inner).take(n), &mut std::io::sink())
+            .map_err(|e| Error::Gguf(e.to_string()))?;
+        if copied == n {
+            Ok(())
+        } else {
+            Err(Error::Gguf("truncated header".to_string()))
+        }
+    }
+
+    fn value(&mut self, ty: u32) -> Result<GgufValue> {
+        Ok(match ty {
+            0 => GgufValue::Uint(self.bytes::<1>()?[0].into()),
+            1 => GgufValue::Int(i8::from_le_bytes(self.bytes()?).into()),
+            2 => GgufValue::Uint(u16::from_le_bytes(self.bytes()?).into()),
+            3 => GgufValue::Int(i16::from_le_bytes(self.bytes()?).into()),
+            4 => GgufValue::Uint(self.u32()?.into()),
+            5 => GgufValue::Int(i32::from_le_bytes(self.bytes()?).into()),
+            6 => GgufValue::Float(f32::from_le_bytes(self.bytes()?).into()),
+            7 => GgufValue::Bool(self.bytes::<1>()?[0] != 0),
+            8 => GgufValue::String(self.string()?),
+            9 => {
+                let elem = self.u32()?;
+                let len = self.count()?;
+                self.skip_array(elem, len)?;
+                GgufValue::Array { len }
+            }
+            10 => GgufValue::Uint(self.u64()?),
+            11 => GgufValue::Int(i64::from_le_bytes(self.bytes()?)),
+            12 => GgufValue::Float(f64::from_le_bytes(self.bytes()?)),
+            other => return Err(Error::Gguf(format!("unknown value type {other}"))),
+        })
+    }
+
+    fn skip_array(&mut self, elem: u32, len: u64) -> Result<()> {
+        let width = match elem {
+            0 | 1 | 7 => 1,
+            2 | 3 => 2,
+            4..=6 => 4,
+            10..=12 => 8,
+            _ => 0,
+        };
+        if width > 0 {
+            return self.skip(len.saturating_mul(width));
+        }
+        for _ in 0..len {
+            match elem {
+                8 => {
+                    let n = self.count()?;
+                    self.skip(n)?;
+                }
+                _ => {
+                    self.value(elem)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut out = (s.len() as u64).to_le_bytes().to_vec();
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    #[test]
+    fn test_read_gguf_metadata() {
+        let mut data = b"GGUF".to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&4u64.to_le_bytes());
+        data.extend(string("general.architecture"));
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend(string("llama"));
+        data.extend(string("llama.context_length"));
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&8192u32.to_le_bytes());
+        data.extend(string("tokenizer.ggml.tokens"));
+        data.extend_from_slice(&9u32.to_le_bytes());
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend_from_slice(&2u64.to_le_bytes());
+        data.extend(string("<s>"));
+        data.extend(string("</s>"));
+        data.extend(string("general.name"));
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend(string("Tiny Llama"));
+
+        let meta = read_gguf(data.as_slice()).unwrap();
+        assert_eq!(meta.architecture(), Some("llama"));
+        assert_eq!(meta.context_length(), Some(8192));
+        assert_eq!(meta.name(), Some("Tiny Llama"));
+        assert_eq!(
+            meta.metadata["tokenizer.ggml.tokens"],
+            GgufValue::Array { len: 2 }
+        );
+        assert!(read_gguf(&data[..20]).is_err());
+
+        let mut bounded = HeaderReader::new(data.as_slice(), 1 << 30);
+        assert_eq!(read_gguf(&mut bounded).unwrap().name(), Some("Tiny Llama"));
+        assert_eq!(bounded.bytes_fetched(), data.len() as u64);
+        let err = read_gguf(HeaderReader::new(data.as_slice(), 40)).unwrap_err();
+        assert!(err.to_string().contains("past the first 40 bytes"), "{err}");
+    }
+
+    #[test]
+    fn test_gguf_summary() {
+        let mut data = b"GGUF".to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&5u64.to_le_bytes());
+        data.extend(string("general.architecture"));
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend(string("llama"));
+        data.extend(string("general.size_label"));
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend(string("8B"));
+        data.extend(string("general.file_type"));
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&15u32.to_le_bytes());
+        data.extend(string("llama.context_length"));
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&8192u32.to_le_bytes());
+        // A vocabulary cut short: only reachable by reading past the summary fields.
+        data.extend(string("tokenizer.ggml.tokens"));
+        data.extend_from_slice(&9u32.to_le_bytes());
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend_from_slice(&1000u64.to_le_bytes());
+        assert!(read_gguf(data.as_slice()).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::fixtures::FakeStore::builder()
+            .model("llama3:8b")
+            .layer(crate::LayerKind::Weights, &data)
+            .build(dir.path())
+            .unwrap();
+        let expected = GgufSummary {
+            architecture: Some("llama".to_string()),
+            parameter_count: Some(8_000_000_000),
+            quantization: Some("Q4_K_M".to_string()),
+            context_length: Some(8192),
+        };
+        assert_eq!(
+            GgufSummary::read(&store.blob_path(&data)),
+            Some(expected.clone())
+        );
+        let outcome = crate::scan_manifests(
+            &crate::ScanArgs::new(
+                store.models_dir().join("manifests"),
+                store.models_dir().join("blobs"),
+            )
+            .with_verbose(true),
+        );
+        assert_eq!(outcome.models[0].gguf, Some(expected));
+    }
+
+    #[test]
+    fn test_gguf_summary_partial() {
+        let header = |kv_count: u64| {
+            let mut data = b"GGUF".to_vec();
+            data.extend_from_slice(&3u32.to_le_bytes());
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data.extend_from_slice(&kv_count.to_le_bytes());
+            data
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let summary = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, data).unwrap();
+            GgufSummary::read(&path)
+        };
+
+        // Cut off in the middle of the first key.
+        let mut truncated = header(2);
+        truncated.extend(string("general.architecture"));
+        truncated.truncate(truncated.len() - 4);
+        assert_eq!(summary("truncated", &truncated), None);
+
+        // Well-formed, but none of the fields.
+        let mut unrelated = header(1);
+        unrelated.extend(string("general.name"));
+        unrelated.extend_from_slice(&8u32.to_le_bytes());
+        unrelated.extend(string("Llama"));
+        assert_eq!(summary("unrelated", &unrelated), None);
+
+        // Reading stops at the vocabulary even when summary keys follow it, so the
+        // (here cut short) array is never read.
+        let mut partial = header(3);
+        partial.extend(string("general.architecture"));
+        partial.extend_from_slice(&8u32.to_le_bytes());
+        partial.extend(string("llama"));
+        partial.extend(string("tokenizer.ggml.tokens"));
+        partial.extend_from_slice(&9u32.to_le_bytes());
+        partial.extend_from_slice(&8u32.to_le_bytes());
+        partial.extend_from_slice(&1000u64.to_le_bytes());
+        assert_eq!(
+            summary("partial", &partial),
+            Some(GgufSummary {
+                architecture: Some("llama".to_string()),
+                ..GgufSummary::default()
+            })
+        );
+
+        // Nothing past the first read is fetched looking for the fields.
+        let mut padded = header(2);
+        padded.extend(string("general.description"));
+        padded.extend_from_slice(&8u32.to_le_bytes());
+        padded.extend(string(&"x".repeat(FIRST_HEADER_READ)));
+        padded.extend(string("general.architecture"));
+        padded.extend_from_slice(&8u32.to_le_bytes());
+        padded.extend(string("llama"));
+        assert_eq!(summary("padded", &padded), None);
+        assert!(read_gguf(padded.as_slice()).is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    fmt::Write,
+    fs, io,
+    path::Path,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+/// Lowercase hex encoding.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+}
+
+/// Digest of in-memory bytes.
+#[must_use]
+pub fn sha256_digest(bytes: &[u8]) -> crate::Digest {
+    crate::Digest::sha256(Sha256::digest(bytes).into())
+}
+
+/// Process-wide cap on hashed bytes per second; 0 means none.
+static HASH_RATE_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// When the next hashed chunk may start under [`HASH_RATE_LIMIT`].
+static NEXT_SLOT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Cap the combined throughput of [`sha256_file`] and [`VerifiedReader`] across all
+/// threads, e.g. to scrub a store on a machine that is serving inference; `None` lifts
+/// the cap.
+pub fn set_hash_rate_limit(bytes_per_sec: Option<u64>) {
+    HASH_RATE_LIMIT.store(bytes_per_sec.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Account for `n` hashed bytes, sleeping as long as needed to stay under the limit.
+fn throttle(n: usize) {
+    let limit = HASH_RATE_LIMIT.load(Ordering::Relaxed);
+    if limit == 0 || n == 0 {
+        return;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let cost = Duration::from_secs_f64(n as f64 / limit as f64);
+    let wait = {
+        let mut next = NEXT_SLOT.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let start = next.filter(|t| *t > now).unwrap_or(now);
+        *next = Some(start + cost);
+        start - now
+    };
+    std::thread::sleep(wait);
+}
+
+/// Reader that feeds [`throttle`].
+struct Throttled<R>(R);
+
+impl<R: io::Read> io::Read for Throttled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        throttle(n);
+        Ok(n)
+    }
+}
+
+/// Stream a file through SHA-256, returning its digest.
+pub fn sha256_file(path: &Path) -> Result<crate::Digest> {
+    let io_err = |source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut hasher = Sha256::new();
+    let file = fs::File::open(path).map_err(io_err)?;
+    io::copy(&mut Throttled(file), &mut hasher).map_err(io_err)?;
+    Ok(crate::Digest::sha256(hasher.finalize().into()))
+}
+
+/// Reader over a blob that hashes everything it yields and, at end of file, fails with
+/// [`io::ErrorKind::InvalidData`] (wrapping [`Error::DigestMismatch`]) unless the content
+/// matches the expected digest. Returned by [`crate::BlobStore::open_verified`].
+///
+/// Data is handed out before it can be checked, so consumers must treat everything
+/// read as provisional until they have seen end of file without an error.
+#[derive(Debug)]
+pub struct VerifiedReader<R = fs::File> {
+    inner: R,
+    hasher: Sha256,
+    expected: crate::Digest,
+    path: std::path::PathBuf,
+    /// Bytes hashed so far
+    read: u64,
+    verified: bool,
+}
+
+impl<R> VerifiedReader<R> {
+    /// Wrap any reader, e.g. a download; `path` names the source in the mismatch error.
+    pub fn new(inner: R, expected: crate::Digest, path: std::path::PathBuf) -> Self {
+        VerifiedReader {
+            inner,
+            hasher: Sha256::new(),
+            expected,
+            path,
+            read: 0,
+            verified: false,
+        }
+    }
+
+    /// The digest the content must match.
+    #[must_use]
+    pub fn expected(&self) -> crate::Digest {
+        self.expected
+    }
+
+    /// Bytes read so far.
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.read
+    }
+
+    /// Whether end of file was reached and the content matched.
+    #[must_use]
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+}
+
+impl<R: io::Read> io::Read for VerifiedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.verified {
+            return self.inner.read(buf);
+        }
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.hasher.update(&buf[..n]);
+            self.read += n as u64;
+            throttle(n);
+            return Ok(n);
+        }
+        let actual = crate::Digest::sha256(self.hasher.clone().finalize().into());
+        if actual != self.expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::DigestMismatch {
+                    path: self.path.clone(),
+                    expected: self.expected,
+                    actual,
+                },
+            ));
+        }
+        self.verified = true;
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::BlobStore;
+
+    #[test]
+    fn test_open_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = BlobStore::new(dir.path());
+        let digest = sha256_digest(b"weights");
+        fs::write(blobs.path_for(&digest), b"weights").unwrap();
+
+        let mut reader = blobs.open_verified(&digest).unwrap();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"weights");
+        assert!(reader.is_verified());
+
+        fs::write(blobs.path_for(&digest), b"weighty").unwrap();
+        let mut reader = blobs.open_verified(&digest).unwrap();
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(matches!(
+            err.into_inner().unwrap().downcast::<Error>().as_deref(),
+            Ok(Error::DigestMismatch { .. })
+        ));
+        assert!(!reader.is_verified());
+    }
+}
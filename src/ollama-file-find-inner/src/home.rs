@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::compute_mtime;
+
+/// What lives in Ollama's own directory besides the models: the identity key it signs
+/// registry requests with and the `ollama run` prompt history. Contents of the history
+/// are never read beyond counting entries.
+#[derive(Debug, Clone, Serialize)]
+pub struct OllamaHome {
+    pub path: PathBuf,
+    pub exists: bool,
+    /// `id_ed25519.pub`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<PublicKeyInfo>,
+    /// Whether the matching private key (`id_ed25519`) is present
+    pub private_key: bool,
+    /// `history`, the interactive prompt history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<HistoryInfo>,
+    /// Whether the models directory in use is this directory's `models`
+    pub holds_models: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicKeyInfo {
+    pub path: PathBuf,
+    /// Key type from the OpenSSH line, e.g. `ssh-ed25519`
+    pub key_type: String,
+    /// `SHA256:<base64>`, as `ssh-keygen -l` prints it (needs the `signatures` feature)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// The key as Ollama shows it for registering with ollama.com
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryInfo {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Non-empty lines
+    pub entries: usize,
+    /// Seconds since the epoch of the last write
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+}
+
+impl OllamaHome {
+    /// Ollama's directory for a models directory: its parent when it is the default
+    /// `<home>/.ollama/models` layout (which also covers service installs such as
+    /// `/usr/share/ollama/.ollama`), otherwise `~/.ollama`.
+    #[must_use]
+    pub fn for_models_dir(models_dir: &Path) -> PathBuf {
+        match models_dir.parent() {
+            Some(parent)
+                if models_dir.file_name().is_some_and(|n| n == "models")
+                    && parent.file_name().is_some_and(|n| n == ".ollama") =>
+            {
+                parent.to_path_buf()
+            }
+            _ => dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".ollama"),
+        }
+    }
+
+    #[must_use]
+    pub fn inspect(dir: &Path, models_dir: &Path) -> Self {
+        let public_key = fs::read_to_string(dir.join("id_ed25519.pub"))
+            .ok()
+            .and_then(|text| {
+                let line = text.lines().find(|l| !l.trim().is_empty())?.trim();
+                let (key_type, _) = line.split_once(' ')?;
+                Some(PublicKeyInfo {
+                    path: dir.join("id_ed25519.pub"),
+                    key_type: key_type.to_string(),
+                    fingerprint: fingerprint(line),
+                    key: line.to_string(),
+                })
+            });
+        let history_path = dir.join("history");
+        let history = fs::read(&history_path).ok().map(|body| HistoryInfo {
+            bytes: body.len() as u64,
+            entries: body
+                .split(|&b| b == b'\n')
+                .filter(|l| !l.trim_ascii().is_empty())
+                .count(),
+            modified: compute_mtime(&history_path),
+            path: history_path,
+        });
+        let same = |a: &Path, b: &Path| match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        };
+        OllamaHome {
+            path: dir.to_path_buf(),
+            exists: dir.is_dir(),
+            public_key,
+            private_key: dir.join("id_ed25519").is_file(),
+            history,
+            holds_models: same(&dir.join("models"), models_dir),
+        }
+    }
+}
+
+#[cfg(feature = "signatures")]
+fn fingerprint(line: &str) -> Option<String> {
+    let (key, _) = crate::signature::parse_public_keys(line)
+        .into_iter()
+        .next()?;
+    Some(crate::signature::ssh_fingerprint(&key))
+}
+
+#[cfg(not(feature = "signatures"))]
+fn fingerprint(_line: &str) -> Option<String> {
+    None
+}
@@ -0,0 +1,273 @@
+use std::{fmt, path::Path};
+
+use serde::Serialize;
+
+use crate::{Error, ManifestData, Result};
+
+/// A fix applied by the lenient manifest parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Repair {
+    /// Leading UTF-8 byte order mark
+    ByteOrderMark,
+    /// `//` and `/* */` comments
+    Comments,
+    /// Commas missing between values or members
+    MissingCommas,
+    /// Commas directly before a closing bracket
+    TrailingCommas,
+    /// Bytes after the end of the document, e.g. the tail of a longer manifest that a
+    /// shorter one was written over
+    TrailingGarbage,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Repair::ByteOrderMark => "byte order mark",
+            Repair::Comments => "comments",
+            Repair::MissingCommas => "missing commas",
+            Repair::TrailingCommas => "trailing commas",
+            Repair::TrailingGarbage => "trailing garbage",
+        })
+    }
+}
+
+/// Parse manifest bytes read from `path`, falling back to [`repair_json`] when strict
+/// parsing fails. Returns the repairs that were needed (none for a well-formed file).
+/// Empty and truncated manifests are not guessed at and fail as with strict parsing.
+pub(crate) fn parse_manifest_lenient(
+    path: &Path,
+    data: &[u8],
+) -> Result<(ManifestData, Vec<Repair>)> {
+    match crate::parse_manifest(path, data) {
+        Ok(manifest) => Ok((manifest, Vec::new())),
+        Err(e @ Error::EmptyManifest(_)) => Err(e),
+        Err(e) => {
+            let (fixed, repairs) = repair_json(data);
+            if repairs.is_empty() {
+                return Err(e);
+            }
+            crate::parse_manifest(path, &fixed)
+                .map(|manifest| (manifest, repairs))
+                .map_err(|_| e)
+        }
+    }
+}
+
+/// Rewrite almost-JSON into JSON: drop a byte order mark, comments, trailing commas,
+/// and anything after the top-level object or array; insert missing commas. String
+/// contents are never touched. Each kind of repair is reported once, in order of first
+/// occurrence.
+#[must_use]
+pub fn repair_json(input: &[u8]) -> (Vec<u8>, Vec<Repair>) {
+    let mut repairs = Vec::new();
+    let mut note = |r| {
+        if !repairs.contains(&r) {
+            repairs.push(r);
+        }
+    };
+    let mut input = input;
+    if let Some(rest) = input.strip_prefix(b"\xEF\xBB\xBF") {
+        input = rest;
+        note(Repair::ByteOrderMark);
+    }
+
+    let mut out = Vec::with_capacity(input.len());
+    // The last token written completes a value, so another value needs a comma first.
+    let mut after_value = false;
+    // Position in `out` of a comma no value has followed yet.
+    let mut pending_comma: Option<usize> = None;
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < input.len() {
+        let b = input[i];
+        match b {
+            b'/' if input.get(i + 1) == Some(&b'/') => {
+                i = input[i..]
+                    .iter()
+                    .position(|&c| c == b'\n')
+                    .map_or(input.len(), |n| i + n);
+                note(Repair::Comments);
+                continue;
+            }
+            b'/' if input.get(i + 1) == Some(&b'*') => {
+                i = input[i + 2..]
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(input.len(), |n| i + 2 + n + 2);
+                note(Repair::Comments);
+                continue;
+            }
+            b'"' | b'{' | b'[' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n' => {
+                if after_value {
+                    out.push(b',');
+                    note(Repair::MissingCommas);
+                }
+                pending_comma = None;
+                let end = match b {
+                    b'"' => string_end(input, i),
+                    b'{' | b'[' => i + 1,
+                    _ => {
+                        i + input[i..]
+                            .iter()
+                            .position(|c| !(c.is_ascii_alphanumeric() || b"+-.".contains(c)))
+                            .unwrap_or(input.len() - i)
+                    }
+                };
+                out.extend_from_slice(&input[i..end]);
+                i = end;
+                if matches!(b, b'{' | b'[') {
+                    depth += 1;
+                    after_value = false;
+                } else {
+                    after_value = true;
+                }
+                continue;
+            }
+            b'}' | b']' => {
+                if let Some(at) = pending_comma.take() {
+                    out.remove(at);
+                    note(Repair::TrailingCommas);
+                }
+                out.push(b);
+                after_value = true;
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    if input[i + 1..].iter().any(|c| !c.is_ascii_whitespace()) {
+                        note(Repair::TrailingGarbage);
+                    }
+                    break;
+                }
+            }
+            b',' => {
+                out.push(b);
+                pending_comma = Some(out.len() - 1);
+                after_value = false;
+            }
+            b':' => {
+                out.push(b);
+                after_value = false;
+            }
+            _ => out.push(b),
+        }
+        i += 1;
+    }
+    (out, repairs)
+}
+
+/// Index just past the string starting at `start` (or the end of input if unterminated).
+fn string_end(input: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < input.len() {
+        match input[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    input.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde_json::Value;
+
+    fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(Value::from),
+            "[ -~]{0,12}".prop_map(Value::String),
+        ];
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::btree_map("[ -~]{0,8}", inner, 0..4)
+                    .prop_map(|m| Value::Object(m.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn manifest_like() -> impl Strategy<Value = Value> {
+        prop::collection::btree_map("[a-z]{1,8}", json_value(), 1..5)
+            .prop_map(|m| Value::Object(m.into_iter().collect()))
+    }
+
+    /// Corrupt pretty-printed JSON line by line. Pretty output ends every line outside
+    /// a string, so comments and comma edits there never land inside string contents.
+    fn corrupt(pretty: &str, edits: &[u8], garbage: &str, bom: bool) -> Vec<u8> {
+        let lines: Vec<&str> = pretty.lines().collect();
+        let mut text = String::new();
+        for (n, line) in lines.iter().enumerate() {
+            let edit = edits.get(n).copied().unwrap_or(0);
+            let closes_next = lines
+                .get(n + 1)
+                .is_some_and(|l| l.trim_start().starts_with(['}', ']']));
+            let mut line = (*line).to_string();
+            match edit % 4 {
+                1 if line.ends_with(',') => {
+                    line.pop();
+                }
+                2 if closes_next && !line.ends_with(['{', '[']) => line.push(','),
+                3 => line.push_str(" // note, \"quoted\" {"),
+                _ => {}
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text.push_str(garbage);
+        let mut bytes = if bom {
+            b"\xEF\xBB\xBF".to_vec()
+        } else {
+            Vec::new()
+        };
+        bytes.extend_from_slice(text.as_bytes());
+        bytes
+    }
+
+    proptest! {
+        #[test]
+        fn test_repair_recovers_corrupted_json(
+            value in manifest_like(),
+            edits in prop::collection::vec(any::<u8>(), 0..64),
+            garbage in "[ -~]{0,16}",
+            bom in any::<bool>(),
+        ) {
+            let pretty = serde_json::to_string_pretty(&value).unwrap();
+            let (fixed, _) = repair_json(&corrupt(&pretty, &edits, &garbage, bom));
+            let parsed: Value = serde_json::from_slice(&fixed).unwrap();
+            prop_assert_eq!(parsed, value);
+        }
+
+        #[test]
+        fn test_repair_leaves_valid_json_alone(value in manifest_like()) {
+            let compact = serde_json::to_vec(&value).unwrap();
+            let (fixed, repairs) = repair_json(&compact);
+            prop_assert!(repairs.is_empty());
+            prop_assert_eq!(fixed, compact);
+        }
+    }
+
+    #[test]
+    fn test_repair_reports_fixes() {
+        let input = b"\xEF\xBB\xBF{\"a\": 1 // one\n \"b\": [1, 2,],}\n}garbage";
+        let (fixed, repairs) = repair_json(input);
+        assert_eq!(
+            serde_json::from_slice::<Value>(&fixed).unwrap(),
+            serde_json::json!({"a": 1, "b": [1, 2]})
+        );
+        assert_eq!(
+            repairs,
+            [
+                Repair::ByteOrderMark,
+                Repair::Comments,
+                Repair::MissingCommas,
+                Repair::TrailingCommas,
+                Repair::TrailingGarbage
+            ]
+        );
+    }
+}
@@ -2,16 +2,114 @@ use std::{
     env, fs, io,
     mem::take,
     path::{Path, PathBuf},
-    time::SystemTime,
+    time::{Duration, Instant, SystemTime},
 };
 
 mod models;
-pub use models::{BlobPathInfo, LayerInfo, ListedModel};
+pub use models::{
+    BlobPathInfo, BlobSizes, BlobStatus, CONFIG_MEDIA_TYPE, DEFAULT_HOST, DEFAULT_TAG,
+    LIBRARY_NAMESPACE, LayerInfo, LayerInfoRef, LayerKind, ListedModel, MANIFEST_MEDIA_TYPE,
+    ManifestData, ManifestDataRef, ModelId, RuntimeState, size_breakdown,
+};
+
+mod digest;
+pub use digest::{Digest, DigestAlgorithm};
+
+mod media_type;
+pub use media_type::MediaType;
 
 mod scan_args;
-pub use scan_args::ScanArgs;
+pub use scan_args::{HiddenPolicy, ScanArgs};
+
+mod store;
+pub use store::{
+    BlobEntry, BlobStore, MIN_DIGEST_PREFIX, ManifestStore, StoredManifest, StrayFile,
+};
+
+mod atomic;
+pub use atomic::{copy_atomic, write_atomic};
+
+mod gc;
+pub use gc::{GcPlan, GcReport, gc_plan, remove_unreferenced};
+
+mod pull;
+pub use pull::{PullEstimate, PullLayer, estimate_pull};
+
+mod license;
+pub use license::{LicenseFamily, ModelLicense};
+
+pub mod gguf;
+
+mod fit;
+pub use fit::{Fit, MemoryEstimate, RUNTIME_OVERHEAD};
+
+mod dedupe;
+pub use dedupe::{KeepPolicy, TagGroup, TagVariant, tag_groups};
+
+mod summary;
+pub use summary::StoreSummary;
+
+mod primary;
+pub use primary::{LargestLayer, PrimarySelector, WeightsFirst, is_weights_media_type};
+
+mod hash;
+pub use hash::{VerifiedReader, set_hash_rate_limit, sha256_digest, sha256_file};
+
+pub mod chunks;
+
+mod devices;
+pub use devices::{device_id, map_per_device};
+
+mod classify;
+pub use classify::{BlobClass, CLASSIFY_SAMPLE_BYTES, classify_file};
+
+mod collate;
+pub use collate::Collation;
+
+mod case;
+mod home;
+pub use home::{HistoryInfo, OllamaHome, PublicKeyInfo};
+mod punycode;
+pub use case::{CaseCollision, NameLevel, case_collisions, is_case_insensitive};
 
-use crate::models::{ManifestData, ModelId};
+mod lenient;
+pub use lenient::{Repair, repair_json};
+
+mod seal;
+pub use seal::{SEAL_VERSION, Seal, SealChange, SealedModel};
+
+mod synthetic;
+pub use synthetic::SyntheticStore;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod fixtures;
+
+mod provenance;
+pub use provenance::{Origin, Provenance, is_signature_media_type};
+
+#[cfg(feature = "signatures")]
+pub mod signature;
+
+mod edit;
+pub use edit::{LayerEdit, StrippedModel, clone_model, set_layer, strip_layers};
+
+mod manifest_backup;
+pub use manifest_backup::{BACKUP_DIR, DEFAULT_BACKUP_RETENTION, ManifestBackup};
+
+mod pin;
+pub use pin::{PIN_DIR, Pin};
+
+mod recover;
+pub use recover::{RECOVERED_NAMESPACE, RecoveryCandidate, recovery_candidates};
+
+#[cfg(feature = "network")]
+pub mod registry;
+
+#[cfg(feature = "network")]
+pub mod api;
+
+/// Where `ollama serve` listens unless configured otherwise.
+pub const DEFAULT_API_URL: &str = "http://127.0.0.1:11434";
 
 /// Library wide result type.
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -36,13 +134,116 @@ pub enum Error {
     InvalidComponentPath(PathBuf),
     #[error("Invalid components: {0:?}")]
     InvalidComponents(Vec<String>),
+    #[error("Invalid model name: {0}")]
+    InvalidModelName(String),
+    #[error("Invalid digest: {0:?}")]
+    InvalidDigest(String),
+    #[error("Content of {path} hashes to {actual}, expected {expected}")]
+    DigestMismatch {
+        path: PathBuf,
+        expected: Digest,
+        actual: Digest,
+    },
+    #[error("Skipping linked directory (junction or symlink) at {0}")]
+    LinkedDirectory(PathBuf),
+    #[error("Registry request to {url} failed: {message}")]
+    Registry { url: String, message: String },
+    #[error("Ollama API request to {url} failed: {message}")]
+    Api { url: String, message: String },
+    #[error("Refusing to garbage-collect: {0} manifest(s) could not be read")]
+    GcUnsafe(usize),
+    #[error("Refusing to seal: {0} manifest(s) could not be read")]
+    SealUnsafe(usize),
+    #[error("GGUF parse error: {0}")]
+    Gguf(String),
+    #[error("Manifest is empty (zero bytes or only whitespace): {0}")]
+    EmptyManifest(PathBuf),
+    #[error("Manifest is truncated (JSON ends early): {0}")]
+    TruncatedManifest(PathBuf),
+    #[error("Signature error: {0}")]
+    Signature(String),
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+    #[error("No blob matches {0}")]
+    BlobNotFound(String),
+    #[error("{prefix} is ambiguous: it matches {}", matches.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    AmbiguousDigest {
+        prefix: String,
+        matches: Vec<Digest>,
+    },
+    #[error("No manifest backup of {model}{}", at.map(|t| format!(" at or before {t}")).unwrap_or_default())]
+    NoBackup { model: String, at: Option<u64> },
+    #[error("Malformed digest {digest:?} in manifest of {model} (expected sha256:<64 hex digits>)")]
+    MalformedDigest { model: String, digest: String },
+}
+
+impl Error {
+    /// Name `id` as the model of a [`Error::MalformedDigest`], which [`parse_manifest`]
+    /// can only report by manifest path.
+    pub(crate) fn in_model(self, id: &ModelId) -> Self {
+        match self {
+            Error::MalformedDigest { digest, .. } => Error::MalformedDigest {
+                model: id.normalize(),
+                digest,
+            },
+            e => e,
+        }
+    }
 }
 
 /// Outcome of a scan: the successfully parsed models plus any errors that occurred.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ScanOutcome {
     pub models: Vec<ListedModel>,
     pub errors: Vec<Error>,
+    pub stats: ScanStats,
+}
+
+/// Counters for one [`scan_manifests`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Files visited as manifest candidates (directories excluded)
+    pub manifests_seen: usize,
+    /// Manifests that became models
+    pub parsed: usize,
+    /// Manifests left out by the [`HiddenPolicy`] (visible ones with
+    /// [`HiddenPolicy::Only`])
+    pub skipped_hidden: usize,
+    /// Entries that produced an error (including walk errors)
+    pub errored: usize,
+    /// Blob files whose metadata was read (verbose scans only)
+    pub blobs_stated: usize,
+    /// Sum of the on-disk lengths of those blobs
+    pub bytes_stated: u64,
+    /// Wall-clock time of the whole scan
+    pub duration: Duration,
+}
+
+impl ScanStats {
+    fn merge(&mut self, other: &ScanStats) {
+        self.manifests_seen += other.manifests_seen;
+        self.parsed += other.parsed;
+        self.skipped_hidden += other.skipped_hidden;
+        self.errored += other.errored;
+        self.blobs_stated += other.blobs_stated;
+        self.bytes_stated += other.bytes_stated;
+    }
+}
+
+impl std::fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} manifests seen, {} parsed, {} hidden, {} errors; {} blobs ({} bytes) stat'ed; {:.1} ms",
+            self.manifests_seen,
+            self.parsed,
+            self.skipped_hidden,
+            self.errored,
+            self.blobs_stated,
+            self.bytes_stated,
+            self.duration.as_secs_f64() * 1000.0
+        )
+    }
 }
 
 /// Locate the models directory (`OLLAMA_MODELS` or fallback to $HOME/.ollama/models)
@@ -58,6 +259,61 @@ pub fn ollama_models_dir() -> PathBuf {
     home.join(".ollama").join("models")
 }
 
+/// Base URL of the Ollama API: `OLLAMA_HOST` (the variable `ollama` itself reads) if set,
+/// otherwise [`DEFAULT_API_URL`].
+#[must_use]
+pub fn ollama_api_url() -> String {
+    match env::var("OLLAMA_HOST") {
+        Ok(host) if !host.trim().is_empty() => api_url(&host),
+        _ => DEFAULT_API_URL.to_string(),
+    }
+}
+
+/// Turn an `OLLAMA_HOST`-style address (`host`, `host:port`, `:port`, `[::1]:port`,
+/// `https://host`) into a base URL the way Ollama does: `http` and port 11434 unless
+/// given (80 / 443 for an explicit scheme). Bind-all addresses (`0.0.0.0`, `::`) become
+/// loopback, since they are what the server listens on, not somewhere to connect to.
+#[must_use]
+pub fn api_url(host: &str) -> String {
+    let s = host.trim().trim_end_matches('/');
+    let (scheme, rest, default_port) = match s.split_once("://") {
+        Some(("https", rest)) => ("https", rest, "443"),
+        Some(("http", rest)) => ("http", rest, "80"),
+        Some((scheme, rest)) => (scheme, rest, "11434"),
+        None => ("http", s, "11434"),
+    };
+    let (hostport, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (host, port) = match hostport.strip_prefix('[').and_then(|v6| v6.split_once(']')) {
+        Some((host, tail)) => (host, tail.strip_prefix(':')),
+        None => match hostport.rsplit_once(':') {
+            // More than one colon without brackets: a bare IPv6 address.
+            Some((host, port)) if !host.contains(':') => (host, Some(port)),
+            _ => (hostport, None),
+        },
+    };
+    let host = match host {
+        "" | "0.0.0.0" => "127.0.0.1".to_string(),
+        "::" => "[::1]".to_string(),
+        h if h.contains(':') => format!("[{h}]"),
+        h => h.to_string(),
+    };
+    let port = port.filter(|p| !p.is_empty()).unwrap_or(default_port);
+    let path = if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{path}")
+    };
+    format!("{scheme}://{host}:{port}{path}")
+}
+
+/// The [`BACKUP_DIR`] or [`PIN_DIR`] directly under the manifests root, which hold
+/// copies rather than models.
+fn is_backup_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() == 1
+        && entry.file_type().is_dir()
+        && (entry.file_name() == BACKUP_DIR || entry.file_name() == PIN_DIR)
+}
+
 /// Get the relative path components for a directory entry.
 fn relative_components(entry: &walkdir::DirEntry, root: &Path) -> Result<Vec<String>> {
     if !entry.path().starts_with(root) {
@@ -75,7 +331,7 @@ fn relative_components(entry: &walkdir::DirEntry, root: &Path) -> Result<Vec<Str
 }
 
 /// Interpret path components as (host?, namespace, model, tag).
-fn parse_components(mut comps: Vec<String>, include_hidden: bool) -> Result<Option<ModelId>> {
+fn parse_components(mut comps: Vec<String>, hidden: HiddenPolicy) -> Result<Option<ModelId>> {
     // Accept either:
     //   4 components: host / namespace / model / tag
     //   3 components:          namespace / model / tag
@@ -84,8 +340,8 @@ fn parse_components(mut comps: Vec<String>, include_hidden: bool) -> Result<Opti
         _ => return Err(Error::InvalidComponents(comps)),
     }
 
-    // Exclude any component starting with '.' unless explicitly allowed.
-    if !include_hidden && comps.iter().any(|c| c.starts_with('.')) {
+    // A component starting with '.' makes the model hidden.
+    if !hidden.admits(comps.iter().any(|c| c.starts_with('.'))) {
         return Ok(None);
     }
 
@@ -115,13 +371,42 @@ fn load_manifest(path: &Path) -> Result<ManifestData> {
         path: path.to_path_buf(),
         source: e,
     })?;
-    let parsed = serde_json::from_slice(&data).map_err(|e| Error::Json {
-        path: path.to_path_buf(),
-        source: e,
+    parse_manifest(path, &data)
+}
+
+/// Parse manifest bytes read from `path` (used only for error reporting).
+fn parse_manifest<'a, T: serde::Deserialize<'a>>(path: &Path, data: &'a [u8]) -> Result<T> {
+    if data.iter().all(u8::is_ascii_whitespace) {
+        return Err(Error::EmptyManifest(path.to_path_buf()));
+    }
+    let parsed = serde_json::from_slice(data).map_err(|e| {
+        if e.is_eof() {
+            Error::TruncatedManifest(path.to_path_buf())
+        } else if let Some(digest) = malformed_digest(data) {
+            Error::MalformedDigest {
+                model: path.display().to_string(),
+                digest,
+            }
+        } else {
+            Error::Json {
+                path: path.to_path_buf(),
+                source: e,
+            }
+        }
     })?;
     Ok(parsed)
 }
 
+/// The first digest in a manifest that isn't `sha256:` and 64 hex digits, if the
+/// manifest is otherwise well-formed enough to find it.
+fn malformed_digest(data: &[u8]) -> Option<String> {
+    let manifest: ManifestDataRef = serde_json::from_slice(data).ok()?;
+    manifest
+        .entries()
+        .find(|l| l.digest().is_err())
+        .map(|l| l.digest.to_string())
+}
+
 /// Sum layer + config sizes, returning None if no declared sizes exist.
 fn compute_total_size(layers: &[LayerInfo], config: Option<&LayerInfo>) -> Option<u64> {
     let mut sum = 0u64;
@@ -150,69 +435,229 @@ fn compute_mtime(path: &Path) -> Option<u64> {
         .map(|d| d.as_secs())
 }
 
-/// Attempt to turn a filesystem entry into a `ListedModel` (only if it's a manifest file
-/// with valid components). Returns `None` for directories, hidden-excluded entries, or
-/// any IO / parse failures.
-fn process_entry(entry: &walkdir::DirEntry, args: &ScanArgs) -> Result<Option<ListedModel>> {
+/// What a scanned directory entry turned out to be.
+enum Visit {
+    Directory,
+    /// Left out by the [`HiddenPolicy`]
+    Filtered,
+    Model(Box<ListedModel>),
+}
+
+/// Turn a filesystem entry into a `ListedModel` if it is a manifest file with valid
+/// components. Directories and entries the hidden policy leaves out are reported as
+/// such; unreadable or malformed manifests and paths are errors.
+fn process_entry(entry: &walkdir::DirEntry, args: &ScanArgs) -> Result<Visit> {
     if entry.file_type().is_dir() {
-        return Ok(None);
+        return Ok(Visit::Directory);
+    }
+    // Junctions and directory symlinks are not descended into; say so instead of
+    // failing to read them as manifests.
+    if entry.path_is_symlink() && entry.path().is_dir() {
+        return Err(Error::LinkedDirectory(entry.path().to_path_buf()));
     }
     let comps = relative_components(entry, &args.root)?;
-    let Some(id) = parse_components(comps, args.include_hidden)? else {
-        return Ok(None);
+    // `None` only when the hidden policy leaves it out; malformed paths are errors.
+    let Some(id) = parse_components(comps, args.hidden)? else {
+        return Ok(Visit::Filtered);
     };
     let manifest_path = entry.path();
-    let manifest = load_manifest(manifest_path)?;
-    let model = ListedModel::new(id, manifest_path);
-    if args.verbose {
-        Ok(Some(model.into_verbose(manifest, &args.blobs_root)))
+    let data = fs::read(manifest_path)
+        .map_err(|source| Error::Io {
+            path: manifest_path.to_path_buf(),
+            source,
+        })
+        .map_err(|e| e.in_model(&id))?;
+    let (manifest, repairs) = if args.lenient {
+        lenient::parse_manifest_lenient(manifest_path, &data)
     } else {
-        Ok(Some(model))
+        parse_manifest(manifest_path, &data).map(|m| (m, Vec::new()))
     }
+    .map_err(|e| e.in_model(&id))?;
+    let model = ListedModel {
+        repairs,
+        ..ListedModel::new(id, manifest_path)
+    };
+    // The bytes as stored, before any lenient repair, are what Ollama hashes.
+    let model = if args.verbose {
+        ListedModel {
+            manifest_digest: Some(hash::sha256_digest(&data)),
+            ..model.into_verbose_with(manifest, &args.blobs_root, args.primary.as_ref())
+        }
+    } else if args.summary {
+        ListedModel {
+            manifest_digest: Some(hash::sha256_digest(&data)),
+            total_size: compute_total_size(&manifest.layers, manifest.config.as_ref()),
+            mtime: compute_mtime(manifest_path),
+            ..model
+        }
+    } else {
+        model
+    };
+    Ok(Visit::Model(Box::new(model)))
 }
 
 /// Scan manifests and construct `ListedModel` entries.
+///
+/// Second-level directories (`<host>/<namespace>`, or `<namespace>/<model>` in the
+/// three-component layout) are walked concurrently with the `parallel` feature. Entries
+/// are visited in file-name order and merged in that order, so `errors` is the same from
+/// run to run however the work was scheduled.
 #[must_use]
 pub fn scan_manifests(args: &ScanArgs) -> ScanOutcome {
-    let mut models = Vec::new();
-    let mut errors = Vec::new();
-    for entry_res in walkdir::WalkDir::new(&args.root).follow_links(false) {
+    let started = Instant::now();
+    let mut outcome = ScanOutcome::default();
+    // Down to namespace level sequentially: stray files there are still reported, and
+    // the namespace directories become the units of work.
+    let mut namespaces = Vec::new();
+    let shallow = walkdir::WalkDir::new(&args.root)
+        .follow_links(false)
+        .max_depth(2)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !is_backup_dir(e));
+    for entry_res in shallow {
         match entry_res {
-            Ok(entry) => match process_entry(&entry, args) {
-                Ok(Some(model)) => models.push(model),
-                Ok(None) => {}
-                Err(e) => errors.push(e),
-            },
-            Err(e) => errors.push(Error::WalkDir(e)),
+            Ok(entry) if entry.depth() == 2 && entry.file_type().is_dir() => {
+                namespaces.push(entry.into_path());
+            }
+            Ok(entry) => outcome.record(process_entry(&entry, args)),
+            Err(e) => outcome.record_walk_error(e),
         }
     }
-    models.sort_unstable_by(|a, b| a.name.cmp(&b.name));
-    ScanOutcome { models, errors }
+    for part in map_namespaces(namespaces, |dir| scan_namespace(&dir, args)) {
+        outcome.models.extend(part.models);
+        outcome.errors.extend(part.errors);
+        outcome.stats.merge(&part.stats);
+    }
+    outcome
+        .models
+        .sort_unstable_by(|a, b| args.collation.compare(&a.name, &b.name));
+    outcome.stats.duration = started.elapsed();
+    outcome
 }
 
-/// Build blob path info list and decide primary digest.
-/// Build blob info records for layers + optional config, returning the primary digest chosen.
-/// Primary heuristic: largest (by declared size) layer; fall back to config if none.
-#[must_use]
-pub fn build_blob_infos<'a>(
-    layers: &'a [LayerInfo],
-    config: Option<&'a LayerInfo>,
-    blobs_root: &Path,
-) -> (Option<&'a str>, Vec<BlobPathInfo>) {
-    let mut primary_digest_idx: Option<usize> = None;
-    let mut max_size: u64 = 0;
-    for (i, l) in layers.iter().enumerate() {
-        if let Some(sz) = l.size
-            && sz > max_size
-        {
-            max_size = sz;
-            primary_digest_idx = Some(i);
+/// [`scan_manifests`] for async callers: the walk runs on Tokio's blocking thread pool,
+/// so a server embedding the library keeps serving requests while thousands of
+/// manifests are read and blobs statted. Must be called from within a Tokio runtime.
+///
+/// This wraps the blocking scan rather than using `tokio::fs`, and so holds one
+/// blocking-pool thread for the whole scan. `tokio::fs` would hand each read and stat
+/// to that pool one by one anyway, and keeping a single scan implementation means the
+/// async results can't drift from [`scan_manifests`].
+///
+/// A panic during the scan is resumed in the caller; if the runtime shuts down before
+/// the scan runs, the outcome holds a single [`Error::Io`] for `args.root`.
+#[cfg(feature = "async")]
+pub async fn scan_manifests_async(args: ScanArgs<'_>) -> ScanOutcome {
+    let args = args.into_owned();
+    let root = args.root.to_path_buf();
+    match tokio::task::spawn_blocking(move || scan_manifests(&args)).await {
+        Ok(outcome) => outcome,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => ScanOutcome {
+            errors: vec![Error::Io {
+                path: root,
+                source: io::Error::other(e),
+            }],
+            ..ScanOutcome::default()
+        },
+    }
+}
+
+fn scan_namespace(dir: &Path, args: &ScanArgs) -> ScanOutcome {
+    let mut outcome = ScanOutcome::default();
+    let walk = walkdir::WalkDir::new(dir)
+        .follow_links(false)
+        .min_depth(1)
+        .sort_by_file_name();
+    for entry_res in walk {
+        match entry_res {
+            Ok(entry) => outcome.record(process_entry(&entry, args)),
+            Err(e) => outcome.record_walk_error(e),
         }
     }
+    outcome
+}
+
+impl ScanOutcome {
+    fn record(&mut self, res: Result<Visit>) {
+        let stats = &mut self.stats;
+        match res {
+            Ok(Visit::Directory) => return,
+            Ok(Visit::Filtered) => stats.skipped_hidden += 1,
+            Ok(Visit::Model(model)) => {
+                stats.parsed += 1;
+                for b in model.blob_paths.iter().flatten() {
+                    if let Some(len) = b.sizes.actual {
+                        stats.blobs_stated += 1;
+                        stats.bytes_stated += len;
+                    }
+                }
+                self.models.push(*model);
+            }
+            Err(e) => {
+                stats.errored += 1;
+                self.errors.push(e);
+            }
+        }
+        stats.manifests_seen += 1;
+    }
+
+    fn record_walk_error(&mut self, e: walkdir::Error) {
+        self.stats.errored += 1;
+        self.errors.push(Error::WalkDir(e));
+    }
+}
+
+/// Map over namespace directories, keeping input order in the output.
+#[cfg(feature = "parallel")]
+fn map_namespaces<F>(dirs: Vec<PathBuf>, f: F) -> Vec<ScanOutcome>
+where
+    F: Fn(PathBuf) -> ScanOutcome + Sync + Send,
+{
+    use rayon::prelude::*;
+    dirs.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn map_namespaces<F>(dirs: Vec<PathBuf>, f: F) -> Vec<ScanOutcome>
+where
+    F: Fn(PathBuf) -> ScanOutcome,
+{
+    dirs.into_iter().map(f).collect()
+}
+
+/// Find a scanned model by the name a user would type (`mistral` matches `mistral:latest`).
+#[must_use]
+pub fn find_model<'a>(models: &'a [ListedModel], name: &str) -> Option<&'a ListedModel> {
+    let wanted = name.parse::<ModelId>().ok()?.normalize();
+    models.iter().find(|m| m.name == wanted)
+}
+
+/// Build blob info records for layers + optional config, returning the primary digest chosen
+/// by [`WeightsFirst`]: the largest weights layer, else the largest layer; the config if none.
+#[must_use]
+pub fn build_blob_infos(
+    layers: &[LayerInfo],
+    config: Option<&LayerInfo>,
+    blobs_root: &Path,
+) -> (Option<Digest>, Vec<BlobPathInfo>) {
+    build_blob_infos_with(layers, config, blobs_root, &WeightsFirst)
+}
+
+/// [`build_blob_infos`] with another [`PrimarySelector`].
+#[must_use]
+pub fn build_blob_infos_with(
+    layers: &[LayerInfo],
+    config: Option<&LayerInfo>,
+    blobs_root: &Path,
+    selector: &dyn PrimarySelector,
+) -> (Option<Digest>, Vec<BlobPathInfo>) {
+    let primary_digest_idx = selector.select(layers);
     let mut out = Vec::with_capacity(layers.len() + usize::from(config.is_some()));
     let primary_digest = primary_digest_idx
-        .and_then(|i| layers.get(i).map(|l| l.digest.as_ref()))
-        .or_else(|| config.map(|c| c.digest.as_ref()));
+        .and_then(|i| layers.get(i).map(|l| l.digest))
+        .or_else(|| config.map(|c| c.digest));
     for l in layers.iter().chain(config.iter().copied()) {
         out.push(build_blob_path_info(l, blobs_root));
     }
@@ -222,27 +667,91 @@ pub fn build_blob_infos<'a>(
 /// Produce a `BlobPathInfo` for the provided layer/config entry.
 #[must_use]
 pub fn build_blob_path_info(l: &LayerInfo, blobs_root: &Path) -> BlobPathInfo {
-    let path = digest_to_blob_path(blobs_root, &l.digest);
-    let (exists, actual_size, size_ok) = match fs::metadata(&path) {
+    let path = l.digest.blob_path(blobs_root);
+    let mut sizes = BlobSizes {
+        declared: l.size,
+        ..Default::default()
+    };
+    let offline = match fs::metadata(&path) {
         Ok(meta) => {
-            let a = meta.len();
-            let ok = l.size.map(|decl| decl == a);
-            (true, Some(a), ok)
+            sizes.actual = Some(meta.len());
+            sizes.physical = physical_size(&path, &meta);
+            is_offline(&meta)
         }
-        Err(_) => (false, None, None),
+        Err(_) => false,
     };
     BlobPathInfo {
-        digest: l.digest.clone(),
+        digest: l.digest,
         media_type: l.media_type.clone(),
-        declared_size: l.size,
+        sizes,
         path,
-        exists,
-        size_ok,
-        actual_size,
+        status: BlobStatus::from_sizes(&sizes, offline),
+        offline,
         primary: false,
     }
 }
 
+/// Whether a file is a cloud placeholder (e.g. OneDrive "files on demand") or otherwise
+/// marked offline, meaning reading its contents would trigger a download.
+#[must_use]
+pub fn is_offline(meta: &fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Storage::FileSystem::{
+            FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS,
+            FILE_ATTRIBUTE_RECALL_ON_OPEN,
+        };
+        meta.file_attributes()
+            & (FILE_ATTRIBUTE_OFFLINE
+                | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS
+                | FILE_ATTRIBUTE_RECALL_ON_OPEN)
+            != 0
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = meta;
+        false
+    }
+}
+
+/// Bytes actually allocated on disk for a file.
+///
+/// Smaller than the apparent length for sparse files and on filesystems that compress
+/// transparently (ZFS, APFS, NTFS). Note that btrfs reports uncompressed extents here.
+#[must_use]
+pub fn physical_size(path: &Path, meta: &fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = path;
+        Some(meta.blocks() * 512)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::{
+            Foundation::{GetLastError, NO_ERROR},
+            Storage::FileSystem::{GetCompressedFileSizeW, INVALID_FILE_SIZE},
+        };
+        let _ = meta;
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut high = 0u32;
+        // SAFETY: `wide` is NUL terminated and `high` outlives the call.
+        let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &raw mut high) };
+        // INVALID_FILE_SIZE is also a valid low word, so the error code disambiguates.
+        if low == INVALID_FILE_SIZE && unsafe { GetLastError() } != NO_ERROR {
+            return None;
+        }
+        Some((u64::from(high) << 32) | u64::from(low))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (path, meta);
+        None
+    }
+}
+
 /// Translate a content digest (e.g. `sha256:abcd...`) to Ollama's on-disk blob path.
 #[must_use]
 pub fn digest_to_blob_path(blobs_root: &Path, digest: &str) -> PathBuf {
@@ -266,4 +775,200 @@ mod tests {
         let p = digest_to_blob_path(&root, "sha256:1234abcd");
         assert_eq!(p, PathBuf::from("/tmp/blobs/sha256-1234abcd"));
     }
+
+    #[test]
+    fn test_api_url() {
+        for (host, url) in [
+            ("", "http://127.0.0.1:11434"),
+            ("0.0.0.0", "http://127.0.0.1:11434"),
+            (":8080", "http://127.0.0.1:8080"),
+            ("gpu-box", "http://gpu-box:11434"),
+            ("gpu-box:9000", "http://gpu-box:9000"),
+            (
+                "https://ollama.example.com",
+                "https://ollama.example.com:443",
+            ),
+            ("http://10.0.0.2/", "http://10.0.0.2:80"),
+            ("[::]:11500", "http://[::1]:11500"),
+            ("fe80::1", "http://[fe80::1]:11434"),
+        ] {
+            assert_eq!(api_url(host), url, "{host:?}");
+        }
+    }
+
+    #[test]
+    pub fn test_load_manifest_empty_and_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty");
+        let truncated = dir.path().join("truncated");
+        fs::write(&empty, b"").unwrap();
+        fs::write(&truncated, br#"{"layers":[{"digest":"sha256:ab"#).unwrap();
+        assert!(matches!(
+            load_manifest(&empty),
+            Err(Error::EmptyManifest(_))
+        ));
+        assert!(matches!(
+            load_manifest(&truncated),
+            Err(Error::TruncatedManifest(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_manifest_malformed_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latest");
+        let good = format!("sha256:{}", "ab".repeat(32));
+        for bad in [
+            "sha256:abc",
+            "md5:0123",
+            &format!("sha256:{}", "zz".repeat(32)),
+        ] {
+            let json = format!(
+                r#"{{"config":{{"digest":"{good}","mediaType":"x","size":1}},"layers":[{{"digest":"{bad}","mediaType":"x","size":1}}]}}"#
+            );
+            fs::write(&path, json).unwrap();
+            match load_manifest(&path) {
+                Err(Error::MalformedDigest { digest, .. }) => assert_eq!(digest, bad),
+                other => panic!("{bad}: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve_digest_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = BlobStore::new(dir.path());
+        let (a, b) = (
+            "9f3a1".to_string() + &"0".repeat(59),
+            "9f3a2".to_string() + &"0".repeat(59),
+        );
+        for hex in [&a, &b] {
+            fs::write(dir.path().join(format!("sha256-{hex}")), b"").unwrap();
+        }
+        let a: Digest = format!("sha256:{a}").parse().unwrap();
+        assert_eq!(blobs.resolve(&a.to_string()).unwrap(), a);
+        assert_eq!(blobs.resolve("9f3a1").unwrap(), a);
+        assert_eq!(blobs.resolve("sha256:9F3A1").unwrap(), a);
+        assert!(matches!(
+            blobs.resolve("9f3a"),
+            Err(Error::AmbiguousDigest { matches, .. }) if matches.len() == 2
+        ));
+        assert!(matches!(blobs.resolve("0000"), Err(Error::BlobNotFound(_))));
+        assert!(matches!(blobs.resolve("9f3"), Err(Error::InvalidDigest(_))));
+        assert!(matches!(
+            blobs.resolve("9f3z"),
+            Err(Error::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_scan_manifests_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("manifests");
+        let manifest = br#"{"schemaVersion":2,"layers":[]}"#;
+        for rel in [
+            "registry.ollama.ai/library/phi4/latest",
+            "registry.ollama.ai/library/llama3/8b",
+            "hf.co/org/qwen/q4",
+            "ns/model/tag",
+            "ns/model/.staging",
+        ] {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, manifest).unwrap();
+        }
+        for rel in [
+            "stray",
+            "hf.co/org/bad/tag",
+            "registry.ollama.ai/library/bad/tag",
+        ] {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+
+        let outcome = scan_manifests(&ScanArgs::new(&root, dir.path().join("blobs")));
+        let names: Vec<_> = outcome.models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "hf.co/org/qwen:q4",
+                "llama3:8b",
+                "ns/model:tag",
+                "phi4:latest"
+            ]
+        );
+        let errors: Vec<_> = outcome.errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("stray"));
+        assert!(errors[1].contains("hf.co"));
+        assert!(errors[2].contains("registry.ollama.ai"));
+        let stats = outcome.stats;
+        assert_eq!(
+            (stats.manifests_seen, stats.parsed, stats.skipped_hidden),
+            (8, 4, 1)
+        );
+        assert_eq!(stats.errored, 3);
+
+        let hidden = scan_manifests(
+            &ScanArgs::new(&root, dir.path().join("blobs")).with_hidden(HiddenPolicy::Only),
+        );
+        let names: Vec<_> = hidden.models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["ns/model:.staging"]);
+        assert_eq!(hidden.stats.skipped_hidden, 6);
+    }
+
+    #[test]
+    fn test_manifest_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::fixtures::FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .build(dir.path())
+            .unwrap();
+        let args = || {
+            ScanArgs::new(
+                store.models_dir().join("manifests"),
+                store.models_dir().join("blobs"),
+            )
+        };
+        assert_eq!(scan_manifests(&args()).models[0].manifest_digest, None);
+        let summary = scan_manifests(&args().with_summary(true));
+        let path = store.manifest_path("llama3:8b");
+        let digest = Some(sha256_file(&path).unwrap());
+        let m = &summary.models[0];
+        assert_eq!(m.manifest_digest, digest);
+        assert!(m.total_size.is_some() && m.mtime.is_some());
+        assert!(m.blob_paths.is_none() && m.gguf.is_none());
+        let verbose = scan_manifests(&args().with_verbose(true));
+        assert_eq!(verbose.models[0].manifest_digest, digest);
+        assert_eq!(verbose.models[0].total_size, m.total_size);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_scan_manifests_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::fixtures::FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .model("phi4")
+            .layer(LayerKind::Weights, b"phi")
+            .build(dir.path())
+            .unwrap();
+        let root = store.models_dir().join("manifests");
+        let blobs = store.models_dir().join("blobs");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let outcome = runtime.block_on(scan_manifests_async(
+            ScanArgs::new(&root, &blobs).with_verbose(true),
+        ));
+        let sync = scan_manifests(&ScanArgs::new(&root, &blobs).with_verbose(true));
+        let names = |o: &ScanOutcome| o.models.iter().map(|m| m.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&outcome), ["llama3:8b", "phi4:latest"]);
+        assert_eq!(names(&outcome), names(&sync));
+        assert!(outcome.errors.is_empty());
+        assert!(outcome.models[0].primary_blob_path.is_some());
+    }
 }
@@ -0,0 +1,146 @@
+use std::{fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Digest, LayerInfo, LayerKind};
+
+/// Broad license family, detected from license text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseFamily {
+    #[serde(rename = "apache-2.0")]
+    Apache2,
+    Mit,
+    /// Meta's Llama community licenses (Llama 2, 3, 3.1, ...)
+    LlamaCommunity,
+    /// Google's Gemma terms of use
+    Gemma,
+    /// Any license restricting commercial use (CC BY-NC, research-only, ...)
+    NonCommercial,
+    Other,
+}
+
+impl LicenseFamily {
+    /// Classify license text. Non-commercial terms win over everything else, since they
+    /// are what compliance reviews care about most.
+    #[must_use]
+    pub fn detect(text: &str) -> Self {
+        let t = text.to_lowercase();
+        let has = |needle: &str| t.contains(needle);
+        if has("non-commercial")
+            || has("noncommercial")
+            || has("cc-by-nc")
+            || has("cc by-nc")
+            || has("research purposes only")
+        {
+            LicenseFamily::NonCommercial
+        } else if has("apache license") && has("version 2.0") {
+            LicenseFamily::Apache2
+        } else if has("llama") && has("community license") {
+            LicenseFamily::LlamaCommunity
+        } else if has("gemma terms of use") {
+            LicenseFamily::Gemma
+        } else if has("mit license") || has("permission is hereby granted, free of charge") {
+            LicenseFamily::Mit
+        } else {
+            LicenseFamily::Other
+        }
+    }
+}
+
+impl fmt::Display for LicenseFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LicenseFamily::Apache2 => "apache-2.0",
+            LicenseFamily::Mit => "mit",
+            LicenseFamily::LlamaCommunity => "llama-community",
+            LicenseFamily::Gemma => "gemma",
+            LicenseFamily::NonCommercial => "non-commercial",
+            LicenseFamily::Other => "other",
+        })
+    }
+}
+
+/// The license layers of one model, read from the blob store.
+#[derive(Debug, Clone, Default)]
+pub struct ModelLicense {
+    /// Text of each readable license layer, in manifest order
+    pub texts: Vec<String>,
+    /// Digests of license layers whose blob is absent or unreadable
+    pub missing: Vec<Digest>,
+}
+
+impl ModelLicense {
+    /// Read every license layer among `layers` from `blobs_root`.
+    #[must_use]
+    pub fn read(layers: &[LayerInfo], blobs_root: &Path) -> Self {
+        let mut out = ModelLicense::default();
+        for layer in layers.iter().filter(|l| l.kind() == LayerKind::License) {
+            match fs::read(layer.digest.blob_path(blobs_root)) {
+                Ok(bytes) => out.texts.push(String::from_utf8_lossy(&bytes).into_owned()),
+                Err(_) => out.missing.push(layer.digest),
+            }
+        }
+        out
+    }
+
+    /// Whether the manifest declares no license layer at all.
+    #[must_use]
+    pub fn is_absent(&self) -> bool {
+        self.texts.is_empty() && self.missing.is_empty()
+    }
+
+    /// Family of the readable license text, if any. With several layers the most
+    /// restrictive (non-commercial) one wins, otherwise the first.
+    #[must_use]
+    pub fn family(&self) -> Option<LicenseFamily> {
+        let families: Vec<_> = self
+            .texts
+            .iter()
+            .map(|t| LicenseFamily::detect(t))
+            .collect();
+        families
+            .iter()
+            .find(|&&f| f == LicenseFamily::NonCommercial)
+            .or(families.first())
+            .copied()
+    }
+
+    /// Case-insensitive search across all readable license text.
+    #[must_use]
+    pub fn contains(&self, needle: &str) -> bool {
+        let needle = needle.to_lowercase();
+        self.texts
+            .iter()
+            .any(|t| t.to_lowercase().contains(&needle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_family() {
+        let cases = [
+            (
+                "Apache License\nVersion 2.0, January 2004",
+                LicenseFamily::Apache2,
+            ),
+            (
+                "META LLAMA 3 COMMUNITY LICENSE AGREEMENT",
+                LicenseFamily::LlamaCommunity,
+            ),
+            ("Gemma Terms of Use", LicenseFamily::Gemma),
+            (
+                "Attribution-NonCommercial 4.0 International",
+                LicenseFamily::NonCommercial,
+            ),
+            ("MIT License\n\nCopyright (c) 2024", LicenseFamily::Mit),
+            ("All rights reserved.", LicenseFamily::Other),
+        ];
+        for (text, family) in cases {
+            assert_eq!(LicenseFamily::detect(text), family, "{text}");
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Copies of manifests taken before they are overwritten, kept under
+//! `manifests/.bak/<host>/<namespace>/<model>/<tag>/`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Serialize;
+
+use crate::{Error, ManifestStore, ModelId, Result, write_atomic};
+
+/// Directory under the manifests root that holds backups; scans never descend into it.
+pub const BACKUP_DIR: &str = ".bak";
+
+/// Backups kept per model unless set with [`ManifestStore::with_backup_retention`].
+pub const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+/// One saved copy of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestBackup {
+    pub path: PathBuf,
+    /// Seconds since the epoch when the copy was taken
+    pub taken_at: u64,
+    /// Distinguishes copies taken within the same second (file `<taken_at>-<seq>`)
+    #[serde(skip)]
+    pub seq: u32,
+}
+
+impl ManifestBackup {
+    fn parse(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        let (secs, seq) = match name.split_once('-') {
+            Some((secs, seq)) => (secs, seq.parse().ok()?),
+            None => (name, 0),
+        };
+        Some(ManifestBackup {
+            taken_at: secs.parse().ok()?,
+            seq,
+            path,
+        })
+    }
+}
+
+impl ManifestStore {
+    /// Write `body` as the manifest at `path` (a path under this store, normally from
+    /// [`ManifestStore::path_for`]). An existing, different manifest there is first
+    /// copied to the backup directory, which is then pruned to the retention limit.
+    pub fn write_manifest(&self, path: &Path, body: &[u8]) -> Result<Option<ManifestBackup>> {
+        let backup = match fs::read(path) {
+            Ok(current) if current == body => return Ok(None),
+            Ok(current) => Some(self.save_backup(path, &current)?),
+            Err(_) => None,
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| Error::Io {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        write_atomic(path, body)?;
+        Ok(backup)
+    }
+
+    /// Saved copies of the manifest of `id`, oldest first.
+    #[must_use]
+    pub fn backups(&self, id: &ModelId) -> Vec<ManifestBackup> {
+        self.backups_in(
+            &self
+                .root()
+                .join(BACKUP_DIR)
+                .join(self.relative_path_for(id)),
+        )
+    }
+
+    /// Put back the newest backup of `id` taken at or before `at` (seconds since the
+    /// epoch; the newest overall if `None`). The manifest being replaced is itself
+    /// backed up, so a restore can be undone the same way.
+    pub fn restore_backup(&self, id: &ModelId, at: Option<u64>) -> Result<ManifestBackup> {
+        let chosen = self
+            .backups(id)
+            .into_iter()
+            .rev()
+            .find(|b| at.is_none_or(|at| b.taken_at <= at))
+            .ok_or_else(|| Error::NoBackup {
+                model: id.normalize(),
+                at,
+            })?;
+        let body = fs::read(&chosen.path).map_err(|source| Error::Io {
+            path: chosen.path.clone(),
+            source,
+        })?;
+        // Refuse to restore something that would not load.
+        crate::parse_manifest::<crate::ManifestData>(&chosen.path, &body)?;
+        self.write_manifest(&self.path_for(id), &body)?;
+        Ok(chosen)
+    }
+
+    /// Delete the model `id`: its manifest is backed up like an overwrite and then
+    /// removed, along with directories left empty. Blobs are not touched; the backup
+    /// holds them back from GC (see [`crate::GcPlan::held`]), so
+    /// [`ManifestStore::restore_backup`] undoes the removal.
+    pub fn remove_model(&self, id: &ModelId) -> Result<ManifestBackup> {
+        let stored = self
+            .find(id)
+            .ok_or_else(|| Error::ModelNotFound(id.normalize()))?;
+        let io_err = |source| Error::Io {
+            path: stored.path.clone(),
+            source,
+        };
+        let body = fs::read(&stored.path).map_err(io_err)?;
+        let backup = self.save_backup(&stored.path, &body)?;
+        fs::remove_file(&stored.path).map_err(io_err)?;
+        let mut dir = stored.path.parent();
+        while let Some(d) = dir
+            && d != self.root()
+            && fs::remove_dir(d).is_ok()
+        {
+            dir = d.parent();
+        }
+        Ok(backup)
+    }
+
+    fn save_backup(&self, manifest_path: &Path, body: &[u8]) -> Result<ManifestBackup> {
+        let relative = manifest_path
+            .strip_prefix(self.root())
+            .map_err(|_| Error::InvalidComponentPath(manifest_path.to_path_buf()))?;
+        let dir = self.root().join(BACKUP_DIR).join(relative);
+        let taken_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        // After the newest copy of the same second, even if older ones were pruned.
+        let seq = self
+            .backups_in(&dir)
+            .iter()
+            .filter(|b| b.taken_at == taken_at)
+            .map(|b| b.seq + 1)
+            .max()
+            .unwrap_or(0);
+        let path = dir.join(if seq == 0 {
+            taken_at.to_string()
+        } else {
+            format!("{taken_at}-{seq}")
+        });
+        fs::create_dir_all(&dir).map_err(|source| Error::Io {
+            path: dir.clone(),
+            source,
+        })?;
+        write_atomic(&path, body)?;
+
+        let existing = self.backups_in(&dir);
+        for old in existing
+            .iter()
+            .take(existing.len().saturating_sub(self.backup_retention()))
+        {
+            let _ = fs::remove_file(&old.path);
+        }
+        Ok(ManifestBackup {
+            path,
+            taken_at,
+            seq,
+        })
+    }
+
+    fn backups_in(&self, dir: &Path) -> Vec<ManifestBackup> {
+        let mut found: Vec<ManifestBackup> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| ManifestBackup::parse(e.ok()?.path()))
+            .collect();
+        found.sort_by_key(|b| (b.taken_at, b.seq));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ManifestStore::new(dir.path()).with_backup_retention(2);
+        let id: ModelId = "llama3:8b".parse().unwrap();
+        let path = store.path_for(&id);
+        let version = |n: u32| format!(r#"{{"schemaVersion":2,"layers":[],"v":{n}}}"#);
+
+        assert!(
+            store
+                .write_manifest(&path, version(1).as_bytes())
+                .unwrap()
+                .is_none()
+        );
+        for n in 2..=4 {
+            assert!(
+                store
+                    .write_manifest(&path, version(n).as_bytes())
+                    .unwrap()
+                    .is_some()
+            );
+        }
+        // Unchanged content takes no backup.
+        assert!(
+            store
+                .write_manifest(&path, version(4).as_bytes())
+                .unwrap()
+                .is_none()
+        );
+        let backups = store.backups(&id);
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[1].path).unwrap(), version(3));
+
+        store.restore_backup(&id, None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), version(3));
+        // The restored-over version is kept too.
+        let last = store.backups(&id).pop().unwrap();
+        assert_eq!(fs::read_to_string(last.path).unwrap(), version(4));
+        assert!(matches!(
+            store.restore_backup(&id, Some(0)),
+            Err(Error::NoBackup { .. })
+        ));
+
+        let removed = store.remove_model(&id).unwrap();
+        assert_eq!(fs::read_to_string(removed.path).unwrap(), version(3));
+        assert!(!path.exists() && !path.parent().unwrap().exists());
+        store.restore_backup(&id, None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), version(3));
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A shared media type string. A store uses only a handful of distinct media types
+/// across all its layers, so the first [`MAX_INTERNED`] are allocated once and shared;
+/// clones and comparisons are cheap.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MediaType(Arc<str>);
+
+/// Distinct media types kept for sharing. Strings from peers and registries are not
+/// trusted to be few, so past this each new one gets its own allocation, freed with it.
+const MAX_INTERNED: usize = 256;
+
+static INTERNED: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+
+impl MediaType {
+    /// Intern a media type string.
+    #[must_use]
+    pub fn new(media_type: &str) -> Self {
+        let mut set = INTERNED
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(interned) = set.get(media_type) {
+            return MediaType(Arc::clone(interned));
+        }
+        let shared: Arc<str> = media_type.into();
+        if set.len() < MAX_INTERNED {
+            set.insert(Arc::clone(&shared));
+        }
+        MediaType(shared)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether this is a well-formed `type/subtype` (optionally with parameters).
+    /// Manifests with other media types still load; `verify-manifests` reports them.
+    #[must_use]
+    pub fn is_well_formed(&self) -> bool {
+        let essence = self.0.split(';').next().unwrap_or_default();
+        essence
+            .split_once('/')
+            .is_some_and(|(ty, sub)| !ty.is_empty() && !sub.trim().is_empty())
+    }
+}
+
+impl AsRef<str> for MediaType {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for MediaType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for MediaType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: std::borrow::Cow<'de, str> = Deserialize::deserialize(deserializer)?;
+        Ok(MediaType::new(&s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_type_interned() {
+        let a = MediaType::new("application/vnd.ollama.image.model");
+        let b: MediaType = serde_json::from_str("\"application/vnd.ollama.image.model\"").unwrap();
+        assert_eq!(a, b);
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert!(a.is_well_formed());
+
+        let odd: MediaType = serde_json::from_str("\"model\"").unwrap();
+        assert!(!odd.is_well_formed());
+        // Past the bound, new strings are still equal by value but no longer kept.
+        for i in 0..MAX_INTERNED {
+            let _ = MediaType::new(&format!("application/x-test-{i}"));
+        }
+        let len = INTERNED.get().unwrap().lock().unwrap().len();
+        assert!(len <= MAX_INTERNED, "{len}");
+        let (c, d) = (
+            MediaType::new("application/x-late"),
+            MediaType::new("application/x-late"),
+        );
+        assert_eq!(c, d);
+    }
+}
@@ -1,45 +1,191 @@
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use mime::Mime;
 use serde::{Deserialize, Serialize};
 
-mod mime_serde {
-    use mime::Mime;
-    use serde::Serializer;
+use crate::{Digest, MediaType, Repair, gguf::GgufSummary};
 
-    pub fn serialize<S>(mime: &Mime, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&mime.to_string())
-    }
+/// Media type of the manifests Ollama writes.
+pub const MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+/// Media type of a manifest's config blob.
+pub const CONFIG_MEDIA_TYPE: &str = "application/vnd.docker.container.image.v1+json";
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Mime, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s: &str = serde::Deserialize::deserialize(deserializer)?;
-        s.parse().map_err(serde::de::Error::custom)
-    }
-}
-
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ManifestData {
     #[serde(default)]
     pub layers: Vec<LayerInfo>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub config: Option<LayerInfo>,
+    /// OCI manifest annotations, when the publisher set any
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Fields not modeled above (`schemaVersion`, `mediaType`, ...), kept so a manifest
+    /// can be written back unchanged
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ManifestData {
+    /// A schema 2 manifest as written by `ollama pull`.
+    #[must_use]
+    pub fn new(config: LayerInfo, layers: Vec<LayerInfo>) -> Self {
+        let mut extra = serde_json::Map::new();
+        extra.insert("schemaVersion".to_string(), 2.into());
+        extra.insert("mediaType".to_string(), MANIFEST_MEDIA_TYPE.into());
+        ManifestData {
+            layers,
+            config: Some(config),
+            annotations: BTreeMap::new(),
+            extra,
+        }
+    }
+}
+
+/// Borrowed view of a manifest for hot paths that only need digests and sizes (GC
+/// marking, usage totals). Strings point into the input buffer unless the JSON escapes
+/// them, and annotations and other fields are skipped without being parsed.
+#[derive(Deserialize, Debug)]
+pub struct ManifestDataRef<'a> {
+    #[serde(default, borrow)]
+    pub layers: Vec<LayerInfoRef<'a>>,
+    #[serde(default, borrow)]
+    pub config: Option<LayerInfoRef<'a>>,
+}
+
+impl ManifestDataRef<'_> {
+    /// Layers followed by the config blob, if any.
+    pub fn entries(&self) -> impl Iterator<Item = &LayerInfoRef<'_>> {
+        self.layers.iter().chain(self.config.iter())
+    }
+}
+
+/// Borrowed counterpart of [`LayerInfo`]; the digest is validated on demand.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LayerInfoRef<'a> {
+    #[serde(borrow)]
+    pub digest: Cow<'a, str>,
+    #[serde(rename = "mediaType", borrow)]
+    pub media_type: Cow<'a, str>,
+    pub size: Option<u64>,
+}
+
+impl LayerInfoRef<'_> {
+    pub fn digest(&self) -> crate::Result<Digest> {
+        self.digest.parse()
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> LayerKind {
+        LayerKind::from_media_type(&self.media_type)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LayerInfo {
-    pub digest: String,
+    pub digest: Digest,
     #[serde(rename = "mediaType")]
-    #[serde(with = "mime_serde")]
-    pub media_type: Mime,
+    pub media_type: MediaType,
     pub size: Option<u64>,
 }
 
+/// Role of a layer, derived from its media type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerKind {
+    /// Model weights (`application/vnd.ollama.image.model`)
+    Weights,
+    Adapter,
+    /// Vision projector
+    Projector,
+    Template,
+    System,
+    Params,
+    Messages,
+    License,
+    /// Image config blob
+    Config,
+    Other,
+}
+
+impl LayerKind {
+    /// Classify an Ollama media type (`application/vnd.ollama.image.<kind>`).
+    #[must_use]
+    pub fn from_media_type(media_type: &str) -> Self {
+        match media_type.strip_prefix("application/vnd.ollama.image.") {
+            Some("model") => LayerKind::Weights,
+            Some("adapter") => LayerKind::Adapter,
+            Some("projector") => LayerKind::Projector,
+            // `prompt` is the pre-template name for the same layer.
+            Some("template" | "prompt") => LayerKind::Template,
+            Some("system") => LayerKind::System,
+            Some("params") => LayerKind::Params,
+            Some("messages") => LayerKind::Messages,
+            Some("license") => LayerKind::License,
+            _ if media_type.starts_with("application/vnd.docker.container.image") => {
+                LayerKind::Config
+            }
+            _ => LayerKind::Other,
+        }
+    }
+
+    /// Media type Ollama writes for this kind (`application/octet-stream` for `Other`).
+    #[must_use]
+    pub fn media_type(self) -> &'static str {
+        match self {
+            LayerKind::Weights => "application/vnd.ollama.image.model",
+            LayerKind::Adapter => "application/vnd.ollama.image.adapter",
+            LayerKind::Projector => "application/vnd.ollama.image.projector",
+            LayerKind::Template => "application/vnd.ollama.image.template",
+            LayerKind::System => "application/vnd.ollama.image.system",
+            LayerKind::Params => "application/vnd.ollama.image.params",
+            LayerKind::Messages => "application/vnd.ollama.image.messages",
+            LayerKind::License => "application/vnd.ollama.image.license",
+            LayerKind::Config => CONFIG_MEDIA_TYPE,
+            LayerKind::Other => "application/octet-stream",
+        }
+    }
+}
+
+impl std::fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LayerKind::Weights => "weights",
+            LayerKind::Adapter => "adapter",
+            LayerKind::Projector => "projector",
+            LayerKind::Template => "template",
+            LayerKind::System => "system",
+            LayerKind::Params => "params",
+            LayerKind::Messages => "messages",
+            LayerKind::License => "license",
+            LayerKind::Config => "config",
+            LayerKind::Other => "other",
+        })
+    }
+}
+
+impl LayerInfo {
+    #[must_use]
+    pub fn kind(&self) -> LayerKind {
+        LayerKind::from_media_type(self.media_type.as_ref())
+    }
+}
+
+/// Declared bytes per layer kind across a manifest's layers and config.
+#[must_use]
+pub fn size_breakdown<'a>(
+    layers: impl IntoIterator<Item = &'a LayerInfo>,
+) -> BTreeMap<LayerKind, u64> {
+    let mut out = BTreeMap::new();
+    for l in layers {
+        *out.entry(l.kind()).or_default() += l.size.unwrap_or(0);
+    }
+    out
+}
+
 #[derive(Debug, Serialize)]
 pub struct ListedModel {
     /// Normalized display name (matches `ollama list` style)
@@ -48,6 +194,10 @@ pub struct ListedModel {
     pub model_id: ModelId,
     /// Filesystem path to manifest
     pub manifest_path: PathBuf,
+    /// SHA-256 of the manifest file; `ollama list` shows its first 12 hex digits as the
+    /// ID (if verbose)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_digest: Option<Digest>,
     /// Layers (if verbose)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub layers: Option<Vec<LayerInfo>>,
@@ -56,15 +206,46 @@ pub struct ListedModel {
     /// Total summed size (if verbose)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total_size: Option<u64>,
+    /// Declared bytes per layer kind, e.g. how much is a vision projector (if verbose)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_breakdown: Option<BTreeMap<LayerKind, u64>>,
     /// Manifest mtime (if verbose)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mtime: Option<u64>,
     /// Primary model blob path (if `blob_paths`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub primary_blob_path: Option<PathBuf>,
+    /// Architecture, parameter count, quantization and context length from the GGUF
+    /// header of the primary blob (if verbose and it is a readable GGUF file)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gguf: Option<GgufSummary>,
     /// All blob paths (if `blob_paths`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub blob_paths: Option<Vec<BlobPathInfo>>,
+    /// Fixes the lenient parser had to make to read the manifest
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<Repair>,
+    /// Whether a running Ollama server has it loaded (only when asked for)
+    #[serde(flatten)]
+    pub runtime: Option<RuntimeState>,
+}
+
+/// In-memory state of a model as reported by a running Ollama server (`/api/ps`).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuntimeState {
+    pub running: bool,
+    /// API endpoints that reported the model loaded; the memory fields come from the first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<String>,
+    /// Bytes in memory (system RAM plus VRAM)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_in_memory: Option<u64>,
+    /// Bytes held in GPU memory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_vram: Option<u64>,
+    /// When the server will unload it (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
 }
 
 impl ListedModel {
@@ -74,25 +255,46 @@ impl ListedModel {
             name: model_id.normalize(),
             model_id,
             manifest_path: manifest_path.into(),
+            manifest_digest: None,
             layers: None,
             config: None,
             total_size: None,
+            size_breakdown: None,
             mtime: None,
             primary_blob_path: None,
+            gguf: None,
             blob_paths: None,
+            repairs: Vec::new(),
+            runtime: None,
         }
     }
 
+    /// Fill in the verbose details from the parsed manifest, with the primary blob chosen
+    /// by [`crate::WeightsFirst`].
     #[must_use]
     pub fn into_verbose(self, manifest: ManifestData, blobs_root: impl AsRef<Path>) -> Self {
+        self.into_verbose_with(manifest, blobs_root, &crate::WeightsFirst)
+    }
+
+    /// [`ListedModel::into_verbose`] with another [`crate::PrimarySelector`].
+    #[must_use]
+    pub fn into_verbose_with(
+        self,
+        manifest: ManifestData,
+        blobs_root: impl AsRef<Path>,
+        selector: &dyn crate::PrimarySelector,
+    ) -> Self {
         let blobs_root = blobs_root.as_ref();
         let total_size = crate::compute_total_size(&manifest.layers, manifest.config.as_ref());
+        let size_breakdown = size_breakdown(manifest.layers.iter().chain(manifest.config.iter()));
         let mtime = crate::compute_mtime(&self.manifest_path);
-        let (primary_digest, mut infos) =
-            crate::build_blob_infos(&manifest.layers, manifest.config.as_ref(), blobs_root);
-        let primary_blob_path = primary_digest
-            .as_ref()
-            .map(|d| crate::digest_to_blob_path(blobs_root, d));
+        let (primary_digest, mut infos) = crate::build_blob_infos_with(
+            &manifest.layers,
+            manifest.config.as_ref(),
+            blobs_root,
+            selector,
+        );
+        let primary_blob_path = primary_digest.map(|d| d.blob_path(blobs_root));
         if let Some(pd) = primary_digest {
             for bi in &mut infos {
                 if bi.digest == pd {
@@ -100,31 +302,174 @@ impl ListedModel {
                 }
             }
         }
+        // Only local weights are opened: reading a cloud placeholder would download it.
+        let gguf = infos
+            .iter()
+            .find(|b| {
+                b.primary
+                    && !b.offline
+                    && LayerKind::from_media_type(b.media_type.as_ref()) == LayerKind::Weights
+                    && b.sizes.actual.is_some_and(|n| n > 0)
+            })
+            .and_then(|b| GgufSummary::read(&b.path));
         ListedModel {
             layers: Some(manifest.layers),
             config: manifest.config,
             total_size,
+            size_breakdown: Some(size_breakdown),
             mtime,
             primary_blob_path,
+            gguf,
             blob_paths: Some(infos),
             ..self
         }
     }
+
+    /// Whether any referenced blob is missing or has the wrong size; `None` when the
+    /// record carries no blob details (non-verbose scans).
+    #[must_use]
+    pub fn is_broken(&self) -> Option<bool> {
+        self.blob_paths
+            .as_ref()
+            .map(|blobs| blobs.iter().any(|b| b.status.is_problem()))
+    }
+
+    /// Remove fields that change without the model's content changing (timestamps).
+    pub fn strip_volatile(&mut self) {
+        self.mtime = None;
+    }
+
+    /// Put the record in a stable form for diffing: layers and blob paths sorted by
+    /// digest, and volatile fields removed.
+    pub fn canonicalize(&mut self) {
+        if let Some(layers) = &mut self.layers {
+            layers.sort_by_key(|l| l.digest);
+        }
+        if let Some(blobs) = &mut self.blob_paths {
+            blobs.sort_by_key(|b| b.digest);
+        }
+        self.strip_volatile();
+    }
 }
 
 #[derive(Debug, serde::Serialize, Clone)]
 pub struct BlobPathInfo {
-    pub digest: String,
-    #[serde(with = "mime_serde")]
-    pub media_type: Mime,
-    pub declared_size: Option<u64>,
+    pub digest: Digest,
+    pub media_type: MediaType,
+    pub sizes: BlobSizes,
     pub path: PathBuf,
-    pub exists: bool,
-    pub size_ok: Option<bool>, // Only Some if both declared & actual size available
-    pub actual_size: Option<u64>,
+    pub status: BlobStatus,
+    /// Cloud placeholder whose content is not local; hashing it would force a download
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub offline: bool,
     pub primary: bool,
 }
 
+/// State of a referenced blob on disk, judged from its sizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobStatus {
+    /// Present with the declared size
+    Ok,
+    Missing,
+    /// Present but zero bytes, though the manifest declares content
+    Empty,
+    /// Smaller than declared, typically an interrupted copy or download
+    Truncated,
+    /// Larger than declared
+    SizeMismatch,
+    /// Present, but the size cannot be judged: no declared size, or a cloud placeholder
+    /// whose local size says nothing about its content
+    Unverified,
+}
+
+impl BlobStatus {
+    /// Judge a blob from its sizes (`actual` is `None` when the file does not exist).
+    #[must_use]
+    pub fn from_sizes(sizes: &BlobSizes, offline: bool) -> Self {
+        let Some(actual) = sizes.actual else {
+            return BlobStatus::Missing;
+        };
+        match sizes.declared {
+            _ if offline => BlobStatus::Unverified,
+            None => BlobStatus::Unverified,
+            Some(declared) if declared == actual => BlobStatus::Ok,
+            Some(_) if actual == 0 => BlobStatus::Empty,
+            Some(declared) if actual < declared => BlobStatus::Truncated,
+            Some(_) => BlobStatus::SizeMismatch,
+        }
+    }
+
+    /// Whether the blob file exists at all.
+    #[must_use]
+    pub fn is_present(self) -> bool {
+        self != BlobStatus::Missing
+    }
+
+    /// Whether the blob is known to be unusable (missing or wrong size).
+    #[must_use]
+    pub fn is_problem(self) -> bool {
+        !matches!(self, BlobStatus::Ok | BlobStatus::Unverified)
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlobStatus::Ok => "ok",
+            BlobStatus::Missing => "missing",
+            BlobStatus::Empty => "empty",
+            BlobStatus::Truncated => "truncated",
+            BlobStatus::SizeMismatch => "size_mismatch",
+            BlobStatus::Unverified => "unverified",
+        }
+    }
+}
+
+impl std::fmt::Display for BlobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The different ways of measuring a blob.
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlobSizes {
+    /// Size recorded in the manifest
+    pub declared: Option<u64>,
+    /// Apparent size of the file on disk
+    pub actual: Option<u64>,
+    /// Bytes actually allocated on disk (block count), if the platform reports it
+    pub physical: Option<u64>,
+}
+
+impl BlobSizes {
+    /// Whether the declared and actual sizes agree; `None` unless both are known.
+    #[must_use]
+    pub fn size_ok(&self) -> Option<bool> {
+        Some(self.declared? == self.actual?)
+    }
+
+    /// Bytes saved on disk by sparseness or transparent compression (apparent minus
+    /// allocated), if both are known.
+    #[must_use]
+    pub fn allocation_savings(&self) -> Option<u64> {
+        Some(self.actual?.saturating_sub(self.physical?))
+    }
+
+    /// Accumulate another set of sizes. A field stays `None` only while no
+    /// contribution for it has been seen.
+    pub fn add(&mut self, other: &BlobSizes) {
+        fn sum(a: &mut Option<u64>, b: Option<u64>) {
+            if let Some(b) = b {
+                *a = Some(a.unwrap_or(0) + b);
+            }
+        }
+        sum(&mut self.declared, other.declared);
+        sum(&mut self.actual, other.actual);
+        sum(&mut self.physical, other.physical);
+    }
+}
+
 /// Internal helper grouping the model identity parts.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModelId {
@@ -134,17 +479,26 @@ pub struct ModelId {
     pub tag: String,
 }
 
+/// Registry host Ollama assumes when a name has none.
+pub const DEFAULT_HOST: &str = "registry.ollama.ai";
+/// Namespace of the official model library.
+pub const LIBRARY_NAMESPACE: &str = "library";
+/// Tag Ollama assumes when a name has none.
+pub const DEFAULT_TAG: &str = "latest";
+
 impl ModelId {
-    /// Attempt to mirror Ollama list naming rules
+    /// Attempt to mirror Ollama list naming rules. Punycode hosts are shown decoded
+    /// (`xn--bcher-kva.example` as `bücher.example`).
     pub fn normalize(&self) -> String {
         let Self {
-            host,
             namespace,
             model,
             tag,
+            ..
         } = self;
-        let default_host = "registry.ollama.ai";
-        let library_ns = "library";
+        let host = self.display_host();
+        let default_host = DEFAULT_HOST;
+        let library_ns = LIBRARY_NAMESPACE;
         match (host, namespace) {
             (Some(h), Some(ns)) if h == default_host && ns == library_ns => {
                 format!("{model}:{tag}")
@@ -156,12 +510,122 @@ impl ModelId {
             _ => format!("{model}:{tag}"),
         }
     }
+
+    /// Namespace in the same short form as [`ModelId::normalize`]: `library` or `team`
+    /// on the default registry, `host/team` elsewhere.
+    #[must_use]
+    pub fn qualified_namespace(&self) -> String {
+        let ns = self.namespace.as_deref().unwrap_or(LIBRARY_NAMESPACE);
+        match self.display_host().as_deref() {
+            Some(h) if h != DEFAULT_HOST => format!("{h}/{ns}"),
+            _ => ns.to_string(),
+        }
+    }
+
+    /// The host with any punycode labels decoded; `host` keeps the form found on disk.
+    #[must_use]
+    pub fn display_host(&self) -> Option<String> {
+        let host = self.host.as_deref()?;
+        Some(crate::punycode::decode_host(host).unwrap_or_else(|| host.to_string()))
+    }
+
+    /// The model's page on ollama.com (`https://ollama.com/library/llama3.1:8b`, or
+    /// `https://ollama.com/<namespace>/<model>:<tag>` for a community namespace);
+    /// `None` for models from other registries.
+    #[must_use]
+    pub fn web_url(&self) -> Option<String> {
+        if self.display_host().is_some_and(|h| h != DEFAULT_HOST) {
+            return None;
+        }
+        let ns = self.namespace.as_deref().unwrap_or(LIBRARY_NAMESPACE);
+        Some(format!(
+            "https://ollama.com/{ns}/{}:{}",
+            self.model, self.tag
+        ))
+    }
+}
+
+impl FromStr for ModelId {
+    type Err = crate::Error;
+
+    /// Parse a name the way a user would type it (`mistral`, `mistral:7b`,
+    /// `ns/model:tag`, `host/ns/model:tag`), filling in Ollama's defaults. Hosts may
+    /// carry a port (`myregistry.local:5000/ns/model:tag`); a port-qualified first part
+    /// is always the host, so `myregistry.local:5000/model` is in `library`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::Error::InvalidModelName(s.to_string());
+        let (path, tag) = match s.rsplit_once(':') {
+            Some((path, tag)) if !tag.contains('/') => (path, tag),
+            _ => (s, DEFAULT_TAG),
+        };
+        let parts: Vec<&str> = path.split('/').collect();
+        if tag.is_empty() || parts.iter().any(|p| p.is_empty()) {
+            return Err(invalid());
+        }
+        let (host, namespace, model) = match parts.as_slice() {
+            [model] => (DEFAULT_HOST, LIBRARY_NAMESPACE, *model),
+            [host, model] if host.contains(':') => (*host, LIBRARY_NAMESPACE, *model),
+            [namespace, model] => (DEFAULT_HOST, *namespace, *model),
+            [host, namespace, model] => (*host, *namespace, *model),
+            _ => return Err(invalid()),
+        };
+        if let Some((name, port)) = host.rsplit_once(':')
+            && (name.is_empty() || port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        Ok(ModelId {
+            host: Some(host.to_string()),
+            namespace: Some(namespace.to_string()),
+            model: model.to_string(),
+            tag: tag.to_string(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_manifest_data_ref_borrows() {
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        let json = format!(
+            r#"{{"schemaVersion":2,"annotations":{{"k":"v"}},
+                "config":{{"digest":"{digest}","mediaType":"application/vnd.docker.container.image.v1+json","size":2}},
+                "layers":[{{"digest":"{digest}","mediaType":"application\/vnd.ollama.image.model","size":1}}]}}"#
+        );
+        let m: ManifestDataRef = serde_json::from_str(&json).unwrap();
+        assert_eq!(m.entries().count(), 2);
+        let layer = &m.layers[0];
+        assert!(matches!(layer.digest, Cow::Borrowed(_)));
+        // Escaped strings fall back to an owned copy.
+        assert!(matches!(layer.media_type, Cow::Owned(_)));
+        assert_eq!(layer.kind(), LayerKind::Weights);
+        assert_eq!(layer.digest().unwrap().to_string(), digest);
+    }
+
+    #[test]
+    fn test_blob_status() {
+        let sizes = |declared, actual| BlobSizes {
+            declared,
+            actual,
+            physical: None,
+        };
+        let cases = [
+            (sizes(Some(10), Some(10)), false, BlobStatus::Ok),
+            (sizes(Some(10), None), false, BlobStatus::Missing),
+            (sizes(Some(10), Some(0)), false, BlobStatus::Empty),
+            (sizes(Some(10), Some(4)), false, BlobStatus::Truncated),
+            (sizes(Some(10), Some(12)), false, BlobStatus::SizeMismatch),
+            (sizes(None, Some(12)), false, BlobStatus::Unverified),
+            (sizes(Some(10), Some(0)), true, BlobStatus::Unverified),
+        ];
+        for (s, offline, expected) in cases {
+            assert_eq!(BlobStatus::from_sizes(&s, offline), expected);
+        }
+    }
+
     #[test]
     pub fn test_normalize() {
         assert_eq!(
@@ -184,6 +648,16 @@ mod tests {
             .normalize(),
             "apple/OpenELM:latest"
         );
+        assert_eq!(
+            ModelId {
+                host: Some("example.com".to_string()),
+                namespace: Some("team".to_string()),
+                model: "m".to_string(),
+                tag: "latest".to_string(),
+            }
+            .qualified_namespace(),
+            "example.com/team"
+        );
         assert_eq!(
             ModelId {
                 host: None,
@@ -214,5 +688,57 @@ mod tests {
             .normalize(),
             "phi4:latest"
         );
+        let web_url = |name: &str| name.parse::<ModelId>().unwrap().web_url();
+        assert_eq!(
+            web_url("llama3.1:8b").as_deref(),
+            Some("https://ollama.com/library/llama3.1:8b")
+        );
+        assert_eq!(
+            web_url("apple/OpenELM").as_deref(),
+            Some("https://ollama.com/apple/OpenELM:latest")
+        );
+        assert_eq!(web_url("hf.co/org/m:q4"), None);
+    }
+
+    #[test]
+    pub fn test_parse_name() {
+        let id: ModelId = "mistral".parse().unwrap();
+        assert_eq!(id.normalize(), "mistral:latest");
+        assert_eq!(id.namespace.as_deref(), Some("library"));
+        let id: ModelId = "apple/OpenELM:1b".parse().unwrap();
+        assert_eq!(id.normalize(), "apple/OpenELM:1b");
+        let id: ModelId = "myhost/myns/lips:code".parse().unwrap();
+        assert_eq!(id.host.as_deref(), Some("myhost"));
+        assert!("a/b/c/d:e".parse::<ModelId>().is_err());
+        assert!("mistral:".parse::<ModelId>().is_err());
+
+        let id: ModelId = "myregistry.local:5000/team/m".parse().unwrap();
+        assert_eq!(id.host.as_deref(), Some("myregistry.local:5000"));
+        assert_eq!(id.tag, "latest");
+        assert_eq!(id.normalize(), "myregistry.local:5000/team/m:latest");
+        assert_eq!(id.qualified_namespace(), "myregistry.local:5000/team");
+        let id: ModelId = "localhost:5000/m:v1".parse().unwrap();
+        assert_eq!(id.normalize(), "localhost:5000/library/m:v1");
+        assert!("localhost:http/team/m".parse::<ModelId>().is_err());
+    }
+
+    #[test]
+    pub fn test_layer_kind() {
+        assert_eq!(
+            LayerKind::from_media_type("application/vnd.ollama.image.model"),
+            LayerKind::Weights
+        );
+        assert_eq!(
+            LayerKind::from_media_type("application/vnd.ollama.image.projector"),
+            LayerKind::Projector
+        );
+        assert_eq!(
+            LayerKind::from_media_type("application/vnd.docker.container.image.v1+json"),
+            LayerKind::Config
+        );
+        assert_eq!(
+            LayerKind::from_media_type("application/octet-stream"),
+            LayerKind::Other
+        );
     }
 }
@@ -0,0 +1,198 @@
+//! Pinned models, recorded in `manifests/.pinned/pins.json`, optionally with a hardlink
+//! of the manifest under `manifests/.pinned/<host>/<namespace>/<model>/<tag>`.
+//!
+//! A pin keeps the digests the model referenced when it was pinned, so GC holds on to
+//! those blobs (see [`crate::GcPlan::held`]) even after the manifest itself is removed;
+//! the linked copy lets the manifest be put back.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Digest, Error, ManifestStore, ModelId, Result, copy_atomic, write_atomic};
+
+/// Directory under the manifests root that holds pins; scans never descend into it.
+pub const PIN_DIR: &str = ".pinned";
+
+const PINS_FILE: &str = "pins.json";
+
+/// One pinned model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    /// Normalized model name
+    pub model: String,
+    /// Seconds since the epoch when the pin was taken
+    pub pinned_at: u64,
+    /// Config and layer digests of the manifest at that time
+    pub digests: Vec<Digest>,
+    /// Hardlink (or copy, where links are unsupported) of the manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy: Option<PathBuf>,
+}
+
+impl ManifestStore {
+    /// Pin `id`, replacing an earlier pin of it. With `link`, the manifest is also
+    /// hardlinked into [`PIN_DIR`] so it survives removal of the original.
+    pub fn pin(&self, id: &ModelId, link: bool) -> Result<Pin> {
+        let stored = self
+            .find(id)
+            .ok_or_else(|| Error::ModelNotFound(id.normalize()))?;
+        let mut digests: Vec<Digest> = stored
+            .data
+            .layers
+            .iter()
+            .chain(stored.data.config.iter())
+            .map(|l| l.digest)
+            .collect();
+        digests.sort_unstable();
+        digests.dedup();
+
+        let copy = if link {
+            let to = self.pin_dir().join(self.relative_path_for(&stored.id));
+            link_or_copy(&stored.path, &to)?;
+            Some(to)
+        } else {
+            None
+        };
+        let pin = Pin {
+            model: stored.id.normalize(),
+            pinned_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            digests,
+            copy,
+        };
+
+        let mut pins = self.pins()?;
+        if let Some(old) = pins.iter().find(|p| p.model == pin.model)
+            && pin.copy.is_none()
+            && let Some(old_copy) = &old.copy
+        {
+            let _ = fs::remove_file(old_copy);
+        }
+        pins.retain(|p| p.model != pin.model);
+        pins.push(pin.clone());
+        self.write_pins(&pins)?;
+        Ok(pin)
+    }
+
+    /// Remove the pin of `id` and its linked copy, returning it if there was one.
+    pub fn unpin(&self, id: &ModelId) -> Result<Option<Pin>> {
+        let wanted = id.normalize();
+        let mut pins = self.pins()?;
+        let Some(at) = pins.iter().position(|p| p.model == wanted) else {
+            return Ok(None);
+        };
+        let pin = pins.remove(at);
+        self.write_pins(&pins)?;
+        if let Some(copy) = &pin.copy {
+            let _ = fs::remove_file(copy);
+        }
+        Ok(Some(pin))
+    }
+
+    /// Every pin, in the order taken.
+    pub fn pins(&self) -> Result<Vec<Pin>> {
+        let path = self.pin_dir().join(PINS_FILE);
+        match fs::read(&path) {
+            Ok(body) => {
+                serde_json::from_slice(&body).map_err(|source| Error::Json { path, source })
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(source) => Err(Error::Io { path, source }),
+        }
+    }
+
+    /// Put the pinned copy of `id` back in place of its manifest (see
+    /// [`ManifestStore::write_manifest`]), returning the manifest path.
+    pub fn restore_pinned(&self, id: &ModelId) -> Result<PathBuf> {
+        let wanted = id.normalize();
+        let copy = self
+            .pins()?
+            .into_iter()
+            .find(|p| p.model == wanted)
+            .and_then(|p| p.copy)
+            .ok_or(Error::ModelNotFound(wanted))?;
+        let body = fs::read(&copy).map_err(|source| Error::Io {
+            path: copy.clone(),
+            source,
+        })?;
+        crate::parse_manifest::<crate::ManifestData>(&copy, &body)?;
+        let path = self.path_for(id);
+        self.write_manifest(&path, &body)?;
+        Ok(path)
+    }
+
+    fn pin_dir(&self) -> PathBuf {
+        self.root().join(PIN_DIR)
+    }
+
+    fn write_pins(&self, pins: &[Pin]) -> Result<()> {
+        let dir = self.pin_dir();
+        fs::create_dir_all(&dir).map_err(|source| Error::Io {
+            path: dir.clone(),
+            source,
+        })?;
+        let path = dir.join(PINS_FILE);
+        let body = serde_json::to_vec_pretty(pins).map_err(|source| Error::Json {
+            path: path.clone(),
+            source,
+        })?;
+        write_atomic(&path, &body)
+    }
+}
+
+/// Hardlink `from` to `to`, replacing whatever is there, or copy it where the
+/// filesystem has no hardlinks.
+fn link_or_copy(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|source| Error::Io {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    let _ = fs::remove_file(to);
+    if fs::hard_link(from, to).is_err() {
+        copy_atomic(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LayerKind, fixtures::FakeStore, gc_plan};
+
+    #[test]
+    fn test_pin_keeps_blobs_and_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .build(dir.path())
+            .unwrap();
+        let (manifests, blobs) = (store.manifests(), store.blobs());
+        let id: ModelId = "llama3:8b".parse().unwrap();
+
+        let pin = manifests.pin(&id, true).unwrap();
+        assert_eq!(manifests.pins().unwrap()[0], pin);
+        let path = manifests.path_for(&id);
+        fs::remove_file(&path).unwrap();
+        // The pinned copy is not a model, but its blobs are held back from GC.
+        assert!(manifests.find(&id).is_none());
+        let mut plan = gc_plan(&manifests, &blobs).unwrap();
+        plan.release_held();
+        assert!(plan.unreachable.is_empty());
+        assert_eq!(plan.pinned.len(), pin.digests.len());
+
+        manifests.restore_pinned(&id).unwrap();
+        assert!(manifests.find(&id).is_some());
+        assert!(manifests.unpin(&id).unwrap().is_some());
+        assert!(!pin.copy.unwrap().exists());
+        assert!(manifests.pins().unwrap().is_empty());
+    }
+}
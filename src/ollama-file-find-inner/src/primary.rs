@@ -0,0 +1,84 @@
+use std::fmt;
+
+use crate::{LayerInfo, LayerKind};
+
+/// Chooses the layer [`crate::ListedModel::primary_blob_path`] points at. Set one on a
+/// scan with [`crate::ScanArgs::with_primary_selector`]; the default is [`WeightsFirst`].
+pub trait PrimarySelector: fmt::Debug + Send + Sync {
+    /// Index into `layers` of the primary layer, or `None` to fall back to the config blob.
+    fn select(&self, layers: &[LayerInfo]) -> Option<usize>;
+}
+
+/// The largest weights layer: `application/vnd.ollama.image.model`, or a `tensor` layer
+/// such as safetensors imports write. Models without one fall back to the largest layer
+/// of any kind, so a projector or adapter bigger than the weights is never picked over
+/// them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightsFirst;
+
+impl PrimarySelector for WeightsFirst {
+    fn select(&self, layers: &[LayerInfo]) -> Option<usize> {
+        largest(layers, |l| is_weights_media_type(l.media_type.as_str()))
+            .or_else(|| largest(layers, |_| true))
+    }
+}
+
+/// The largest layer by declared size, whatever its kind (the heuristic before
+/// [`WeightsFirst`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LargestLayer;
+
+impl PrimarySelector for LargestLayer {
+    fn select(&self, layers: &[LayerInfo]) -> Option<usize> {
+        largest(layers, |_| true)
+    }
+}
+
+/// Whether a layer media type holds model weights: Ollama's GGUF `model` layer or a
+/// tensor layer (`application/vnd.ollama.image.tensor`, OCI `...tensor...` types).
+#[must_use]
+pub fn is_weights_media_type(media_type: &str) -> bool {
+    LayerKind::from_media_type(media_type) == LayerKind::Weights
+        || media_type.to_ascii_lowercase().contains("tensor")
+}
+
+/// Index of the first largest layer among those `keep` admits, ignoring layers without a
+/// declared size or with size 0.
+fn largest(layers: &[LayerInfo], keep: impl Fn(&LayerInfo) -> bool) -> Option<usize> {
+    let mut best: Option<(usize, u64)> = None;
+    for (i, l) in layers.iter().enumerate().filter(|(_, l)| keep(l)) {
+        if let Some(size) = l.size
+            && size > best.map_or(0, |(_, s)| s)
+        {
+            best = Some((i, size));
+        }
+    }
+    best.map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MediaType;
+
+    #[test]
+    fn test_weights_first() {
+        let layer = |kind: LayerKind, byte: u8, size: u64| LayerInfo {
+            digest: format!("sha256:{}", format!("{byte:02x}").repeat(32))
+                .parse()
+                .unwrap(),
+            media_type: MediaType::new(kind.media_type()),
+            size: Some(size),
+        };
+        // A vision model whose projector outweighs its (small, heavily quantized) weights.
+        let layers = [
+            layer(LayerKind::Weights, 1, 800),
+            layer(LayerKind::Projector, 2, 900),
+            layer(LayerKind::Template, 3, 10),
+        ];
+        assert_eq!(WeightsFirst.select(&layers), Some(0));
+        assert_eq!(LargestLayer.select(&layers), Some(1));
+        assert_eq!(WeightsFirst.select(&layers[1..]), Some(0));
+        assert!(is_weights_media_type("application/vnd.ollama.image.tensor"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{DEFAULT_HOST, Digest, LIBRARY_NAMESPACE, ManifestData, ModelId};
+
+/// OCI annotations that name where an image came from, in order of preference.
+const SOURCE_ANNOTATIONS: [&str; 2] = [
+    "org.opencontainers.image.source",
+    "org.opencontainers.image.url",
+];
+
+/// Who published a model, judged from where its manifest lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// `registry.ollama.ai/library`, the official model library (also where
+    /// `ollama create` puts names without a namespace)
+    OfficialLibrary,
+    /// A user or organization namespace on `registry.ollama.ai`
+    CommunityNamespace,
+    /// Any other registry (`hf.co`, a self-hosted one)
+    ThirdPartyRegistry,
+}
+
+/// Where a model came from and what vouches for it, from its name and manifest alone.
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    /// Registry host (punycode decoded)
+    pub registry: String,
+    pub namespace: String,
+    pub origin: Origin,
+    /// The manifest carries at least one signature or attestation layer (not verified)
+    pub signed: bool,
+    /// Digests of those layers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<Digest>,
+    /// Source URL from the manifest annotations, if the publisher set one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl Provenance {
+    #[must_use]
+    pub fn of(id: &ModelId, manifest: &ManifestData) -> Self {
+        let registry = id
+            .display_host()
+            .unwrap_or_else(|| DEFAULT_HOST.to_string());
+        let namespace = id
+            .namespace
+            .clone()
+            .unwrap_or_else(|| LIBRARY_NAMESPACE.to_string());
+        let origin = match (registry == DEFAULT_HOST, namespace == LIBRARY_NAMESPACE) {
+            (true, true) => Origin::OfficialLibrary,
+            (true, false) => Origin::CommunityNamespace,
+            (false, _) => Origin::ThirdPartyRegistry,
+        };
+        let signatures: Vec<Digest> = manifest
+            .layers
+            .iter()
+            .filter(|l| is_signature_media_type(l.media_type.as_str()))
+            .map(|l| l.digest)
+            .collect();
+        let source = SOURCE_ANNOTATIONS
+            .iter()
+            .find_map(|key| manifest.annotations.get(*key).cloned());
+        Provenance {
+            registry,
+            namespace,
+            origin,
+            signed: !signatures.is_empty(),
+            signatures,
+            source,
+            annotations: manifest.annotations.clone(),
+        }
+    }
+}
+
+/// Whether a layer media type holds a signature or attestation (cosign, Notary,
+/// in-toto / DSSE envelopes, or an Ollama-style `...signature` layer).
+#[must_use]
+pub fn is_signature_media_type(media_type: &str) -> bool {
+    let media_type = media_type.to_ascii_lowercase();
+    ["signature", "attestation", "in-toto", "dsse", "cosign"]
+        .iter()
+        .any(|needle| media_type.contains(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LayerInfo, MediaType};
+
+    #[test]
+    fn test_provenance_of() {
+        let layer = |media_type: &str, byte: u8| LayerInfo {
+            digest: format!("sha256:{}", format!("{byte:02x}").repeat(32))
+                .parse()
+                .unwrap(),
+            media_type: MediaType::new(media_type),
+            size: Some(1),
+        };
+        let mut manifest = ManifestData::new(
+            layer(crate::CONFIG_MEDIA_TYPE, 0),
+            vec![layer("application/vnd.ollama.image.model", 1)],
+        );
+        let official = Provenance::of(&"llama3:8b".parse().unwrap(), &manifest);
+        assert_eq!(official.origin, Origin::OfficialLibrary);
+        assert!(!official.signed);
+
+        manifest
+            .layers
+            .push(layer("application/vnd.dev.cosign.simplesigning.v1+json", 2));
+        manifest.annotations.insert(
+            "org.opencontainers.image.source".to_string(),
+            "https://example.com/m".to_string(),
+        );
+        let third = Provenance::of(&"hf.co/org/m:q4".parse().unwrap(), &manifest);
+        assert_eq!(third.origin, Origin::ThirdPartyRegistry);
+        assert_eq!(third.registry, "hf.co");
+        assert!(third.signed);
+        assert_eq!(third.source.as_deref(), Some("https://example.com/m"));
+        let community = Provenance::of(&"apple/OpenELM".parse().unwrap(), &manifest);
+        assert_eq!(community.origin, Origin::CommunityNamespace);
+    }
+}
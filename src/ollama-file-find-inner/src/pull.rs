@@ -0,0 +1,84 @@
+use serde::Serialize;
+
+use crate::{BlobStore, Digest, ManifestData};
+
+/// How much of a manifest's content would have to be downloaded into a blob store.
+#[derive(Debug, Clone, Serialize)]
+pub struct PullEstimate {
+    /// Sum of all declared layer + config sizes
+    pub total_bytes: u64,
+    /// Bytes already present locally (shared with installed models)
+    pub present_bytes: u64,
+    /// Bytes a pull would actually fetch
+    pub download_bytes: u64,
+    pub layers: Vec<PullLayer>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PullLayer {
+    pub digest: Digest,
+    pub size: u64,
+    /// A complete blob with this digest already exists locally
+    pub present: bool,
+}
+
+/// Intersect a (typically remote) manifest with the local blob store.
+///
+/// A local blob only counts as present when its size matches the declared size, since
+/// Ollama re-downloads truncated blobs.
+#[must_use]
+pub fn estimate_pull(manifest: &ManifestData, blobs: &BlobStore) -> PullEstimate {
+    let mut estimate = PullEstimate {
+        total_bytes: 0,
+        present_bytes: 0,
+        download_bytes: 0,
+        layers: Vec::new(),
+    };
+    for layer in manifest.layers.iter().chain(manifest.config.iter()) {
+        let size = layer.size.unwrap_or(0);
+        let present = std::fs::metadata(blobs.path_for(&layer.digest))
+            .is_ok_and(|meta| layer.size.is_none_or(|declared| declared == meta.len()));
+        estimate.total_bytes += size;
+        if present {
+            estimate.present_bytes += size;
+        } else {
+            estimate.download_bytes += size;
+        }
+        estimate.layers.push(PullLayer {
+            digest: layer.digest,
+            size,
+            present,
+        });
+    }
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_pull_counts_shared_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let blobs = BlobStore::new(dir.path());
+        let have = Digest::sha256([0xaa; 32]);
+        let short = Digest::sha256([0xbb; 32]);
+        let need = Digest::sha256([0xcc; 32]);
+        std::fs::write(blobs.path_for(&have), b"1234").unwrap();
+        // Truncated local copy must still be downloaded.
+        std::fs::write(blobs.path_for(&short), b"12").unwrap();
+        let manifest: ManifestData = serde_json::from_value(serde_json::json!({
+            "layers": [
+                { "digest": have, "mediaType": "application/vnd.ollama.image.model", "size": 4 },
+                { "digest": short, "mediaType": "application/vnd.ollama.image.model", "size": 4 },
+                { "digest": need, "mediaType": "application/vnd.ollama.image.model", "size": 10 },
+            ]
+        }))
+        .unwrap();
+
+        let estimate = estimate_pull(&manifest, &blobs);
+        assert_eq!(estimate.total_bytes, 18);
+        assert_eq!(estimate.present_bytes, 4);
+        assert_eq!(estimate.download_bytes, 14);
+    }
+}
@@ -0,0 +1,174 @@
+//! Punycode (RFC 3492) for internationalized registry hosts. Ollama only accepts ASCII
+//! hosts, so a host like `bücher.example` is stored on disk as `xn--bcher-kva.example`.
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+const ACE_PREFIX: &str = "xn--";
+const DIGITS: &[u8; 36] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Decode every `xn--` label of a host for display. Returns `None` when nothing needs
+/// decoding or a label is not valid punycode (the raw host is shown as is).
+pub(crate) fn decode_host(host: &str) -> Option<String> {
+    if !host.split('.').any(is_ace) {
+        return None;
+    }
+    let labels = host
+        .split('.')
+        .map(|label| {
+            if is_ace(label) {
+                decode(&label[ACE_PREFIX.len()..])
+            } else {
+                Some(label.to_string())
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(labels.join("."))
+}
+
+/// Encode every non-ASCII label of a host to its `xn--` form, the way it is stored on
+/// disk. ASCII hosts are returned unchanged.
+pub(crate) fn encode_host(host: &str) -> String {
+    if host.is_ascii() {
+        return host.to_string();
+    }
+    host.split('.')
+        .map(|label| {
+            if label.is_ascii() {
+                label.to_string()
+            } else {
+                format!("{ACE_PREFIX}{}", encode(label))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn is_ace(label: &str) -> bool {
+    label
+        .get(..ACE_PREFIX.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(ACE_PREFIX))
+}
+
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    k.saturating_sub(bias).clamp(T_MIN, T_MAX)
+}
+
+fn decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+    let (mut n, mut i, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    let mut digits = extended.bytes().peekable();
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut w = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = match digits.next()? {
+                b @ b'a'..=b'z' => u32::from(b - b'a'),
+                b @ b'A'..=b'Z' => u32::from(b - b'A'),
+                b @ b'0'..=b'9' => u32::from(b - b'0') + 26,
+                _ => return None,
+            };
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = u32::try_from(output.len()).ok()? + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(usize::try_from(i).ok()?, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+fn encode(input: &str) -> String {
+    let chars: Vec<u32> = input.chars().map(u32::from).collect();
+    let mut output: String = input.chars().filter(char::is_ascii).collect();
+    let basic = u32::try_from(output.len()).unwrap_or(u32::MAX);
+    if basic > 0 {
+        output.push('-');
+    }
+    let digit = |d: u32| char::from(DIGITS[d as usize]);
+    let (mut n, mut delta, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    let mut handled = basic;
+    let total = u32::try_from(chars.len()).unwrap_or(u32::MAX);
+    while handled < total {
+        let Some(&m) = chars.iter().filter(|&&c| c >= n).min() else {
+            break;
+        };
+        delta = delta.saturating_add((m - n).saturating_mul(handled + 1));
+        n = m;
+        for &c in &chars {
+            if c < n {
+                delta += 1;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_round_trip() {
+        for (unicode, ace) in [
+            ("bücher.example", "xn--bcher-kva.example"),
+            ("münchen.de:5000", "xn--mnchen-3ya.de:5000"),
+            ("例え.jp", "xn--r8jz45g.jp"),
+        ] {
+            assert_eq!(decode_host(ace).as_deref(), Some(unicode));
+            assert_eq!(encode_host(unicode), ace);
+        }
+        assert_eq!(decode_host("registry.ollama.ai"), None);
+        assert_eq!(decode_host("xn--!!.example"), None);
+    }
+}
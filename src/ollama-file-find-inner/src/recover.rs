@@ -0,0 +1,207 @@
+use std::{collections::HashSet, fs, path::PathBuf};
+
+use crate::{
+    BlobClass, BlobEntry, BlobStore, CONFIG_MEDIA_TYPE, DEFAULT_HOST, Digest, Error, LayerInfo,
+    ManifestData, ManifestStore, MediaType, ModelId, Result, classify_file,
+    gguf::{GgufMetadata, read_gguf_file},
+    write_atomic,
+};
+
+/// Namespace recovered models are written under.
+pub const RECOVERED_NAMESPACE: &str = "recovered";
+
+const WEIGHTS_MEDIA_TYPE: &str = "application/vnd.ollama.image.model";
+
+/// An orphaned GGUF blob and the model id a manifest would be synthesized under.
+#[derive(Debug)]
+pub struct RecoveryCandidate {
+    pub blob: BlobEntry,
+    pub metadata: GgufMetadata,
+    /// `recovered/<slug of general.name>:<size label or latest>`
+    pub id: ModelId,
+}
+
+/// Find GGUF files among `orphans` (typically [`crate::GcPlan::unreachable`]) and pick
+/// a unique id for each that does not collide with an existing manifest.
+#[must_use]
+pub fn recovery_candidates(
+    orphans: &[BlobEntry],
+    manifests: &ManifestStore,
+) -> Vec<RecoveryCandidate> {
+    let mut taken = HashSet::new();
+    let mut out = Vec::new();
+    for blob in orphans {
+        if !matches!(classify_file(&blob.path), Ok(BlobClass::Gguf)) {
+            continue;
+        }
+        let Ok(metadata) = read_gguf_file(&blob.path) else {
+            continue;
+        };
+        let hex = blob.digest.hex();
+        let model = metadata
+            .name()
+            .map(slug)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| format!("model-{}", &hex[..12]));
+        let tag = metadata
+            .get_str("general.size_label")
+            .map(slug)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| crate::DEFAULT_TAG.to_string());
+        let mut id = ModelId {
+            host: Some(DEFAULT_HOST.to_string()),
+            namespace: Some(RECOVERED_NAMESPACE.to_string()),
+            model,
+            tag,
+        };
+        if taken.contains(&id.normalize()) || manifests.path_for(&id).exists() {
+            id.tag = format!("{}-{}", id.tag, &hex[..12.min(hex.len())]);
+        }
+        taken.insert(id.normalize());
+        out.push(RecoveryCandidate {
+            blob: blob.clone(),
+            metadata,
+            id,
+        });
+    }
+    out
+}
+
+impl RecoveryCandidate {
+    /// Write a config blob and a manifest pointing at the GGUF blob, returning the
+    /// manifest path. Refuses to overwrite an existing manifest.
+    pub fn write(&self, manifests: &ManifestStore, blobs: &BlobStore) -> Result<PathBuf> {
+        let path = manifests.path_for(&self.id);
+        if path.exists() {
+            return Err(Error::Io {
+                path,
+                source: std::io::ErrorKind::AlreadyExists.into(),
+            });
+        }
+
+        let arch = self.metadata.architecture().unwrap_or("unknown");
+        let config = serde_json::json!({
+            "model_format": "gguf",
+            "model_family": arch,
+            "model_families": [arch],
+            "model_type": self.metadata.get_str("general.size_label").unwrap_or(""),
+            // By name (`Q4_K_M`), as Ollama writes it.
+            "file_type": self.metadata.file_type().unwrap_or_default(),
+            "architecture": "amd64",
+            "os": "linux",
+            "rootfs": { "type": "layers", "diff_ids": [self.blob.digest] },
+        });
+        let config_bytes = serde_json::to_vec(&config).map_err(|source| Error::Json {
+            path: path.clone(),
+            source,
+        })?;
+        let config_digest = crate::hash::sha256_digest(&config_bytes);
+        let config_path = blobs.path_for(&config_digest);
+        if !config_path.exists() {
+            write_atomic(&config_path, &config_bytes)?;
+        }
+
+        let layer = |digest: Digest, media_type: &str, size: u64| LayerInfo {
+            digest,
+            media_type: MediaType::new(media_type),
+            size: Some(size),
+        };
+        let manifest = ManifestData::new(
+            layer(config_digest, CONFIG_MEDIA_TYPE, config_bytes.len() as u64),
+            vec![layer(self.blob.digest, WEIGHTS_MEDIA_TYPE, self.blob.size)],
+        );
+        let body = serde_json::to_vec(&manifest).map_err(|source| Error::Json {
+            path: path.clone(),
+            source,
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| Error::Io {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        write_atomic(&path, &body)?;
+        Ok(path)
+    }
+}
+
+/// Lowercase a name into something usable as a model or tag path component.
+fn slug(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_matches(|c| c == '-' || c == '.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LayerKind, ScanArgs, fixtures::FakeStore, gc_plan, scan_manifests};
+
+    /// A GGUF header naming a model, its size, and its quantization, with no tensors.
+    fn gguf() -> Vec<u8> {
+        let string = |s: &str| [&(s.len() as u64).to_le_bytes()[..], s.as_bytes()].concat();
+        let mut data = b"GGUF".to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&4u64.to_le_bytes());
+        for (key, value) in [
+            ("general.architecture", "llama"),
+            ("general.name", "Llama 3 Tuned"),
+            ("general.size_label", "8B"),
+        ] {
+            data.extend(string(key));
+            data.extend_from_slice(&8u32.to_le_bytes());
+            data.extend(string(value));
+        }
+        data.extend(string("general.file_type"));
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&15u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_recover_write_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let weights = gguf();
+        let store = FakeStore::builder()
+            .model("phi4")
+            .layer(LayerKind::Weights, b"phi weights")
+            .orphan(&weights)
+            .build(dir.path())
+            .unwrap();
+        let (manifests, blobs) = (store.manifests(), store.blobs());
+        let plan = gc_plan(&manifests, &blobs).unwrap();
+        let candidates = recovery_candidates(&plan.unreachable, &manifests);
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert_eq!(candidate.id.normalize(), "recovered/llama-3-tuned:8b");
+        let path = candidate.write(&manifests, &blobs).unwrap();
+        assert!(candidate.write(&manifests, &blobs).is_err());
+
+        let outcome =
+            scan_manifests(&ScanArgs::new(manifests.root(), blobs.root()).with_verbose(true));
+        assert!(outcome.errors.is_empty(), "{:?}", outcome.errors);
+        let recovered = outcome
+            .models
+            .iter()
+            .find(|m| m.manifest_path == path)
+            .unwrap();
+        assert_eq!(recovered.is_broken(), Some(false));
+        assert_eq!(
+            recovered.primary_blob_path.as_deref(),
+            Some(store.blob_path(&weights).as_path())
+        );
+        let config = recovered.config.as_ref().unwrap();
+        let config: serde_json::Value =
+            serde_json::from_str(&blobs.read_text(&config.digest, 4096).unwrap()).unwrap();
+        assert_eq!(config["file_type"], "Q4_K_M");
+        assert_eq!(config["model_type"], "8B");
+        assert_eq!(config["model_family"], "llama");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    DEFAULT_HOST, Digest, Error, LIBRARY_NAMESPACE, MANIFEST_MEDIA_TYPE, ManifestData, ModelId,
+    Result,
+};
+
+/// Registry URL of a model's manifest (`https://<host>/v2/<namespace>/<model>/manifests/<tag>`).
+#[must_use]
+pub fn manifest_url(id: &ModelId) -> String {
+    format!(
+        "https://{}/v2/{}/{}/manifests/{}",
+        id.host.as_deref().unwrap_or(DEFAULT_HOST),
+        id.namespace.as_deref().unwrap_or(LIBRARY_NAMESPACE),
+        id.model,
+        id.tag
+    )
+}
+
+/// Download a model's manifest from its registry.
+pub fn fetch_manifest(id: &ModelId) -> Result<ManifestData> {
+    let url = manifest_url(id);
+    let registry_err = |e: ureq::Error| Error::Registry {
+        url: url.clone(),
+        message: e.to_string(),
+    };
+    ureq::get(&url)
+        .header("Accept", MANIFEST_MEDIA_TYPE)
+        .call()
+        .map_err(registry_err)?
+        .body_mut()
+        .read_json()
+        .map_err(registry_err)
+}
+
+/// Registry URL of a blob (`https://<host>/v2/<namespace>/<model>/blobs/<digest>`).
+#[must_use]
+pub fn blob_url(id: &ModelId, digest: &Digest) -> String {
+    format!(
+        "https://{}/v2/{}/{}/blobs/{digest}",
+        id.host.as_deref().unwrap_or(DEFAULT_HOST),
+        id.namespace.as_deref().unwrap_or(LIBRARY_NAMESPACE),
+        id.model,
+    )
+}
+
+/// Download a small blob (a license, template, or params layer) into memory, failing
+/// if it is larger than `limit` bytes.
+pub fn fetch_blob(id: &ModelId, digest: &Digest, limit: u64) -> Result<Vec<u8>> {
+    let url = blob_url(id, digest);
+    let registry_err = |e: ureq::Error| Error::Registry {
+        url: url.clone(),
+        message: e.to_string(),
+    };
+    ureq::get(&url)
+        .call()
+        .map_err(registry_err)?
+        .body_mut()
+        .with_config()
+        .limit(limit)
+        .read_to_vec()
+        .map_err(registry_err)
+}
+
+/// Largest model page read when looking for its description.
+const MAX_PAGE_BYTES: u64 = 4 << 20;
+
+/// The short description from a model's ollama.com page (its `<meta name="description">`),
+/// or `None` for models from other registries and pages without one.
+pub fn fetch_description(id: &ModelId) -> Result<Option<String>> {
+    let Some(url) = id.web_url() else {
+        return Ok(None);
+    };
+    let registry_err = |e: ureq::Error| Error::Registry {
+        url: url.clone(),
+        message: e.to_string(),
+    };
+    let html = ureq::get(&url)
+        .call()
+        .map_err(registry_err)?
+        .body_mut()
+        .with_config()
+        .limit(MAX_PAGE_BYTES)
+        .read_to_string()
+        .map_err(registry_err)?;
+    Ok(meta_description(&html))
+}
+
+/// The `content` of the first `<meta name="description" ...>` tag, with the entities
+/// HTML escaping produces decoded.
+fn meta_description(html: &str) -> Option<String> {
+    html.split("<meta ").skip(1).find_map(|tag| {
+        let tag = &tag[..tag.find('>')?];
+        if !tag.contains(r#"name="description""#) {
+            return None;
+        }
+        let start = tag.find(r#"content=""#)? + r#"content=""#.len();
+        let content = &tag[start..start + tag[start..].find('"')?];
+        let content = content
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&");
+        Some(content.trim().to_string()).filter(|c| !c.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_description() {
+        let html = r#"<head><meta charset="utf-8"><meta name="description" content="Meta&#39;s model &amp; more" />"#;
+        assert_eq!(
+            meta_description(html).as_deref(),
+            Some("Meta's model & more")
+        );
+        assert_eq!(
+            meta_description("<meta name=\"robots\" content=\"x\">"),
+            None
+        );
+    }
+}
@@ -1,4 +1,31 @@
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, path::Path, sync::Arc};
+
+use crate::{Collation, PrimarySelector, WeightsFirst};
+
+/// Which models to keep with respect to hidden path components (a namespace, model, or
+/// tag starting with '.', as used for staging tags).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HiddenPolicy {
+    /// Skip hidden models
+    #[default]
+    Exclude,
+    /// Keep hidden and visible models
+    Include,
+    /// Keep only hidden models
+    Only,
+}
+
+impl HiddenPolicy {
+    /// Whether a model whose path is (or is not) hidden passes this policy.
+    #[must_use]
+    pub fn admits(self, hidden: bool) -> bool {
+        match self {
+            HiddenPolicy::Exclude => !hidden,
+            HiddenPolicy::Include => true,
+            HiddenPolicy::Only => hidden,
+        }
+    }
+}
 
 /// Arguments controlling a scan of the manifests directory.
 pub struct ScanArgs<'a> {
@@ -6,10 +33,20 @@ pub struct ScanArgs<'a> {
     pub root: Cow<'a, Path>,
     /// Root of the blobs directory (models/blobs)
     pub blobs_root: Cow<'a, Path>,
-    /// Include entries whose components (namespace, tag, etc.) start with '.'
-    pub include_hidden: bool,
+    /// Which entries with components (namespace, tag, etc.) starting with '.' to keep
+    pub hidden: HiddenPolicy,
     /// Include extra detail (layer list, total size, mtime, blob info)
     pub verbose: bool,
+    /// Without `verbose`, still fill in the manifest digest, total size, and mtime (what
+    /// `ollama list` shows) but skip blob stats and GGUF headers
+    pub summary: bool,
+    /// Repair manifests that fail to parse (see [`crate::repair_json`]) instead of
+    /// reporting them as errors
+    pub lenient: bool,
+    /// Order of the returned models by name
+    pub collation: Collation,
+    /// Which layer verbose records name as the primary blob
+    pub primary: Arc<dyn PrimarySelector>,
 }
 
 impl<'a> ScanArgs<'a> {
@@ -23,16 +60,51 @@ impl<'a> ScanArgs<'a> {
     }
 
     #[must_use]
-    pub fn with_include_hidden(self, include_hidden: bool) -> Self {
+    pub fn with_hidden(self, hidden: HiddenPolicy) -> Self {
+        ScanArgs { hidden, ..self }
+    }
+
+    #[must_use]
+    pub fn with_verbose(self, verbose: bool) -> Self {
+        ScanArgs { verbose, ..self }
+    }
+
+    #[must_use]
+    pub fn with_summary(self, summary: bool) -> Self {
+        ScanArgs { summary, ..self }
+    }
+
+    #[must_use]
+    pub fn with_lenient(self, lenient: bool) -> Self {
+        ScanArgs { lenient, ..self }
+    }
+
+    #[must_use]
+    pub fn with_collation(self, collation: Collation) -> Self {
+        ScanArgs { collation, ..self }
+    }
+
+    #[must_use]
+    pub fn with_primary_selector(self, selector: impl PrimarySelector + 'static) -> Self {
         ScanArgs {
-            include_hidden,
+            primary: Arc::new(selector),
             ..self
         }
     }
 
+    /// Copy borrowed paths so the arguments can move to another thread or task.
     #[must_use]
-    pub fn with_verbose(self, verbose: bool) -> Self {
-        ScanArgs { verbose, ..self }
+    pub fn into_owned(self) -> ScanArgs<'static> {
+        ScanArgs {
+            root: Cow::Owned(self.root.into_owned()),
+            blobs_root: Cow::Owned(self.blobs_root.into_owned()),
+            hidden: self.hidden,
+            verbose: self.verbose,
+            summary: self.summary,
+            lenient: self.lenient,
+            collation: self.collation,
+            primary: self.primary,
+        }
     }
 }
 
@@ -44,8 +116,12 @@ impl Default for ScanArgs<'static> {
         ScanArgs {
             root: manifests_root.into(),
             blobs_root: blobs_root.into(),
-            include_hidden: false,
+            hidden: HiddenPolicy::Exclude,
             verbose: false,
+            summary: false,
+            lenient: false,
+            collation: Collation::default(),
+            primary: Arc::new(WeightsFirst),
         }
     }
 }
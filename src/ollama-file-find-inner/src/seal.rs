@@ -0,0 +1,236 @@
+//! Merkle-style checksums of a whole store, for bundles that must arrive unchanged.
+//!
+//! Every blob a manifest references is hashed (its content, not its file name), each
+//! model's root covers its manifest file and those blob hashes, and the store root
+//! covers every model root. Publishing the store root out of band is enough to check a
+//! shipped [`Seal`] file and, through it, the store.
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+
+use crate::{BlobStore, Digest, Error, HiddenPolicy, ManifestStore, Result, sha256_file};
+
+/// Format version written to new seals.
+pub const SEAL_VERSION: u32 = 1;
+
+/// Checksums of every manifest and referenced blob at sealing time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Seal {
+    pub version: u32,
+    /// Hash over all model roots
+    pub root: Digest,
+    /// Keyed by manifest path relative to the manifests directory (`/`-separated)
+    pub models: BTreeMap<String, SealedModel>,
+    /// Content hash of every referenced blob, keyed by its digest
+    pub blobs: BTreeMap<Digest, Digest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedModel {
+    /// Hash over the manifest file and the content hashes of its blobs
+    pub root: Digest,
+    /// SHA-256 of the manifest file
+    pub manifest: Digest,
+    /// Referenced blobs (layers and config), sorted
+    pub blobs: Vec<Digest>,
+}
+
+/// A difference between a [`Seal`] and the store it was checked against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SealChange {
+    /// The seal's recorded roots don't match its own contents
+    SealTampered,
+    ModelAdded {
+        model: String,
+    },
+    ModelRemoved {
+        model: String,
+    },
+    ManifestChanged {
+        model: String,
+    },
+    BlobMissing {
+        digest: Digest,
+    },
+    BlobChanged {
+        digest: Digest,
+        found: Digest,
+    },
+}
+
+impl Seal {
+    /// Hash the store. Hidden tags are included; any unreadable manifest or blob fails
+    /// the seal rather than leaving part of the store unchecked.
+    pub fn compute(manifests: &ManifestStore, blobs: &BlobStore) -> Result<Seal> {
+        let (stored, errors) = manifests.manifests(HiddenPolicy::Include);
+        if !errors.is_empty() {
+            return Err(Error::SealUnsafe(errors.len()));
+        }
+        let mut seal = Seal {
+            version: SEAL_VERSION,
+            root: Digest::sha256([0; 32]),
+            models: BTreeMap::new(),
+            blobs: BTreeMap::new(),
+        };
+        for m in stored {
+            let mut referenced: Vec<Digest> = (m.data.layers.iter().chain(m.data.config.iter()))
+                .map(|l| l.digest)
+                .collect();
+            referenced.sort_unstable();
+            referenced.dedup();
+            for digest in &referenced {
+                if !seal.blobs.contains_key(digest) {
+                    seal.blobs
+                        .insert(*digest, sha256_file(&blobs.path_for(digest))?);
+                }
+            }
+            let manifest = sha256_file(&m.path)?;
+            seal.models.insert(
+                relative_key(manifests.root(), &m.path),
+                SealedModel {
+                    root: Digest::sha256([0; 32]),
+                    manifest,
+                    blobs: referenced,
+                },
+            );
+        }
+        seal.rehash();
+        Ok(seal)
+    }
+
+    /// Whether the recorded model and store roots follow from the recorded hashes.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        let mut copy = self.clone();
+        copy.rehash();
+        copy == *self
+    }
+
+    /// Compare the store against the seal, hashing each sealed blob once. Unlike
+    /// [`Seal::compute`], unreadable manifests and missing blobs are reported as changes.
+    pub fn verify(&self, manifests: &ManifestStore, blobs: &BlobStore) -> Result<Vec<SealChange>> {
+        let mut changes = Vec::new();
+        if !self.is_consistent() {
+            changes.push(SealChange::SealTampered);
+        }
+        let mut found = Vec::new();
+        for entry in manifests.walk(HiddenPolicy::Include) {
+            found.push(relative_key(manifests.root(), &entry?.1));
+        }
+        for model in &found {
+            if !self.models.contains_key(model) {
+                changes.push(SealChange::ModelAdded {
+                    model: model.clone(),
+                });
+            }
+        }
+        for (model, sealed) in &self.models {
+            let path = manifests.root().join(model);
+            if !found.contains(model) {
+                changes.push(SealChange::ModelRemoved {
+                    model: model.clone(),
+                });
+            } else if sha256_file(&path).ok() != Some(sealed.manifest) {
+                changes.push(SealChange::ManifestChanged {
+                    model: model.clone(),
+                });
+            }
+        }
+        for (&digest, &expected) in &self.blobs {
+            match sha256_file(&blobs.path_for(&digest)) {
+                Ok(actual) if actual == expected => {}
+                Ok(actual) => changes.push(SealChange::BlobChanged {
+                    digest,
+                    found: actual,
+                }),
+                Err(_) => changes.push(SealChange::BlobMissing { digest }),
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Recompute model roots from the manifest and blob hashes, then the store root.
+    fn rehash(&mut self) {
+        let mut store = Sha256::new();
+        store.update(b"ollama-file-find seal v1\0");
+        for (key, model) in &mut self.models {
+            let mut hasher = Sha256::new();
+            hasher.update(model.manifest.as_bytes());
+            for digest in &model.blobs {
+                hasher.update(digest.as_bytes());
+                if let Some(content) = self.blobs.get(digest) {
+                    hasher.update(content.as_bytes());
+                }
+            }
+            model.root = Digest::sha256(hasher.finalize().into());
+            store.update((key.len() as u64).to_le_bytes());
+            store.update(key.as_bytes());
+            store.update(model.root.as_bytes());
+        }
+        self.root = Digest::sha256(store.finalize().into());
+    }
+}
+
+fn relative_key(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .iter()
+        .map(|c| c.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{LayerKind, fixtures::FakeStore};
+
+    #[test]
+    fn test_seal_detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights-a")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .model("phi4")
+            .layer(LayerKind::Weights, b"weights-b")
+            .build(dir.path())
+            .unwrap();
+        let seal = Seal::compute(&store.manifests(), &store.blobs()).unwrap();
+        assert_eq!(seal.models.len(), 2);
+        assert!(seal.is_consistent());
+        assert_eq!(
+            seal,
+            Seal::compute(&store.manifests(), &store.blobs()).unwrap()
+        );
+        assert!(
+            seal.verify(&store.manifests(), &store.blobs())
+                .unwrap()
+                .is_empty()
+        );
+
+        fs::write(store.blob_path(b"weights-b"), b"weights-c").unwrap();
+        fs::remove_file(store.blob_path("{{ .Prompt }}")).unwrap();
+        let changes = seal.verify(&store.manifests(), &store.blobs()).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(
+            changes
+                .iter()
+                .any(|c| matches!(c, SealChange::BlobChanged { .. }))
+        );
+        assert!(
+            changes
+                .iter()
+                .any(|c| matches!(c, SealChange::BlobMissing { .. }))
+        );
+
+        let mut forged = seal.clone();
+        forged.models.values_mut().next().unwrap().manifest = Digest::sha256([1; 32]);
+        assert!(!forged.is_consistent());
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    Digest, Error, HiddenPolicy, Result, VerifiedReader,
+    models::{ManifestData, ManifestDataRef, ModelId},
+};
+
+/// Shortest hex prefix [`BlobStore::resolve`] accepts, as with git short hashes.
+pub const MIN_DIGEST_PREFIX: usize = 4;
+
+/// View over the `manifests/` tree of a models directory. Reads never modify it; writes
+/// go through [`ManifestStore::write_manifest`], which keeps a backup of what it replaces.
+#[derive(Debug, Clone)]
+pub struct ManifestStore {
+    root: PathBuf,
+    backup_retention: usize,
+}
+
+/// A manifest file found in a [`ManifestStore`], already parsed.
+#[derive(Debug)]
+pub struct StoredManifest {
+    pub id: ModelId,
+    pub path: PathBuf,
+    pub data: ManifestData,
+}
+
+impl ManifestStore {
+    /// Create a store rooted at the given `manifests` directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        ManifestStore {
+            root: root.into(),
+            backup_retention: crate::DEFAULT_BACKUP_RETENTION,
+        }
+    }
+
+    /// Keep at most `keep` backups per model (see [`ManifestStore::write_manifest`]).
+    #[must_use]
+    pub fn with_backup_retention(self, keep: usize) -> Self {
+        ManifestStore {
+            backup_retention: keep,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn backup_retention(&self) -> usize {
+        self.backup_retention
+    }
+
+    /// Root of the manifests tree.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Walk the tree and parse every manifest, collecting per-entry errors instead of
+    /// stopping at the first one.
+    #[must_use]
+    pub fn manifests(&self, hidden: HiddenPolicy) -> (Vec<StoredManifest>, Vec<Error>) {
+        let mut manifests = Vec::new();
+        let mut errors = Vec::new();
+        for found in self.walk(hidden) {
+            let loaded = found.and_then(|(id, path)| {
+                let data = crate::load_manifest(&path).map_err(|e| e.in_model(&id))?;
+                Ok(StoredManifest { id, path, data })
+            });
+            match loaded {
+                Ok(m) => manifests.push(m),
+                Err(e) => errors.push(e),
+            }
+        }
+        (manifests, errors)
+    }
+
+    /// Walk the tree and hand every manifest to `f` as a borrowed [`ManifestDataRef`],
+    /// reading each file into one reused buffer. Cheaper than [`ManifestStore::manifests`]
+    /// when only digests and sizes are needed. Errors from reading, parsing, or `f` are
+    /// collected per manifest.
+    pub fn visit<F>(&self, hidden: HiddenPolicy, mut f: F) -> Vec<Error>
+    where
+        F: FnMut(&ModelId, &ManifestDataRef<'_>) -> Result<()>,
+    {
+        let mut errors = Vec::new();
+        let mut buf = Vec::new();
+        for found in self.walk(hidden) {
+            let visited = found.and_then(|(id, path)| {
+                buf.clear();
+                fs::File::open(&path)
+                    .and_then(|mut file| file.read_to_end(&mut buf))
+                    .map_err(|source| Error::Io {
+                        path: path.clone(),
+                        source,
+                    })?;
+                let data: ManifestDataRef =
+                    crate::parse_manifest(&path, &buf).map_err(|e| e.in_model(&id))?;
+                // Borrowed digests aren't checked while parsing; do it here so `f` never
+                // sees one it can't turn into a blob path.
+                if let Some(bad) = data.entries().find(|l| l.digest().is_err()) {
+                    return Err(Error::MalformedDigest {
+                        model: id.normalize(),
+                        digest: bad.digest.to_string(),
+                    });
+                }
+                f(&id, &data)
+            });
+            if let Err(e) = visited {
+                errors.push(e);
+            }
+        }
+        errors
+    }
+
+    /// Model ids of every manifest file, derived from paths alone. Much cheaper than
+    /// [`ManifestStore::manifests`] since no file is opened.
+    #[must_use]
+    pub fn ids(&self, hidden: HiddenPolicy) -> (Vec<ModelId>, Vec<Error>) {
+        let mut ids = Vec::new();
+        let mut errors = Vec::new();
+        for found in self.walk(hidden) {
+            match found {
+                Ok((id, _)) => ids.push(id),
+                Err(e) => errors.push(e),
+            }
+        }
+        (ids, errors)
+    }
+
+    /// Where the manifest for `id` lives in the standard four-level layout
+    /// (`<host>/<namespace>/<model>/<tag>`, internationalized hosts in punycode); the
+    /// file may not exist.
+    #[must_use]
+    pub fn path_for(&self, id: &ModelId) -> PathBuf {
+        self.root.join(self.relative_path_for(id))
+    }
+
+    pub(crate) fn relative_path_for(&self, id: &ModelId) -> PathBuf {
+        Path::new(&crate::punycode::encode_host(
+            id.host.as_deref().unwrap_or(crate::DEFAULT_HOST),
+        ))
+        .join(id.namespace.as_deref().unwrap_or(crate::LIBRARY_NAMESPACE))
+        .join(&id.model)
+        .join(&id.tag)
+    }
+
+    /// The manifest for `id` (hidden tags included), if it exists and parses.
+    #[must_use]
+    pub fn find(&self, id: &ModelId) -> Option<StoredManifest> {
+        let wanted = id.normalize();
+        self.walk(HiddenPolicy::Include)
+            .filter_map(Result::ok)
+            .find(|(found, _)| found.normalize() == wanted)
+            .and_then(|(id, path)| {
+                let data = crate::load_manifest(&path).ok()?;
+                Some(StoredManifest { id, path, data })
+            })
+    }
+
+    /// Manifest files under the root with the id implied by their path.
+    pub(crate) fn walk(
+        &self,
+        hidden: HiddenPolicy,
+    ) -> impl Iterator<Item = Result<(ModelId, PathBuf)>> {
+        walkdir::WalkDir::new(&self.root)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|e| !crate::is_backup_dir(e))
+            .filter_map(move |entry_res| {
+                let entry = match entry_res {
+                    Ok(entry) => entry,
+                    Err(e) => return Some(Err(Error::WalkDir(e))),
+                };
+                if entry.file_type().is_dir() {
+                    return None;
+                }
+                if entry.path_is_symlink() && entry.path().is_dir() {
+                    return Some(Err(Error::LinkedDirectory(entry.path().to_path_buf())));
+                }
+                crate::relative_components(&entry, &self.root)
+                    .and_then(|comps| crate::parse_components(comps, hidden))
+                    .map(|id| id.map(|id| (id, entry.into_path())))
+                    .transpose()
+            })
+    }
+}
+
+/// Read-only view over the content-addressed `blobs/` directory.
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+/// A blob file present on disk.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BlobEntry {
+    pub digest: Digest,
+    pub path: PathBuf,
+    /// Apparent file size in bytes
+    pub size: u64,
+    /// Last modification, in seconds since the epoch
+    pub mtime: Option<u64>,
+}
+
+/// A file in the blobs directory that is not a complete blob: a partial download or
+/// something that doesn't belong there. Either way nothing references it.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StrayFile {
+    pub path: PathBuf,
+    /// Apparent file size in bytes
+    pub size: u64,
+    /// Last modification, in seconds since the epoch
+    pub mtime: Option<u64>,
+    /// For partial downloads, the blob being downloaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_of: Option<Digest>,
+}
+
+impl BlobStore {
+    /// Create a store rooted at the given `blobs` directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        BlobStore { root: root.into() }
+    }
+
+    /// Root of the blobs directory.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// On-disk path for a digest (the file may not exist).
+    #[must_use]
+    pub fn path_for(&self, digest: &Digest) -> PathBuf {
+        digest.blob_path(&self.root)
+    }
+
+    /// Store `bytes` as a blob, unless one with the same digest is already there, and
+    /// return the digest.
+    pub fn write_blob(&self, bytes: &[u8]) -> Result<Digest> {
+        let digest = crate::hash::sha256_digest(bytes);
+        let path = self.path_for(&digest);
+        if !path.is_file() {
+            fs::create_dir_all(&self.root).map_err(|source| Error::Io {
+                path: self.root.clone(),
+                source,
+            })?;
+            crate::write_atomic(&path, bytes)?;
+        }
+        Ok(digest)
+    }
+
+    /// Read up to `max_bytes` of a blob as (lossy UTF-8) text; longer blobs are
+    /// truncated. Meant for small metadata layers such as templates and params.
+    pub fn read_text(&self, digest: &Digest, max_bytes: u64) -> Result<String> {
+        let path = self.path_for(digest);
+        let mut buf = Vec::new();
+        fs::File::open(&path)
+            .and_then(|f| f.take(max_bytes).read_to_end(&mut buf))
+            .map_err(|source| Error::Io { path, source })?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    /// Open a blob for streaming, checking its content against `digest` as it is read;
+    /// see [`VerifiedReader`] for when a mismatch is reported.
+    pub fn open_verified(&self, digest: &Digest) -> Result<VerifiedReader> {
+        let path = self.path_for(digest);
+        let file = fs::File::open(&path).map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(VerifiedReader::new(file, *digest, path))
+    }
+
+    /// Resolve a digest the way git resolves short hashes: `spec` is a full digest or a
+    /// hex prefix of at least [`MIN_DIGEST_PREFIX`] characters, with or without
+    /// `sha256:`, matched case-insensitively against the blobs present. A full digest
+    /// is returned as is, present or not; a prefix must match exactly one blob.
+    pub fn resolve(&self, spec: &str) -> Result<Digest> {
+        let spec = spec.trim();
+        if let Ok(digest) = spec.to_ascii_lowercase().parse::<Digest>() {
+            return Ok(digest);
+        }
+        let prefix = spec
+            .strip_prefix("sha256:")
+            .or_else(|| spec.strip_prefix("sha256-"))
+            .unwrap_or(spec)
+            .to_ascii_lowercase();
+        if prefix.len() < MIN_DIGEST_PREFIX
+            || prefix.len() > 64
+            || !prefix.bytes().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(Error::InvalidDigest(spec.to_string()));
+        }
+        let file_prefix = format!("sha256-{prefix}");
+        let mut matches: Vec<Digest> = fs::read_dir(&self.root)
+            .map_err(|source| Error::Io {
+                path: self.root.clone(),
+                source,
+            })?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                let name = name.to_str()?;
+                name.starts_with(&file_prefix)
+                    .then(|| Digest::from_blob_file_name(name))
+                    .flatten()
+            })
+            .collect();
+        matches.sort_unstable();
+        match matches.len() {
+            0 => Err(Error::BlobNotFound(spec.to_string())),
+            1 => Ok(matches[0]),
+            _ => Err(Error::AmbiguousDigest {
+                prefix: spec.to_string(),
+                matches,
+            }),
+        }
+    }
+
+    /// List every file whose name looks like a complete blob (`sha256-<64 hex>`).
+    /// Partial downloads and unrelated files are skipped (see [`BlobStore::stray_files`]).
+    pub fn blobs(&self) -> Result<Vec<BlobEntry>> {
+        let mut out = Vec::new();
+        for (name, path, meta) in self.files()? {
+            let Some(digest) = name.to_str().and_then(Digest::from_blob_file_name) else {
+                continue;
+            };
+            out.push(BlobEntry {
+                digest,
+                path,
+                size: meta.len(),
+                mtime: mtime_secs(&meta),
+            });
+        }
+        out.sort_unstable_by_key(|b| b.digest);
+        Ok(out)
+    }
+
+    /// Every other file directly in the blobs directory: partial downloads (with the
+    /// digest they were heading for) and anything else, sorted by path. No manifest can
+    /// reference them, so their space is reclaimable once no pull is writing to them.
+    pub fn stray_files(&self) -> Result<Vec<StrayFile>> {
+        let mut out = Vec::new();
+        for (name, path, meta) in self.files()? {
+            let name = name.to_string_lossy();
+            if Digest::from_blob_file_name(&name).is_some() {
+                continue;
+            }
+            out.push(StrayFile {
+                partial_of: Digest::from_partial_file_name(&name),
+                path,
+                size: meta.len(),
+                mtime: mtime_secs(&meta),
+            });
+        }
+        out.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        Ok(out)
+    }
+
+    /// Regular files directly under the root, with their names and metadata.
+    fn files(&self) -> Result<Vec<(std::ffi::OsString, PathBuf, fs::Metadata)>> {
+        let io_err = |source| Error::Io {
+            path: self.root.clone(),
+            source,
+        };
+        let mut out = Vec::new();
+        for entry in fs::read_dir(&self.root).map_err(io_err)? {
+            let entry = entry.map_err(io_err)?;
+            let meta = entry.metadata().map_err(|source| Error::Io {
+                path: entry.path(),
+                source,
+            })?;
+            if meta.is_file() {
+                out.push((entry.file_name(), entry.path(), meta));
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn mtime_secs(meta: &fs::Metadata) -> Option<u64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
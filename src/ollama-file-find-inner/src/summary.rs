@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{BlobStore, Digest, Error, HiddenPolicy, ManifestStore, ModelId, Result};
+
+/// Headline numbers for a store, from one borrowed pass over the manifests (see
+/// [`ManifestStore::visit`]) and one listing of the blobs directory; no blob is opened.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StoreSummary {
+    pub models: usize,
+    /// Declared size of every model, shared blobs counted once per model (what the
+    /// sizes in `ollama list` add up to)
+    pub total_bytes: u64,
+    /// Bytes of the distinct blobs those models reference
+    pub unique_bytes: u64,
+    /// Models with a blob that is missing or whose size differs from the manifest
+    pub broken: usize,
+    /// Blobs no manifest references (including ones only backups and pins hold)
+    pub orphaned: usize,
+    pub orphaned_bytes: u64,
+}
+
+impl StoreSummary {
+    /// Summarize the models `hidden` selects. Hidden tags always count as references
+    /// when finding orphans. Manifests that can't be read are returned alongside and
+    /// left out of the counts.
+    pub fn compute(
+        manifests: &ManifestStore,
+        blobs: &BlobStore,
+        hidden: HiddenPolicy,
+    ) -> Result<(Self, Vec<Error>)> {
+        let on_disk: HashMap<Digest, u64> = blobs
+            .blobs()?
+            .into_iter()
+            .map(|b| (b.digest, b.size))
+            .collect();
+        let (selected, _) = manifests.ids(hidden);
+        let selected: HashSet<String> = selected.iter().map(ModelId::normalize).collect();
+
+        let mut summary = StoreSummary::default();
+        let mut referenced: HashSet<Digest> = HashSet::new();
+        let mut counted: HashSet<Digest> = HashSet::new();
+        let errors = manifests.visit(HiddenPolicy::Include, |id, m| {
+            let digests = m
+                .entries()
+                .map(|l| Ok((l.digest()?, l.size)))
+                .collect::<Result<Vec<_>>>()?;
+            referenced.extend(digests.iter().map(|(d, _)| *d));
+            if !selected.contains(&id.normalize()) {
+                return Ok(());
+            }
+            summary.models += 1;
+            let mut broken = false;
+            for (digest, declared) in digests {
+                summary.total_bytes += declared.unwrap_or(0);
+                let actual = on_disk.get(&digest).copied();
+                broken |= actual.is_none() || declared.is_some_and(|d| Some(d) != actual);
+                if counted.insert(digest) {
+                    summary.unique_bytes += actual.or(declared).unwrap_or(0);
+                }
+            }
+            summary.broken += usize::from(broken);
+            Ok(())
+        });
+        for (digest, size) in &on_disk {
+            if !referenced.contains(digest) {
+                summary.orphaned += 1;
+                summary.orphaned_bytes += size;
+            }
+        }
+        Ok((summary, errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LayerKind, fixtures::FakeStore};
+
+    #[test]
+    fn test_store_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FakeStore::builder()
+            .model("llama3:8b")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .model("llama3:latest")
+            .layer(LayerKind::Weights, b"weights")
+            .layer(LayerKind::Template, "{{ .Prompt }}")
+            .model("phi4")
+            .layer(LayerKind::Weights, b"phi")
+            .orphan(b"left over")
+            .build(dir.path())
+            .unwrap();
+        std::fs::remove_file(store.blob_path(b"phi")).unwrap();
+
+        let (summary, errors) =
+            StoreSummary::compute(&store.manifests(), &store.blobs(), HiddenPolicy::Exclude)
+                .unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(summary.models, 3);
+        assert_eq!(summary.broken, 1);
+        assert_eq!(summary.orphaned, 1);
+        assert_eq!(summary.orphaned_bytes, b"left over".len() as u64);
+        // The two llama3 tags share every blob.
+        assert!(summary.unique_bytes < summary.total_bytes);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{
+    CONFIG_MEDIA_TYPE, DEFAULT_HOST, DEFAULT_TAG, Digest, Error, LayerInfo, ManifestData,
+    MediaType, Result, hash::sha256_digest,
+};
+
+/// Media types given to the non-weights layers of generated models, in turn.
+const EXTRA_MEDIA_TYPES: [&str; 5] = [
+    "application/vnd.ollama.image.template",
+    "application/vnd.ollama.image.params",
+    "application/vnd.ollama.image.system",
+    "application/vnd.ollama.image.license",
+    "application/vnd.ollama.image.messages",
+];
+
+/// Shape of a generated models directory, for benchmarks and profiling.
+///
+/// Blob digests are derived from names rather than content, so a generated store suits
+/// scans, GC and usage reports, but not hash verification.
+#[derive(Debug, Clone)]
+pub struct SyntheticStore {
+    /// Number of models, one tag each
+    pub models: usize,
+    /// Layers per model (config excluded); the first is the weights layer
+    pub layers: usize,
+    /// How many of each model's non-weights layers are common to every model, like the
+    /// templates and licenses shared by a model family
+    pub shared_layers: usize,
+    /// Namespaces the models are spread over, round robin
+    pub namespaces: usize,
+    /// Size of every layer blob
+    pub layer_size: u64,
+    /// Create blobs as sparse files instead of writing zeros
+    pub sparse: bool,
+}
+
+impl Default for SyntheticStore {
+    fn default() -> Self {
+        SyntheticStore {
+            models: 100,
+            layers: 4,
+            shared_layers: 2,
+            namespaces: 8,
+            layer_size: 1024,
+            sparse: false,
+        }
+    }
+}
+
+impl SyntheticStore {
+    /// Write `manifests/` and `blobs/` under `models_dir`.
+    pub fn generate(&self, models_dir: &Path) -> Result<()> {
+        let manifests = models_dir.join("manifests");
+        let blobs = models_dir.join("blobs");
+        create_dir(&blobs)?;
+        let mut written = HashSet::new();
+        for i in 0..self.models {
+            let layers = (0..self.layers)
+                .map(|j| {
+                    let label = if j > 0 && j <= self.shared_layers {
+                        format!("shared-{j}")
+                    } else {
+                        format!("model{i}-layer{j}")
+                    };
+                    let media_type = match j {
+                        0 => "application/vnd.ollama.image.model",
+                        _ => EXTRA_MEDIA_TYPES[(j - 1) % EXTRA_MEDIA_TYPES.len()],
+                    };
+                    self.blob(&blobs, &label, media_type, &mut written)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let config = self.blob(
+                &blobs,
+                &format!("model{i}-config"),
+                CONFIG_MEDIA_TYPE,
+                &mut written,
+            )?;
+
+            let path = manifests
+                .join(DEFAULT_HOST)
+                .join(format!("ns{}", i % self.namespaces.max(1)))
+                .join(format!("model{i}"))
+                .join(DEFAULT_TAG);
+            let body =
+                serde_json::to_vec(&ManifestData::new(config, layers)).map_err(|source| {
+                    Error::Json {
+                        path: path.clone(),
+                        source,
+                    }
+                })?;
+            create_dir(path.parent().unwrap_or(&manifests))?;
+            fs::write(&path, body).map_err(|source| Error::Io { path, source })?;
+        }
+        Ok(())
+    }
+
+    /// Layer entry for `label`, creating its blob on first use.
+    fn blob(
+        &self,
+        blobs: &Path,
+        label: &str,
+        media_type: &str,
+        written: &mut HashSet<Digest>,
+    ) -> Result<LayerInfo> {
+        let digest = sha256_digest(label.as_bytes());
+        if written.insert(digest) {
+            let path = digest.blob_path(blobs);
+            self.write_blob(&path)
+                .map_err(|source| Error::Io { path, source })?;
+        }
+        Ok(LayerInfo {
+            digest,
+            media_type: MediaType::new(media_type),
+            size: Some(self.layer_size),
+        })
+    }
+
+    fn write_blob(&self, path: &Path) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        if self.sparse {
+            file.set_len(self.layer_size)
+        } else {
+            io::copy(&mut io::repeat(0).take(self.layer_size), &mut file).map(drop)
+        }
+    }
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+    fs::create_dir_all(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlobStore, ManifestStore, ScanArgs, gc_plan, scan_manifests};
+
+    #[test]
+    fn test_generate_synthetic_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let shape = SyntheticStore {
+            models: 10,
+            layers: 3,
+            shared_layers: 1,
+            namespaces: 4,
+            layer_size: 16,
+            sparse: true,
+        };
+        shape.generate(dir.path()).unwrap();
+
+        let outcome = scan_manifests(
+            &ScanArgs::new(dir.path().join("manifests"), dir.path().join("blobs"))
+                .with_verbose(true),
+        );
+        assert!(outcome.errors.is_empty());
+        assert_eq!(outcome.models.len(), 10);
+        assert!(outcome.models.iter().all(|m| m.is_broken() == Some(false)));
+
+        let plan = gc_plan(
+            &ManifestStore::new(dir.path().join("manifests")),
+            &BlobStore::new(dir.path().join("blobs")),
+        )
+        .unwrap();
+        // Per model: weights, one unshared extra layer, config; plus one shared layer.
+        assert_eq!(plan.reachable.len(), 10 * 3 + 1);
+        assert!(plan.unreachable.is_empty());
+    }
+}
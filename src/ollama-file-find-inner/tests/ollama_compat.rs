@@ -0,0 +1,135 @@
+//! Cross-checks a scan of a real store against what Ollama itself reports, so that
+//! compatibility with its naming and ID rules is tested rather than assumed.
+//!
+//! Opt-in: build with `--features integration` and point `OLLAMA_MODELS` at the store
+//! the server (`OLLAMA_HOST`, default local) or the `ollama` binary uses:
+//!
+//! ```text
+//! OLLAMA_MODELS=~/.ollama/models cargo test -p ollama-file-find --features integration --test ollama_compat
+//! ```
+//!
+//! The test passes with a note when `OLLAMA_MODELS` is unset or neither the API nor the
+//! binary answers, and otherwise fails listing every field that differs.
+#![cfg(feature = "integration")]
+
+use std::{collections::BTreeMap, env, process::Command};
+
+use ollama_file_find::{
+    ListedModel, ScanArgs, api::installed_models, ollama_api_url, ollama_models_dir, scan_manifests,
+};
+
+/// A model as Ollama lists it.
+struct Reference {
+    /// Manifest digest in hex: complete from the API, the 12-digit ID from `ollama list`
+    digest: String,
+    /// Only the API reports exact sizes
+    size: Option<u64>,
+}
+
+/// Ollama's view of the store, and where it came from.
+fn reference() -> Option<(BTreeMap<String, Reference>, String)> {
+    let url = ollama_api_url();
+    match installed_models(&url) {
+        Ok(models) => {
+            let models = models
+                .into_iter()
+                .map(|m| {
+                    let reference = Reference {
+                        digest: m.digest,
+                        size: Some(m.size),
+                    };
+                    (m.name, reference)
+                })
+                .collect();
+            return Some((models, format!("GET {url}/api/tags")));
+        }
+        Err(e) => eprintln!("{e}; trying `ollama list`"),
+    }
+    let output = Command::new("ollama").arg("list").output().ok()?;
+    if !output.status.success() {
+        eprintln!(
+            "`ollama list` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    // NAME  ID  SIZE  MODIFIED, with a header row.
+    let models = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let name = columns.next()?.to_string();
+            let digest = columns.next()?.to_string();
+            Some((name, Reference { digest, size: None }))
+        })
+        .collect();
+    Some((models, "`ollama list`".to_string()))
+}
+
+fn compare(ours: &ListedModel, theirs: &Reference, mismatches: &mut Vec<String>) {
+    match ours.manifest_digest {
+        Some(digest) if theirs.digest.is_empty() || !digest.hex().starts_with(&theirs.digest) => {
+            mismatches.push(format!(
+                "{}: manifest digest {} does not match Ollama's {:?}",
+                ours.name,
+                digest.hex(),
+                theirs.digest
+            ));
+        }
+        Some(_) => {}
+        None => mismatches.push(format!("{}: no manifest digest", ours.name)),
+    }
+    if let Some(size) = theirs.size
+        && ours.total_size != Some(size)
+    {
+        mismatches.push(format!(
+            "{}: size {:?}, Ollama says {size}",
+            ours.name, ours.total_size
+        ));
+    }
+}
+
+#[test]
+fn listing_matches_ollama() {
+    if env::var_os("OLLAMA_MODELS").is_none() {
+        eprintln!("OLLAMA_MODELS is not set; skipping");
+        return;
+    }
+    let Some((reference, source)) = reference() else {
+        eprintln!("neither the Ollama API nor the `ollama` binary is available; skipping");
+        return;
+    };
+    let models = ollama_models_dir();
+    let outcome = scan_manifests(
+        &ScanArgs::new(models.join("manifests"), models.join("blobs")).with_verbose(true),
+    );
+
+    let mut mismatches: Vec<String> = outcome
+        .errors
+        .iter()
+        .map(|e| format!("scan error: {e}"))
+        .collect();
+    let ours: BTreeMap<&str, &ListedModel> = outcome
+        .models
+        .iter()
+        .map(|m| (m.name.as_str(), m))
+        .collect();
+    for (name, theirs) in &reference {
+        match ours.get(name.as_str()) {
+            Some(m) => compare(m, theirs, &mut mismatches),
+            None => mismatches.push(format!("{name}: listed by Ollama but not found")),
+        }
+    }
+    for name in ours.keys().filter(|n| !reference.contains_key(**n)) {
+        mismatches.push(format!("{name}: found but not listed by Ollama"));
+    }
+    assert!(
+        mismatches.is_empty(),
+        "{} difference(s) from {source} for {}:\n{}",
+        mismatches.len(),
+        models.display(),
+        mismatches.join("\n")
+    );
+    eprintln!("{} model(s) match {source}", reference.len());
+}
@@ -0,0 +1,134 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use ollama_file_find::{Digest, ListedModel, write_atomic};
+use serde::Serialize;
+
+use crate::Result;
+
+/// Serialization formats for model listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Pretty-printed JSON array
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Flat CSV (identity, paths, manifest digest, total size, mtime)
+    Csv,
+    Yaml,
+    /// Model names, one per line
+    Plain,
+    /// Columns like `ollama list`: NAME, ID, SIZE, MODIFIED
+    Table,
+}
+
+impl OutputFormat {
+    /// Infer the format from an output file's extension.
+    pub(crate) fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(OutputFormat::Json),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            "csv" => Some(OutputFormat::Csv),
+            "yaml" | "yml" => Some(OutputFormat::Yaml),
+            "txt" => Some(OutputFormat::Plain),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    name: &'a str,
+    host: Option<&'a str>,
+    namespace: Option<&'a str>,
+    model: &'a str,
+    tag: &'a str,
+    manifest_path: &'a Path,
+    manifest_digest: Option<Digest>,
+    total_size: Option<u64>,
+    mtime: Option<u64>,
+    primary_blob_path: Option<&'a PathBuf>,
+}
+
+/// Render a model listing. With `canonical`, object keys are emitted in sorted order
+/// (round-tripping through `serde_json::Value`, whose map is ordered).
+pub(crate) fn render_models(
+    models: &[ListedModel],
+    format: OutputFormat,
+    canonical: bool,
+) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match format {
+        OutputFormat::Csv => {
+            let mut w = csv::Writer::from_writer(&mut out);
+            for m in models {
+                w.serialize(CsvRow {
+                    name: &m.name,
+                    host: m.model_id.host.as_deref(),
+                    namespace: m.model_id.namespace.as_deref(),
+                    model: &m.model_id.model,
+                    tag: &m.model_id.tag,
+                    manifest_path: &m.manifest_path,
+                    manifest_digest: m.manifest_digest,
+                    total_size: m.total_size,
+                    mtime: m.mtime,
+                    primary_blob_path: m.primary_blob_path.as_ref(),
+                })?;
+            }
+            w.flush()?;
+        }
+        OutputFormat::Plain => {
+            for m in models {
+                writeln!(out, "{}", m.name)?;
+            }
+        }
+        OutputFormat::Table => out.extend_from_slice(crate::table::render(models).as_bytes()),
+        _ if canonical => {
+            let values = models
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<serde_json::Result<Vec<_>>>()?;
+            render_documents(&mut out, &values, format)?;
+        }
+        _ => render_documents(&mut out, models, format)?,
+    }
+    Ok(out)
+}
+
+/// Render items in one of the document formats (JSON, NDJSON, YAML).
+fn render_documents<T: Serialize>(
+    out: &mut Vec<u8>,
+    items: &[T],
+    format: OutputFormat,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, items)?;
+            out.push(b'\n');
+        }
+        OutputFormat::Ndjson => {
+            for item in items {
+                serde_json::to_writer(&mut *out, item)?;
+                out.push(b'\n');
+            }
+        }
+        OutputFormat::Yaml => serde_yaml::to_writer(&mut *out, items)?,
+        OutputFormat::Csv | OutputFormat::Plain | OutputFormat::Table => {
+            unreachable!("tabular formats are rendered from the models directly")
+        }
+    }
+    Ok(())
+}
+
+/// Write rendered output to stdout, or atomically replace `path` with it.
+pub(crate) fn emit(path: Option<&Path>, bytes: &[u8]) -> Result<()> {
+    match path {
+        Some(path) => write_atomic(path, bytes)?,
+        None => std::io::stdout().lock().write_all(bytes)?,
+    }
+    Ok(())
+}
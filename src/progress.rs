@@ -0,0 +1,113 @@
+//! Progress of long operations (scrubs, copies, transfers) on stderr: the usual
+//! per-item lines for people, or with `--progress json` one JSON object per line for
+//! programs embedding the CLI.
+
+use std::{
+    fmt::Display,
+    io::Write,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub(crate) enum ProgressMode {
+    /// Per-item lines where a command prints them
+    #[default]
+    Human,
+    /// JSONL events (`start`, `progress`, `done`) instead
+    Json,
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_mode(mode: ProgressMode) {
+    JSON.store(mode == ProgressMode::Json, Ordering::Relaxed);
+}
+
+fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// One line of `--progress json`. Every event carries every key except `item`, so
+/// consumers can rely on the shape.
+#[derive(Serialize)]
+struct Event {
+    event: &'static str,
+    phase: &'static str,
+    current: u64,
+    total: u64,
+    bytes: u64,
+    total_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    item: Option<String>,
+}
+
+/// A phase of `total` items (and `total_bytes`, when known up front) being worked
+/// through, possibly from several threads.
+pub(crate) struct Progress {
+    phase: &'static str,
+    total: u64,
+    total_bytes: Option<u64>,
+    /// Items and bytes done; also serializes the writes, so `current` only goes up.
+    done: Mutex<(u64, u64)>,
+}
+
+impl Progress {
+    pub(crate) fn start(phase: &'static str, total: usize, total_bytes: Option<u64>) -> Self {
+        let progress = Progress {
+            phase,
+            total: total as u64,
+            total_bytes,
+            done: Mutex::new((0, 0)),
+        };
+        progress.emit("start", 0, 0, None);
+        progress
+    }
+
+    /// One item (a blob digest, usually) is done, `bytes` of it.
+    pub(crate) fn step(&self, item: impl Display, bytes: u64) {
+        if !json() {
+            return;
+        }
+        let mut done = self.done.lock().unwrap_or_else(PoisonError::into_inner);
+        done.0 += 1;
+        done.1 += bytes;
+        self.emit("progress", done.0, done.1, Some(item.to_string()));
+    }
+
+    /// A line for people; left out of `--progress json`.
+    pub(crate) fn note(&self, line: impl Display) {
+        if !json() {
+            eprintln!("{line}");
+        }
+    }
+
+    pub(crate) fn finish(self) {
+        let (current, bytes) = *self.done.lock().unwrap_or_else(PoisonError::into_inner);
+        self.emit("done", current, bytes, None);
+    }
+
+    fn emit(&self, event: &'static str, current: u64, bytes: u64, item: Option<String>) {
+        if !json() {
+            return;
+        }
+        let event = Event {
+            event,
+            phase: self.phase,
+            current,
+            total: self.total,
+            bytes,
+            total_bytes: self.total_bytes,
+            item,
+        };
+        if let Ok(line) = serde_json::to_string(&event) {
+            // One write per event keeps lines whole next to other stderr output.
+            let _ = std::io::stderr().write_all(format!("{line}\n").as_bytes());
+        }
+    }
+}
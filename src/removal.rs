@@ -0,0 +1,40 @@
+//! Removing models by name the undoable way, for commands that remove several at once.
+
+use ollama_file_find::{ManifestStore, ModelId};
+use serde::Serialize;
+
+/// A model that could not be removed.
+#[derive(Serialize)]
+pub(crate) struct Failed {
+    pub model: String,
+    pub error: String,
+}
+
+/// Remove each of `names` with [`ManifestStore::remove_model`], which backs up the
+/// manifest first, and say how to undo it or free the blobs. Returns the names removed
+/// and the failures.
+pub(crate) fn remove_models<'a>(
+    manifests: &ManifestStore,
+    names: impl IntoIterator<Item = &'a String>,
+) -> (Vec<String>, Vec<Failed>) {
+    let (mut removed, mut failed) = (Vec::new(), Vec::new());
+    for name in names {
+        match name
+            .parse::<ModelId>()
+            .and_then(|id| manifests.remove_model(&id))
+        {
+            Ok(_) => removed.push(name.clone()),
+            Err(e) => failed.push(Failed {
+                model: name.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    if !removed.is_empty() {
+        eprintln!(
+            "Removed manifests are backed up (`restore-manifest` brings one back); \
+             `prune --include-held --execute` frees their blobs."
+        );
+    }
+    (removed, failed)
+}
@@ -0,0 +1,152 @@
+//! Resume files for long operations (`bundle`, `restore`, the `maintain` scrub): one
+//! line per finished blob, appended and synced as soon as it is done, so an interrupted
+//! run can skip that work with `--resume`.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::SystemTime,
+};
+
+use ollama_file_find::Digest;
+
+use crate::Result;
+
+/// What was known about a blob when it was recorded, to tell cheaply whether the work
+/// done on it still stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Done {
+    pub size: u64,
+    /// Modification time of the file checked, when it matters (seconds since the epoch)
+    pub mtime: Option<u64>,
+}
+
+impl Done {
+    /// Size and modification time of `path`.
+    pub(crate) fn of(path: &Path) -> Option<Done> {
+        let meta = fs::metadata(path).ok()?;
+        Some(Done {
+            size: meta.len(),
+            mtime: meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        })
+    }
+}
+
+pub(crate) struct ResumeLog {
+    path: PathBuf,
+    file: File,
+    done: HashMap<Digest, Done>,
+}
+
+impl ResumeLog {
+    /// Open the log at `path` for the operation `op`. With `resume`, entries left by an
+    /// earlier run of the same operation are loaded (a torn last line is ignored);
+    /// otherwise the log starts empty.
+    pub(crate) fn open(path: &Path, op: &str, resume: bool) -> Result<Self> {
+        let header = format!("# ollama-file-find {op}");
+        let mut done = HashMap::new();
+        let existing = if resume {
+            match fs::read_to_string(path) {
+                Ok(body) => Some(body),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!("No resume file at {}; starting over", path.display());
+                    None
+                }
+                Err(e) => return Err(anyhow::anyhow!("{}: {e}", path.display())),
+            }
+        } else {
+            None
+        };
+        if let Some(body) = &existing {
+            let mut lines = body.lines();
+            if lines.next() != Some(header.as_str()) {
+                anyhow::bail!("{} is not a resume file for {op}", path.display());
+            }
+            for line in lines {
+                if let Some((digest, entry)) = parse_line(line) {
+                    done.insert(digest, entry);
+                }
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = if existing.is_some() {
+            // End a torn last line so the next entry starts on its own.
+            let mut file = OpenOptions::new().append(true).open(path)?;
+            if existing.is_some_and(|b| !b.ends_with('\n')) {
+                file.write_all(b"\n")?;
+            }
+            file
+        } else {
+            let mut file = File::create(path)?;
+            writeln!(file, "{header}")?;
+            file
+        };
+        Ok(ResumeLog {
+            path: path.to_path_buf(),
+            file,
+            done,
+        })
+    }
+
+    /// How `digest` looked when it was recorded, if it was.
+    pub(crate) fn get(&self, digest: &Digest) -> Option<Done> {
+        self.done.get(digest).copied()
+    }
+
+    /// Whether `digest` was recorded and `now` (e.g. [`Done::of`] the file) still
+    /// matches; a recorded mtime of `None` only compares sizes.
+    pub(crate) fn is_done(&self, digest: &Digest, now: Option<Done>) -> bool {
+        match (self.get(digest), now) {
+            (Some(then), Some(now)) => {
+                then.size == now.size && then.mtime.is_none_or(|m| now.mtime == Some(m))
+            }
+            _ => false,
+        }
+    }
+
+    /// Record `digest` as finished, durably, before moving on.
+    pub(crate) fn record(&mut self, digest: Digest, done: Done) -> Result<()> {
+        let mtime = done
+            .mtime
+            .map_or_else(|| "-".to_string(), |m| m.to_string());
+        writeln!(self.file, "{digest} {} {mtime}", done.size)?;
+        self.file.sync_data()?;
+        self.done.insert(digest, done);
+        Ok(())
+    }
+
+    /// The operation completed: remove the log.
+    pub(crate) fn finish(self) -> Result<()> {
+        drop(self.file);
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Lock a log shared between workers (see [`ollama_file_find::map_per_device`]).
+pub(crate) fn lock(log: &Mutex<ResumeLog>) -> MutexGuard<'_, ResumeLog> {
+    log.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn parse_line(line: &str) -> Option<(Digest, Done)> {
+    let mut fields = line.split(' ');
+    let digest = fields.next()?.parse().ok()?;
+    let size = fields.next()?.parse().ok()?;
+    let mtime = match fields.next()? {
+        "-" => None,
+        m => Some(m.parse().ok()?),
+    };
+    Some((digest, Done { size, mtime }))
+}
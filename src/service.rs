@@ -0,0 +1,236 @@
+//! `daemon --install-service`: a per-user service that starts the daemon at login and
+//! restarts it if it exits, as a systemd user unit, a launchd agent, or a Task Scheduler
+//! task depending on the platform.
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ollama_file_find::write_atomic;
+
+use crate::Result;
+
+/// Name of the unit, agent label suffix, and scheduled task.
+const SERVICE_NAME: &str = "ollama-file-find";
+const LAUNCHD_LABEL: &str = "io.github.exotik850.ollama-file-find";
+
+/// Write the service definition for this platform, running this executable with the
+/// current command line minus `--install-service` and with `--models-dir` made absolute,
+/// and print the command that activates it.
+pub(crate) fn install(models_dir: &Path) -> Result<()> {
+    let command = service_command(models_dir)?;
+    let (path, body, activate) = if cfg!(target_os = "macos") {
+        let path = dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("no home directory"))?
+            .join("Library/LaunchAgents")
+            .join(format!("{LAUNCHD_LABEL}.plist"));
+        let activate = format!("launchctl bootstrap gui/$(id -u) '{}'", path.display());
+        (path, launchd_agent(&command).into_bytes(), activate)
+    } else if cfg!(windows) {
+        let path = service_dir().join("daemon-task.xml");
+        let activate = format!(
+            "schtasks /Create /TN {SERVICE_NAME} /XML \"{}\"",
+            path.display()
+        );
+        // Task Scheduler wants the XML in UTF-16.
+        let xml = scheduled_task(&command);
+        let body = [0xFEFF_u16]
+            .into_iter()
+            .chain(xml.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        (path, body, activate)
+    } else {
+        let path = dirs::config_dir()
+            .ok_or_else(|| anyhow::anyhow!("no config directory"))?
+            .join("systemd/user")
+            .join(format!("{SERVICE_NAME}.service"));
+        let activate = format!(
+            "systemctl --user daemon-reload && systemctl --user enable --now {SERVICE_NAME}.service"
+        );
+        (path, systemd_unit(&command).into_bytes(), activate)
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    write_atomic(&path, &body)?;
+    eprintln!(
+        "Wrote {}; start it now and at every login with:",
+        path.display()
+    );
+    println!("{activate}");
+    Ok(())
+}
+
+fn service_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("ollama-file-find")
+}
+
+/// This executable and its arguments, as the service should run it.
+fn service_command(models_dir: &Path) -> Result<Vec<String>> {
+    let exe = std::env::current_exe()?;
+    let mut command = vec![
+        path_string(&exe)?,
+        "--models-dir".to_owned(),
+        path_string(&std::path::absolute(models_dir)?)?,
+    ];
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--models-dir" {
+            args.next();
+            continue;
+        }
+        if arg == "--install-service" || arg.to_string_lossy().starts_with("--models-dir=") {
+            continue;
+        }
+        command.push(os_string(arg)?);
+    }
+    Ok(command)
+}
+
+fn path_string(path: &Path) -> Result<String> {
+    os_string(path.as_os_str().to_owned())
+}
+
+fn os_string(s: OsString) -> Result<String> {
+    s.into_string()
+        .map_err(|s| anyhow::anyhow!("{} is not valid UTF-8", s.to_string_lossy()))
+}
+
+fn systemd_unit(command: &[String]) -> String {
+    let exec: Vec<String> = command
+        .iter()
+        .map(|arg| {
+            // `%` starts a specifier and `$` a variable; quoting covers spaces and quotes.
+            let arg = arg.replace('%', "%%").replace('$', "$$");
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "\"'\\;".contains(c)) {
+                format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                arg
+            }
+        })
+        .collect();
+    format!(
+        "[Unit]\n\
+         Description=ollama-file-find daemon (store index, change log, and local API)\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec.join(" ")
+    )
+}
+
+fn launchd_agent(command: &[String]) -> String {
+    let args: String = command
+        .iter()
+        .map(|arg| format!("    <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    let log = xml_escape(&service_dir().join("daemon.log").to_string_lossy());
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \x20 <key>Label</key>\n\
+         \x20 <string>{LAUNCHD_LABEL}</string>\n\
+         \x20 <key>ProgramArguments</key>\n\
+         \x20 <array>\n\
+         {args}\
+         \x20 </array>\n\
+         \x20 <key>RunAtLoad</key>\n\
+         \x20 <true/>\n\
+         \x20 <key>KeepAlive</key>\n\
+         \x20 <dict>\n\
+         \x20   <key>SuccessfulExit</key>\n\
+         \x20   <false/>\n\
+         \x20 </dict>\n\
+         \x20 <key>StandardErrorPath</key>\n\
+         \x20 <string>{log}</string>\n\
+         </dict>\n\
+         </plist>\n"
+    )
+}
+
+fn scheduled_task(command: &[String]) -> String {
+    let arguments: Vec<String> = command[1..].iter().map(|a| windows_quote(a)).collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-16\"?>\n\
+         <Task version=\"1.2\" xmlns=\"http://schemas.microsoft.com/windows/2004/02/mit/task\">\n\
+         \x20 <RegistrationInfo>\n\
+         \x20   <Description>ollama-file-find daemon (store index, change log, and local API)</Description>\n\
+         \x20 </RegistrationInfo>\n\
+         \x20 <Triggers>\n\
+         \x20   <LogonTrigger>\n\
+         \x20     <Enabled>true</Enabled>\n\
+         \x20   </LogonTrigger>\n\
+         \x20 </Triggers>\n\
+         \x20 <Principals>\n\
+         \x20   <Principal id=\"Author\">\n\
+         \x20     <LogonType>InteractiveToken</LogonType>\n\
+         \x20     <RunLevel>LeastPrivilege</RunLevel>\n\
+         \x20   </Principal>\n\
+         \x20 </Principals>\n\
+         \x20 <Settings>\n\
+         \x20   <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>\n\
+         \x20   <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>\n\
+         \x20   <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>\n\
+         \x20   <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>\n\
+         \x20   <RestartOnFailure>\n\
+         \x20     <Interval>PT1M</Interval>\n\
+         \x20     <Count>999</Count>\n\
+         \x20   </RestartOnFailure>\n\
+         \x20 </Settings>\n\
+         \x20 <Actions Context=\"Author\">\n\
+         \x20   <Exec>\n\
+         \x20     <Command>{}</Command>\n\
+         \x20     <Arguments>{}</Arguments>\n\
+         \x20   </Exec>\n\
+         \x20 </Actions>\n\
+         </Task>\n",
+        xml_escape(&command[0]),
+        xml_escape(&arguments.join(" "))
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quote one argument the way `CommandLineToArgvW` splits it back.
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_owned();
+    }
+    let mut out = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                out.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+            }
+            c => {
+                out.extend(std::iter::repeat_n('\\', backslashes));
+                out.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    out.extend(std::iter::repeat_n('\\', backslashes * 2));
+    out.push('"');
+    out
+}
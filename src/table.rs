@@ -0,0 +1,146 @@
+//! The `ollama list` table: NAME, ID, SIZE, MODIFIED, with Ollama's own rounding of
+//! sizes and relative times so the two outputs can be compared line by line.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ollama_file_find::ListedModel;
+
+/// Hex digits of the manifest digest shown as the ID.
+const ID_LEN: usize = 12;
+
+/// Columns are separated by (at least) this much space.
+const PADDING: &str = "    ";
+
+/// Render models as `ollama list` does. The ID, size, and modification time need a
+/// summary or verbose scan.
+pub(crate) fn render(models: &[ListedModel]) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut rows = vec![["NAME", "ID", "SIZE", "MODIFIED"].map(String::from)];
+    for m in models {
+        rows.push([
+            m.name.clone(),
+            m.manifest_digest
+                .map_or_else(|| "-".to_string(), |d| d.hex()[..ID_LEN].to_string()),
+            m.total_size.map_or_else(|| "-".to_string(), ollama_bytes),
+            m.mtime
+                .map_or_else(|| "-".to_string(), |t| relative_time(now.saturating_sub(t))),
+        ]);
+    }
+
+    let mut widths = [0; 4];
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in &rows {
+        let mut line = String::new();
+        for (w, cell) in widths.iter().zip(row) {
+            line.push_str(&format!("{cell:<w$}{PADDING}"));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Ollama's size rounding: decimal units, one decimal place only below 10 and only
+/// when there is a fraction (`4.7 GB`, `42 GB`, `2 GB`).
+pub(crate) fn ollama_bytes(bytes: u64) -> String {
+    const UNITS: [(u64, &str); 4] = [
+        (1000_u64.pow(4), "TB"),
+        (1000_u64.pow(3), "GB"),
+        (1000_u64.pow(2), "MB"),
+        (1000, "KB"),
+    ];
+    let Some(&(scale, unit)) = UNITS.iter().find(|(scale, _)| bytes >= *scale) else {
+        return format!("{bytes} B");
+    };
+    #[allow(clippy::cast_precision_loss)]
+    let value = bytes as f64 / scale as f64;
+    if value < 10.0 && value.fract() != 0.0 {
+        format!("{value:.1} {unit}")
+    } else {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let whole = value as u64;
+        format!("{whole} {unit}")
+    }
+}
+
+/// An age the way `ollama list` words it (`About a minute ago`, `3 days ago`,
+/// `2 weeks ago`).
+pub(crate) fn relative_time(secs: u64) -> String {
+    let minutes = secs / 60;
+    // Hours round to the nearest, as Ollama's do.
+    let hours = (secs + 30 * 60) / (60 * 60);
+    let age = match secs {
+        0 => "Less than a second".to_string(),
+        1 => "1 second".to_string(),
+        s if s < 60 => format!("{s} seconds"),
+        _ if minutes == 1 => "About a minute".to_string(),
+        _ if minutes < 60 => format!("{minutes} minutes"),
+        _ if hours == 1 => "About an hour".to_string(),
+        _ if hours < 48 => format!("{hours} hours"),
+        _ if hours < 24 * 7 * 2 => format!("{} days", hours / 24),
+        _ if hours < 24 * 30 * 2 => format!("{} weeks", hours / 24 / 7),
+        _ if hours < 24 * 365 * 2 => format!("{} months", hours / 24 / 30),
+        _ => format!("{} years", secs / (60 * 60) / 24 / 365),
+    };
+    format!("{age} ago")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_bytes() {
+        for (bytes, expected) in [
+            (0, "0 B"),
+            (999, "999 B"),
+            (1000, "1 KB"),
+            (1500, "1.5 KB"),
+            (999_999, "999 KB"),
+            (1_000_000, "1 MB"),
+            (2_000_000_000, "2 GB"),
+            (4_700_000_000, "4.7 GB"),
+            // Rounds up to ten but keeps its decimal, as Ollama's does.
+            (9_960_000_000, "10.0 GB"),
+            (10_000_000_000, "10 GB"),
+            (42_700_000_000, "42 GB"),
+            (1_000_000_000_000, "1 TB"),
+        ] {
+            assert_eq!(ollama_bytes(bytes), expected, "{bytes}");
+        }
+    }
+
+    #[test]
+    fn test_relative_time() {
+        const HOUR: u64 = 60 * 60;
+        const DAY: u64 = 24 * HOUR;
+        for (secs, expected) in [
+            (0, "Less than a second ago"),
+            (1, "1 second ago"),
+            (59, "59 seconds ago"),
+            (60, "About a minute ago"),
+            (119, "About a minute ago"),
+            (120, "2 minutes ago"),
+            (HOUR - 1, "59 minutes ago"),
+            (HOUR, "About an hour ago"),
+            (HOUR + 29 * 60, "About an hour ago"),
+            (HOUR + 30 * 60, "2 hours ago"),
+            (47 * HOUR, "47 hours ago"),
+            (2 * DAY, "2 days ago"),
+            (14 * DAY - HOUR, "13 days ago"),
+            (14 * DAY, "2 weeks ago"),
+            (60 * DAY - HOUR, "8 weeks ago"),
+            (60 * DAY, "2 months ago"),
+            (2 * 365 * DAY, "2 years ago"),
+        ] {
+            assert_eq!(relative_time(secs), expected, "{secs}");
+        }
+    }
+}
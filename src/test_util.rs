@@ -0,0 +1,37 @@
+//! Shared setup for the command tests.
+
+use std::fs;
+
+use clap::Parser;
+use ollama_file_find::{LayerKind, ManifestBackup, ModelId, fixtures::FakeStore};
+
+use crate::StorePaths;
+
+#[derive(Parser)]
+struct Cli<T: clap::Args> {
+    #[command(flatten)]
+    args: T,
+}
+
+/// Parse a command's arguments as clap would, from `argv` without the program name.
+pub(crate) fn parse<T: clap::Args>(argv: &[&str]) -> T {
+    Cli::parse_from(std::iter::once("test").chain(argv.iter().copied())).args
+}
+
+pub(crate) fn paths(store: &FakeStore) -> StorePaths {
+    StorePaths::resolve(Some(store.models_dir().to_path_buf())).unwrap()
+}
+
+/// Content of `model`'s layer of `kind`, if it has one. Panics unless the manifest
+/// parses and the blob it references exists.
+pub(crate) fn layer(store: &FakeStore, model: &str, kind: LayerKind) -> Option<Vec<u8>> {
+    let id: ModelId = model.parse().unwrap();
+    let manifest = store.manifests().find(&id).expect("manifest parses");
+    let layer = manifest.data.layers.iter().find(|l| l.kind() == kind)?;
+    let path = store.blobs().path_for(&layer.digest);
+    Some(fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display())))
+}
+
+pub(crate) fn backups(store: &FakeStore, model: &str) -> Vec<ManifestBackup> {
+    store.manifests().backups(&model.parse().unwrap())
+}
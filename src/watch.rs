@@ -0,0 +1,117 @@
+use std::{path::Path, sync::mpsc, time::Duration};
+
+use notify::{Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{Result, StorePaths};
+
+/// Quiet period that ends a burst of filesystem events (a pull touches many files).
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Poll interval used when native notifications are unavailable and none was requested.
+const DEFAULT_POLL: Duration = Duration::from_secs(10);
+
+type EventSender = mpsc::Sender<notify::Result<Event>>;
+
+/// Block forever, calling `on_change` once each burst of changes under the store settles.
+///
+/// Uses native notifications (inotify, FSEvents, ReadDirectoryChangesW) unless `poll` is
+/// given, the store lives on a network mount where those never fire, or they fail to
+/// initialize; in those cases the tree is re-stat'ed every interval instead.
+pub(crate) fn watch_store(
+    paths: &StorePaths,
+    poll: Option<Duration>,
+    mut on_change: impl FnMut(),
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let _watcher: Box<dyn Watcher> = match poll {
+        Some(interval) => Box::new(poll_watcher(paths, tx, interval)?),
+        None if is_network_mount(&paths.manifests) => {
+            eprintln!("Store is on a network filesystem; polling every {DEFAULT_POLL:?}");
+            Box::new(poll_watcher(paths, tx, DEFAULT_POLL)?)
+        }
+        None => match native_watcher(paths, tx.clone()) {
+            Ok(w) => Box::new(w),
+            Err(e) => {
+                eprintln!(
+                    "Warning: native file watching unavailable ({e}); polling every {DEFAULT_POLL:?}"
+                );
+                Box::new(poll_watcher(paths, tx, DEFAULT_POLL)?)
+            }
+        },
+    };
+
+    while let Ok(event) = rx.recv() {
+        match event {
+            // Our own rescans read every manifest; reads are not changes.
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => continue,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Warning: watch error: {e}");
+                continue;
+            }
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        on_change();
+    }
+    Ok(())
+}
+
+fn watch_paths(watcher: &mut dyn Watcher, paths: &StorePaths) -> notify::Result<()> {
+    watcher.watch(&paths.manifests, RecursiveMode::Recursive)?;
+    if paths.blobs.is_dir() {
+        watcher.watch(&paths.blobs, RecursiveMode::NonRecursive)?;
+    }
+    Ok(())
+}
+
+fn native_watcher(paths: &StorePaths, tx: EventSender) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watch_paths(&mut watcher, paths)?;
+    Ok(watcher)
+}
+
+/// Stat-based watcher: compares mtimes each interval, so it works anywhere but costs
+/// one `stat` per file per interval.
+fn poll_watcher(
+    paths: &StorePaths,
+    tx: EventSender,
+    interval: Duration,
+) -> notify::Result<PollWatcher> {
+    let mut watcher = PollWatcher::new(tx, Config::default().with_poll_interval(interval))?;
+    watch_paths(&mut watcher, paths)?;
+    Ok(watcher)
+}
+
+/// Whether `path` is on NFS/SMB, where inotify initializes fine but never sees
+/// changes made by other hosts.
+#[cfg(target_os = "linux")]
+fn is_network_mount(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    const NFS_SUPER_MAGIC: u32 = 0x6969;
+    const SMB_SUPER_MAGIC: u32 = 0x517B;
+    const CIFS_SUPER_MAGIC: u32 = 0xFF53_4D42;
+    const SMB2_SUPER_MAGIC: u32 = 0xFE53_4D42;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `c_path` is NUL terminated and `stat` is large enough for the result.
+    if unsafe { libc::statfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    // SAFETY: statfs succeeded, so the struct is initialized.
+    // `f_type` is a signed word whose width varies by target; the magics are 32-bit.
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    let magic = unsafe { stat.assume_init() }.f_type as u32;
+    matches!(
+        magic,
+        NFS_SUPER_MAGIC | SMB_SUPER_MAGIC | CIFS_SUPER_MAGIC | SMB2_SUPER_MAGIC
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_network_mount(_path: &Path) -> bool {
+    false
+}